@@ -0,0 +1,85 @@
+//! Period queries on long histories. Run with `cargo bench --bench ledger`.
+//!
+//! Books millions of entries on one account, spread over three years, then
+//! times month statements through `Statement::for_period`, which looks only
+//! at the month's day partitions, against filtering the whole history the
+//! way statements did before.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bank::{Account, Bank, SeededRng, Statement, Timestamp, SECONDS_PER_DAY};
+
+const DAYS: u64 = 3 * 365;
+const QUERIES: usize = 20;
+
+fn bank(rows: u64) -> Bank {
+    let mut bank = Bank::new();
+    bank.add_account(Account::new(1, String::from("Holder")));
+    let spacing = DAYS * SECONDS_PER_DAY / rows;
+    for row in 0..rows {
+        bank.set_time(Timestamp(row * spacing));
+        bank.deposit(1, 100).unwrap();
+    }
+    bank
+}
+
+/// Random 30-day periods within the history.
+fn periods() -> Vec<(Timestamp, Timestamp)> {
+    let mut rng = SeededRng::new(5);
+    (0..QUERIES)
+        .map(|_| {
+            let day = rng.range(0, (DAYS - 30) as i64) as u64;
+            let start = Timestamp(day * SECONDS_PER_DAY);
+            (start, start.plus_days(30))
+        })
+        .collect()
+}
+
+fn per_query(elapsed: Duration) -> f64 {
+    elapsed.as_secs_f64() * 1000.0 / QUERIES as f64
+}
+
+fn partitioned(account: &Account, periods: &[(Timestamp, Timestamp)]) -> Duration {
+    let started = Instant::now();
+    for &(start, end) in periods {
+        black_box(Statement::for_period(account, start, end));
+    }
+    started.elapsed()
+}
+
+fn full_scan(account: &Account, periods: &[(Timestamp, Timestamp)]) -> Duration {
+    let started = Instant::now();
+    for &(start, end) in periods {
+        let entries: Vec<_> = account
+            .history()
+            .iter()
+            .filter(|txn| txn.timestamp >= start && txn.timestamp <= end)
+            .cloned()
+            .collect();
+        black_box(entries);
+    }
+    started.elapsed()
+}
+
+fn main() {
+    println!(
+        "{:>10} {:>14} {:>14} {:>14}",
+        "entries", "month entries", "scan ms", "partitioned ms"
+    );
+    let periods = periods();
+    for rows in [1_000_000, 2_000_000, 4_000_000] {
+        let bank = bank(rows);
+        let account = bank.get_account(1).unwrap();
+        let (start, end) = periods[0];
+        let scan = full_scan(account, &periods);
+        let partitioned = partitioned(account, &periods);
+        println!(
+            "{:>10} {:>14} {:>14.2} {:>14.2}",
+            rows,
+            account.history_between(start, end).len(),
+            per_query(scan),
+            per_query(partitioned)
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Map against dense account storage. Run with `cargo bench --bench storage`.
+//!
+//! Times random balance lookups and random transfers on banks whose ids run
+//! densely from 1, once per [`AccountStorage`].
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bank::{Account, AccountStorage, Bank, SeededRng};
+
+const OPERATIONS: usize = 200_000;
+
+fn ids(accounts: u32) -> Vec<(u32, u32)> {
+    let mut rng = SeededRng::new(11);
+    (0..OPERATIONS)
+        .map(|_| {
+            let from = rng.range(1, i64::from(accounts)) as u32;
+            let to = rng.range(1, i64::from(accounts)) as u32;
+            (from, to)
+        })
+        .collect()
+}
+
+fn bank(storage: AccountStorage, count: u32) -> Bank {
+    let mut bank = Bank::with_storage(storage);
+    bank.reserve(count as usize + 1);
+    for id in 1..=count {
+        let mut account = Account::new(id, format!("Holder {}", id));
+        account.deposit(1_000_000).unwrap();
+        bank.add_account(account);
+    }
+    bank
+}
+
+fn per_op(elapsed: Duration) -> f64 {
+    elapsed.as_nanos() as f64 / OPERATIONS as f64
+}
+
+fn lookups(bank: &Bank, pairs: &[(u32, u32)]) -> Duration {
+    let started = Instant::now();
+    for &(id, _) in pairs {
+        black_box(bank.get_account(id).map(Account::balance));
+    }
+    started.elapsed()
+}
+
+fn transfers(bank: &mut Bank, pairs: &[(u32, u32)]) -> Duration {
+    let started = Instant::now();
+    for &(from, to) in pairs {
+        black_box(bank.transfer(from, to, 1).unwrap());
+    }
+    started.elapsed()
+}
+
+fn main() {
+    println!(
+        "{:>10} {:>8} {:>12} {:>12}",
+        "accounts", "storage", "lookup ns", "transfer ns"
+    );
+    for count in [1_000, 100_000, 1_000_000] {
+        let pairs = ids(count);
+        for storage in [AccountStorage::Map, AccountStorage::Dense] {
+            let mut bank = bank(storage, count);
+            let lookup = lookups(&bank, &pairs);
+            let transfer = transfers(&mut bank, &pairs);
+            println!(
+                "{:>10} {:>8} {:>12.1} {:>12.1}",
+                count,
+                format!("{:?}", storage),
+                per_op(lookup),
+                per_op(transfer)
+            );
+        }
+    }
+}
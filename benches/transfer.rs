@@ -0,0 +1,71 @@
+//! Transfer throughput at scale. Run with `cargo bench --bench transfer`.
+//!
+//! Times `Bank::transfer` on a plain bank and with a transfer tax, which
+//! makes each transfer credit a third account.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use bank::{Account, Bank, FlatTax, SeededRng};
+
+const TRANSFERS: usize = 200_000;
+const TAX_ACCOUNT: u32 = 0;
+
+fn pairs(accounts: u32) -> Vec<(u32, u32)> {
+    let mut rng = SeededRng::new(7);
+    (0..TRANSFERS)
+        .map(|_| loop {
+            let from = rng.range(1, i64::from(accounts)) as u32;
+            let to = rng.range(1, i64::from(accounts)) as u32;
+            if from != to {
+                break (from, to);
+            }
+        })
+        .collect()
+}
+
+fn bank(count: u32) -> Bank {
+    let mut bank = Bank::new();
+    for id in 1..=count {
+        let mut account = Account::new(id, format!("Holder {}", id));
+        account.deposit(1_000_000).unwrap();
+        bank.add_account(account);
+    }
+    bank
+}
+
+fn per_op(elapsed: Duration) -> f64 {
+    elapsed.as_nanos() as f64 / TRANSFERS as f64
+}
+
+fn transfers(mut bank: Bank, pairs: &[(u32, u32)]) -> Duration {
+    let started = Instant::now();
+    for &(from, to) in pairs {
+        black_box(bank.transfer(from, to, 100).unwrap());
+    }
+    started.elapsed()
+}
+
+fn main() {
+    println!("{:>10} {:>14} {:>14}", "accounts", "plain ns", "taxed ns");
+    for count in [1_000, 100_000, 1_000_000] {
+        let pairs = pairs(count);
+        let plain = transfers(bank(count), &pairs);
+
+        let mut taxed = bank(count);
+        taxed.add_account(Account::new(TAX_ACCOUNT, "Tax".to_string()));
+        taxed.set_tax_account(TAX_ACCOUNT).unwrap();
+        taxed.set_tax_policy(FlatTax {
+            interest_bps: 0,
+            transfer_bps: 100,
+        });
+        let taxed = transfers(taxed, &pairs);
+
+        println!(
+            "{:>10} {:>14.1} {:>14.1}",
+            count,
+            per_op(plain),
+            per_op(taxed)
+        );
+    }
+}
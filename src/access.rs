@@ -0,0 +1,256 @@
+use std::fmt;
+
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::eod::EndOfDayReport;
+use crate::error::AccountError;
+use crate::outcome::OperationOutcome;
+use crate::receipt::Receipt;
+
+/// Transfers above this many cents need a manager unless the session sets
+/// its own limit.
+const DEFAULT_LARGE_TRANSFER: i64 = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Role {
+    /// Deposits, withdrawals and transfers up to the large-transfer limit.
+    Teller,
+    /// Everything, including large transfers, adjustments and end-of-day
+    /// processing.
+    Manager,
+    /// Read-only access to every account.
+    Auditor,
+    /// The accounts held under this holder name, and nothing else.
+    Customer(String),
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Teller => write!(f, "teller"),
+            Role::Manager => write!(f, "manager"),
+            Role::Auditor => write!(f, "auditor"),
+            Role::Customer(holder) => write!(f, "customer {}", holder),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Deposit,
+    Withdraw,
+    Transfer,
+    ViewAccount,
+    ViewAllAccounts,
+    Adjust,
+    EndOfDay,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Action::Deposit => "deposit",
+            Action::Withdraw => "withdraw",
+            Action::Transfer => "transfer",
+            Action::ViewAccount => "view accounts",
+            Action::ViewAllAccounts => "view all accounts",
+            Action::Adjust => "adjust balances",
+            Action::EndOfDay => "run end of day",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessError {
+    /// The role may never perform this action.
+    Forbidden {
+        role: Role,
+        action: Action,
+    },
+    /// A customer tried to act on an account they do not hold.
+    NotAccountHolder {
+        account_id: u32,
+    },
+    /// The transfer is above the session's limit and needs a manager.
+    ApprovalRequired {
+        amount: i64,
+        limit: i64,
+    },
+    Account(AccountError),
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessError::Forbidden { role, action } => {
+                write!(f, "Not permitted for {}: {}", role, action)
+            }
+            AccessError::NotAccountHolder { account_id } => {
+                write!(f, "Account {} belongs to someone else", account_id)
+            }
+            AccessError::ApprovalRequired { amount, limit } => write!(
+                f,
+                "Transfers of ${:.2} need manager approval (limit ${:.2})",
+                *amount as f64 / 100.0,
+                *limit as f64 / 100.0
+            ),
+            AccessError::Account(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for AccessError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AccessError::Account(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<AccountError> for AccessError {
+    fn from(error: AccountError) -> Self {
+        AccessError::Account(error)
+    }
+}
+
+/// A bank as seen by one user. Every call is checked against the role
+/// before it reaches the bank.
+pub struct Session<'a> {
+    bank: &'a mut Bank,
+    role: Role,
+    large_transfer_limit: i64,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(bank: &'a mut Bank, role: Role) -> Self {
+        Session {
+            bank,
+            role,
+            large_transfer_limit: DEFAULT_LARGE_TRANSFER,
+        }
+    }
+
+    pub fn with_large_transfer_limit(mut self, limit: i64) -> Self {
+        self.large_transfer_limit = limit;
+        self
+    }
+
+    pub fn role(&self) -> &Role {
+        &self.role
+    }
+
+    pub fn deposit(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<Receipt, AccessError> {
+        let id = self.authorize_account(account, Action::Deposit)?;
+        Ok(self.bank.deposit(id, amount)?)
+    }
+
+    pub fn withdraw(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<Receipt, AccessError> {
+        let id = self.authorize_account(account, Action::Withdraw)?;
+        Ok(self.bank.withdraw(id, amount)?)
+    }
+
+    /// Customers may only send from their own accounts, but to anyone.
+    /// Only managers may move more than the large-transfer limit.
+    pub fn transfer(
+        &mut self,
+        from: impl Into<AccountRef>,
+        to: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<OperationOutcome<()>, AccessError> {
+        let from_id = self.authorize_account(from, Action::Transfer)?;
+        let to_id = self.bank.resolve(to)?;
+        if amount > self.large_transfer_limit && self.role != Role::Manager {
+            return Err(AccessError::ApprovalRequired {
+                amount,
+                limit: self.large_transfer_limit,
+            });
+        }
+        Ok(self.bank.transfer(from_id, to_id, amount)?)
+    }
+
+    /// Manager-only correction; see [`Bank::adjust`].
+    pub fn adjust(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        reason: &str,
+        actor: &str,
+    ) -> Result<u64, AccessError> {
+        self.authorize(Action::Adjust)?;
+        Ok(self.bank.adjust(account, amount, reason, actor)?)
+    }
+
+    pub fn account(&self, account: impl Into<AccountRef>) -> Result<&Account, AccessError> {
+        let id = self.authorize_account(account, Action::ViewAccount)?;
+        Ok(&self.bank.accounts[&id])
+    }
+
+    pub fn summary(&self) -> Result<String, AccessError> {
+        self.authorize(Action::ViewAllAccounts)?;
+        Ok(self.bank.summary())
+    }
+
+    pub fn total_balance(&self) -> Result<i64, AccessError> {
+        self.authorize(Action::ViewAllAccounts)?;
+        Ok(self.bank.total_balance())
+    }
+
+    pub fn end_of_day(&mut self) -> Result<EndOfDayReport, AccessError> {
+        self.authorize(Action::EndOfDay)?;
+        Ok(self.bank.end_of_day())
+    }
+
+    fn authorize(&self, action: Action) -> Result<(), AccessError> {
+        let allowed = match self.role {
+            Role::Manager => true,
+            Role::Teller => !matches!(action, Action::Adjust | Action::EndOfDay),
+            Role::Auditor => matches!(action, Action::ViewAccount | Action::ViewAllAccounts),
+            Role::Customer(_) => matches!(
+                action,
+                Action::Deposit | Action::Withdraw | Action::Transfer | Action::ViewAccount
+            ),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(AccessError::Forbidden {
+                role: self.role.clone(),
+                action,
+            })
+        }
+    }
+
+    fn authorize_account(
+        &self,
+        account: impl Into<AccountRef>,
+        action: Action,
+    ) -> Result<u32, AccessError> {
+        self.authorize(action)?;
+        let id = self.bank.resolve(account)?;
+        if let Role::Customer(holder) = &self.role {
+            if self.bank.accounts[&id].holder != *holder {
+                return Err(AccessError::NotAccountHolder { account_id: id });
+            }
+        }
+        Ok(id)
+    }
+}
+
+impl fmt::Debug for Session<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("role", &self.role)
+            .field("large_transfer_limit", &self.large_transfer_limit)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,451 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::chain::{entry_hash, ChainHash};
+use crate::channels::DepositChannel;
+use crate::cosign::{CoSigner, PendingWithdrawal};
+use crate::currency::Currency;
+use crate::disputes::Dispute;
+use crate::error::{AccountError, InputError, PolicyError};
+use crate::fee_debt::FeeShortfall;
+use crate::funds::UnitHolding;
+use crate::goals::SavingsGoal;
+use crate::guardian::Guardianship;
+use crate::interest::InterestTier;
+use crate::ledger::{self, LedgerError};
+use crate::locale::{Locale, Localize};
+use crate::outcome::{OperationOutcome, Warning};
+use crate::overdraft_interest::{OverdraftAccrual, OverdraftInterest};
+use crate::overflow::PROMOTED_KEY;
+use crate::ownership::OwnershipTransfer;
+use crate::partition::DayPartitions;
+use crate::promotions::PromotionAward;
+use crate::rounding::RoundingPolicy;
+use crate::time::Timestamp;
+use crate::transaction::{Transaction, TransactionKind};
+use crate::uuid::Uuid;
+
+/// Withdrawals needed before a "typical spend" can be estimated.
+const TYPICAL_SPEND_MIN_SAMPLES: u32 = 3;
+/// How many times the average withdrawal counts as unusual.
+const UNUSUAL_SPEND_FACTOR: i64 = 3;
+
+/// Funds reserved on an account until `expires_at`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hold {
+    pub id: u64,
+    pub amount: i64,
+    pub expires_at: Timestamp,
+    pub reason: String,
+}
+
+/// A deposited cheque that is credited once it clears, or a deposit through
+/// another channel whose policy delays it the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingCheque {
+    pub id: u64,
+    pub amount: i64,
+    pub clears_at: Timestamp,
+    pub channel: DepositChannel,
+}
+
+/// Identifies an account either by its numeric id or its external reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountRef {
+    Id(u32),
+    Reference(Uuid),
+}
+
+impl From<u32> for AccountRef {
+    fn from(id: u32) -> Self {
+        AccountRef::Id(id)
+    }
+}
+
+impl From<Uuid> for AccountRef {
+    fn from(reference: Uuid) -> Self {
+        AccountRef::Reference(reference)
+    }
+}
+
+impl From<&Uuid> for AccountRef {
+    fn from(reference: &Uuid) -> Self {
+        AccountRef::Reference(*reference)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub(crate) id: u32,
+    pub(crate) reference: Uuid,
+    pub(crate) balance: i64,
+    pub(crate) holder: String,
+    pub(crate) minimum_balance: Option<i64>,
+    pub(crate) withdrawal_count: u32,
+    pub(crate) withdrawal_total: i64,
+    pub(crate) history: Vec<Transaction>,
+    pub(crate) partitions: DayPartitions,
+    pub(crate) interest_rate_bps: u32,
+    pub(crate) interest_tiers: Vec<InterestTier>,
+    pub(crate) accrued_interest: i128,
+    /// The part of the balance above `i64::MAX`, kept when the bank's
+    /// overflow policy promotes.
+    pub(crate) promoted: i128,
+    pub(crate) monthly_fee: i64,
+    pub(crate) fee_shortfall: FeeShortfall,
+    pub(crate) fee_debt: i64,
+    pub(crate) holds: Vec<Hold>,
+    pub(crate) pending_cheques: Vec<PendingCheque>,
+    pub(crate) overdraft_limit: i64,
+    pub(crate) overdraft_opt_in: bool,
+    pub(crate) overdraft_interest: Option<OverdraftInterest>,
+    pub(crate) overdraft_accrual: OverdraftAccrual,
+    pub(crate) display_precision: u32,
+    pub(crate) rounding: RoundingPolicy,
+    pub(crate) currency: Currency,
+    pub(crate) goals: Vec<SavingsGoal>,
+    pub(crate) units: Option<UnitHolding>,
+    pub(crate) disputes: Vec<Dispute>,
+    pub(crate) opened_at: Option<Timestamp>,
+    pub(crate) dormant_since: Option<Timestamp>,
+    /// When dormancy was last lifted, which restarts the inactivity period.
+    pub(crate) reactivated_at: Option<Timestamp>,
+    pub(crate) promotion_awards: Vec<PromotionAward>,
+    pub(crate) co_signer: Option<CoSigner>,
+    pub(crate) pending_withdrawals: Vec<PendingWithdrawal>,
+    pub(crate) guardianship: Option<Guardianship>,
+    pub(crate) ownership_approval_required: bool,
+    pub(crate) pending_ownership_transfer: Option<OwnershipTransfer>,
+    pub(crate) meta: BTreeMap<String, String>,
+}
+
+impl Account {
+    pub fn new(id: u32, holder: String) -> Self {
+        Account {
+            id,
+            reference: Uuid::new_v4(),
+            holder,
+            balance: 0,
+            minimum_balance: None,
+            withdrawal_count: 0,
+            withdrawal_total: 0,
+            history: Vec::new(),
+            partitions: DayPartitions::default(),
+            interest_rate_bps: 0,
+            interest_tiers: Vec::new(),
+            accrued_interest: 0,
+            promoted: 0,
+            monthly_fee: 0,
+            fee_shortfall: FeeShortfall::default(),
+            fee_debt: 0,
+            holds: Vec::new(),
+            pending_cheques: Vec::new(),
+            overdraft_limit: 0,
+            overdraft_opt_in: false,
+            overdraft_interest: None,
+            overdraft_accrual: OverdraftAccrual::default(),
+            display_precision: 2,
+            rounding: RoundingPolicy::default(),
+            currency: Currency::default(),
+            goals: Vec::new(),
+            units: None,
+            disputes: Vec::new(),
+            opened_at: None,
+            dormant_since: None,
+            reactivated_at: None,
+            promotion_awards: Vec::new(),
+            co_signer: None,
+            pending_withdrawals: Vec::new(),
+            guardianship: None,
+            ownership_approval_required: false,
+            pending_ownership_transfer: None,
+            meta: BTreeMap::new(),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Unique external reference generated when the account is created.
+    pub fn reference(&self) -> Uuid {
+        self.reference
+    }
+
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    /// Also sets the display precision to the currency's minor units;
+    /// the balance is taken to be in those units already.
+    pub fn set_currency(&mut self, currency: Currency) {
+        self.currency = currency;
+        self.display_precision = currency.minor_units();
+    }
+
+    pub fn balance(&self) -> i64 {
+        self.balance
+    }
+
+    /// Balance minus any funds reserved by holds.
+    pub fn available_balance(&self) -> i64 {
+        ledger::available(self.balance, self.holds.iter().map(|hold| hold.amount))
+    }
+
+    /// What a debit may draw on: the available balance plus the overdraft
+    /// facility when the holder has opted into overdraft coverage.
+    pub fn spendable_balance(&self) -> i64 {
+        ledger::spendable(
+            self.available_balance(),
+            self.overdraft_limit,
+            self.overdraft_opt_in,
+        )
+    }
+
+    pub fn overdraft_limit(&self) -> i64 {
+        self.overdraft_limit
+    }
+
+    pub fn set_overdraft_limit(&mut self, limit: i64) {
+        self.overdraft_limit = limit.max(0);
+    }
+
+    pub fn overdraft_opt_in(&self) -> bool {
+        self.overdraft_opt_in
+    }
+
+    pub fn set_overdraft_opt_in(&mut self, opt_in: bool) {
+        self.overdraft_opt_in = opt_in;
+    }
+
+    pub(crate) fn insufficient_funds(&self, requested: i64) -> AccountError {
+        let unused_overdraft = if self.overdraft_opt_in {
+            0
+        } else {
+            self.overdraft_limit
+        };
+        AccountError::Policy(PolicyError::InsufficientFunds {
+            requested,
+            available: self.spendable_balance(),
+            unused_overdraft,
+            currency: self.currency,
+        })
+    }
+
+    pub fn held_amount(&self) -> i64 {
+        self.holds.iter().map(|hold| hold.amount).sum()
+    }
+
+    pub fn holds(&self) -> &[Hold] {
+        &self.holds
+    }
+
+    pub fn pending_cheques(&self) -> &[PendingCheque] {
+        &self.pending_cheques
+    }
+
+    pub fn history(&self) -> &[Transaction] {
+        &self.history
+    }
+
+    /// Attaches a free-form value for integrations, e.g. a branch code or
+    /// an external customer number. Replaces any earlier value for `key`.
+    pub fn set_meta(&mut self, key: &str, value: &str) {
+        self.meta.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.meta.get(key).map(String::as_str)
+    }
+
+    pub fn remove_meta(&mut self, key: &str) -> Option<String> {
+        self.meta.remove(key)
+    }
+
+    /// Every metadata entry, ordered by key.
+    pub fn all_meta(&self) -> &BTreeMap<String, String> {
+        &self.meta
+    }
+
+    /// When the account was added to a bank, if it was opened by one.
+    pub fn opened_at(&self) -> Option<Timestamp> {
+        self.opened_at
+    }
+
+    /// The fund units behind the balance, for unit-priced accounts.
+    pub fn unit_holding(&self) -> Option<&UnitHolding> {
+        self.units.as_ref()
+    }
+
+    /// Annual interest rate in basis points (100 = 1%).
+    pub fn interest_rate_bps(&self) -> u32 {
+        self.interest_rate_bps
+    }
+
+    pub fn set_interest_rate_bps(&mut self, rate: u32) {
+        self.interest_rate_bps = rate;
+    }
+
+    /// Balance tiers that replace the flat interest rate when non-empty.
+    pub fn interest_tiers(&self) -> &[InterestTier] {
+        &self.interest_tiers
+    }
+
+    pub fn set_interest_tiers(&mut self, mut tiers: Vec<InterestTier>) {
+        tiers.sort_by_key(|tier| tier.up_to.unwrap_or(i64::MAX));
+        self.interest_tiers = tiers;
+    }
+
+    pub fn monthly_fee(&self) -> i64 {
+        self.monthly_fee
+    }
+
+    pub fn set_monthly_fee(&mut self, fee: i64) {
+        self.monthly_fee = fee;
+    }
+
+    /// Decimal places shown when the balance is displayed.
+    pub fn display_precision(&self) -> u32 {
+        self.display_precision
+    }
+
+    /// Capped at [`RoundingPolicy::MAX_PRECISION`].
+    pub fn set_display_precision(&mut self, precision: u32) {
+        self.display_precision = precision.min(RoundingPolicy::MAX_PRECISION);
+    }
+
+    pub fn rounding(&self) -> RoundingPolicy {
+        self.rounding
+    }
+
+    pub fn set_rounding(&mut self, rounding: RoundingPolicy) {
+        self.rounding = rounding;
+    }
+
+    /// Formats `amount`, in the minor units of the account's currency,
+    /// using this account's display precision and rounding.
+    pub fn format_amount(&self, amount: i64) -> String {
+        Locale::English.money_units(amount, self.currency, self.display_precision, self.rounding)
+    }
+
+    pub fn minimum_balance(&self) -> Option<i64> {
+        self.minimum_balance
+    }
+
+    pub fn set_minimum_balance(&mut self, minimum: Option<i64>) {
+        self.minimum_balance = minimum;
+    }
+
+    /// Average withdrawal size, once enough withdrawals have been seen.
+    pub fn typical_spend(&self) -> Option<i64> {
+        if self.withdrawal_count < TYPICAL_SPEND_MIN_SAMPLES {
+            return None;
+        }
+        Some(self.withdrawal_total / i64::from(self.withdrawal_count))
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} [ref {}]", self, self.reference)
+    }
+
+    /// Appends an entry for a balance change that has already been applied,
+    /// chained to the previous entry.
+    pub(crate) fn book(
+        &mut self,
+        id: u64,
+        kind: TransactionKind,
+        amount: i64,
+        counterparty: Option<u32>,
+        memo: &str,
+        timestamp: Timestamp,
+    ) {
+        self.absorb_promoted();
+        let previous = self
+            .history
+            .last()
+            .map_or(ChainHash::GENESIS, |txn| txn.chain_hash);
+        let mut metadata = BTreeMap::new();
+        if self.promoted != 0 {
+            metadata.insert(PROMOTED_KEY.to_string(), self.promoted.to_string());
+        }
+        let mut txn = Transaction {
+            id,
+            account_id: self.id,
+            kind,
+            amount,
+            balance_after: self.balance,
+            counterparty,
+            timestamp,
+            memo: memo.to_string(),
+            category: None,
+            tags: BTreeSet::new(),
+            rounding: self.rounding,
+            metadata,
+            chain_hash: ChainHash::GENESIS,
+        };
+        txn.chain_hash = entry_hash(&previous, &txn);
+        self.partitions.insert(self.history.len(), timestamp);
+        self.history.push(txn);
+        self.settle_fee_debt(amount);
+    }
+
+    pub fn deposit(&mut self, amount: i64) -> Result<OperationOutcome<i64>, AccountError> {
+        self.balance = ledger::credit(self.balance, amount).map_err(|error| match error {
+            LedgerError::NegativeAmount => AccountError::Validation(InputError::NegativeAmount),
+            _ => AccountError::Validation(InputError::AmountOverflow),
+        })?;
+        Ok(OperationOutcome::with_warnings(
+            self.balance,
+            self.balance_warnings(),
+        ))
+    }
+
+    pub fn withdraw(&mut self, amount: i64) -> Result<OperationOutcome<i64>, AccountError> {
+        let balance =
+            ledger::debit(self.balance, self.spendable_balance(), amount).map_err(|error| {
+                match error {
+                    LedgerError::NegativeAmount => {
+                        AccountError::Validation(InputError::NegativeAmount)
+                    }
+                    LedgerError::InsufficientFunds { .. } => self.insufficient_funds(amount),
+                    LedgerError::Overflow => AccountError::Validation(InputError::AmountOverflow),
+                }
+            })?;
+        let mut warnings = Vec::new();
+        if let Some(typical) = self.typical_spend() {
+            if amount > typical.saturating_mul(UNUSUAL_SPEND_FACTOR) {
+                warnings.push(Warning::UnusualSpend {
+                    amount,
+                    typical,
+                    currency: self.currency,
+                });
+            }
+        }
+        self.balance = balance;
+        self.withdrawal_count = self.withdrawal_count.saturating_add(1);
+        self.withdrawal_total = self.withdrawal_total.saturating_add(amount);
+        warnings.extend(self.balance_warnings());
+        Ok(OperationOutcome::with_warnings(self.balance, warnings))
+    }
+
+    pub(crate) fn balance_warnings(&self) -> Vec<Warning> {
+        match self.minimum_balance {
+            Some(minimum) if self.balance < minimum => vec![Warning::BelowMinimumBalance {
+                balance: self.balance,
+                minimum,
+                currency: self.currency,
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
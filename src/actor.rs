@@ -0,0 +1,576 @@
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::eod::EndOfDayReport;
+use crate::error::{AccountError, StateError, StorageError};
+use crate::metrics::Metrics;
+use crate::outcome::OperationOutcome;
+use crate::receipt::Receipt;
+use crate::replica::{ReplicaEvent, ReplicaFeed};
+use crate::wal::{WalEntry, WriteAheadLog};
+
+/// How long a sender with a deadline waits before retrying a full queue.
+const SEND_RETRY: Duration = Duration::from_millis(1);
+
+type Job = Box<dyn FnOnce(&Bank) + Send>;
+/// Given the bank once the entry is logged, or why logging failed.
+type LoggedJob = Box<dyn FnOnce(Result<&mut Bank, ActorError>) + Send>;
+
+enum Command {
+    Run(Job),
+    Logged(WalEntry, LoggedJob),
+    CompactLog(u64, mpsc::Sender<Result<(), ActorError>>),
+    /// Snapshots the bank for a new replica and feeds it from then on.
+    Subscribe(ReplicaFeed, mpsc::Sender<io::Result<Vec<u8>>>),
+    RefreshReplica(ReplicaFeed, mpsc::Sender<()>),
+    Shutdown,
+}
+
+/// A command and, for calls with a deadline, the claim deciding whether it
+/// still runs.
+struct Queued {
+    command: Command,
+    claim: Option<Arc<Claim>>,
+}
+
+const WAITING: u8 = 0;
+const STARTED: u8 = 1;
+const ABANDONED: u8 = 2;
+
+/// Settles the race between a caller with a deadline and the bank task.
+/// The task starts the command only before the deadline and only if the
+/// caller hasn't given up, and the caller gives up only on a command not
+/// started, so a call that times out leaves nothing behind.
+struct Claim {
+    deadline: Instant,
+    state: AtomicU8,
+}
+
+impl Claim {
+    fn new(budget: Duration) -> Arc<Self> {
+        Arc::new(Claim {
+            deadline: Instant::now() + budget,
+            state: AtomicU8::new(WAITING),
+        })
+    }
+
+    fn start(&self) -> bool {
+        Instant::now() < self.deadline && self.settle(STARTED)
+    }
+
+    fn abandon(&self) -> bool {
+        self.settle(ABANDONED)
+    }
+
+    fn settle(&self, state: u8) -> bool {
+        self.state
+            .compare_exchange(WAITING, state, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActorError {
+    /// The bank task has shut down and no longer accepts commands.
+    Stopped,
+    /// The command queue is full; only returned by the `try_` methods.
+    Busy,
+    /// The bank task didn't start the operation before its deadline; it
+    /// was not applied. Only returned by the `_within` methods.
+    Timeout,
+    /// The write-ahead log could not be written; the operation was not
+    /// applied.
+    Storage(StorageError),
+    Account(AccountError),
+}
+
+impl fmt::Display for ActorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorError::Stopped => write!(f, "The bank has shut down"),
+            ActorError::Busy => write!(f, "The bank is busy, try again later"),
+            ActorError::Timeout => write!(f, "The bank did not get to the operation in time"),
+            ActorError::Storage(error) => write!(f, "Could not write the log: {}", error),
+            ActorError::Account(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ActorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ActorError::Storage(error) => Some(error),
+            ActorError::Account(error) => Some(error),
+            ActorError::Stopped | ActorError::Busy | ActorError::Timeout => None,
+        }
+    }
+}
+
+impl From<AccountError> for ActorError {
+    fn from(error: AccountError) -> Self {
+        ActorError::Account(error)
+    }
+}
+
+/// A bank owned by its own thread. Commands are queued on a bounded
+/// channel and applied one at a time, in the order they were sent.
+pub struct BankActor {
+    handle: BankHandle,
+    thread: JoinHandle<Bank>,
+}
+
+impl BankActor {
+    /// Moves `bank` onto a new thread. Senders block once `capacity`
+    /// commands are waiting.
+    pub fn spawn(bank: Bank, capacity: usize) -> Self {
+        BankActor::start(bank, capacity, None)
+    }
+
+    /// Like [`BankActor::spawn`], but every change made through the handles
+    /// is appended to `wal` before it is applied.
+    pub fn spawn_logged(bank: Bank, capacity: usize, wal: WriteAheadLog) -> Self {
+        BankActor::start(bank, capacity, Some(wal))
+    }
+
+    fn start(bank: Bank, capacity: usize, mut wal: Option<WriteAheadLog>) -> Self {
+        if let Some(wal) = &mut wal {
+            wal.continue_after(bank.wal_sequence());
+        }
+        let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+        let metrics = Arc::new(Metrics::default());
+        let thread = {
+            let metrics = Arc::clone(&metrics);
+            thread::spawn(move || run(bank, receiver, wal, &metrics))
+        };
+        BankActor {
+            handle: BankHandle { sender, metrics },
+            thread,
+        }
+    }
+
+    pub fn handle(&self) -> BankHandle {
+        self.handle.clone()
+    }
+
+    /// Finishes every command queued so far, stops the task and returns
+    /// the bank. Handles still held elsewhere get [`ActorError::Stopped`].
+    pub fn shutdown(self) -> Bank {
+        let _ = self.handle.send(Command::Shutdown);
+        self.thread.join().expect("bank task panicked")
+    }
+}
+
+impl fmt::Debug for BankActor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BankActor").finish_non_exhaustive()
+    }
+}
+
+fn run(
+    mut bank: Bank,
+    receiver: Receiver<Queued>,
+    mut wal: Option<WriteAheadLog>,
+    metrics: &Metrics,
+) -> Bank {
+    let mut replicas: Vec<ReplicaFeed> = Vec::new();
+    for Queued { command, claim } in receiver {
+        metrics.dequeued();
+        // Dropping the command closes its reply channel, which tells the
+        // caller it timed out.
+        if claim.is_some_and(|claim| !claim.start()) {
+            continue;
+        }
+        match command {
+            Command::Run(job) => job(&bank),
+            Command::Logged(entry, job) => {
+                let timestamp = bank.now();
+                if let Some(wal) = &mut wal {
+                    match wal.append(timestamp, &entry) {
+                        Ok(sequence) => bank.wal_sequence = sequence,
+                        Err(error) => {
+                            job(Err(ActorError::Storage(error.into())));
+                            continue;
+                        }
+                    }
+                }
+                job(Ok(&mut bank));
+                replicas
+                    .retain(|feed| feed.publish(ReplicaEvent::Applied(timestamp, entry.clone())));
+            }
+            Command::Subscribe(feed, reply) => {
+                let snapshot = snapshot_bytes(&bank);
+                if snapshot.is_ok() {
+                    replicas.push(feed);
+                }
+                let _ = reply.send(snapshot);
+            }
+            Command::RefreshReplica(feed, reply) => {
+                if let Ok(snapshot) = snapshot_bytes(&bank) {
+                    feed.publish(ReplicaEvent::Reset(snapshot));
+                }
+                let _ = reply.send(());
+            }
+            Command::CompactLog(through, reply) => {
+                let result = match &mut wal {
+                    Some(wal) if wal.is_audit_log() => wal
+                        .checkpoint(&bank)
+                        .map(|_| ())
+                        .map_err(|error| ActorError::Storage(error.into())),
+                    Some(wal) => wal
+                        .compact(through)
+                        .map_err(|error| ActorError::Storage(error.into())),
+                    None => Ok(()),
+                };
+                let _ = reply.send(result);
+            }
+            Command::Shutdown => break,
+        }
+    }
+    bank
+}
+
+fn snapshot_bytes(bank: &Bank) -> io::Result<Vec<u8>> {
+    let mut snapshot = Vec::new();
+    bank.save_snapshot(&mut snapshot)?;
+    Ok(snapshot)
+}
+
+/// A cloneable, thread-safe way to talk to a [`BankActor`].
+#[derive(Clone)]
+pub struct BankHandle {
+    sender: SyncSender<Queued>,
+    metrics: Arc<Metrics>,
+}
+
+impl BankHandle {
+    /// Runs `f` on the bank task and waits for its result, blocking while
+    /// the queue is full. `f` only reads the bank; changes go through the
+    /// methods that log them first.
+    pub fn call<T, F>(&self, f: F) -> Result<T, ActorError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Bank) -> T + Send + 'static,
+    {
+        let (command, reply) = job(f);
+        self.send(command)?;
+        reply.recv().map_err(|_| ActorError::Stopped)
+    }
+
+    /// Like [`BankHandle::call`], but fails with [`ActorError::Busy`]
+    /// instead of waiting for space in the queue.
+    pub fn try_call<T, F>(&self, f: F) -> Result<T, ActorError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Bank) -> T + Send + 'static,
+    {
+        let (command, reply) = job(f);
+        self.metrics.enqueued();
+        let queued = Queued {
+            command,
+            claim: None,
+        };
+        self.sender.try_send(queued).map_err(|error| {
+            self.metrics.dequeued();
+            match error {
+                TrySendError::Full(_) => ActorError::Busy,
+                TrySendError::Disconnected(_) => ActorError::Stopped,
+            }
+        })?;
+        reply.recv().map_err(|_| ActorError::Stopped)
+    }
+
+    /// Like [`BankHandle::call`], but fails with [`ActorError::Timeout`]
+    /// unless the bank task starts `f` within `budget`, counting any wait
+    /// for space in the queue. A call that times out never runs; one that
+    /// starts in time is waited for, however long it takes.
+    pub fn call_within<T, F>(&self, budget: Duration, f: F) -> Result<T, ActorError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Bank) -> T + Send + 'static,
+    {
+        let claim = Claim::new(budget);
+        let (command, reply) = job(f);
+        self.send_claimed(command, &claim)?;
+        reply_within(&claim, &reply)
+    }
+
+    /// Counts and times the deposits, withdrawals and transfers sent
+    /// through every handle of this actor, and tracks the queue depth.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    pub fn open_account(&self, id: u32, holder: &str) -> Result<(), ActorError> {
+        let account = Account::new(id, holder.to_string());
+        let entry = WalEntry::OpenAccount {
+            id,
+            holder: account.holder.clone(),
+            reference: account.reference,
+        };
+        self.measured("open", entry, None, move |bank| {
+            bank.add_account(account);
+            Ok(())
+        })
+    }
+
+    pub fn deposit(&self, account: u32, amount: i64) -> Result<Receipt, ActorError> {
+        self.deposit_maybe_within(account, amount, None)
+    }
+
+    /// Like [`BankHandle::deposit`], but fails with [`ActorError::Timeout`],
+    /// depositing nothing, unless the bank task gets to it within `budget`.
+    pub fn deposit_within(
+        &self,
+        account: u32,
+        amount: i64,
+        budget: Duration,
+    ) -> Result<Receipt, ActorError> {
+        self.deposit_maybe_within(account, amount, Some(budget))
+    }
+
+    pub fn withdraw(&self, account: u32, amount: i64) -> Result<Receipt, ActorError> {
+        self.withdraw_maybe_within(account, amount, None)
+    }
+
+    /// Like [`BankHandle::withdraw`], with a deadline as for
+    /// [`BankHandle::deposit_within`].
+    pub fn withdraw_within(
+        &self,
+        account: u32,
+        amount: i64,
+        budget: Duration,
+    ) -> Result<Receipt, ActorError> {
+        self.withdraw_maybe_within(account, amount, Some(budget))
+    }
+
+    pub fn transfer(
+        &self,
+        from: u32,
+        to: u32,
+        amount: i64,
+    ) -> Result<OperationOutcome<()>, ActorError> {
+        self.transfer_maybe_within(from, to, amount, None)
+    }
+
+    /// Like [`BankHandle::transfer`], with a deadline as for
+    /// [`BankHandle::deposit_within`].
+    pub fn transfer_within(
+        &self,
+        from: u32,
+        to: u32,
+        amount: i64,
+        budget: Duration,
+    ) -> Result<OperationOutcome<()>, ActorError> {
+        self.transfer_maybe_within(from, to, amount, Some(budget))
+    }
+
+    fn deposit_maybe_within(
+        &self,
+        account: u32,
+        amount: i64,
+        budget: Option<Duration>,
+    ) -> Result<Receipt, ActorError> {
+        let entry = WalEntry::Deposit { account, amount };
+        self.measured("deposit", entry, budget, move |bank| {
+            bank.deposit(account, amount)
+        })
+    }
+
+    fn withdraw_maybe_within(
+        &self,
+        account: u32,
+        amount: i64,
+        budget: Option<Duration>,
+    ) -> Result<Receipt, ActorError> {
+        let entry = WalEntry::Withdraw { account, amount };
+        self.measured("withdraw", entry, budget, move |bank| {
+            bank.withdraw(account, amount)
+        })
+    }
+
+    fn transfer_maybe_within(
+        &self,
+        from: u32,
+        to: u32,
+        amount: i64,
+        budget: Option<Duration>,
+    ) -> Result<OperationOutcome<()>, ActorError> {
+        let entry = WalEntry::Transfer { from, to, amount };
+        self.measured("transfer", entry, budget, move |bank| {
+            bank.transfer(from, to, amount)
+        })
+    }
+
+    /// Moves the bank's clock on by `duration`, closing each business day
+    /// passed, as [`Bank::advance_time`] does.
+    pub fn advance_time(&self, duration: Duration) -> Result<Vec<EndOfDayReport>, ActorError> {
+        let seconds = duration.as_secs();
+        let entry = WalEntry::AdvanceTime { seconds };
+        self.measured("advance_time", entry, None, move |bank| {
+            Ok(bank.advance_time(Duration::from_secs(seconds)))
+        })
+    }
+
+    pub fn end_of_day(&self) -> Result<EndOfDayReport, ActorError> {
+        self.measured("end_of_day", WalEntry::EndOfDay, None, |bank| {
+            Ok(bank.end_of_day())
+        })
+    }
+
+    /// Drops the log entries up to `through` once a snapshot holding them
+    /// is on disk, or writes a checkpoint to an audit log instead. Does
+    /// nothing for an actor without a log.
+    pub(crate) fn compact_log(&self, through: u64) -> Result<(), ActorError> {
+        let (reply, receiver) = mpsc::channel();
+        self.send(Command::CompactLog(through, reply))?;
+        receiver.recv().map_err(|_| ActorError::Stopped)?
+    }
+
+    pub(crate) fn subscribe(&self, feed: ReplicaFeed) -> Result<Vec<u8>, ActorError> {
+        let (reply, receiver) = mpsc::channel();
+        self.send(Command::Subscribe(feed, reply))?;
+        let snapshot = receiver.recv().map_err(|_| ActorError::Stopped)?;
+        snapshot.map_err(|error| ActorError::Storage(error.into()))
+    }
+
+    pub(crate) fn refresh_replica(&self, feed: ReplicaFeed) -> Result<(), ActorError> {
+        let (reply, receiver) = mpsc::channel();
+        self.send(Command::RefreshReplica(feed, reply))?;
+        receiver.recv().map_err(|_| ActorError::Stopped)
+    }
+
+    fn send(&self, command: Command) -> Result<(), ActorError> {
+        self.metrics.enqueued();
+        let queued = Queued {
+            command,
+            claim: None,
+        };
+        self.sender.send(queued).map_err(|_| {
+            self.metrics.dequeued();
+            ActorError::Stopped
+        })
+    }
+
+    /// Queues `command` to start before `claim`'s deadline, waiting until
+    /// then for space in the queue.
+    fn send_claimed(&self, command: Command, claim: &Arc<Claim>) -> Result<(), ActorError> {
+        self.metrics.enqueued();
+        let mut queued = Queued {
+            command,
+            claim: Some(Arc::clone(claim)),
+        };
+        loop {
+            let now = Instant::now();
+            let error = match self.sender.try_send(queued) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Full(_)) if now >= claim.deadline => ActorError::Timeout,
+                Err(TrySendError::Full(returned)) => {
+                    queued = returned;
+                    thread::sleep((claim.deadline - now).min(SEND_RETRY));
+                    continue;
+                }
+                Err(TrySendError::Disconnected(_)) => ActorError::Stopped,
+            };
+            self.metrics.dequeued();
+            return Err(error);
+        }
+    }
+
+    /// Logs `entry`, then applies `f`, counting and timing the operation.
+    /// With a `budget`, the operation times out unless started within it.
+    fn measured<T, F>(
+        &self,
+        operation: &'static str,
+        entry: WalEntry,
+        budget: Option<Duration>,
+        f: F,
+    ) -> Result<T, ActorError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Bank) -> Result<T, AccountError> + Send + 'static,
+    {
+        let started = Instant::now();
+        let (sender, receiver) = mpsc::channel();
+        let command = Command::Logged(
+            entry,
+            Box::new(move |bank| {
+                let _ = sender.send(bank.map(f));
+            }),
+        );
+        let reply = match budget.map(Claim::new) {
+            Some(claim) => self
+                .send_claimed(command, &claim)
+                .and_then(|_| reply_within(&claim, &receiver)),
+            None => self
+                .send(command)
+                .and_then(|_| receiver.recv().map_err(|_| ActorError::Stopped)),
+        };
+        let result = reply.and_then(|result| Ok(result??));
+        let outcome = match &result {
+            Ok(_) => "ok",
+            Err(ActorError::Account(_)) => "refused",
+            Err(ActorError::Timeout) => "timeout",
+            Err(_) => "unavailable",
+        };
+        self.metrics.record(operation, outcome, started.elapsed());
+        result
+    }
+
+    pub fn balance(&self, account: u32) -> Result<i64, ActorError> {
+        self.call(move |bank| bank.get_account(account).map(|account| account.balance()))?
+            .ok_or(ActorError::Account(AccountError::State(
+                StateError::AccountNotFound,
+            )))
+    }
+
+    pub fn total_balance(&self) -> Result<i64, ActorError> {
+        self.call(|bank| bank.total_balance())
+    }
+
+    pub fn summary(&self) -> Result<String, ActorError> {
+        self.call(|bank| bank.summary())
+    }
+}
+
+impl fmt::Debug for BankHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BankHandle").finish_non_exhaustive()
+    }
+}
+
+/// Waits for the reply to a command sent with `claim`, giving up on it at
+/// the deadline unless the bank task has started it by then.
+fn reply_within<T>(claim: &Claim, receiver: &Receiver<T>) -> Result<T, ActorError> {
+    let remaining = claim.deadline.saturating_duration_since(Instant::now());
+    match receiver.recv_timeout(remaining) {
+        Ok(reply) => Ok(reply),
+        Err(error) if claim.abandon() => Err(match error {
+            // Closed before the deadline: the task stopped without running it.
+            RecvTimeoutError::Disconnected if Instant::now() < claim.deadline => {
+                ActorError::Stopped
+            }
+            _ => ActorError::Timeout,
+        }),
+        // Started in time, so the reply is on its way.
+        Err(_) => receiver.recv().map_err(|_| ActorError::Stopped),
+    }
+}
+
+fn job<T, F>(f: F) -> (Command, Receiver<T>)
+where
+    T: Send + 'static,
+    F: FnOnce(&Bank) -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let command = Command::Run(Box::new(move |bank| {
+        let _ = sender.send(f(bank));
+    }));
+    (command, receiver)
+}
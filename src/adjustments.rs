@@ -0,0 +1,36 @@
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError};
+use crate::transaction::TransactionKind;
+
+impl Bank {
+    /// Books a correction of `amount` (either sign) straight to the balance,
+    /// skipping the funds, overdraft and minimum-balance checks that apply to
+    /// customer operations. `reason` becomes the entry's memo, and reason and
+    /// actor are both kept in its metadata. Returns the entry's id.
+    pub fn adjust(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        reason: &str,
+        actor: &str,
+    ) -> Result<u64, AccountError> {
+        if reason.trim().is_empty() || actor.trim().is_empty() {
+            return Err(AccountError::Validation(InputError::MissingAuditDetails));
+        }
+        let id = self.resolve(account)?;
+        let account = self.accounts.get_mut(&id).unwrap();
+        account.balance = account
+            .balance
+            .checked_add(amount)
+            .ok_or(AccountError::Validation(InputError::AmountOverflow))?;
+
+        let txn_id = self.record(id, TransactionKind::Adjustment, amount, None, reason);
+        self.amend_transaction(id, txn_id, |txn| {
+            txn.metadata
+                .insert("reason".to_string(), reason.to_string());
+            txn.metadata.insert("actor".to_string(), actor.to_string());
+        });
+        Ok(txn_id)
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::chain::reseal;
+use crate::error::{AccountError, StateError};
+use crate::rewards::RewardsAccount;
+use crate::snapshot::PersistError;
+
+const HEADER: &str = "account-archive";
+
+/// One account packed up to move between banks or to share as a fixture:
+/// its settings, history, holds, goals, disputes, metadata and rewards.
+///
+/// Written as a version line followed by a one-account snapshot, so
+/// everything the snapshot format keeps for an account is kept here too.
+#[derive(Debug, Clone)]
+pub struct AccountArchive {
+    pub version: u32,
+    pub(crate) account: Account,
+    pub(crate) rewards: Option<RewardsAccount>,
+    /// The exporting bank's item counter, so imported hold and goal ids
+    /// stay unique.
+    pub(crate) next_item_id: u64,
+}
+
+impl AccountArchive {
+    /// The archive format version written by this build.
+    pub const VERSION: u32 = 1;
+
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}\t{}", HEADER, self.version)?;
+        let mut bank = Bank::starting_at(self.account.opened_at.unwrap_or_default());
+        bank.next_item_id = self.next_item_id;
+        bank.accounts.insert(self.account.id, self.account.clone());
+        if let Some(rewards) = &self.rewards {
+            bank.rewards.insert(self.account.id, rewards.clone());
+        }
+        bank.save_snapshot(writer)
+    }
+
+    /// Reads an archive written by [`AccountArchive::write`]. Archives from
+    /// a newer version are refused.
+    pub fn read<R: BufRead>(mut reader: R) -> Result<AccountArchive, PersistError> {
+        let corrupt = |message: &str| PersistError::Corrupt {
+            line: 1,
+            message: message.to_string(),
+        };
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let version: u32 = header
+            .trim_end()
+            .strip_prefix(HEADER)
+            .and_then(|rest| rest.strip_prefix('\t'))
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(|| corrupt("missing account archive header"))?;
+        if version > AccountArchive::VERSION {
+            return Err(corrupt("account archive is from a newer version"));
+        }
+        let mut bank = Bank::load_snapshot(reader)?;
+        let ids: Vec<u32> = bank.accounts.keys().copied().collect();
+        let [id] = ids[..] else {
+            return Err(corrupt("an account archive holds exactly one account"));
+        };
+        Ok(AccountArchive {
+            version,
+            account: bank.accounts.remove(&id).unwrap(),
+            rewards: bank.rewards.remove(&id),
+            next_item_id: bank.next_item_id,
+        })
+    }
+}
+
+impl Bank {
+    /// Packs up a copy of an account; the account stays in this bank.
+    pub fn export_account(&self, account_id: u32) -> Result<AccountArchive, AccountError> {
+        let account = self
+            .accounts
+            .get(&account_id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        Ok(AccountArchive {
+            version: AccountArchive::VERSION,
+            account: account.clone(),
+            rewards: self.rewards.get(&account_id).cloned(),
+            next_item_id: self.next_item_id,
+        })
+    }
+
+    /// Adds an archived account under its original id. Its transactions
+    /// are renumbered to follow this bank's, keeping their order, and the
+    /// hash chain is re-sealed. Refused if the id or reference is taken.
+    pub fn import_account(&mut self, archive: AccountArchive) -> Result<u32, AccountError> {
+        let AccountArchive {
+            mut account,
+            rewards,
+            next_item_id,
+            ..
+        } = archive;
+        let id = account.id;
+        if self.accounts.contains_key(&id) || self.references.contains_key(&account.reference) {
+            return Err(AccountError::State(StateError::AccountExists));
+        }
+        let renumbered: HashMap<u64, u64> = account
+            .history
+            .iter()
+            .map(|txn| (txn.id, self.allocate_transaction_id()))
+            .collect();
+        let new_id = |old: u64| renumbered.get(&old).copied().unwrap_or(old);
+        for txn in &mut account.history {
+            txn.id = new_id(txn.id);
+            if let Some(disputed) = txn.metadata.get_mut("disputed_transaction") {
+                if let Ok(old) = disputed.parse() {
+                    *disputed = new_id(old).to_string();
+                }
+            }
+        }
+        reseal(&mut account.history, 0);
+        for dispute in &mut account.disputes {
+            dispute.transaction_id = new_id(dispute.transaction_id);
+        }
+        for award in &mut account.promotion_awards {
+            award.deposit_transaction = new_id(award.deposit_transaction);
+            award.transaction_id = new_id(award.transaction_id);
+        }
+        if let Some(mut rewards) = rewards {
+            for entry in &mut rewards.entries {
+                entry.transaction_id = new_id(entry.transaction_id);
+            }
+            self.rewards.insert(id, rewards);
+        }
+        self.next_item_id = self.next_item_id.max(next_item_id);
+        self.references.insert(account.reference, id);
+        self.accounts.insert(id, account);
+        Ok(id)
+    }
+}
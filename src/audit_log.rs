@@ -0,0 +1,164 @@
+//! Rebuilding a bank from a complete write-ahead log when its snapshot is
+//! lost, checked against the balance checkpoints written along the way.
+
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use crate::auth::to_hex;
+use crate::bank::Bank;
+use crate::crypto::sha256;
+use crate::snapshot::PersistError;
+use crate::wal::{read_entries, WalEntry};
+
+#[derive(Debug)]
+pub enum RebuildError {
+    Persist(PersistError),
+    /// The log doesn't start at sequence 1: earlier entries were compacted
+    /// away, so a snapshot is needed to start from.
+    Compacted {
+        first_sequence: u64,
+    },
+    /// The replayed balances disagree with a checkpoint, so the log is
+    /// incomplete or something changed balances without logging it.
+    ChecksumMismatch {
+        sequence: u64,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for RebuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebuildError::Persist(error) => write!(f, "{}", error),
+            RebuildError::Compacted { first_sequence } => write!(
+                f,
+                "The log starts at entry {}; earlier entries were compacted",
+                first_sequence
+            ),
+            RebuildError::ChecksumMismatch {
+                sequence,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Balances disagree with checkpoint {}: expected {}, replay gave {}",
+                sequence, expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for RebuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RebuildError::Persist(error) => Some(error),
+            RebuildError::Compacted { .. } | RebuildError::ChecksumMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<PersistError> for RebuildError {
+    fn from(error: PersistError) -> Self {
+        RebuildError::Persist(error)
+    }
+}
+
+/// What [`Bank::rebuild_from_audit_log`] replayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RebuildReport {
+    pub replayed: usize,
+    /// Operations refused on replay, as they were when first applied.
+    pub refused: usize,
+    pub checkpoints_verified: usize,
+    /// The last checkpoint the balances matched.
+    pub verified_through: Option<u64>,
+    /// Entries after the last checkpoint, which nothing could check.
+    pub unverified: usize,
+}
+
+impl RebuildReport {
+    /// Whether the final balances were checked against a checkpoint.
+    pub fn fully_verified(&self) -> bool {
+        self.checkpoints_verified > 0 && self.unverified == 0
+    }
+}
+
+impl fmt::Display for RebuildReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Replayed {} entries ({} refused); {} checkpoints verified",
+            self.replayed, self.refused, self.checkpoints_verified
+        )?;
+        if self.unverified > 0 {
+            write!(f, ", {} entries after the last one", self.unverified)?;
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    /// A SHA-256 digest of every account's id and balance, in id order.
+    pub fn balance_checksum(&self) -> String {
+        let mut balances = String::new();
+        for id in self.sorted_account_ids() {
+            balances.push_str(&format!("{}\t{}\n", id, self.accounts[&id].balance));
+        }
+        to_hex(&sha256(balances.as_bytes()))
+    }
+
+    /// Reconstructs a bank from nothing but an audit log, as kept with
+    /// [`WriteAheadLog::as_audit_log`](crate::WriteAheadLog::as_audit_log)
+    /// or [`DaemonConfig::audit_log`](crate::DaemonConfig::audit_log). Every
+    /// entry is replayed as of its logged time, and the balances are
+    /// checked at each checkpoint.
+    ///
+    /// Only logged operations are replayed. If anything else moved money,
+    /// such as an end of day, the first checkpoint after it won't match.
+    pub fn rebuild_from_audit_log(
+        path: impl AsRef<Path>,
+    ) -> Result<(Bank, RebuildReport), RebuildError> {
+        let entries = read_entries(path.as_ref())?;
+        let mut report = RebuildReport::default();
+        let Some(first) = entries.first() else {
+            return Ok((Bank::new(), report));
+        };
+        if first.sequence != 1 {
+            return Err(RebuildError::Compacted {
+                first_sequence: first.sequence,
+            });
+        }
+        let mut bank = Bank::starting_at(first.timestamp);
+        for logged in &entries {
+            if let WalEntry::Checkpoint {
+                accounts,
+                total,
+                checksum,
+            } = &logged.entry
+            {
+                let actual = bank.balance_checksum();
+                if bank.len() != *accounts || bank.total_balance() != *total || actual != *checksum
+                {
+                    return Err(RebuildError::ChecksumMismatch {
+                        sequence: logged.sequence,
+                        expected: checksum.clone(),
+                        actual,
+                    });
+                }
+                bank.wal_sequence = logged.sequence;
+                report.checkpoints_verified += 1;
+                report.verified_through = Some(logged.sequence);
+                report.unverified = 0;
+                continue;
+            }
+            if bank.apply_logged(logged).is_err() {
+                report.refused += 1;
+            }
+            report.replayed += 1;
+            report.unverified += 1;
+        }
+        Ok((bank, report))
+    }
+}
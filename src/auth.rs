@@ -0,0 +1,268 @@
+//! Holder logins: credentials checked against a salted hash, and expiring
+//! session tokens that open a customer [`Session`].
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::access::{Role, Session};
+use crate::bank::Bank;
+use crate::crypto::{pbkdf2_sha256, sha256};
+use crate::error::{AccountError, StateError};
+use crate::time::Timestamp;
+use crate::uuid::random_u64;
+
+/// Sessions last this many seconds from login or renewal unless the bank
+/// sets its own lifetime.
+pub(crate) const DEFAULT_SESSION_TTL: u64 = 15 * 60;
+const CREDENTIAL_ITERATIONS: u32 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// The holder has no credential or it doesn't match. The two are not
+    /// told apart, so holder names can't be probed.
+    InvalidCredentials,
+    /// The token was never issued, or has been revoked.
+    InvalidSession,
+    SessionExpired,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::InvalidCredentials => write!(f, "Wrong holder name or credential"),
+            AuthError::InvalidSession => write!(f, "Unknown or revoked session"),
+            AuthError::SessionExpired => write!(f, "Session expired; log in again"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Proof of a login, handed to the holder and presented with every
+/// customer operation. Its `Debug` output hides the token itself.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SessionToken(String);
+
+impl SessionToken {
+    /// Wraps a token received back from the holder, e.g. from a header.
+    pub fn new(token: impl Into<String>) -> Self {
+        SessionToken(token.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn generate() -> Self {
+        let words: Vec<String> = (0..4).map(|_| format!("{:016x}", random_u64())).collect();
+        SessionToken(words.concat())
+    }
+
+    /// Sessions are looked up by the token's hash, so the bank never keeps
+    /// usable tokens in memory dumps or debug output.
+    fn digest(&self) -> [u8; 32] {
+        sha256(self.0.as_bytes())
+    }
+}
+
+impl fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SessionToken(..)")
+    }
+}
+
+impl fmt::Display for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Credential {
+    pub salt: [u8; 16],
+    pub hash: [u8; 32],
+}
+
+impl Credential {
+    fn new(secret: &str) -> Self {
+        let mut salt = [0; 16];
+        salt[..8].copy_from_slice(&random_u64().to_le_bytes());
+        salt[8..].copy_from_slice(&random_u64().to_le_bytes());
+        Credential {
+            salt,
+            hash: pbkdf2_sha256(secret.as_bytes(), &salt, CREDENTIAL_ITERATIONS),
+        }
+    }
+
+    /// Compares in constant time.
+    fn matches(&self, secret: &str) -> bool {
+        let hash = pbkdf2_sha256(secret.as_bytes(), &self.salt, CREDENTIAL_ITERATIONS);
+        hash.iter()
+            .zip(&self.hash)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn parse_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Like [`parse_hex`], for any number of bytes.
+pub(crate) fn parse_hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SessionEntry {
+    holder: String,
+    expires_at: Timestamp,
+}
+
+/// Credentials and live sessions. Sessions are not saved in snapshots, so
+/// a restarted bank logs everyone out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Auth {
+    pub credentials: HashMap<String, Credential>,
+    sessions: HashMap<[u8; 32], SessionEntry>,
+    pub session_ttl: u64,
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Auth {
+            credentials: HashMap::new(),
+            sessions: HashMap::new(),
+            session_ttl: DEFAULT_SESSION_TTL,
+        }
+    }
+}
+
+impl Bank {
+    /// Sets the secret `holder` logs in with, ending any sessions they
+    /// have open. The holder must hold at least one account.
+    pub fn set_credential(&mut self, holder: &str, secret: &str) -> Result<(), AccountError> {
+        if !self
+            .accounts
+            .values()
+            .any(|account| account.holder == holder)
+        {
+            return Err(AccountError::State(StateError::AccountNotFound));
+        }
+        self.auth
+            .credentials
+            .insert(holder.to_string(), Credential::new(secret));
+        self.revoke_sessions(holder);
+        Ok(())
+    }
+
+    /// Removes `holder`'s credential and ends their sessions.
+    pub fn remove_credential(&mut self, holder: &str) -> bool {
+        self.revoke_sessions(holder);
+        self.auth.credentials.remove(holder).is_some()
+    }
+
+    pub fn session_ttl(&self) -> u64 {
+        self.auth.session_ttl
+    }
+
+    /// How many seconds sessions last from login or renewal. Sessions
+    /// already open keep their expiry until renewed.
+    pub fn set_session_ttl(&mut self, seconds: u64) {
+        self.auth.session_ttl = seconds.max(1);
+    }
+
+    /// Checks `secret` against `holder`'s credential and opens a session
+    /// lasting [`Bank::session_ttl`] seconds of bank time.
+    pub fn login(&mut self, holder: &str, secret: &str) -> Result<SessionToken, AuthError> {
+        let now = self.now;
+        self.auth
+            .sessions
+            .retain(|_, session| session.expires_at > now);
+        let valid = self
+            .auth
+            .credentials
+            .get(holder)
+            .is_some_and(|credential| credential.matches(secret));
+        if !valid {
+            return Err(AuthError::InvalidCredentials);
+        }
+        let token = SessionToken::generate();
+        self.auth.sessions.insert(
+            token.digest(),
+            SessionEntry {
+                holder: holder.to_string(),
+                expires_at: Timestamp(now.0.saturating_add(self.auth.session_ttl)),
+            },
+        );
+        Ok(token)
+    }
+
+    /// The holder a live session belongs to.
+    pub fn session_holder(&self, token: &SessionToken) -> Result<&str, AuthError> {
+        let session = self
+            .auth
+            .sessions
+            .get(&token.digest())
+            .ok_or(AuthError::InvalidSession)?;
+        if session.expires_at <= self.now {
+            return Err(AuthError::SessionExpired);
+        }
+        Ok(&session.holder)
+    }
+
+    /// When the session expires.
+    pub fn session_expiry(&self, token: &SessionToken) -> Result<Timestamp, AuthError> {
+        self.session_holder(token)?;
+        Ok(self.auth.sessions[&token.digest()].expires_at)
+    }
+
+    /// Pushes a live session's expiry to [`Bank::session_ttl`] seconds from
+    /// now. Expired sessions can't be renewed.
+    pub fn renew_session(&mut self, token: &SessionToken) -> Result<Timestamp, AuthError> {
+        self.session_holder(token)?;
+        let expires_at = Timestamp(self.now.0.saturating_add(self.auth.session_ttl));
+        if let Some(session) = self.auth.sessions.get_mut(&token.digest()) {
+            session.expires_at = expires_at;
+        }
+        Ok(expires_at)
+    }
+
+    /// Logs a session out. Returns whether it was open.
+    pub fn revoke_session(&mut self, token: &SessionToken) -> bool {
+        self.auth.sessions.remove(&token.digest()).is_some()
+    }
+
+    /// Logs `holder` out everywhere, returning how many sessions ended.
+    pub fn revoke_sessions(&mut self, holder: &str) -> usize {
+        let before = self.auth.sessions.len();
+        self.auth
+            .sessions
+            .retain(|_, session| session.holder != holder);
+        before - self.auth.sessions.len()
+    }
+
+    /// The customer-facing view of the bank for a live session: a
+    /// [`Session`] limited to the accounts of the holder who logged in.
+    pub fn customer_session(&mut self, token: &SessionToken) -> Result<Session<'_>, AuthError> {
+        let holder = self.session_holder(token)?.to_string();
+        Ok(Session::new(self, Role::Customer(holder)))
+    }
+}
@@ -0,0 +1,636 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::account::{Account, AccountRef, Hold};
+use crate::auth::Auth;
+use crate::chain::reseal;
+use crate::channels::{ChannelPolicy, DepositChannel};
+use crate::currency::{Currency, CurrencyTotals};
+use crate::deductions::DeductionOrder;
+use crate::documents::{Document, DocumentStorage};
+use crate::dormancy::DormancyPolicy;
+use crate::duplicates::DuplicateCheck;
+use crate::eod::EndOfDayConfig;
+use crate::error::{AccountError, InputError, PolicyError, StateError};
+use crate::funds::{PriceSource, Quotes};
+use crate::fx::{FxOrder, RateSource};
+use crate::ids::{allocate, IdGenerator};
+use crate::locale::{Locale, Localize};
+use crate::notify::Notifications;
+use crate::outcome::{OperationOutcome, Warning};
+use crate::overflow::OverflowPolicy;
+use crate::payees::PayeeLimits;
+use crate::promotions::Promotion;
+use crate::receipt::Receipt;
+use crate::remainders::Remainders;
+use crate::rewards::RewardsAccount;
+use crate::scheduler::Scheduler;
+use crate::store::AccountStore;
+use crate::sweeps::SweepRule;
+use crate::tagging::TagEdit;
+use crate::tax::{transfer_tax, TaxPolicy};
+use crate::time::Timestamp;
+use crate::time_format::TimeFormat;
+use crate::transaction::{Transaction, TransactionKind};
+use crate::uuid::Uuid;
+use crate::validation::{HolderValidation, ValidationMode};
+use crate::zero_amount::ZeroAmountPolicy;
+
+#[derive(Debug)]
+pub struct Bank {
+    pub(crate) accounts: AccountStore,
+    pub(crate) references: HashMap<Uuid, u32>,
+    pub(crate) rewards: HashMap<u32, RewardsAccount>,
+    pub(crate) deduction_orders: Vec<DeductionOrder>,
+    pub(crate) now: Timestamp,
+    pub(crate) next_transaction_id: u64,
+    pub(crate) id_generator: Option<Box<dyn IdGenerator>>,
+    pub(crate) next_item_id: u64,
+    pub(crate) end_of_day_config: EndOfDayConfig,
+    pub(crate) locale: Locale,
+    pub(crate) time_format: TimeFormat,
+    pub(crate) duplicate_check: Option<DuplicateCheck>,
+    pub(crate) sweep_rules: Vec<SweepRule>,
+    pub(crate) rate_source: Option<Arc<dyn RateSource>>,
+    pub(crate) fx_orders: Vec<FxOrder>,
+    pub(crate) scheduler: Scheduler,
+    pub(crate) notifications: Notifications,
+    pub(crate) price_sources: HashMap<String, Arc<dyn PriceSource>>,
+    pub(crate) zero_amount_policy: ZeroAmountPolicy,
+    pub(crate) validation_mode: ValidationMode,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) dormancy_policy: Option<DormancyPolicy>,
+    pub(crate) payee_limits: Option<PayeeLimits>,
+    pub(crate) holder_exposure_limit: Option<i64>,
+    pub(crate) tag_edits: Vec<TagEdit>,
+    pub(crate) documents: BTreeMap<u64, Document>,
+    pub(crate) document_storage: DocumentStorage,
+    pub(crate) payees: HashMap<(u32, u32), Timestamp>,
+    pub(crate) promotions: Vec<Promotion>,
+    pub(crate) marketing_account: Option<u32>,
+    pub(crate) tax_policy: Option<Arc<dyn TaxPolicy>>,
+    pub(crate) tax_account: Option<u32>,
+    pub(crate) rounding_accounts: BTreeMap<Currency, u32>,
+    pub(crate) remainders: Remainders,
+    pub(crate) channel_policies: BTreeMap<DepositChannel, ChannelPolicy>,
+    pub(crate) holder_validation: HolderValidation,
+    pub(crate) auth: Auth,
+    pub(crate) wal_sequence: u64,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        Bank::starting_at(Timestamp::now())
+    }
+
+    /// Creates a bank whose clock starts at `now`, for deterministic simulations.
+    pub fn starting_at(now: Timestamp) -> Self {
+        Bank {
+            accounts: AccountStore::default(),
+            references: HashMap::new(),
+            rewards: HashMap::new(),
+            deduction_orders: Vec::new(),
+            now,
+            next_transaction_id: 1,
+            id_generator: None,
+            next_item_id: 1,
+            end_of_day_config: EndOfDayConfig::default(),
+            locale: Locale::default(),
+            time_format: TimeFormat::default(),
+            duplicate_check: None,
+            sweep_rules: Vec::new(),
+            rate_source: None,
+            fx_orders: Vec::new(),
+            scheduler: Scheduler::default(),
+            notifications: Notifications::default(),
+            price_sources: HashMap::new(),
+            zero_amount_policy: ZeroAmountPolicy::default(),
+            validation_mode: ValidationMode::default(),
+            overflow_policy: OverflowPolicy::default(),
+            dormancy_policy: None,
+            payee_limits: None,
+            holder_exposure_limit: None,
+            tag_edits: Vec::new(),
+            documents: BTreeMap::new(),
+            document_storage: DocumentStorage::default(),
+            payees: HashMap::new(),
+            promotions: Vec::new(),
+            marketing_account: None,
+            tax_policy: None,
+            tax_account: None,
+            rounding_accounts: BTreeMap::new(),
+            remainders: Remainders::new(),
+            channel_policies: BTreeMap::new(),
+            holder_validation: HolderValidation::default(),
+            auth: Auth::default(),
+            wal_sequence: 0,
+        }
+    }
+
+    /// Creates a bank with room for `accounts` accounts before it needs to
+    /// reallocate.
+    pub fn with_capacity(accounts: usize) -> Self {
+        let mut bank = Bank::new();
+        bank.reserve(accounts);
+        bank
+    }
+
+    /// Makes room for at least `additional` more accounts.
+    pub fn reserve(&mut self, additional: usize) {
+        self.accounts.reserve(additional);
+        self.references.reserve(additional);
+    }
+
+    /// Releases spare capacity in the bank's maps and lists and in every
+    /// account's history.
+    pub fn shrink_to_fit(&mut self) {
+        self.accounts.shrink_to_fit();
+        self.references.shrink_to_fit();
+        self.rewards.shrink_to_fit();
+        self.deduction_orders.shrink_to_fit();
+        self.sweep_rules.shrink_to_fit();
+        self.fx_orders.shrink_to_fit();
+        self.scheduler.shrink_to_fit();
+        for account in self.accounts.values_mut() {
+            account.history.shrink_to_fit();
+            account.holds.shrink_to_fit();
+            account.pending_cheques.shrink_to_fit();
+        }
+    }
+
+    /// The number of accounts.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn now(&self) -> Timestamp {
+        self.now
+    }
+
+    pub fn set_time(&mut self, now: Timestamp) {
+        self.now = now;
+    }
+
+    pub fn add_account(&mut self, mut account: Account) {
+        account.opened_at.get_or_insert(self.now);
+        let id = account.id;
+        let opening_balance = account.balance;
+        let needs_opening_entry = opening_balance != 0 && account.history.is_empty();
+        self.references.insert(account.reference, id);
+        if let Some(replaced) = self.accounts.insert(id, account) {
+            self.references.remove(&replaced.reference);
+        }
+        if needs_opening_entry {
+            self.record(
+                id,
+                TransactionKind::OpeningBalance,
+                opening_balance,
+                None,
+                "",
+            );
+        }
+    }
+
+    /// The balances of all accounts, totalled per currency.
+    pub fn balances_by_currency(&self) -> CurrencyTotals {
+        let mut totals = CurrencyTotals::new();
+        for account in self.accounts.values() {
+            totals.add(account.currency, account.balance);
+        }
+        totals
+    }
+
+    /// The sum of every balance, stopping at the limits of an `i64` once
+    /// saturated or promoted balances pass them.
+    pub fn total_balance(&self) -> i64 {
+        let total: i128 = self
+            .accounts
+            .values()
+            .map(|account| i128::from(account.balance))
+            .sum();
+        total.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+    }
+
+    pub fn summary(&self) -> String {
+        self.summary_in(self.locale)
+    }
+
+    /// Resolves an id or external reference to the account's numeric id.
+    pub fn resolve(&self, account: impl Into<AccountRef>) -> Result<u32, AccountError> {
+        let id = self.id_of(account)?;
+        if self.accounts.contains_key(&id) {
+            Ok(id)
+        } else {
+            Err(AccountError::State(StateError::AccountNotFound))
+        }
+    }
+
+    /// Like [`Bank::resolve`], but without checking that a numeric id
+    /// exists, for callers that look the account up straight afterwards.
+    pub(crate) fn id_of(&self, account: impl Into<AccountRef>) -> Result<u32, AccountError> {
+        match account.into() {
+            AccountRef::Id(id) => Ok(id),
+            AccountRef::Reference(reference) => self
+                .references
+                .get(&reference)
+                .copied()
+                .ok_or(AccountError::State(StateError::AccountNotFound)),
+        }
+    }
+
+    pub fn get_account_mut(&mut self, account: impl Into<AccountRef>) -> Option<&mut Account> {
+        let id = self.resolve(account).ok()?;
+        self.accounts.get_mut(&id)
+    }
+
+    pub fn get_account(&self, account: impl Into<AccountRef>) -> Option<&Account> {
+        let id = self.resolve(account).ok()?;
+        self.accounts.get(&id)
+    }
+
+    /// Accounts whose metadata has `key` set to `value`, ordered by id.
+    pub fn find_where(&self, key: &str, value: &str) -> Vec<&Account> {
+        self.sorted_account_ids()
+            .into_iter()
+            .map(|id| &self.accounts[&id])
+            .filter(|account| account.meta(key) == Some(value))
+            .collect()
+    }
+
+    pub fn transaction(&self, txn_id: u64) -> Option<&Transaction> {
+        self.accounts
+            .values()
+            .flat_map(|account| account.history.iter())
+            .find(|txn| txn.id == txn_id)
+    }
+
+    pub fn deposit(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        if let Some(skipped) = self.check_zero_amount(amount)? {
+            return Ok(self.receipt(id, None, vec![skipped]));
+        }
+        self.revalue(id)?;
+        let account = self
+            .accounts
+            .get_mut(&id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        let outcome = account.credit(amount, self.overflow_policy)?;
+        let amount = outcome.value;
+        let txn_id = self.record(id, TransactionKind::Deposit, amount, None, "");
+        self.apply_deduction_orders(id, amount);
+        Ok(self.receipt(id, Some(txn_id), outcome.warnings))
+    }
+
+    pub fn withdraw(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        self.withdraw_categorized(id, amount, None)
+    }
+
+    /// Withdraws funds for a spend in `category` (e.g. "groceries").
+    pub fn withdraw_in_category(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        category: &str,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        self.withdraw_categorized(id, amount, Some(category))
+    }
+
+    fn withdraw_categorized(
+        &mut self,
+        id: u32,
+        amount: i64,
+        category: Option<&str>,
+    ) -> Result<Receipt, AccountError> {
+        if let Some(skipped) = self.check_zero_amount(amount)? {
+            return Ok(self.receipt(id, None, vec![skipped]));
+        }
+        if let Some(account) = self.accounts.get(&id) {
+            account.check_not_dormant()?;
+        }
+        self.require_co_signature(id, amount, category)?;
+        if let Some(account) = self.accounts.get(&id) {
+            account.check_spending_cap(self.now, amount)?;
+        }
+        self.debit_categorized(id, amount, category)
+    }
+
+    /// Books a withdrawal that needs no further approval.
+    pub(crate) fn debit_categorized(
+        &mut self,
+        id: u32,
+        amount: i64,
+        category: Option<&str>,
+    ) -> Result<Receipt, AccountError> {
+        self.revalue(id)?;
+        if self
+            .accounts
+            .get(&id)
+            .is_some_and(|account| account.spendable_balance() >= amount)
+        {
+            self.check_exposure(&[(id, -amount)])?;
+        }
+        let account = self
+            .accounts
+            .get_mut(&id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        let outcome = account.withdraw(amount)?;
+        let txn_id = self.record(id, TransactionKind::Withdrawal, -amount, None, "");
+        if let Some(category) = category {
+            self.amend_transaction(id, txn_id, |txn| {
+                txn.category = Some(category.to_string());
+            });
+        }
+        self.earn_rewards(id, txn_id, amount, category, None);
+        self.notify_withdrawal(id, amount);
+        Ok(self.receipt(id, Some(txn_id), outcome.warnings))
+    }
+
+    /// Deposits a cheque that is credited at the end of the day it clears.
+    pub fn deposit_cheque(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        clearing_days: u32,
+    ) -> Result<u64, AccountError> {
+        let id = self.resolve(account)?;
+        let cheque = self.add_pending_deposit(id, amount, clearing_days, DepositChannel::Cheque)?;
+        Ok(cheque.id)
+    }
+
+    /// Reserves `amount` of the available balance for `days` days.
+    pub fn place_hold(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        days: u32,
+        reason: &str,
+    ) -> Result<u64, AccountError> {
+        let id = self.resolve(account)?;
+        if amount < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let hold_id = self.next_item_id;
+        let expires_at = self.now.plus_days(days);
+        let account = self
+            .accounts
+            .get_mut(&id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        if account.available_balance() < amount {
+            return Err(AccountError::Policy(PolicyError::InsufficientFunds {
+                requested: amount,
+                available: account.available_balance(),
+                unused_overdraft: 0,
+                currency: account.currency,
+            }));
+        }
+        account.holds.push(Hold {
+            id: hold_id,
+            amount,
+            expires_at,
+            reason: reason.to_string(),
+        });
+        self.next_item_id += 1;
+        Ok(hold_id)
+    }
+
+    pub fn release_hold(
+        &mut self,
+        account: impl Into<AccountRef>,
+        hold_id: u64,
+    ) -> Result<Hold, AccountError> {
+        let id = self.resolve(account)?;
+        let account = self
+            .accounts
+            .get_mut(&id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        let index = account
+            .holds
+            .iter()
+            .position(|hold| hold.id == hold_id)
+            .ok_or(AccountError::State(StateError::HoldNotFound))?;
+        Ok(account.holds.remove(index))
+    }
+
+    pub fn transfer(
+        &mut self,
+        from: impl Into<AccountRef>,
+        to: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<OperationOutcome<()>, AccountError> {
+        if amount < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+
+        let from_id = self.id_of(from)?;
+        let to_id = self.id_of(to)?;
+        self.transfer_between(from_id, to_id, amount, false, false)
+    }
+
+    pub(crate) fn transfer_between(
+        &mut self,
+        from_id: u32,
+        to_id: u32,
+        amount: i64,
+        allow_duplicate: bool,
+        approved: bool,
+    ) -> Result<OperationOutcome<()>, AccountError> {
+        if amount < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        if from_id == to_id {
+            let Some(account) = self.accounts.get(&from_id) else {
+                return Err(AccountError::State(StateError::AccountNotFound));
+            };
+            if self.validation_mode == ValidationMode::Strict {
+                return Err(AccountError::Validation(InputError::SameAccount));
+            }
+            // Nothing moves, but the attempt is refused where a real
+            // transfer out would be, and otherwise booked at zero.
+            account.check_not_dormant()?;
+            let memo = format!(
+                "Transfer of {} to the same account",
+                account.format_amount(amount)
+            );
+            self.record(
+                from_id,
+                TransactionKind::TransferOut,
+                0,
+                Some(from_id),
+                &memo,
+            );
+            return Ok(OperationOutcome::with_warnings(
+                (),
+                vec![Warning::SelfTransfer],
+            ));
+        }
+        let skipped = self.check_zero_amount(amount)?;
+        // The exposure check needs the holder's other accounts, so they are
+        // summed up front; everything else works on the pair, which is
+        // looked up once.
+        let exposure = self.exposure_check(&[from_id, to_id]);
+        let [Some(from), Some(to)] = self.accounts.get_disjoint_mut([&from_id, &to_id]) else {
+            return Err(AccountError::State(StateError::AccountNotFound));
+        };
+        if let Some(skipped) = skipped {
+            return Ok(OperationOutcome::with_warnings((), vec![skipped]));
+        }
+        // Units trade at today's price, as they do for deposits and
+        // withdrawals.
+        let quotes = Quotes::new(&self.price_sources, self.now);
+        for account in [&mut *from, &mut *to] {
+            account.revalue(&quotes, || {
+                allocate(&mut self.id_generator, &mut self.next_transaction_id)
+            })?;
+        }
+        if from.currency != to.currency {
+            return Err(AccountError::Validation(InputError::MixedCurrencies));
+        }
+        let tax = transfer_tax(&self.tax_policy, self.tax_account, from, to, amount);
+        let debit = amount.saturating_add(tax);
+        if from.spendable_balance() >= debit {
+            if let Some(exposure) = &exposure {
+                exposure.check(&[(from, -debit), (to, amount)])?;
+            }
+        } else if tax > 0 {
+            return Err(from.insufficient_funds(debit));
+        }
+        let duplicate = match self.duplicate_check {
+            Some(check) if !allow_duplicate => {
+                check.check_transfer(self.now, from, to_id, amount)?
+            }
+            _ => None,
+        };
+        if let Some(limits) = &self.payee_limits {
+            limits.check_transfer(&self.payees, self.now, from, to_id, amount)?;
+        }
+        from.check_not_dormant()?;
+        if !approved {
+            from.require_co_signature(&mut self.next_item_id, self.now, amount, None, Some(to_id))?;
+            from.check_spending_cap(self.now, debit)?;
+        }
+        let mut saturated = None;
+        let mut amount = amount;
+        if to.balance.checked_add(amount).is_none() {
+            match self.overflow_policy {
+                OverflowPolicy::Reject => {
+                    return Err(AccountError::Validation(InputError::AmountOverflow))
+                }
+                OverflowPolicy::Saturate => {
+                    let credited = i64::MAX - to.balance;
+                    saturated = Some(Warning::AmountSaturated {
+                        requested: amount,
+                        credited,
+                        currency: to.currency,
+                    });
+                    amount = credited;
+                }
+                OverflowPolicy::Promote => {}
+            }
+        }
+        let withdrawn = from.withdraw(amount)?;
+        to.credit(amount, self.overflow_policy)?;
+
+        let out_id = allocate(&mut self.id_generator, &mut self.next_transaction_id);
+        let in_id = allocate(&mut self.id_generator, &mut self.next_transaction_id);
+        let now = self.now;
+        let holds_units = (from.units.is_some(), to.units.is_some());
+        from.book(
+            out_id,
+            TransactionKind::TransferOut,
+            -amount,
+            Some(to_id),
+            "",
+            now,
+        );
+        to.book(
+            in_id,
+            TransactionKind::TransferIn,
+            amount,
+            Some(from_id),
+            "",
+            now,
+        );
+        if holds_units.0 {
+            self.trade_units(from_id, out_id, -amount);
+        }
+        if holds_units.1 {
+            self.trade_units(to_id, in_id, amount);
+        }
+        self.earn_rewards(from_id, out_id, amount, None, Some(to_id));
+        self.collect_tax(from_id, tax, "transfer", out_id);
+
+        let mut outcome = withdrawn.map(|_| ());
+        outcome.warnings.extend(duplicate);
+        outcome.warnings.extend(saturated);
+        Ok(outcome)
+    }
+
+    /// Books an entry for a balance change that has already been applied.
+    pub(crate) fn record(
+        &mut self,
+        account_id: u32,
+        kind: TransactionKind,
+        amount: i64,
+        counterparty: Option<u32>,
+        memo: &str,
+    ) -> u64 {
+        let id = self.allocate_transaction_id();
+        let timestamp = self.now;
+        if let Some(account) = self.accounts.get_mut(&account_id) {
+            account.book(id, kind, amount, counterparty, memo, timestamp);
+        }
+        if amount != 0 && kind != TransactionKind::Revaluation {
+            self.trade_units(account_id, id, amount);
+        }
+        id
+    }
+
+    /// Edits a booked entry, re-sealing the hash chain from it onwards.
+    pub(crate) fn amend_transaction(
+        &mut self,
+        account_id: u32,
+        txn_id: u64,
+        amend: impl FnOnce(&mut Transaction),
+    ) {
+        let Some(account) = self.accounts.get_mut(&account_id) else {
+            return;
+        };
+        if let Some(index) = account.history.iter().rposition(|txn| txn.id == txn_id) {
+            amend(&mut account.history[index]);
+            reseal(&mut account.history, index);
+        }
+    }
+
+    pub(crate) fn sorted_account_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.accounts.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+impl Default for Bank {
+    fn default() -> Self {
+        Bank::new()
+    }
+}
+
+impl fmt::Display for Bank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(self.locale))
+    }
+}
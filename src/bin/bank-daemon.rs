@@ -0,0 +1,146 @@
+//! Runs a bank until SIGINT or SIGTERM, applying operations read from
+//! standard input and persisting its state to a snapshot file.
+//!
+//! Usage: `bank-daemon STATE_FILE [--flush-secs SECONDS] [--health HOST:PORT]
+//! [--wal LOG_FILE]`.
+//! Input lines are `kind,account,amount[,to]` rows as accepted by
+//! `parse_csv`, or `open,account,holder` to open an account. On a signal
+//! the daemon finishes the operations already queued and writes the state
+//! before exiting. With `--wal`, every operation is logged before it is
+//! applied and replayed on the next start, so a crash loses nothing.
+
+use std::error::Error;
+use std::io::{self, BufRead};
+use std::thread;
+use std::time::Duration;
+
+use bank::{parse_csv, BankHandle, BatchOp, Daemon, DaemonConfig};
+
+#[cfg(unix)]
+mod signals {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+
+    static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+
+    extern "C" fn request_shutdown(_: i32) {
+        REQUESTED.store(true, Ordering::SeqCst);
+    }
+
+    pub fn install() {
+        // SAFETY: the handler only stores to an atomic, which is
+        // async-signal-safe.
+        unsafe {
+            signal(SIGINT, request_shutdown);
+            signal(SIGTERM, request_shutdown);
+        }
+    }
+
+    pub fn requested() -> bool {
+        REQUESTED.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(not(unix))]
+mod signals {
+    pub fn install() {}
+
+    pub fn requested() -> bool {
+        false
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut state_path = None;
+    let mut flush_interval = None;
+    let mut health = None;
+    let mut wal = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--flush-secs" => {
+                let value = args.next().ok_or("--flush-secs needs a number")?;
+                flush_interval = Some(Duration::from_secs(value.parse()?));
+            }
+            "--health" => health = Some(args.next().ok_or("--health needs an address")?),
+            "--wal" => wal = Some(args.next().ok_or("--wal needs a file")?),
+            "-h" | "--help" => {
+                println!(
+                    "Usage: bank-daemon STATE_FILE [--flush-secs SECONDS] [--health HOST:PORT] [--wal LOG_FILE]"
+                );
+                return Ok(());
+            }
+            _ if state_path.is_none() => state_path = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
+        }
+    }
+
+    let mut config = DaemonConfig::new(state_path.ok_or("missing STATE_FILE")?);
+    if let Some(interval) = flush_interval {
+        config = config.flush_every(interval);
+    }
+    if let Some(address) = &health {
+        config = config.health_on(address);
+    }
+    if let Some(path) = wal {
+        config = config.write_ahead_log(path);
+    }
+    signals::install();
+    let daemon = Daemon::start(config)?;
+    if let Some(address) = daemon.health_address() {
+        eprintln!("Health endpoint on http://{}/health", address);
+        eprintln!("Metrics on http://{}/metrics", address);
+    }
+
+    let handle = daemon.handle();
+    thread::spawn(move || read_operations(&handle));
+    while !signals::requested() {
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    eprintln!("Shutting down");
+    let bank = daemon.shutdown()?;
+    eprintln!("Saved {} accounts", bank.len());
+    Ok(())
+}
+
+fn read_operations(handle: &BankHandle) {
+    for (index, line) in io::stdin().lock().lines().enumerate() {
+        let Ok(line) = line else {
+            break;
+        };
+        if let Err(error) = apply(handle, line.trim()) {
+            eprintln!("line {}: {}", index + 1, error);
+        }
+    }
+}
+
+fn apply(handle: &BankHandle, line: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(rest) = line.strip_prefix("open,") {
+        let (id, holder) = rest
+            .split_once(',')
+            .ok_or("open needs an account and holder")?;
+        handle.open_account(id.trim().parse()?, holder.trim())?;
+        return Ok(());
+    }
+    for operation in parse_csv(line.as_bytes()).map_err(|error| error.message)? {
+        match operation.op {
+            BatchOp::Deposit { account, amount } => {
+                handle.deposit(account, amount)?;
+            }
+            BatchOp::Withdraw { account, amount } => {
+                handle.withdraw(account, amount)?;
+            }
+            BatchOp::Transfer { from, to, amount } => {
+                handle.transfer(from, to, amount)?;
+            }
+        }
+    }
+    Ok(())
+}
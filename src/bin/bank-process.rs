@@ -0,0 +1,46 @@
+//! Streams a CSV of `client,tx,type,amount` operations and prints the final
+//! state of every client account as CSV.
+//!
+//! Usage: `bank-process [--dispute-window DEPOSITS] [FILE]`. Reads standard
+//! input when no file (or `-`) is given. A summary goes to standard error.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+use bank::StreamProcessor;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut path = None;
+    let mut dispute_window = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dispute-window" => {
+                let value = args.next().ok_or("--dispute-window needs a number")?;
+                dispute_window = Some(value.parse::<usize>()?);
+            }
+            "-h" | "--help" => {
+                println!("Usage: bank-process [--dispute-window DEPOSITS] [FILE]");
+                return Ok(());
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
+        }
+    }
+
+    let mut processor = match dispute_window {
+        Some(deposits) => StreamProcessor::with_dispute_window(deposits),
+        None => StreamProcessor::new(),
+    };
+    let report = match path.as_deref() {
+        None | Some("-") => processor.process(io::stdin().lock())?,
+        Some(path) => processor.process(BufReader::new(File::open(path)?))?,
+    };
+
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    processor.write_accounts(&mut stdout)?;
+    stdout.flush()?;
+    eprintln!("{}", report);
+    Ok(())
+}
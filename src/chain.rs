@@ -0,0 +1,124 @@
+//! Tamper-evident history: every entry stores a SHA-256 hash of its own
+//! contents together with the hash of the entry before it, so changing,
+//! removing or reordering any booked entry breaks the chain from there on.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bank::Bank;
+use crate::crypto::Sha256;
+use crate::snapshot::{escape, kind_code};
+use crate::transaction::Transaction;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ChainHash(pub [u8; 32]);
+
+impl ChainHash {
+    /// What the first entry of every history chains from.
+    pub const GENESIS: ChainHash = ChainHash([0; 32]);
+}
+
+impl fmt::Display for ChainHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ChainHash {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(());
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, index) in bytes.iter_mut().zip((0..64).step_by(2)) {
+            *byte = u8::from_str_radix(&s[index..index + 2], 16).map_err(|_| ())?;
+        }
+        Ok(ChainHash(bytes))
+    }
+}
+
+/// The first entry whose stored hash does not match its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainBreak {
+    pub account_id: u32,
+    pub transaction_id: u64,
+}
+
+impl fmt::Display for ChainBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "History of account {} was altered at transaction {}",
+            self.account_id, self.transaction_id
+        )
+    }
+}
+
+impl std::error::Error for ChainBreak {}
+
+/// Hashes everything about `txn` except its own stored hash.
+pub(crate) fn entry_hash(previous: &ChainHash, txn: &Transaction) -> ChainHash {
+    let mut hasher = Sha256::new();
+    hasher.update(&previous.0);
+    let fields = format!(
+        "{}\t{}\t{}\t{}\t{}\t{:?}\t{}\t{}\t{:?}\t{}",
+        txn.id,
+        txn.account_id,
+        kind_code(txn.kind),
+        txn.amount,
+        txn.balance_after,
+        txn.counterparty,
+        txn.timestamp.seconds(),
+        txn.rounding,
+        txn.category.as_deref().map(escape),
+        escape(&txn.memo)
+    );
+    hasher.update(fields.as_bytes());
+    for (key, value) in &txn.metadata {
+        hasher.update(format!("\n{}\t{}", escape(key), escape(value)).as_bytes());
+    }
+    for tag in &txn.tags {
+        hasher.update(format!("\n#{}", escape(tag)).as_bytes());
+    }
+    ChainHash(hasher.finalize())
+}
+
+/// Recomputes the hashes of `history[from..]`, for deliberate rewrites such
+/// as pruning or redenomination.
+pub(crate) fn reseal(history: &mut [Transaction], from: usize) {
+    let mut previous = match from {
+        0 => ChainHash::GENESIS,
+        _ => history[from - 1].chain_hash,
+    };
+    for txn in &mut history[from..] {
+        txn.chain_hash = entry_hash(&previous, txn);
+        previous = txn.chain_hash;
+    }
+}
+
+impl Bank {
+    /// Checks every account's hash chain, returning how many entries were
+    /// verified or the first entry that no longer matches.
+    pub fn verify_chain(&self) -> Result<usize, ChainBreak> {
+        let mut verified = 0;
+        for id in self.sorted_account_ids() {
+            let mut previous = ChainHash::GENESIS;
+            for txn in &self.accounts[&id].history {
+                if entry_hash(&previous, txn) != txn.chain_hash {
+                    return Err(ChainBreak {
+                        account_id: id,
+                        transaction_id: txn.id,
+                    });
+                }
+                previous = txn.chain_hash;
+                verified += 1;
+            }
+        }
+        Ok(verified)
+    }
+}
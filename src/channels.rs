@@ -0,0 +1,271 @@
+//! The channel money came in through. Each deposit records whether it was
+//! cash over the counter, a cheque, a wire or a transfer from inside the
+//! bank, each channel has its own clearing policy, and reports break
+//! deposits down by channel.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::account::{AccountRef, PendingCheque};
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::error::{AccountError, InputError, StateError};
+use crate::receipt::Receipt;
+use crate::time::Timestamp;
+use crate::transaction::{Transaction, TransactionKind};
+
+/// How a deposit reached the bank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DepositChannel {
+    Cash,
+    Cheque,
+    Wire,
+    /// Paid in from elsewhere in the bank, such as a branch's own account.
+    Internal,
+}
+
+impl DepositChannel {
+    pub const ALL: [DepositChannel; 4] = [
+        DepositChannel::Cash,
+        DepositChannel::Cheque,
+        DepositChannel::Wire,
+        DepositChannel::Internal,
+    ];
+
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            DepositChannel::Cash => "cash",
+            DepositChannel::Cheque => "cheque",
+            DepositChannel::Wire => "wire",
+            DepositChannel::Internal => "internal",
+        }
+    }
+
+    pub(crate) fn from_code(code: &str) -> Option<Self> {
+        DepositChannel::ALL
+            .into_iter()
+            .find(|channel| channel.code() == code)
+    }
+}
+
+impl fmt::Display for DepositChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.code())
+    }
+}
+
+/// What the bank does with deposits through one channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelPolicy {
+    /// Days before the deposit is credited; zero credits it straight away.
+    pub clearing_days: u32,
+}
+
+impl ChannelPolicy {
+    pub const INSTANT: ChannelPolicy = ChannelPolicy { clearing_days: 0 };
+
+    pub fn clearing(days: u32) -> Self {
+        ChannelPolicy {
+            clearing_days: days,
+        }
+    }
+
+    /// Cheques take two days to clear; everything else is instant.
+    pub fn default_for(channel: DepositChannel) -> Self {
+        match channel {
+            DepositChannel::Cheque => ChannelPolicy::clearing(2),
+            _ => ChannelPolicy::INSTANT,
+        }
+    }
+}
+
+/// What became of a deposit through a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelDeposit {
+    Credited(Receipt),
+    /// Waiting to clear as pending item `id`; end of day credits it once
+    /// `clears_at` has passed.
+    Clearing {
+        id: u64,
+        clears_at: Timestamp,
+    },
+}
+
+impl Transaction {
+    /// The channel a deposit came in through. Entries booked before
+    /// channels were recorded, or by a plain [`Bank::deposit`], count as
+    /// cash, and cleared cheques as cheques. `None` for entries that aren't
+    /// deposits.
+    pub fn deposit_channel(&self) -> Option<DepositChannel> {
+        match self.kind {
+            TransactionKind::Deposit | TransactionKind::ChequeDeposit => {}
+            _ => return None,
+        }
+        match self.metadata.get("channel") {
+            Some(code) => DepositChannel::from_code(code),
+            None if self.kind == TransactionKind::ChequeDeposit => Some(DepositChannel::Cheque),
+            None => Some(DepositChannel::Cash),
+        }
+    }
+}
+
+/// Deposits through one channel in one currency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelLine {
+    pub currency: Currency,
+    pub channel: DepositChannel,
+    /// Deposits credited during the period.
+    pub count: usize,
+    pub amount: i64,
+    /// Deposits still waiting to clear now.
+    pub clearing: i64,
+}
+
+/// Deposits over `[period_start, period_end]` by currency and channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelReport {
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+    pub lines: Vec<ChannelLine>,
+}
+
+impl ChannelReport {
+    /// Everything credited through `channel` in `currency`.
+    pub fn credited(&self, currency: Currency, channel: DepositChannel) -> i64 {
+        self.lines
+            .iter()
+            .find(|line| line.currency == currency && line.channel == channel)
+            .map_or(0, |line| line.amount)
+    }
+}
+
+impl fmt::Display for ChannelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Deposits by channel, {} to {}",
+            self.period_start.date(),
+            self.period_end.date()
+        )?;
+        for line in &self.lines {
+            write!(
+                f,
+                "\n  {} {:<8} {:>5} credited {:>12}  clearing {:>12}",
+                line.currency, line.channel, line.count, line.amount, line.clearing
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    /// Replaces `channel`'s policy. Cheques paid in with
+    /// [`Bank::deposit_cheque`] keep the clearing days given there.
+    pub fn set_channel_policy(&mut self, channel: DepositChannel, policy: ChannelPolicy) {
+        if policy == ChannelPolicy::default_for(channel) {
+            self.channel_policies.remove(&channel);
+        } else {
+            self.channel_policies.insert(channel, policy);
+        }
+    }
+
+    pub fn channel_policy(&self, channel: DepositChannel) -> ChannelPolicy {
+        self.channel_policies
+            .get(&channel)
+            .copied()
+            .unwrap_or_else(|| ChannelPolicy::default_for(channel))
+    }
+
+    /// Deposits `amount` received through `channel`, crediting it now or
+    /// holding it until it clears as the channel's policy says.
+    pub fn deposit_via(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        channel: DepositChannel,
+    ) -> Result<ChannelDeposit, AccountError> {
+        let id = self.resolve(account)?;
+        let clearing_days = self.channel_policy(channel).clearing_days;
+        if clearing_days > 0 {
+            let pending = self.add_pending_deposit(id, amount, clearing_days, channel)?;
+            return Ok(ChannelDeposit::Clearing {
+                id: pending.id,
+                clears_at: pending.clears_at,
+            });
+        }
+        let receipt = self.deposit(id, amount)?;
+        if let Some(txn_id) = receipt.transaction_id {
+            self.amend_transaction(id, txn_id, |txn| {
+                txn.metadata
+                    .insert("channel".to_string(), channel.code().to_string());
+            });
+        }
+        Ok(ChannelDeposit::Credited(receipt))
+    }
+
+    /// Queues a deposit that is credited at the first end of day after
+    /// `clearing_days` have passed.
+    pub(crate) fn add_pending_deposit(
+        &mut self,
+        account_id: u32,
+        amount: i64,
+        clearing_days: u32,
+        channel: DepositChannel,
+    ) -> Result<PendingCheque, AccountError> {
+        if amount < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let pending = PendingCheque {
+            id: self.next_item_id,
+            amount,
+            clears_at: self.now.plus_days(clearing_days),
+            channel,
+        };
+        self.accounts
+            .get_mut(&account_id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?
+            .pending_cheques
+            .push(pending.clone());
+        self.next_item_id += 1;
+        Ok(pending)
+    }
+
+    /// Deposits credited over `period`, inclusive, and those still
+    /// clearing, by currency and channel.
+    pub fn deposit_channels(&self, period: (Timestamp, Timestamp)) -> ChannelReport {
+        let (period_start, period_end) = period;
+        let mut lines: BTreeMap<(Currency, DepositChannel), ChannelLine> = BTreeMap::new();
+        for account in self.accounts.values() {
+            let currency = account.currency;
+            for txn in account.history_between(period_start, period_end) {
+                if let Some(channel) = txn.deposit_channel() {
+                    let line = line_for(&mut lines, currency, channel);
+                    line.count += 1;
+                    line.amount += txn.amount;
+                }
+            }
+            for pending in &account.pending_cheques {
+                line_for(&mut lines, currency, pending.channel).clearing += pending.amount;
+            }
+        }
+        ChannelReport {
+            period_start,
+            period_end,
+            lines: lines.into_values().collect(),
+        }
+    }
+}
+
+fn line_for(
+    lines: &mut BTreeMap<(Currency, DepositChannel), ChannelLine>,
+    currency: Currency,
+    channel: DepositChannel,
+) -> &mut ChannelLine {
+    lines.entry((currency, channel)).or_insert(ChannelLine {
+        currency,
+        channel,
+        count: 0,
+        amount: 0,
+        clearing: 0,
+    })
+}
@@ -0,0 +1,333 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::bank::Bank;
+use crate::error::AccountError;
+use crate::snapshot::{Fields, PersistError};
+use crate::time::Timestamp;
+
+const LOG_FILE: &str = "events.log";
+const CHECKPOINT_PREFIX: &str = "checkpoint-";
+const CHECKPOINT_SUFFIX: &str = ".snap";
+
+/// A state change applied to a simulated bank, as recorded in the event log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationEvent {
+    Deposit {
+        account: u32,
+        amount: i64,
+    },
+    Withdraw {
+        account: u32,
+        amount: i64,
+    },
+    Transfer {
+        from: u32,
+        to: u32,
+        amount: i64,
+    },
+    EndOfDay,
+    SetTime(Timestamp),
+    /// See [`Bank::advance_time`].
+    AdvanceTime(Duration),
+}
+
+impl SimulationEvent {
+    fn encode(&self) -> String {
+        match *self {
+            SimulationEvent::Deposit { account, amount } => {
+                format!("deposit\t{}\t{}", account, amount)
+            }
+            SimulationEvent::Withdraw { account, amount } => {
+                format!("withdraw\t{}\t{}", account, amount)
+            }
+            SimulationEvent::Transfer { from, to, amount } => {
+                format!("transfer\t{}\t{}\t{}", from, to, amount)
+            }
+            SimulationEvent::EndOfDay => "end-of-day".to_string(),
+            SimulationEvent::SetTime(time) => format!("set-time\t{}", time.seconds()),
+            SimulationEvent::AdvanceTime(duration) => format!("advance\t{}", duration.as_secs()),
+        }
+    }
+
+    fn decode(fields: &mut Fields<'_>) -> Result<Self, PersistError> {
+        Ok(match fields.raw()? {
+            "deposit" => SimulationEvent::Deposit {
+                account: fields.parse()?,
+                amount: fields.parse()?,
+            },
+            "withdraw" => SimulationEvent::Withdraw {
+                account: fields.parse()?,
+                amount: fields.parse()?,
+            },
+            "transfer" => SimulationEvent::Transfer {
+                from: fields.parse()?,
+                to: fields.parse()?,
+                amount: fields.parse()?,
+            },
+            "end-of-day" => SimulationEvent::EndOfDay,
+            "set-time" => SimulationEvent::SetTime(Timestamp(fields.parse()?)),
+            "advance" => SimulationEvent::AdvanceTime(Duration::from_secs(fields.parse()?)),
+            other => return Err(fields.corrupt(format!("unknown event '{}'", other))),
+        })
+    }
+}
+
+impl Bank {
+    /// Applies a simulation event. Only deposits, withdrawals and transfers
+    /// can fail.
+    pub fn apply_event(&mut self, event: SimulationEvent) -> Result<(), AccountError> {
+        match event {
+            SimulationEvent::Deposit { account, amount } => {
+                self.deposit(account, amount).map(|_| ())
+            }
+            SimulationEvent::Withdraw { account, amount } => {
+                self.withdraw(account, amount).map(|_| ())
+            }
+            SimulationEvent::Transfer { from, to, amount } => {
+                self.transfer(from, to, amount).map(|_| ())
+            }
+            SimulationEvent::EndOfDay => {
+                self.end_of_day();
+                Ok(())
+            }
+            SimulationEvent::SetTime(time) => {
+                self.set_time(time);
+                Ok(())
+            }
+            SimulationEvent::AdvanceTime(duration) => {
+                self.advance_time(duration);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// When a [`Simulation`] writes checkpoints. A checkpoint is taken as soon
+/// as either limit is reached; `None` disables that limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointConfig {
+    pub directory: PathBuf,
+    pub every_events: Option<u64>,
+    /// Bytes of event log written since the last checkpoint.
+    pub max_log_bytes: Option<u64>,
+    /// How many checkpoint files to keep on disk.
+    pub keep: usize,
+}
+
+impl CheckpointConfig {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        CheckpointConfig {
+            directory: directory.into(),
+            every_events: Some(10_000),
+            max_log_bytes: None,
+            keep: 2,
+        }
+    }
+
+    pub fn every_events(mut self, events: u64) -> Self {
+        self.every_events = Some(events);
+        self
+    }
+
+    pub fn max_log_bytes(mut self, bytes: u64) -> Self {
+        self.max_log_bytes = Some(bytes);
+        self
+    }
+
+    pub fn keep(mut self, checkpoints: usize) -> Self {
+        self.keep = checkpoints.max(1);
+        self
+    }
+}
+
+/// A bank driven by logged events, checkpointed to disk so a crashed run can
+/// resume from the latest checkpoint plus the tail of the log.
+///
+/// Every event is appended to the log before it is applied, and failed
+/// events are logged too, so replaying reproduces the run exactly.
+#[derive(Debug)]
+pub struct Simulation {
+    bank: Bank,
+    config: CheckpointConfig,
+    log: BufWriter<File>,
+    sequence: u64,
+    events_since_checkpoint: u64,
+    bytes_since_checkpoint: u64,
+}
+
+impl Simulation {
+    /// Starts a new simulation in `config.directory`, discarding any earlier
+    /// log and checkpoints there. An initial checkpoint is written so the
+    /// run can always be resumed.
+    pub fn create(bank: Bank, config: CheckpointConfig) -> Result<Self, PersistError> {
+        fs::create_dir_all(&config.directory)?;
+        for (_, path) in checkpoints(&config.directory)? {
+            fs::remove_file(path)?;
+        }
+        let log = File::create(config.directory.join(LOG_FILE))?;
+        let mut simulation = Simulation {
+            bank,
+            config,
+            log: BufWriter::new(log),
+            sequence: 0,
+            events_since_checkpoint: 0,
+            bytes_since_checkpoint: 0,
+        };
+        simulation.checkpoint()?;
+        Ok(simulation)
+    }
+
+    /// Restores the latest checkpoint in `config.directory` and replays the
+    /// events logged after it. A partially written last line is discarded.
+    pub fn resume(config: CheckpointConfig) -> Result<Self, PersistError> {
+        let (sequence, path) = checkpoints(&config.directory)?.pop().ok_or_else(|| {
+            PersistError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no checkpoint to resume from",
+            ))
+        })?;
+        let mut bank = Bank::load_snapshot(BufReader::new(File::open(path)?))?;
+
+        let log_path = config.directory.join(LOG_FILE);
+        let contents = fs::read(&log_path)?;
+        let complete = contents
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |index| index + 1);
+        let mut last = 0;
+        let mut replayed = 0;
+        let mut replayed_bytes = 0;
+        for (index, line) in contents[..complete].lines().enumerate() {
+            let line = line?;
+            let mut fields = Fields::new(index + 1, &line);
+            let event_sequence: u64 = fields.parse()?;
+            if event_sequence != last + 1 {
+                return Err(fields.corrupt("event log is out of sequence"));
+            }
+            last = event_sequence;
+            if event_sequence <= sequence {
+                continue;
+            }
+            let event = SimulationEvent::decode(&mut fields)?;
+            let _ = bank.apply_event(event);
+            replayed += 1;
+            replayed_bytes += line.len() as u64 + 1;
+        }
+        if last < sequence {
+            return Err(PersistError::Corrupt {
+                line: 0,
+                message: "event log ends before the checkpoint".to_string(),
+            });
+        }
+
+        let log = OpenOptions::new().write(true).open(&log_path)?;
+        log.set_len(complete as u64)?;
+        let mut log = BufWriter::new(log);
+        io::Seek::seek(log.get_mut(), io::SeekFrom::End(0))?;
+        Ok(Simulation {
+            bank,
+            config,
+            log,
+            sequence: last,
+            events_since_checkpoint: replayed,
+            bytes_since_checkpoint: replayed_bytes,
+        })
+    }
+
+    pub fn bank(&self) -> &Bank {
+        &self.bank
+    }
+
+    /// The number of events applied since the simulation was created.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn config(&self) -> &CheckpointConfig {
+        &self.config
+    }
+
+    /// Logs and applies an event, checkpointing afterwards if a limit in the
+    /// config has been reached. The outer error is an I/O failure; the inner
+    /// one is the event's own result.
+    pub fn apply(
+        &mut self,
+        event: SimulationEvent,
+    ) -> Result<Result<(), AccountError>, PersistError> {
+        let line = format!("{}\t{}\n", self.sequence + 1, event.encode());
+        self.log.write_all(line.as_bytes())?;
+        self.log.flush()?;
+        self.sequence += 1;
+        self.events_since_checkpoint += 1;
+        self.bytes_since_checkpoint += line.len() as u64;
+
+        let outcome = self.bank.apply_event(event);
+        let due_by_count = self
+            .config
+            .every_events
+            .is_some_and(|every| self.events_since_checkpoint >= every);
+        let due_by_size = self
+            .config
+            .max_log_bytes
+            .is_some_and(|limit| self.bytes_since_checkpoint >= limit);
+        if due_by_count || due_by_size {
+            self.checkpoint()?;
+        }
+        Ok(outcome)
+    }
+
+    /// Writes a checkpoint of the current state and prunes old ones. The
+    /// snapshot is written to a temporary file and renamed into place.
+    pub fn checkpoint(&mut self) -> Result<PathBuf, PersistError> {
+        let path = self.config.directory.join(format!(
+            "{}{:020}{}",
+            CHECKPOINT_PREFIX, self.sequence, CHECKPOINT_SUFFIX
+        ));
+        let temporary = path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&temporary)?);
+            self.bank.save_snapshot(&mut writer)?;
+            writer
+                .into_inner()
+                .map_err(|error| error.into_error())?
+                .sync_all()?;
+        }
+        fs::rename(&temporary, &path)?;
+        self.events_since_checkpoint = 0;
+        self.bytes_since_checkpoint = 0;
+
+        let existing = checkpoints(&self.config.directory)?;
+        let excess = existing.len().saturating_sub(self.config.keep.max(1));
+        for (_, old) in existing.into_iter().take(excess) {
+            fs::remove_file(old)?;
+        }
+        Ok(path)
+    }
+
+    /// Stops the simulation, handing back the bank.
+    pub fn into_bank(self) -> Bank {
+        self.bank
+    }
+}
+
+/// The checkpoint files in `directory`, oldest first.
+fn checkpoints(directory: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let sequence = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(CHECKPOINT_PREFIX))
+            .and_then(|name| name.strip_suffix(CHECKPOINT_SUFFIX))
+            .and_then(|number| number.parse::<u64>().ok());
+        if let Some(sequence) = sequence {
+            found.push((sequence, path));
+        }
+    }
+    found.sort();
+    Ok(found)
+}
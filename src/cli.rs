@@ -0,0 +1,466 @@
+//! The `bank` binary's commands and options, and the usage text, shell
+//! completions and man page generated from them. This module belongs to
+//! the binary, not the library.
+
+/// An option taking a value, or a flag when `value` is `None`.
+pub struct Opt {
+    pub long: &'static str,
+    pub short: Option<char>,
+    pub value: Option<&'static str>,
+    /// The values to complete; any value when empty.
+    pub choices: &'static [&'static str],
+    pub about: &'static str,
+}
+
+/// A positional argument. Completes `choices`, or file names when empty.
+pub struct Operand {
+    pub name: &'static str,
+    pub optional: bool,
+    pub choices: &'static [&'static str],
+}
+
+pub struct Command {
+    pub name: &'static str,
+    pub operands: &'static [Operand],
+    pub options: &'static [Opt],
+    pub about: &'static str,
+}
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+pub const GLOBAL_OPTIONS: &[Opt] = &[
+    Opt {
+        long: "format",
+        short: None,
+        value: Some("FORMAT"),
+        choices: &["plain", "table", "json"],
+        about: "Print plain text, an aligned table, or one JSON object",
+    },
+    Opt {
+        long: "help",
+        short: Some('h'),
+        value: None,
+        choices: &[],
+        about: "Print usage and exit",
+    },
+];
+
+/// Skips the confirmation commands that change or reverse money ask for.
+const YES: Opt = Opt {
+    long: "yes",
+    short: Some('y'),
+    value: None,
+    choices: &[],
+    about: "Go ahead without asking for confirmation",
+};
+
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "new-account",
+        operands: &[Operand {
+            name: "STATE_FILE",
+            optional: false,
+            choices: &[],
+        }],
+        options: &[],
+        about: "Open an account in a snapshot through an interactive wizard",
+    },
+    Command {
+        name: "seed",
+        operands: &[Operand {
+            name: "STATE_FILE",
+            optional: true,
+            choices: &[],
+        }],
+        options: &[
+            Opt {
+                long: "accounts",
+                short: None,
+                value: Some("N"),
+                choices: &[],
+                about: "Number of accounts to generate",
+            },
+            Opt {
+                long: "history-days",
+                short: None,
+                value: Some("N"),
+                choices: &[],
+                about: "Days of history to generate",
+            },
+            Opt {
+                long: "seed",
+                short: None,
+                value: Some("N"),
+                choices: &[],
+                about: "Seed for a reproducible bank",
+            },
+            Opt {
+                long: "start",
+                short: None,
+                value: Some("YYYY-MM-DD"),
+                choices: &[],
+                about: "First day of the history",
+            },
+        ],
+        about: "Write a new snapshot of generated sample accounts",
+    },
+    Command {
+        name: "chargeback",
+        operands: &[
+            Operand {
+                name: "STATE_FILE",
+                optional: false,
+                choices: &[],
+            },
+            Operand {
+                name: "TRANSACTION",
+                optional: false,
+                choices: &[],
+            },
+        ],
+        options: &[YES],
+        about: "Reverse a deposit, disputing it first if need be, after confirmation",
+    },
+    Command {
+        name: "import",
+        operands: &[
+            Operand {
+                name: "STATE_FILE",
+                optional: false,
+                choices: &[],
+            },
+            Operand {
+                name: "CSV_FILE",
+                optional: false,
+                choices: &[],
+            },
+        ],
+        options: &[
+            Opt {
+                long: "confirm-above",
+                short: None,
+                value: Some("CENTS"),
+                choices: &[],
+                about: "Ask for confirmation when the batch moves more than this (default 1000000)",
+            },
+            YES,
+        ],
+        about: "Apply a CSV batch of deposits, withdrawals and transfers",
+    },
+    Command {
+        name: "completions",
+        operands: &[Operand {
+            name: "SHELL",
+            optional: false,
+            choices: SHELLS,
+        }],
+        options: &[],
+        about: "Print a completion script for bash, zsh or fish",
+    },
+    Command {
+        name: "man",
+        operands: &[],
+        options: &[],
+        about: "Print the man page",
+    },
+];
+
+fn synopsis(opt: &Opt) -> String {
+    match (opt.value, opt.choices) {
+        (None, _) => format!("--{}", opt.long),
+        (Some(_), choices) if !choices.is_empty() => {
+            format!("--{} {}", opt.long, choices.join("|"))
+        }
+        (Some(value), _) => format!("--{} {}", opt.long, value),
+    }
+}
+
+fn operand_synopsis(operand: &Operand) -> String {
+    let name = match operand.choices {
+        [] => operand.name.to_string(),
+        choices => choices.join("|"),
+    };
+    if operand.optional {
+        format!("[{}]", name)
+    } else {
+        name
+    }
+}
+
+fn command_synopsis(command: &Command) -> String {
+    let mut parts = vec![command.name.to_string()];
+    parts.extend(command.operands.iter().map(operand_synopsis));
+    parts.extend(
+        command
+            .options
+            .iter()
+            .map(|opt| format!("[{}]", synopsis(opt))),
+    );
+    parts.join(" ")
+}
+
+pub fn usage() -> String {
+    let mut lines = vec![format!(
+        "Usage: bank [{}] [COMMAND]",
+        synopsis(&GLOBAL_OPTIONS[0])
+    )];
+    lines.push(String::new());
+    lines.push("Without a command, runs a walk through the bank's features.".to_string());
+    lines.push(String::new());
+    lines.push("Commands:".to_string());
+    for command in COMMANDS {
+        lines.push(format!("  {}", command_synopsis(command)));
+        lines.push(format!("      {}", command.about));
+    }
+    lines.push(String::new());
+    lines.push("Options:".to_string());
+    for opt in GLOBAL_OPTIONS {
+        lines.push(format!("  {}", synopsis(opt)));
+        lines.push(format!("      {}", opt.about));
+    }
+    lines.join("\n")
+}
+
+/// The completion script for `shell`, or `None` if it isn't one of
+/// [`SHELLS`].
+pub fn completions(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash()),
+        "zsh" => Some(zsh()),
+        "fish" => Some(fish()),
+        _ => None,
+    }
+}
+
+fn option_words(options: &[Opt]) -> Vec<String> {
+    options
+        .iter()
+        .map(|opt| format!("--{}", opt.long))
+        .collect()
+}
+
+fn bash() -> String {
+    let mut words: Vec<String> = COMMANDS
+        .iter()
+        .map(|command| command.name.to_string())
+        .collect();
+    words.extend(option_words(GLOBAL_OPTIONS));
+    let mut script = String::from("# bash completion for bank\n_bank() {\n");
+    script.push_str(
+        "    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n",
+    );
+    script.push_str("    case \"$prev\" in\n");
+    let options = GLOBAL_OPTIONS
+        .iter()
+        .chain(COMMANDS.iter().flat_map(|command| command.options));
+    for opt in options.filter(|opt| opt.value.is_some()) {
+        script.push_str(&format!(
+            "        --{}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+            opt.long,
+            opt.choices.join(" ")
+        ));
+    }
+    script.push_str("    esac\n");
+    script.push_str("    local command=\"\" i\n");
+    script.push_str("    for ((i = 1; i < COMP_CWORD; i++)); do\n");
+    script.push_str("        case \"${COMP_WORDS[i]}\" in\n");
+    script.push_str("            --format) ((i++)) ;;\n");
+    script.push_str("            -*) ;;\n");
+    script.push_str("            *) command=\"${COMP_WORDS[i]}\"; break ;;\n");
+    script.push_str("        esac\n");
+    script.push_str("    done\n");
+    script.push_str("    case \"$command\" in\n");
+    script.push_str(&format!(
+        "        \"\") COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;\n",
+        words.join(" ")
+    ));
+    for command in COMMANDS {
+        let options = option_words(command.options).join(" ");
+        let operand = match command.operands.first() {
+            Some(operand) if operand.choices.is_empty() => "compgen -f -- \"$cur\"".to_string(),
+            Some(operand) => format!("compgen -W \"{}\" -- \"$cur\"", operand.choices.join(" ")),
+            None => "true".to_string(),
+        };
+        if options.is_empty() {
+            script.push_str(&format!(
+                "        {}) COMPREPLY=($({})) ;;\n",
+                command.name, operand
+            ));
+        } else {
+            script.push_str(&format!(
+                "        {}) if [[ \"$cur\" == -* ]]; then COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); else COMPREPLY=($({})); fi ;;\n",
+                command.name, options, operand
+            ));
+        }
+    }
+    script.push_str("    esac\n}\ncomplete -F _bank bank\n");
+    script
+}
+
+fn zsh_option(opt: &Opt) -> String {
+    let names = match opt.short {
+        Some(short) => format!("{{-{},--{}}}", short, opt.long),
+        None => format!("--{}", opt.long),
+    };
+    let value = match (opt.value, opt.choices) {
+        (None, _) => String::new(),
+        (Some(value), []) => format!(":{}: ", value),
+        (Some(value), choices) => format!(":{}:({})", value, choices.join(" ")),
+    };
+    match opt.short {
+        Some(_) => format!("{}'[{}]{}'", names, opt.about, value),
+        None => format!("'{}[{}]{}'", names, opt.about, value),
+    }
+}
+
+fn zsh() -> String {
+    let mut script = String::from("#compdef bank\n\n_bank() {\n    local line state\n");
+    script.push_str("    _arguments -C \\\n");
+    for opt in GLOBAL_OPTIONS {
+        script.push_str(&format!("        {} \\\n", zsh_option(opt)));
+    }
+    script.push_str("        '1: :->command' \\\n");
+    script.push_str("        '*:: :->args'\n");
+    script.push_str("    case $state in\n");
+    script.push_str("        command)\n");
+    script.push_str("            local -a commands\n");
+    script.push_str("            commands=(\n");
+    for command in COMMANDS {
+        script.push_str(&format!(
+            "                '{}:{}'\n",
+            command.name, command.about
+        ));
+    }
+    script.push_str("            )\n");
+    script.push_str("            _describe 'command' commands ;;\n");
+    script.push_str("        args)\n");
+    script.push_str("            case $line[1] in\n");
+    for command in COMMANDS {
+        let mut specs: Vec<String> = command.options.iter().map(zsh_option).collect();
+        for (index, operand) in command.operands.iter().enumerate() {
+            let action = match operand.choices {
+                [] => "_files".to_string(),
+                choices => format!("({})", choices.join(" ")),
+            };
+            let colons = if operand.optional { "::" } else { ":" };
+            specs.push(format!(
+                "'{}{}{}:{}'",
+                index + 1,
+                colons,
+                operand.name,
+                action
+            ));
+        }
+        if specs.is_empty() {
+            continue;
+        }
+        script.push_str(&format!(
+            "                {}) _arguments {} ;;\n",
+            command.name,
+            specs.join(" ")
+        ));
+    }
+    script.push_str("            esac ;;\n");
+    script.push_str("    esac\n}\n\n_bank \"$@\"\n");
+    script
+}
+
+fn fish_option(condition: &str, opt: &Opt) -> String {
+    let mut line = format!("complete -c bank{} -l {}", condition, opt.long);
+    if let Some(short) = opt.short {
+        line.push_str(&format!(" -s {}", short));
+    }
+    match (opt.value, opt.choices) {
+        (None, _) => {}
+        (Some(_), []) => line.push_str(" -x"),
+        (Some(_), choices) => line.push_str(&format!(" -xa '{}'", choices.join(" "))),
+    }
+    line.push_str(&format!(" -d '{}'\n", opt.about));
+    line
+}
+
+fn fish() -> String {
+    let mut script = String::from("# fish completion for bank\ncomplete -c bank -f\n");
+    for opt in GLOBAL_OPTIONS {
+        script.push_str(&fish_option("", opt));
+    }
+    for command in COMMANDS {
+        script.push_str(&format!(
+            "complete -c bank -n __fish_use_subcommand -a {} -d '{}'\n",
+            command.name, command.about
+        ));
+    }
+    for command in COMMANDS {
+        let condition = format!(" -n '__fish_seen_subcommand_from {}'", command.name);
+        match command.operands.first() {
+            Some(operand) if operand.choices.is_empty() => {
+                script.push_str(&format!("complete -c bank{} -F\n", condition));
+            }
+            Some(operand) => script.push_str(&format!(
+                "complete -c bank{} -xa '{}'\n",
+                condition,
+                operand.choices.join(" ")
+            )),
+            None => {}
+        }
+        for opt in command.options {
+            script.push_str(&fish_option(&condition, opt));
+        }
+    }
+    script
+}
+
+/// Escapes `text` for roff: backslashes and hyphens, which would otherwise
+/// be typeset as hyphenation points.
+fn roff(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('-', "\\-")
+}
+
+pub fn man_page() -> String {
+    let mut page = format!(
+        ".TH BANK 1 \"\" \"bank {}\" \"User Commands\"\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    page.push_str(".SH NAME\nbank \\- walk through and administer a simple bank\n");
+    page.push_str(".SH SYNOPSIS\n.B bank\n");
+    page.push_str(&format!(
+        "[{}] [\\fICOMMAND\\fR]\n",
+        roff(&synopsis(&GLOBAL_OPTIONS[0]))
+    ));
+    page.push_str(".SH DESCRIPTION\n");
+    page.push_str(
+        "Without a command, \\fBbank\\fR runs a walk through the bank's features: \
+         accounts, transfers, a declined withdrawal and an end of day.\n",
+    );
+    page.push_str(".SH COMMANDS\n");
+    for command in COMMANDS {
+        page.push_str(&format!(
+            ".TP\n\\fB{}\\fR\n{}\n",
+            roff(&command_synopsis(command)),
+            roff(command.about)
+        ));
+        if command.options.is_empty() {
+            continue;
+        }
+        page.push_str(".RS\n");
+        for opt in command.options {
+            page.push_str(&format!(
+                ".TP\n\\fB{}\\fR\n{}\n",
+                roff(&synopsis(opt)),
+                roff(opt.about)
+            ));
+        }
+        page.push_str(".RE\n");
+    }
+    page.push_str(".SH OPTIONS\n");
+    for opt in GLOBAL_OPTIONS {
+        page.push_str(&format!(
+            ".TP\n\\fB{}\\fR\n{}\n",
+            roff(&synopsis(opt)),
+            roff(opt.about)
+        ));
+    }
+    page.push_str(".SH FILES\n.TP\n\\fIbank.snapshot\\fR\n");
+    page.push_str("Where \\fBseed\\fR writes when no \\fISTATE_FILE\\fR is given.\n");
+    page
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+
+use crate::bank::Bank;
+
+/// A value that differs between the two banks, for one account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch<T> {
+    pub account_id: u32,
+    /// The value in the bank [`Bank::reconcile_against`] was called on.
+    pub ours: T,
+    pub theirs: T,
+}
+
+/// The result of [`Bank::reconcile_against`]. Account ids are in
+/// ascending order throughout.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BankComparison {
+    pub only_ours: Vec<u32>,
+    pub only_theirs: Vec<u32>,
+    pub balance_mismatches: Vec<Mismatch<i64>>,
+    /// Accounts whose histories hold a different number of entries.
+    pub transaction_count_mismatches: Vec<Mismatch<usize>>,
+}
+
+impl BankComparison {
+    /// Whether the two banks agree on every account checked.
+    pub fn is_consistent(&self) -> bool {
+        self.only_ours.is_empty()
+            && self.only_theirs.is_empty()
+            && self.balance_mismatches.is_empty()
+            && self.transaction_count_mismatches.is_empty()
+    }
+}
+
+impl fmt::Display for BankComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_consistent() {
+            return writeln!(f, "Banks match");
+        }
+        writeln!(
+            f,
+            "{} accounts only here, {} only there, {} balances and {} histories differ",
+            self.only_ours.len(),
+            self.only_theirs.len(),
+            self.balance_mismatches.len(),
+            self.transaction_count_mismatches.len()
+        )?;
+        for id in &self.only_ours {
+            writeln!(f, "  Account {}: only here", id)?;
+        }
+        for id in &self.only_theirs {
+            writeln!(f, "  Account {}: only there", id)?;
+        }
+        for mismatch in &self.balance_mismatches {
+            writeln!(
+                f,
+                "  Account {}: balance ${:.2} here, ${:.2} there",
+                mismatch.account_id,
+                mismatch.ours as f64 / 100.0,
+                mismatch.theirs as f64 / 100.0
+            )?;
+        }
+        for mismatch in &self.transaction_count_mismatches {
+            writeln!(
+                f,
+                "  Account {}: {} transactions here, {} there",
+                mismatch.account_id, mismatch.ours, mismatch.theirs
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    /// Compares this bank with `other` account by account, e.g. to check
+    /// that a migrated or replicated copy matches the original.
+    pub fn reconcile_against(&self, other: &Bank) -> BankComparison {
+        let mut comparison = BankComparison::default();
+        for id in self.sorted_account_ids() {
+            let ours = &self.accounts[&id];
+            let Some(theirs) = other.accounts.get(&id) else {
+                comparison.only_ours.push(id);
+                continue;
+            };
+            if ours.balance != theirs.balance {
+                comparison.balance_mismatches.push(Mismatch {
+                    account_id: id,
+                    ours: ours.balance,
+                    theirs: theirs.balance,
+                });
+            }
+            if ours.history.len() != theirs.history.len() {
+                comparison.transaction_count_mismatches.push(Mismatch {
+                    account_id: id,
+                    ours: ours.history.len(),
+                    theirs: theirs.history.len(),
+                });
+            }
+        }
+        comparison.only_theirs = other
+            .sorted_account_ids()
+            .into_iter()
+            .filter(|id| !self.accounts.contains_key(id))
+            .collect();
+        comparison
+    }
+}
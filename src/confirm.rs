@@ -0,0 +1,38 @@
+//! The yes-or-no question the CLI asks before destructive commands, after
+//! showing what the command would do.
+
+use std::io::{self, BufRead, Write};
+
+use crate::wizard::yes_or_no;
+
+/// Shows a preview of an action's effect and asks the operator to go ahead.
+#[derive(Debug)]
+pub struct Confirmation<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Confirmation<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Confirmation { input, output }
+    }
+
+    /// Prints `preview` and asks until the answer is yes or no. Running out
+    /// of input counts as no, so nothing happens unattended.
+    pub fn ask(&mut self, preview: &str) -> io::Result<bool> {
+        writeln!(self.output, "{}\n", preview)?;
+        loop {
+            write!(self.output, "Proceed? (y/n): ")?;
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                writeln!(self.output)?;
+                return Ok(false);
+            }
+            match yes_or_no(line.trim()) {
+                Ok(answer) => return Ok(answer),
+                Err(message) => writeln!(self.output, "  {}", message)?,
+            }
+        }
+    }
+}
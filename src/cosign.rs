@@ -0,0 +1,231 @@
+use crate::account::{Account, AccountRef, Hold};
+use crate::bank::Bank;
+use crate::error::{AccountError, PolicyError, StateError};
+use crate::receipt::Receipt;
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// A second person who must approve an account's larger withdrawals and
+/// transfers out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoSigner {
+    pub name: String,
+    /// Withdrawals and transfers above this many cents wait for approval.
+    pub threshold: i64,
+    /// Requests not approved within this many days lapse.
+    pub expiry_days: u32,
+}
+
+impl CoSigner {
+    pub fn new(name: &str, threshold: i64, expiry_days: u32) -> Self {
+        CoSigner {
+            name: name.to_string(),
+            threshold,
+            expiry_days,
+        }
+    }
+}
+
+/// A withdrawal or transfer waiting for the co-signer. Its amount is held
+/// until it is approved, rejected or lapses; the request shares its id with
+/// the hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingWithdrawal {
+    pub id: u64,
+    pub amount: i64,
+    pub category: Option<String>,
+    /// The account a transfer goes to once approved; `None` for a
+    /// withdrawal.
+    pub to: Option<u32>,
+    pub requested_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+impl Account {
+    pub fn co_signer(&self) -> Option<&CoSigner> {
+        self.co_signer.as_ref()
+    }
+
+    /// Requires `co_signer` to approve withdrawals and transfers above their
+    /// threshold,
+    /// or lifts the requirement with `None`. Requests already pending are
+    /// kept.
+    pub fn set_co_signer(&mut self, co_signer: Option<CoSigner>) {
+        self.co_signer = co_signer;
+    }
+
+    pub fn pending_withdrawals(&self) -> &[PendingWithdrawal] {
+        &self.pending_withdrawals
+    }
+
+    /// Turns a withdrawal, or a transfer `to` another account, above the
+    /// co-signer threshold into a pending request, holding its amount, and
+    /// refuses it with [`PolicyError::CoSignatureRequired`]. A minor's
+    /// guardian acts as co-signer when the account has none of its own.
+    /// The request takes the id in `next_request_id`.
+    pub(crate) fn require_co_signature(
+        &mut self,
+        next_request_id: &mut u64,
+        now: Timestamp,
+        amount: i64,
+        category: Option<&str>,
+        to: Option<u32>,
+    ) -> Result<(), AccountError> {
+        let Some(co_signer) = self
+            .co_signer
+            .clone()
+            .or_else(|| self.guardian_approver(now))
+        else {
+            return Ok(());
+        };
+        if amount <= co_signer.threshold {
+            return Ok(());
+        }
+        if self.spendable_balance() < amount {
+            return Err(self.insufficient_funds(amount));
+        }
+        let request_id = *next_request_id;
+        let expires_at = now.plus_days(co_signer.expiry_days);
+        self.holds.push(Hold {
+            id: request_id,
+            amount,
+            expires_at,
+            reason: format!("Awaiting approval by {}", co_signer.name),
+        });
+        self.pending_withdrawals.push(PendingWithdrawal {
+            id: request_id,
+            amount,
+            category: category.map(str::to_string),
+            to,
+            requested_at: now,
+            expires_at,
+        });
+        *next_request_id += 1;
+        Err(AccountError::Policy(PolicyError::CoSignatureRequired {
+            request_id,
+        }))
+    }
+}
+
+impl Bank {
+    /// [`Account::require_co_signature`] for a withdrawal from `id`.
+    pub(crate) fn require_co_signature(
+        &mut self,
+        id: u32,
+        amount: i64,
+        category: Option<&str>,
+    ) -> Result<(), AccountError> {
+        let account = self
+            .accounts
+            .get_mut(&id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        account.require_co_signature(&mut self.next_item_id, self.now, amount, category, None)
+    }
+
+    /// Carries out a pending withdrawal or transfer once `co_signer`
+    /// approves it. The hold is released first, so it is checked against
+    /// the balance as it is now.
+    pub fn approve_withdrawal(
+        &mut self,
+        account: impl Into<AccountRef>,
+        request_id: u64,
+        co_signer: &str,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        let account = &self.accounts[&id];
+        if account
+            .co_signer
+            .as_ref()
+            .is_none_or(|expected| expected.name != co_signer)
+        {
+            return Err(AccountError::Policy(PolicyError::WrongCoSigner));
+        }
+        let request = self.take_pending_withdrawal(id, request_id)?;
+        self.carry_out(id, request)
+    }
+
+    /// Books an approved request: the transfer it names, without asking
+    /// for approval again, or otherwise the withdrawal. The receipt is for
+    /// the debit on `id`.
+    pub(crate) fn carry_out(
+        &mut self,
+        id: u32,
+        request: PendingWithdrawal,
+    ) -> Result<Receipt, AccountError> {
+        let Some(to) = request.to else {
+            return self.debit_categorized(id, request.amount, request.category.as_deref());
+        };
+        let outcome = self.transfer_between(id, to, request.amount, true, true)?;
+        let transaction_id = self.accounts[&id]
+            .history
+            .iter()
+            .rev()
+            .find(|txn| txn.kind == TransactionKind::TransferOut && txn.counterparty == Some(to))
+            .map(|txn| txn.id);
+        Ok(self.receipt(id, transaction_id, outcome.warnings))
+    }
+
+    /// Drops a pending withdrawal and releases its hold.
+    pub fn reject_withdrawal(
+        &mut self,
+        account: impl Into<AccountRef>,
+        request_id: u64,
+    ) -> Result<PendingWithdrawal, AccountError> {
+        let id = self.resolve(account)?;
+        self.take_pending_withdrawal(id, request_id)
+    }
+
+    pub(crate) fn take_pending_withdrawal(
+        &mut self,
+        id: u32,
+        request_id: u64,
+    ) -> Result<PendingWithdrawal, AccountError> {
+        let now = self.now;
+        let account = self
+            .accounts
+            .get_mut(&id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        let index = account
+            .pending_withdrawals
+            .iter()
+            .position(|request| request.id == request_id)
+            .ok_or(AccountError::State(StateError::WithdrawalRequestNotFound))?;
+        let request = account.pending_withdrawals.remove(index);
+        account.holds.retain(|hold| hold.id != request_id);
+        if request.expires_at <= now {
+            return Err(AccountError::State(StateError::WithdrawalRequestNotFound));
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfers_above_the_threshold_wait_for_the_co_signer() {
+        let mut bank = Bank::new();
+        let mut account = Account::new(1, "Ann".to_string());
+        account.set_co_signer(Some(CoSigner::new("Bob", 1_000, 7)));
+        bank.add_account(account);
+        bank.add_account(Account::new(2, "Cy".to_string()));
+        bank.deposit(1, 60_000).unwrap();
+
+        let refused = bank.transfer(1, 2, 50_000).unwrap_err();
+        let AccountError::Policy(PolicyError::CoSignatureRequired { request_id }) = refused else {
+            panic!("expected a co-signature request, got {:?}", refused);
+        };
+        assert_eq!(bank.get_account(2).unwrap().balance(), 0);
+        assert_eq!(bank.get_account(1).unwrap().spendable_balance(), 10_000);
+
+        let receipt = bank.approve_withdrawal(1, request_id, "Bob").unwrap();
+        assert_eq!((receipt.amount, receipt.balance), (-50_000, 10_000));
+        assert_eq!(bank.get_account(2).unwrap().balance(), 50_000);
+        assert!(bank
+            .get_account(1)
+            .unwrap()
+            .pending_withdrawals()
+            .is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::locale::{Locale, Localize};
+
+/// An ISO 4217 currency code such as `USD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const USD: Currency = Currency(*b"USD");
+    pub const EUR: Currency = Currency(*b"EUR");
+    pub const GEL: Currency = Currency(*b"GEL");
+    pub const JPY: Currency = Currency(*b"JPY");
+    pub const BHD: Currency = Currency(*b"BHD");
+
+    /// Accepts any three ASCII letters, in either case.
+    pub fn new(code: &str) -> Option<Currency> {
+        let bytes: [u8; 3] = code.as_bytes().try_into().ok()?;
+        if !bytes.iter().all(u8::is_ascii_alphabetic) {
+            return None;
+        }
+        Some(Currency(bytes.map(|byte| byte.to_ascii_uppercase())))
+    }
+
+    pub fn code(&self) -> &str {
+        std::str::from_utf8(&self.0).unwrap_or("???")
+    }
+
+    /// Whether the code is one ISO 4217 assigns. [`Currency::new`] accepts
+    /// any three letters; a strict [`ValidationMode`](crate::ValidationMode)
+    /// accepts only these.
+    pub fn is_iso4217(&self) -> bool {
+        ISO_4217_CODES.contains(&self.code())
+    }
+
+    /// Digits after the decimal point in the currency's smallest unit, per
+    /// ISO 4217: 0 for the yen, 3 for the Bahraini dinar, 2 for most.
+    /// Amounts in accounts held in the currency count these units.
+    pub fn minor_units(&self) -> u32 {
+        match &self.0 {
+            b"BIF" | b"CLP" | b"DJF" | b"GNF" | b"ISK" | b"JPY" | b"KMF" | b"KRW" | b"PYG"
+            | b"RWF" | b"UGX" | b"VND" | b"VUV" | b"XAF" | b"XOF" | b"XPF" => 0,
+            b"BHD" | b"IQD" | b"JOD" | b"KWD" | b"LYD" | b"OMR" | b"TND" => 3,
+            _ => 2,
+        }
+    }
+
+    /// The sign written with amounts in the currency, for the few that have
+    /// a well-known one; the rest are written with their code.
+    pub fn symbol(&self) -> Option<&'static str> {
+        match &self.0 {
+            b"USD" => Some("$"),
+            b"EUR" => Some("€"),
+            b"GBP" => Some("£"),
+            b"JPY" => Some("¥"),
+            b"GEL" => Some("₾"),
+            _ => None,
+        }
+    }
+}
+
+/// The active ISO 4217 codes, including funds and precious metals.
+const ISO_4217_CODES: [&str; 178] = [
+    "AED", "AFN", "ALL", "AMD", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT", "BGN",
+    "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD",
+    "CDF", "CHE", "CHF", "CHW", "CLF", "CLP", "CNY", "COP", "COU", "CRC", "CUP", "CVE", "CZK",
+    "DJF", "DKK", "DOP", "DZD", "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL", "GHS",
+    "GIP", "GMD", "GNF", "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR", "IQD",
+    "IRR", "ISK", "JMD", "JOD", "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD", "KYD",
+    "KZT", "LAK", "LBP", "LKR", "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT",
+    "MOP", "MRU", "MUR", "MVR", "MWK", "MXN", "MXV", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK",
+    "NPR", "NZD", "OMR", "PAB", "PEN", "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON", "RSD",
+    "RUB", "RWF", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD", "SSP",
+    "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD", "TZS",
+    "UAH", "UGX", "USD", "USN", "UYI", "UYU", "UYW", "UZS", "VED", "VES", "VND", "VUV", "WST",
+    "XAF", "XAG", "XAU", "XBA", "XBB", "XBC", "XBD", "XCD", "XCG", "XDR", "XOF", "XPD", "XPF",
+    "XPT", "XSU", "XTS", "XUA", "XXX", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::USD
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Amounts kept apart by currency, since minor units of one can't be added
+/// to another's.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CurrencyTotals(BTreeMap<Currency, i64>);
+
+impl CurrencyTotals {
+    pub fn new() -> Self {
+        CurrencyTotals::default()
+    }
+
+    /// Adds `amount` to the total in `currency`, stopping at the limits of
+    /// an `i64`.
+    pub fn add(&mut self, currency: Currency, amount: i64) {
+        if amount == 0 {
+            return;
+        }
+        let total = self.0.entry(currency).or_insert(0);
+        *total = total.saturating_add(amount);
+    }
+
+    /// The total in `currency`, zero if nothing was added in it.
+    pub fn get(&self, currency: Currency) -> i64 {
+        self.0.get(&currency).copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Each currency's total, in code order.
+    pub fn iter(&self) -> impl Iterator<Item = (Currency, i64)> + '_ {
+        self.0.iter().map(|(currency, total)| (*currency, *total))
+    }
+}
+
+impl fmt::Display for CurrencyTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
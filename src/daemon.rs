@@ -0,0 +1,326 @@
+//! Keeps a bank running in the background: state is loaded at startup,
+//! flushed to disk on a timer and once more on shutdown, and an optional
+//! HTTP endpoint reports whether the bank is responding and serves
+//! Prometheus metrics.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::actor::{ActorError, BankActor, BankHandle};
+use crate::bank::Bank;
+use crate::documents::DocumentStorage;
+use crate::retry::RetryPolicy;
+use crate::snapshot::PersistError;
+use crate::wal::WriteAheadLog;
+
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+/// How often background threads check whether they should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaemonConfig {
+    pub state_path: PathBuf,
+    pub flush_interval: Duration,
+    /// `host:port` for the health and metrics endpoints; `None` serves
+    /// neither.
+    pub health_address: Option<String>,
+    pub queue_capacity: usize,
+    /// Where operations are logged before they are applied; `None` keeps
+    /// no log, so anything done since the last flush is lost in a crash.
+    pub wal_path: Option<PathBuf>,
+    /// How writing the state file is retried when it fails transiently.
+    pub retry_policy: RetryPolicy,
+    /// Keeps the write-ahead log as an audit log: checkpointed at every
+    /// flush instead of compacted, so [`Bank::rebuild_from_audit_log`] can
+    /// restore the bank if the state file is lost.
+    pub audit_log: bool,
+}
+
+impl DaemonConfig {
+    pub fn new(state_path: impl Into<PathBuf>) -> Self {
+        DaemonConfig {
+            state_path: state_path.into(),
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            health_address: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            wal_path: None,
+            retry_policy: RetryPolicy::none(),
+            audit_log: false,
+        }
+    }
+
+    pub fn flush_every(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    pub fn health_on(mut self, address: &str) -> Self {
+        self.health_address = Some(address.to_string());
+        self
+    }
+
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity;
+        self
+    }
+
+    pub fn write_ahead_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wal_path = Some(path.into());
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Logs to `path` and keeps the whole log as an audit log.
+    pub fn audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wal_path = Some(path.into());
+        self.audit_log = true;
+        self
+    }
+}
+
+/// A bank behind a [`BankActor`] whose state survives restarts.
+///
+/// Operations go through [`Daemon::handle`]. [`Daemon::shutdown`] finishes
+/// every operation already queued, then writes the state one last time.
+/// The daemon does not install signal handlers itself; the `bank-daemon`
+/// binary calls `shutdown` on SIGINT and SIGTERM.
+#[derive(Debug)]
+pub struct Daemon {
+    actor: BankActor,
+    state_path: PathBuf,
+    wal_path: Option<PathBuf>,
+    audit_log: bool,
+    retry_policy: RetryPolicy,
+    stopping: Arc<AtomicBool>,
+    flusher: JoinHandle<()>,
+    health: Option<(SocketAddr, JoinHandle<()>)>,
+}
+
+impl Daemon {
+    /// Loads the snapshot at `config.state_path`, or starts an empty bank if
+    /// there is none yet, replays the write-ahead log over it if one is
+    /// configured, and starts the flush and health threads. Documents kept
+    /// inline are moved to [`DocumentStorage::beside`] the state file.
+    pub fn start(config: DaemonConfig) -> Result<Daemon, PersistError> {
+        let mut bank = match File::open(&config.state_path) {
+            Ok(file) => Bank::load_snapshot(BufReader::new(file))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Bank::new(),
+            Err(error) => return Err(error.into()),
+        };
+        if *bank.document_storage() == DocumentStorage::Inline {
+            bank.set_document_storage(DocumentStorage::beside(&config.state_path))?;
+        }
+        let health_listener = match &config.health_address {
+            Some(address) => {
+                let listener = TcpListener::bind(address)?;
+                listener.set_nonblocking(true)?;
+                Some(listener)
+            }
+            None => None,
+        };
+
+        let actor = match &config.wal_path {
+            Some(path) => {
+                let mut wal = WriteAheadLog::open(path)?;
+                if config.audit_log {
+                    wal = wal.as_audit_log();
+                }
+                bank.replay(&wal)?;
+                BankActor::spawn_logged(bank, config.queue_capacity, wal)
+            }
+            None => BankActor::spawn(bank, config.queue_capacity),
+        };
+        let stopping = Arc::new(AtomicBool::new(false));
+        let flusher = {
+            let (handle, stopping) = (actor.handle(), Arc::clone(&stopping));
+            let (path, interval) = (config.state_path.clone(), config.flush_interval);
+            let policy = config.retry_policy;
+            thread::spawn(move || flush_periodically(&handle, &path, interval, &policy, &stopping))
+        };
+        let health = match health_listener {
+            Some(listener) => {
+                let address = listener.local_addr()?;
+                let (handle, stopping) = (actor.handle(), Arc::clone(&stopping));
+                let thread = thread::spawn(move || serve_health(&listener, &handle, &stopping));
+                Some((address, thread))
+            }
+            None => None,
+        };
+        Ok(Daemon {
+            actor,
+            state_path: config.state_path,
+            wal_path: config.wal_path,
+            audit_log: config.audit_log,
+            retry_policy: config.retry_policy,
+            stopping,
+            flusher,
+            health,
+        })
+    }
+
+    pub fn handle(&self) -> BankHandle {
+        self.actor.handle()
+    }
+
+    /// Where the health endpoint is listening, useful after binding port 0.
+    pub fn health_address(&self) -> Option<SocketAddr> {
+        self.health.as_ref().map(|(address, _)| *address)
+    }
+
+    /// Writes the current state now instead of waiting for the timer.
+    pub fn flush(&self) -> Result<(), PersistError> {
+        flush(&self.actor.handle(), &self.state_path, &self.retry_policy)
+    }
+
+    /// Stops the background threads, completes the operations already
+    /// queued and persists the final state, returning the bank.
+    pub fn shutdown(self) -> Result<Bank, PersistError> {
+        self.stopping.store(true, Ordering::SeqCst);
+        let _ = self.flusher.join();
+        if let Some((_, thread)) = self.health {
+            let _ = thread.join();
+        }
+        let bank = self.actor.shutdown();
+        let mut snapshot = Vec::new();
+        bank.save_snapshot(&mut snapshot)?;
+        write_atomically(&self.state_path, &snapshot, &self.retry_policy)?;
+        if let Some(path) = &self.wal_path {
+            let mut wal = WriteAheadLog::open(path)?;
+            if self.audit_log {
+                wal.checkpoint(&bank)?;
+            } else {
+                wal.compact(bank.wal_sequence())?;
+            }
+        }
+        Ok(bank)
+    }
+}
+
+/// The snapshot and the last log entry it holds.
+fn snapshot(handle: &BankHandle) -> Result<(Vec<u8>, u64), PersistError> {
+    let (snapshot, sequence) = handle
+        .call(|bank| {
+            let mut snapshot = Vec::new();
+            let saved = bank.save_snapshot(&mut snapshot).map(|_| snapshot);
+            (saved, bank.wal_sequence())
+        })
+        .map_err(|error| io::Error::other(error.to_string()))?;
+    Ok((snapshot?, sequence))
+}
+
+/// Writes the snapshot, then drops the log entries it holds. Entries logged
+/// meanwhile stay in the log.
+fn flush(handle: &BankHandle, path: &Path, policy: &RetryPolicy) -> Result<(), PersistError> {
+    let (snapshot, sequence) = snapshot(handle)?;
+    write_atomically(path, &snapshot, policy)?;
+    compact_log(handle, sequence)?;
+    Ok(())
+}
+
+fn compact_log(handle: &BankHandle, through: u64) -> io::Result<()> {
+    handle
+        .compact_log(through)
+        .map_err(|error| io::Error::other(error.to_string()))
+}
+
+/// Writes to a temporary file next to `path` and renames it into place, so
+/// a crash mid-write leaves the previous state intact. Transient failures
+/// start the write over, as `policy` allows.
+fn write_atomically(path: &Path, contents: &[u8], policy: &RetryPolicy) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    policy.run(|_| {
+        let mut file = File::create(&temporary)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temporary, path)
+    })?;
+    Ok(())
+}
+
+/// Flushes every `interval` while the state has changed since the last
+/// flush. Failures are retried at the next interval.
+fn flush_periodically(
+    handle: &BankHandle,
+    path: &Path,
+    interval: Duration,
+    policy: &RetryPolicy,
+    stopping: &AtomicBool,
+) {
+    let mut last_flushed: Option<Vec<u8>> = None;
+    let mut due = Instant::now() + interval;
+    while !stopping.load(Ordering::SeqCst) {
+        thread::sleep(POLL_INTERVAL.min(interval));
+        if Instant::now() < due {
+            continue;
+        }
+        due = Instant::now() + interval;
+        let Ok((snapshot, sequence)) = snapshot(handle) else {
+            continue;
+        };
+        if last_flushed.as_ref() != Some(&snapshot)
+            && write_atomically(path, &snapshot, policy).is_ok()
+            && compact_log(handle, sequence).is_ok()
+        {
+            last_flushed = Some(snapshot);
+        }
+    }
+}
+
+/// Answers `GET /health` with 200 while the bank task responds and 503
+/// when it is busy or stopped, and `GET /metrics` with the handle's
+/// [`crate::Metrics`]; every other path is a 404.
+fn serve_health(listener: &TcpListener, handle: &BankHandle, stopping: &AtomicBool) {
+    while !stopping.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let _ = answer_health(stream, handle);
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err(_) => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+fn answer_health(mut stream: TcpStream, handle: &BankHandle) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut request = [0; 1024];
+    let read = stream.read(&mut request)?;
+    let request = String::from_utf8_lossy(&request[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = match path {
+        "/health" => match handle.try_call(|bank| bank.len()) {
+            Ok(accounts) => ("200 OK", format!("ok\naccounts {}\n", accounts)),
+            Err(ActorError::Busy) => ("503 Service Unavailable", "busy\n".to_string()),
+            Err(_) => ("503 Service Unavailable", "stopped\n".to_string()),
+        },
+        // The gauges are left out rather than waiting on a busy bank.
+        "/metrics" => {
+            let gauges = handle
+                .try_call(|bank| (bank.total_balance(), bank.len()))
+                .ok();
+            ("200 OK", handle.metrics().render(gauges))
+        }
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
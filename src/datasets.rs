@@ -0,0 +1,274 @@
+//! Synthetic but realistic transaction histories for exercises and demos:
+//! monthly salaries and rent, everyday spending spread over weighted
+//! categories, and the occasional large purchase.
+
+use std::io::{self, Write};
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::rng::SeededRng;
+use crate::summary::csv_field;
+use crate::time::{Date, Timestamp};
+use crate::uuid::Uuid;
+
+const HOLDERS: [&str; 8] = [
+    "Giorgi", "Nino", "Luka", "Mariam", "Davit", "Ana", "Levan", "Tamar",
+];
+
+/// Everyday purchases happen between these hours.
+const SHOPPING_HOURS: (i64, i64) = (8, 21);
+
+/// A kind of everyday spending and how often it is picked relative to the
+/// others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendingCategory {
+    pub name: String,
+    pub weight: u32,
+    pub min_amount: i64,
+    pub max_amount: i64,
+}
+
+/// What to generate. Amounts are ranges in cents; each account draws its own
+/// salary and rent from them once and keeps them for the whole period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetProfile {
+    pub accounts: usize,
+    pub months: u32,
+    /// Generates this many days of history instead of `months` months.
+    pub days: Option<u32>,
+    pub start: Date,
+    pub seed: u64,
+    pub salary: (i64, i64),
+    pub rent: (i64, i64),
+    pub purchases_per_month: u32,
+    pub spending: Vec<SpendingCategory>,
+    /// The chance of one large purchase in any given month.
+    pub large_purchase_chance: f64,
+    pub large_purchase: (i64, i64),
+}
+
+impl Default for DatasetProfile {
+    fn default() -> Self {
+        let category = |name: &str, weight, min_amount, max_amount| SpendingCategory {
+            name: name.to_string(),
+            weight,
+            min_amount,
+            max_amount,
+        };
+        DatasetProfile {
+            accounts: 10,
+            months: 12,
+            days: None,
+            start: Date::new(2024, 1, 1),
+            seed: 0,
+            salary: (250_000, 600_000),
+            rent: (80_000, 180_000),
+            purchases_per_month: 30,
+            spending: vec![
+                category("groceries", 50, 1_500, 12_000),
+                category("dining", 20, 1_200, 6_000),
+                category("transport", 20, 250, 4_000),
+                category("entertainment", 10, 1_000, 8_000),
+            ],
+            large_purchase_chance: 0.1,
+            large_purchase: (50_000, 250_000),
+        }
+    }
+}
+
+impl DatasetProfile {
+    pub fn new(accounts: usize, months: u32) -> Self {
+        DatasetProfile {
+            accounts,
+            months,
+            ..DatasetProfile::default()
+        }
+    }
+
+    /// `days` days of history rather than whole months.
+    pub fn days(mut self, days: u32) -> Self {
+        self.days = Some(days);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn starting(mut self, start: Date) -> Self {
+        self.start = start;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Event {
+    Salary(i64),
+    Spend(i64, String),
+}
+
+/// Builds a bank with `profile.accounts` accounts and `profile.months`
+/// months (or `profile.days` days) of history starting on `profile.start`.
+/// The same profile always produces the same bank, account references
+/// included. Spending that the balance can't cover is skipped.
+pub fn generate(profile: &DatasetProfile) -> Bank {
+    let mut rng = SeededRng::new(profile.seed);
+    // References come from their own stream so that the histories drawn
+    // from `rng` stay what they were before references were seeded.
+    let mut references = SeededRng::new(!profile.seed);
+    let start = Timestamp::from_date(profile.start);
+    let end = match profile.days {
+        Some(days) => start.plus_days(days),
+        None => Timestamp::from_date(add_months(profile.start, profile.months)),
+    };
+    let mut bank = Bank::starting_at(start);
+
+    // (id, pay day, salary, rent day, rent)
+    let accounts: Vec<(u32, u32, i64, u32, i64)> = (1..=profile.accounts as u32)
+        .map(|id| {
+            let holder = rng.pick(&HOLDERS).copied().unwrap_or("Holder");
+            let salary = rng.range(profile.salary.0, profile.salary.1);
+            // Half a salary to start with, so spending before the first
+            // pay day is not all skipped.
+            let mut account = Account::new(id, holder.to_string());
+            account.reference = seeded_reference(&mut references);
+            account.balance = salary / 2;
+            bank.add_account(account);
+            (
+                id,
+                rng.range(1, 28) as u32,
+                salary,
+                rng.range(1, 5) as u32,
+                rng.range(profile.rent.0, profile.rent.1),
+            )
+        })
+        .collect();
+    let total_weight: u32 = profile
+        .spending
+        .iter()
+        .map(|category| category.weight)
+        .sum();
+
+    let mut day = start;
+    while day < end {
+        let date = day.date();
+        let purchase_chance =
+            f64::from(profile.purchases_per_month) / f64::from(date.days_in_month());
+        let large_purchase_chance = profile.large_purchase_chance / f64::from(date.days_in_month());
+        let mut events: Vec<(u64, u32, Event)> = Vec::new();
+        for &(id, pay_day, salary, rent_day, rent) in &accounts {
+            if date.day == pay_day {
+                events.push((hours(6), id, Event::Salary(salary)));
+            }
+            if date.day == rent_day {
+                events.push((hours(7), id, Event::Spend(rent, "rent".to_string())));
+            }
+            // Several purchases a day are possible when the monthly count
+            // exceeds the number of days.
+            let mut chance = purchase_chance;
+            while total_weight > 0 && chance > 0.0 && rng.chance(chance.min(1.0)) {
+                let category = pick_weighted(&mut rng, &profile.spending, total_weight);
+                let amount = rng.range(category.min_amount, category.max_amount);
+                events.push((
+                    shopping_time(&mut rng),
+                    id,
+                    Event::Spend(amount, category.name.clone()),
+                ));
+                chance -= 1.0;
+            }
+            if rng.chance(large_purchase_chance) {
+                let amount = rng.range(profile.large_purchase.0, profile.large_purchase.1);
+                events.push((
+                    shopping_time(&mut rng),
+                    id,
+                    Event::Spend(amount, "large purchase".to_string()),
+                ));
+            }
+        }
+        events.sort_by_key(|(time, id, _)| (*time, *id));
+        for (time, id, event) in events {
+            bank.set_time(Timestamp(day.seconds() + time));
+            let _ = match event {
+                Event::Salary(amount) => bank.deposit(id, amount),
+                Event::Spend(amount, category) => bank.withdraw_in_category(id, amount, &category),
+            };
+        }
+        day = day.plus_days(1);
+    }
+    bank.set_time(end);
+    bank
+}
+
+/// Writes every booked entry as
+/// `account,holder,transaction,timestamp,kind,category,amount,balance`, in
+/// account id order. Amounts are in cents.
+pub fn write_csv<W: Write>(bank: &Bank, writer: &mut W) -> io::Result<()> {
+    writeln!(
+        writer,
+        "account,holder,transaction,timestamp,kind,category,amount,balance"
+    )?;
+    for id in bank.sorted_account_ids() {
+        let account = &bank.accounts[&id];
+        for txn in &account.history {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                id,
+                csv_field(&account.holder),
+                txn.id,
+                txn.timestamp.seconds(),
+                csv_field(&txn.kind.to_string()),
+                csv_field(txn.category.as_deref().unwrap_or("")),
+                txn.amount,
+                txn.balance_after
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// A version 4 UUID drawn from `rng` instead of the system's randomness.
+fn seeded_reference(rng: &mut SeededRng) -> Uuid {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&rng.next_u64().to_be_bytes());
+    bytes[8..].copy_from_slice(&rng.next_u64().to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    Uuid::from_bytes(bytes)
+}
+
+fn pick_weighted<'a>(
+    rng: &mut SeededRng,
+    categories: &'a [SpendingCategory],
+    total_weight: u32,
+) -> &'a SpendingCategory {
+    let mut roll = rng.range(0, i64::from(total_weight) - 1);
+    for category in categories {
+        roll -= i64::from(category.weight);
+        if roll < 0 {
+            return category;
+        }
+    }
+    &categories[categories.len() - 1]
+}
+
+fn shopping_time(rng: &mut SeededRng) -> u64 {
+    rng.range(SHOPPING_HOURS.0 * 3600, SHOPPING_HOURS.1 * 3600) as u64
+}
+
+fn hours(hours: u64) -> u64 {
+    hours * 3600
+}
+
+/// The same day `months` later, or the last day of that month if it is
+/// shorter.
+fn add_months(date: Date, months: u32) -> Date {
+    let month_index = i64::from(date.year) * 12 + i64::from(date.month - 1) + i64::from(months);
+    let month = Date::new(
+        month_index.div_euclid(12) as i32,
+        month_index.rem_euclid(12) as u32 + 1,
+        1,
+    );
+    Date::new(month.year, month.month, date.day.min(month.days_in_month()))
+}
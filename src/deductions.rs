@@ -0,0 +1,159 @@
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError, StateError};
+use crate::transaction::TransactionKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeductionStatus {
+    Active,
+    Completed,
+    Cancelled,
+}
+
+/// Routes a share of every deposit on `account_id` to `beneficiary_id` until
+/// `target` has been collected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeductionOrder {
+    pub id: u64,
+    pub account_id: u32,
+    pub beneficiary_id: u32,
+    /// Share of each deposit, in basis points (2000 = 20%).
+    pub rate_bps: u32,
+    pub target: i64,
+    pub collected: i64,
+    pub status: DeductionStatus,
+}
+
+impl DeductionOrder {
+    pub fn remaining(&self) -> i64 {
+        self.target - self.collected
+    }
+
+    /// Collected share of the target, in whole percent.
+    pub fn progress_percent(&self) -> u32 {
+        if self.target <= 0 {
+            return 100;
+        }
+        (i128::from(self.collected) * 100 / i128::from(self.target)) as u32
+    }
+}
+
+impl Bank {
+    pub fn attach_deduction_order(
+        &mut self,
+        account: impl Into<AccountRef>,
+        beneficiary: impl Into<AccountRef>,
+        rate_bps: u32,
+        target: i64,
+    ) -> Result<u64, AccountError> {
+        let account_id = self.resolve(account)?;
+        let beneficiary_id = self.resolve(beneficiary)?;
+        if target < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        if account_id == beneficiary_id {
+            return Err(AccountError::Validation(InputError::SameAccount));
+        }
+        if rate_bps == 0 || rate_bps > 10_000 {
+            return Err(AccountError::Validation(InputError::InvalidRate));
+        }
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        self.deduction_orders.push(DeductionOrder {
+            id,
+            account_id,
+            beneficiary_id,
+            rate_bps,
+            target,
+            collected: 0,
+            status: DeductionStatus::Active,
+        });
+        Ok(id)
+    }
+
+    pub fn cancel_deduction_order(&mut self, order_id: u64) -> Result<(), AccountError> {
+        let order = self
+            .deduction_orders
+            .iter_mut()
+            .find(|order| order.id == order_id)
+            .ok_or(AccountError::State(StateError::DeductionOrderNotFound))?;
+        if order.status == DeductionStatus::Active {
+            order.status = DeductionStatus::Cancelled;
+        }
+        Ok(())
+    }
+
+    pub fn deduction_order(&self, order_id: u64) -> Option<&DeductionOrder> {
+        self.deduction_orders
+            .iter()
+            .find(|order| order.id == order_id)
+    }
+
+    pub fn deduction_orders_for(&self, account: impl Into<AccountRef>) -> Vec<&DeductionOrder> {
+        let Ok(id) = self.resolve(account) else {
+            return Vec::new();
+        };
+        self.deduction_orders
+            .iter()
+            .filter(|order| order.account_id == id)
+            .collect()
+    }
+
+    /// Routes the configured share of a just-credited deposit to each active
+    /// order's beneficiary, oldest order first.
+    pub(crate) fn apply_deduction_orders(&mut self, account_id: u32, deposited: i64) {
+        for index in 0..self.deduction_orders.len() {
+            let order = &self.deduction_orders[index];
+            if order.account_id != account_id || order.status != DeductionStatus::Active {
+                continue;
+            }
+            let (order_id, beneficiary_id, rate_bps, remaining) = (
+                order.id,
+                order.beneficiary_id,
+                order.rate_bps,
+                order.remaining(),
+            );
+            let Some(account) = self.accounts.get(&account_id) else {
+                return;
+            };
+            let share = account
+                .rounding
+                .divide(i128::from(deposited) * i128::from(rate_bps), 10_000)
+                as i64;
+            let amount = share.min(remaining).min(account.available_balance());
+            if amount <= 0 {
+                continue;
+            }
+            let Some(beneficiary) = self.accounts.get_mut(&beneficiary_id) else {
+                continue;
+            };
+            let Some(new_balance) = beneficiary.balance.checked_add(amount) else {
+                continue;
+            };
+            beneficiary.balance = new_balance;
+            self.accounts.get_mut(&account_id).unwrap().balance -= amount;
+
+            let memo = format!("Deduction order {}", order_id);
+            self.record(
+                account_id,
+                TransactionKind::TransferOut,
+                -amount,
+                Some(beneficiary_id),
+                &memo,
+            );
+            self.record(
+                beneficiary_id,
+                TransactionKind::TransferIn,
+                amount,
+                Some(account_id),
+                &memo,
+            );
+
+            let order = &mut self.deduction_orders[index];
+            order.collected += amount;
+            if order.collected >= order.target {
+                order.status = DeductionStatus::Completed;
+            }
+        }
+    }
+}
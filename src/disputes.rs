@@ -0,0 +1,135 @@
+use crate::account::Hold;
+use crate::bank::Bank;
+use crate::error::{AccountError, StateError};
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisputeStatus {
+    /// The disputed amount is held.
+    Open,
+    /// The deposit stood and the hold was released.
+    Resolved,
+    /// The deposit was reversed.
+    ChargedBack,
+}
+
+/// A challenge to a deposit, kept on the account it was booked to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dispute {
+    pub transaction_id: u64,
+    pub amount: i64,
+    pub hold_id: u64,
+    pub opened_at: Timestamp,
+    pub status: DisputeStatus,
+}
+
+impl Bank {
+    /// Disputes a deposit, holding its amount until the dispute is resolved
+    /// or charged back. The hold is placed even if the money has already been
+    /// spent, so the available balance may go negative. Each deposit can be
+    /// disputed once. Returns the id of the hold.
+    pub fn dispute(&mut self, transaction_id: u64) -> Result<u64, AccountError> {
+        let txn = self
+            .transaction(transaction_id)
+            .ok_or(AccountError::State(StateError::TransactionNotFound))?;
+        if !matches!(
+            txn.kind,
+            TransactionKind::Deposit | TransactionKind::ChequeDeposit
+        ) {
+            return Err(AccountError::State(StateError::NotDisputable));
+        }
+        let (account_id, amount) = (txn.account_id, txn.amount);
+        let hold_id = self.next_item_id;
+        let now = self.now;
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        if account
+            .disputes
+            .iter()
+            .any(|dispute| dispute.transaction_id == transaction_id)
+        {
+            return Err(AccountError::State(StateError::NotDisputable));
+        }
+        account.holds.push(Hold {
+            id: hold_id,
+            amount,
+            expires_at: Timestamp(u64::MAX),
+            reason: format!("Dispute of transaction #{}", transaction_id),
+        });
+        account.disputes.push(Dispute {
+            transaction_id,
+            amount,
+            hold_id,
+            opened_at: now,
+            status: DisputeStatus::Open,
+        });
+        self.next_item_id += 1;
+        Ok(hold_id)
+    }
+
+    /// Closes a dispute in the holder's favour, releasing the held amount.
+    pub fn resolve_dispute(&mut self, transaction_id: u64) -> Result<(), AccountError> {
+        self.close_dispute(transaction_id, DisputeStatus::Resolved)?;
+        Ok(())
+    }
+
+    /// Closes a dispute by reversing the deposit: the hold is released and
+    /// the amount is debited, even into a negative balance. Returns the id of
+    /// the chargeback entry.
+    pub fn chargeback(&mut self, transaction_id: u64) -> Result<u64, AccountError> {
+        let (account_id, amount) =
+            self.close_dispute(transaction_id, DisputeStatus::ChargedBack)?;
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        account.balance -= amount;
+        let memo = format!("Chargeback of transaction #{}", transaction_id);
+        let txn_id = self.record(
+            account_id,
+            TransactionKind::Chargeback,
+            -amount,
+            None,
+            &memo,
+        );
+        self.amend_transaction(account_id, txn_id, |txn| {
+            txn.metadata.insert(
+                "disputed_transaction".to_string(),
+                transaction_id.to_string(),
+            );
+        });
+        Ok(txn_id)
+    }
+
+    /// Open and closed disputes on an account, oldest first.
+    pub fn disputes(&self, account_id: u32) -> &[Dispute] {
+        self.accounts
+            .get(&account_id)
+            .map(|account| account.disputes.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn close_dispute(
+        &mut self,
+        transaction_id: u64,
+        status: DisputeStatus,
+    ) -> Result<(u32, i64), AccountError> {
+        let (account_id, dispute) = self
+            .accounts
+            .values_mut()
+            .find_map(|account| {
+                let id = account.id;
+                account
+                    .disputes
+                    .iter_mut()
+                    .find(|dispute| {
+                        dispute.transaction_id == transaction_id
+                            && dispute.status == DisputeStatus::Open
+                    })
+                    .map(|dispute| (id, dispute))
+            })
+            .ok_or(AccountError::State(StateError::DisputeNotFound))?;
+        dispute.status = status;
+        let (hold_id, amount) = (dispute.hold_id, dispute.amount);
+        let account = self.accounts.get_mut(&account_id).unwrap();
+        account.holds.retain(|hold| hold.id != hold_id);
+        Ok((account_id, amount))
+    }
+}
@@ -0,0 +1,319 @@
+//! Small documents kept with holders and accounts, such as ID scans and
+//! signed mandates. Each is stored with the SHA-256 of its contents so any
+//! change to it is caught when it is read back. Contents live in the
+//! bank's snapshots or, for a bank persisted to a file, in a directory
+//! beside that file.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::auth::to_hex;
+use crate::bank::Bank;
+use crate::crypto::sha256;
+use crate::error::{AccountError, InputError, StateError, StorageError};
+use crate::time::Timestamp;
+
+/// What a document is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DocumentKind {
+    IdScan,
+    SignedMandate,
+    Other,
+}
+
+impl DocumentKind {
+    pub const ALL: [DocumentKind; 3] = [
+        DocumentKind::IdScan,
+        DocumentKind::SignedMandate,
+        DocumentKind::Other,
+    ];
+
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            DocumentKind::IdScan => "id-scan",
+            DocumentKind::SignedMandate => "mandate",
+            DocumentKind::Other => "other",
+        }
+    }
+
+    pub(crate) fn from_code(code: &str) -> Option<Self> {
+        DocumentKind::ALL
+            .into_iter()
+            .find(|kind| kind.code() == code)
+    }
+}
+
+impl fmt::Display for DocumentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.code())
+    }
+}
+
+/// Who a document belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DocumentOwner {
+    /// A holder, by name, across all their accounts.
+    Holder(String),
+    Account(u32),
+}
+
+impl fmt::Display for DocumentOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentOwner::Holder(holder) => write!(f, "holder {}", holder),
+            DocumentOwner::Account(id) => write!(f, "account {}", id),
+        }
+    }
+}
+
+/// Where document contents are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DocumentStorage {
+    /// In the bank, and so inside its snapshots.
+    #[default]
+    Inline,
+    /// As files in the directory, each named by its content hash.
+    Directory(PathBuf),
+}
+
+impl DocumentStorage {
+    /// A directory beside the bank's state file: the documents of a bank
+    /// saved to `bank.state` go in `bank.state.documents`.
+    pub fn beside(state_path: impl AsRef<Path>) -> Self {
+        let mut directory = state_path.as_ref().as_os_str().to_owned();
+        directory.push(".documents");
+        DocumentStorage::Directory(PathBuf::from(directory))
+    }
+
+    fn write(&self, sha256: &[u8; 32], content: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let DocumentStorage::Directory(directory) = self else {
+            return Ok(Some(content.to_vec()));
+        };
+        let path = file_path(directory, sha256);
+        // Files are named by their contents, so one already there is this
+        // document.
+        if !path.exists() {
+            fs::create_dir_all(directory)?;
+            let temporary = path.with_extension("tmp");
+            fs::write(&temporary, content)?;
+            fs::rename(&temporary, &path)?;
+        }
+        Ok(None)
+    }
+}
+
+fn file_path(directory: &Path, sha256: &[u8; 32]) -> PathBuf {
+    directory.join(format!("{}.doc", to_hex(sha256)))
+}
+
+/// A document attached with [`Bank::attach_document`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub id: u64,
+    pub owner: DocumentOwner,
+    pub kind: DocumentKind,
+    /// The file name it was attached under.
+    pub name: String,
+    pub size: usize,
+    pub sha256: [u8; 32],
+    pub attached_at: Timestamp,
+    /// The contents under [`DocumentStorage::Inline`].
+    pub(crate) content: Option<Vec<u8>>,
+}
+
+impl Document {
+    /// The largest document the bank takes: 1 MiB.
+    pub const MAX_SIZE: usize = 1024 * 1024;
+
+    pub fn sha256_hex(&self) -> String {
+        to_hex(&self.sha256)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} {:<8} {} ({} bytes, sha256 {}) attached {}",
+            self.id,
+            self.owner,
+            self.kind,
+            self.name,
+            self.size,
+            self.sha256_hex(),
+            self.attached_at.date()
+        )
+    }
+}
+
+impl Bank {
+    pub fn document_storage(&self) -> &DocumentStorage {
+        &self.document_storage
+    }
+
+    /// Moves every document's contents to `storage`. Files in a directory
+    /// left behind are not deleted. Stops at the first document that can't
+    /// be read or written, leaving the storage as it was.
+    pub fn set_document_storage(&mut self, storage: DocumentStorage) -> io::Result<()> {
+        let mut moved = BTreeMap::new();
+        for (id, document) in &self.documents {
+            let content = self.document_content(document)?;
+            moved.insert(*id, storage.write(&document.sha256, &content)?);
+        }
+        for (id, content) in moved {
+            self.documents.get_mut(&id).unwrap().content = content;
+        }
+        self.document_storage = storage;
+        Ok(())
+    }
+
+    /// Attaches `content` to `owner` as `name`, returning the document's
+    /// id. The owner must be an account in the bank, or the holder of one.
+    pub fn attach_document(
+        &mut self,
+        owner: DocumentOwner,
+        kind: DocumentKind,
+        name: &str,
+        content: &[u8],
+    ) -> Result<u64, AccountError> {
+        let missing = match &owner {
+            DocumentOwner::Account(id) => {
+                (!self.accounts.contains_key(id)).then_some(StateError::AccountNotFound)
+            }
+            DocumentOwner::Holder(holder) => {
+                let held = self
+                    .accounts
+                    .values()
+                    .any(|account| account.holder == *holder);
+                (!held).then_some(StateError::HolderNotFound)
+            }
+        };
+        if let Some(error) = missing {
+            return Err(AccountError::State(error));
+        }
+        if content.len() > Document::MAX_SIZE {
+            return Err(AccountError::Validation(InputError::DocumentTooLarge {
+                size: content.len(),
+                limit: Document::MAX_SIZE,
+            }));
+        }
+        let sha256 = sha256(content);
+        let stored = self.document_storage.write(&sha256, content)?;
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        self.documents.insert(
+            id,
+            Document {
+                id,
+                owner,
+                kind,
+                name: name.to_string(),
+                size: content.len(),
+                sha256,
+                attached_at: self.now,
+                content: stored,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Attaches the file at `path` under its file name.
+    pub fn attach_document_file(
+        &mut self,
+        owner: DocumentOwner,
+        kind: DocumentKind,
+        path: impl AsRef<Path>,
+    ) -> Result<u64, AccountError> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let content = fs::read(path)?;
+        self.attach_document(owner, kind, &name, &content)
+    }
+
+    pub fn document(&self, id: u64) -> Option<&Document> {
+        self.documents.get(&id)
+    }
+
+    /// Every document, oldest first.
+    pub fn documents(&self) -> Vec<&Document> {
+        self.documents.values().collect()
+    }
+
+    /// The documents attached to `owner`, oldest first. An account's list
+    /// leaves out its holder's documents; ask for those separately.
+    pub fn documents_of(&self, owner: &DocumentOwner) -> Vec<&Document> {
+        self.documents
+            .values()
+            .filter(|document| document.owner == *owner)
+            .collect()
+    }
+
+    /// The contents of document `id`, checked against its hash.
+    pub fn read_document(&self, id: u64) -> Result<Vec<u8>, AccountError> {
+        let document = self
+            .documents
+            .get(&id)
+            .ok_or(AccountError::State(StateError::DocumentNotFound))?;
+        let content = self.document_content(document)?;
+        if sha256(&content) != document.sha256 {
+            return Err(AccountError::Storage(StorageError::DocumentAltered {
+                document_id: id,
+            }));
+        }
+        Ok(content)
+    }
+
+    /// Ids of the documents whose contents are missing or no longer match
+    /// their hash.
+    pub fn verify_documents(&self) -> Vec<u64> {
+        self.documents
+            .keys()
+            .copied()
+            .filter(|id| self.read_document(*id).is_err())
+            .collect()
+    }
+
+    /// Detaches document `id`. Its file is deleted unless another document
+    /// has the same contents.
+    pub fn remove_document(&mut self, id: u64) -> Result<Document, AccountError> {
+        let document = self
+            .documents
+            .get(&id)
+            .ok_or(AccountError::State(StateError::DocumentNotFound))?;
+        if let DocumentStorage::Directory(directory) = &self.document_storage {
+            let shared = self
+                .documents
+                .values()
+                .any(|other| other.id != id && other.sha256 == document.sha256);
+            if !shared {
+                match fs::remove_file(file_path(directory, &document.sha256)) {
+                    Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                        return Err(error.into())
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(self.documents.remove(&id).unwrap())
+    }
+
+    fn document_content(&self, document: &Document) -> io::Result<Vec<u8>> {
+        if let Some(content) = &document.content {
+            return Ok(content.clone());
+        }
+        match &self.document_storage {
+            DocumentStorage::Directory(directory) => {
+                fs::read(file_path(directory, &document.sha256))
+            }
+            DocumentStorage::Inline => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "document contents are missing",
+            )),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Dormant accounts: accounts the holder hasn't used for a set number of
+//! months. As at most banks, a dormant account takes money in but lets none
+//! out until the holder has it reactivated, and may be charged a monthly
+//! dormancy fee that never takes it below zero.
+
+use std::fmt;
+
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::eod::EndOfDayReport;
+use crate::error::{AccountError, PolicyError};
+use crate::time::{Date, Timestamp};
+use crate::transaction::TransactionKind;
+
+const DORMANCY_FEE_MEMO: &str = "Dormancy fee";
+
+/// When accounts become dormant and what that costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DormancyPolicy {
+    /// Months without activity before an account is dormant.
+    pub inactive_months: u32,
+    /// Charged on the last day of each month the account is dormant; zero
+    /// for none.
+    pub monthly_fee: i64,
+}
+
+impl DormancyPolicy {
+    pub fn after_months(months: u32) -> Self {
+        DormancyPolicy {
+            inactive_months: months.max(1),
+            monthly_fee: 0,
+        }
+    }
+
+    pub fn with_fee(mut self, fee: i64) -> Self {
+        self.monthly_fee = fee.max(0);
+        self
+    }
+}
+
+/// One dormant account in a [`DormancyReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DormantAccount {
+    pub account_id: u32,
+    pub holder: String,
+    pub currency: Currency,
+    pub balance: i64,
+    /// The holder's last deposit, withdrawal or outgoing transfer, or when
+    /// the account was opened if there was none.
+    pub last_activity: Option<Timestamp>,
+    pub dormant_since: Timestamp,
+}
+
+/// Every dormant account, longest dormant first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DormancyReport {
+    pub accounts: Vec<DormantAccount>,
+}
+
+impl DormancyReport {
+    /// What the dormant accounts hold in `currency`.
+    pub fn total_balance(&self, currency: Currency) -> i64 {
+        self.accounts
+            .iter()
+            .filter(|account| account.currency == currency)
+            .map(|account| account.balance)
+            .sum()
+    }
+}
+
+impl fmt::Display for DormancyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dormant accounts: {}", self.accounts.len())?;
+        for account in &self.accounts {
+            let last_activity = match account.last_activity {
+                Some(timestamp) => timestamp.date().to_string(),
+                None => "never".to_string(),
+            };
+            write!(
+                f,
+                "\n  {:<8} {:<20} {} {:>12}  last activity {}  dormant since {}",
+                account.account_id,
+                account.holder,
+                account.currency,
+                account.balance,
+                last_activity,
+                account.dormant_since.date()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Account {
+    pub fn is_dormant(&self) -> bool {
+        self.dormant_since.is_some()
+    }
+
+    pub fn dormant_since(&self) -> Option<Timestamp> {
+        self.dormant_since
+    }
+
+    /// When the holder last used the account: their latest deposit,
+    /// withdrawal or outgoing transfer. Interest, fees and money sent in by
+    /// others don't count. Falls back to when the account was opened.
+    pub fn last_activity(&self) -> Option<Timestamp> {
+        self.history
+            .iter()
+            .rev()
+            .find(|txn| {
+                matches!(
+                    txn.kind,
+                    TransactionKind::OpeningBalance
+                        | TransactionKind::Deposit
+                        | TransactionKind::ChequeDeposit
+                        | TransactionKind::Withdrawal
+                        | TransactionKind::TransferOut
+                )
+            })
+            .map(|txn| txn.timestamp)
+            .or(self.opened_at)
+    }
+
+    pub(crate) fn check_not_dormant(&self) -> Result<(), AccountError> {
+        match self.dormant_since {
+            Some(_) => Err(AccountError::Policy(PolicyError::AccountDormant)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Bank {
+    pub fn dormancy_policy(&self) -> Option<DormancyPolicy> {
+        self.dormancy_policy
+    }
+
+    /// `None` stops detecting dormancy and charging for it; accounts
+    /// already dormant stay so until reactivated.
+    pub fn set_dormancy_policy(&mut self, policy: Option<DormancyPolicy>) {
+        self.dormancy_policy = policy;
+    }
+
+    /// Lifts dormancy, typically once the holder has confirmed who they
+    /// are. The inactivity period starts over from now.
+    pub fn reactivate(&mut self, account: impl Into<AccountRef>) -> Result<(), AccountError> {
+        let id = self.resolve(account)?;
+        let now = self.now;
+        let account = self.accounts.get_mut(&id).unwrap();
+        if account.dormant_since.take().is_some() {
+            account.reactivated_at = Some(now);
+        }
+        Ok(())
+    }
+
+    pub fn dormant_accounts(&self) -> DormancyReport {
+        let mut accounts: Vec<DormantAccount> = self
+            .accounts
+            .values()
+            .filter_map(|account| {
+                Some(DormantAccount {
+                    account_id: account.id,
+                    holder: account.holder.clone(),
+                    currency: account.currency,
+                    balance: account.balance,
+                    last_activity: account.last_activity(),
+                    dormant_since: account.dormant_since?,
+                })
+            })
+            .collect();
+        accounts.sort_by_key(|account| (account.dormant_since, account.account_id));
+        DormancyReport { accounts }
+    }
+
+    /// Marks accounts inactive for the policy's period as dormant, then on
+    /// the last day of the month charges dormant accounts the dormancy fee.
+    pub(crate) fn detect_dormancy(&mut self, report: &mut EndOfDayReport) {
+        let Some(policy) = self.dormancy_policy else {
+            return;
+        };
+        let cutoff = Timestamp::from_date(months_before(self.now.date(), policy.inactive_months));
+        let now = self.now;
+        for id in self.sorted_account_ids() {
+            let account = self.accounts.get_mut(&id).unwrap();
+            if account.dormant_since.is_none() {
+                let active_since = account.last_activity().max(account.reactivated_at);
+                if active_since.is_some_and(|active| active < cutoff) {
+                    account.dormant_since = Some(now);
+                    report.accounts_made_dormant += 1;
+                }
+            }
+            if account.dormant_since.is_none()
+                || policy.monthly_fee == 0
+                || !now.date().is_last_day_of_month()
+            {
+                continue;
+            }
+            let fee = policy.monthly_fee.min(account.available_balance());
+            if fee <= 0 || account.charge_fee(fee).is_none() {
+                continue;
+            }
+            let currency = account.currency;
+            self.record(id, TransactionKind::Fee, -fee, None, DORMANCY_FEE_MEMO);
+            report.dormancy_fees.add(currency, fee);
+        }
+    }
+}
+
+/// The same day `months` months earlier, or the month's last day if it is
+/// shorter.
+fn months_before(date: Date, months: u32) -> Date {
+    let index = date.year * 12 + date.month as i32 - 1 - months as i32;
+    let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+    let last_day = Date::new(year, month, 1).days_in_month();
+    Date::new(year, month, date.day.min(last_day))
+}
@@ -0,0 +1,189 @@
+//! Running a bank's clock against the wall clock, either in real time or
+//! sped up, so end of day, scheduled operations and everything else that
+//! waits on the clock happen on their own.
+
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::actor::{ActorError, BankHandle};
+use crate::eod::EndOfDayReport;
+use crate::time::SECONDS_PER_DAY;
+
+const DEFAULT_TICK: Duration = Duration::from_millis(50);
+
+/// How fast simulated time passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationSpeed {
+    /// One simulated second per second.
+    RealTime,
+    /// This many simulated seconds per second.
+    Accelerated(u64),
+}
+
+impl SimulationSpeed {
+    /// A simulated day every second.
+    pub const DAY_PER_SECOND: SimulationSpeed = SimulationSpeed::Accelerated(SECONDS_PER_DAY);
+
+    fn factor(self) -> u128 {
+        match self {
+            SimulationSpeed::RealTime => 1,
+            SimulationSpeed::Accelerated(factor) => u128::from(factor),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ClockState {
+    speed: SimulationSpeed,
+    paused: bool,
+    /// When simulated time was last brought up to date.
+    synced_at: Instant,
+    /// Simulated nanoseconds owed but not yet a whole second.
+    carry_nanos: u128,
+    reports: Vec<EndOfDayReport>,
+}
+
+/// Moves the bank behind a [`BankHandle`] forward with the wall clock,
+/// through [`Bank::advance_time`](crate::Bank::advance_time), so every
+/// end of day and scheduled operation passed on the way runs as it would
+/// have at that time.
+///
+/// The clock is brought up to date every tick and whenever the driver is
+/// paused or its speed changes, so no simulated time is lost or counted
+/// twice. Operations sent through other handles meanwhile see the
+/// simulated time as of the last tick.
+#[derive(Debug)]
+pub struct SimulationDriver {
+    handle: BankHandle,
+    state: Arc<Mutex<ClockState>>,
+    stopping: Arc<AtomicBool>,
+    ticker: Option<JoinHandle<()>>,
+}
+
+impl SimulationDriver {
+    /// Starts driving the bank at `speed`, ticking every 50ms.
+    pub fn start(handle: BankHandle, speed: SimulationSpeed) -> Self {
+        SimulationDriver::with_tick(handle, speed, DEFAULT_TICK)
+    }
+
+    /// Starts driving the bank, bringing its clock up to date every `tick`.
+    pub fn with_tick(handle: BankHandle, speed: SimulationSpeed, tick: Duration) -> Self {
+        let state = Arc::new(Mutex::new(ClockState {
+            speed,
+            paused: false,
+            synced_at: Instant::now(),
+            carry_nanos: 0,
+            reports: Vec::new(),
+        }));
+        let stopping = Arc::new(AtomicBool::new(false));
+        let ticker = {
+            let (handle, state, stopping) =
+                (handle.clone(), Arc::clone(&state), Arc::clone(&stopping));
+            thread::spawn(move || {
+                while !stopping.load(Ordering::SeqCst) {
+                    thread::sleep(tick);
+                    if sync(&handle, &mut lock(&state)).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+        SimulationDriver {
+            handle,
+            state,
+            stopping,
+            ticker: Some(ticker),
+        }
+    }
+
+    /// Stops the clock until [`SimulationDriver::resume`]. Simulated time
+    /// up to now is applied first.
+    pub fn pause(&self) -> Result<(), ActorError> {
+        let mut state = lock(&self.state);
+        sync(&self.handle, &mut state)?;
+        state.paused = true;
+        Ok(())
+    }
+
+    /// Starts the clock again from where it was paused; the time spent
+    /// paused is skipped.
+    pub fn resume(&self) {
+        let mut state = lock(&self.state);
+        if state.paused {
+            state.paused = false;
+            state.synced_at = Instant::now();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        lock(&self.state).paused
+    }
+
+    pub fn speed(&self) -> SimulationSpeed {
+        lock(&self.state).speed
+    }
+
+    /// Changes the speed from now on. Time up to now still passes at the
+    /// old speed.
+    pub fn set_speed(&self, speed: SimulationSpeed) -> Result<(), ActorError> {
+        let mut state = lock(&self.state);
+        sync(&self.handle, &mut state)?;
+        state.speed = speed;
+        Ok(())
+    }
+
+    /// End-of-day reports from the days run since the last call.
+    pub fn take_reports(&self) -> Vec<EndOfDayReport> {
+        mem::take(&mut lock(&self.state).reports)
+    }
+
+    /// Brings the clock up to date one last time and stops the driver,
+    /// returning the reports not yet taken. The bank keeps running.
+    pub fn stop(mut self) -> Result<Vec<EndOfDayReport>, ActorError> {
+        self.stop_ticker();
+        let mut state = lock(&self.state);
+        sync(&self.handle, &mut state)?;
+        Ok(mem::take(&mut state.reports))
+    }
+
+    fn stop_ticker(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        if let Some(ticker) = self.ticker.take() {
+            let _ = ticker.join();
+        }
+    }
+}
+
+impl Drop for SimulationDriver {
+    fn drop(&mut self) {
+        self.stop_ticker();
+    }
+}
+
+fn lock(state: &Mutex<ClockState>) -> MutexGuard<'_, ClockState> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Advances the bank by the simulated time passed since the last sync.
+fn sync(handle: &BankHandle, state: &mut ClockState) -> Result<(), ActorError> {
+    let now = Instant::now();
+    let elapsed = now.duration_since(state.synced_at);
+    state.synced_at = now;
+    if state.paused {
+        return Ok(());
+    }
+    let nanos = state.carry_nanos + elapsed.as_nanos() * state.speed.factor();
+    let seconds = u64::try_from(nanos / 1_000_000_000).unwrap_or(u64::MAX);
+    state.carry_nanos = nanos % 1_000_000_000;
+    if seconds == 0 {
+        return Ok(());
+    }
+    let reports = handle.advance_time(Duration::from_secs(seconds))?;
+    state.reports.extend(reports);
+    Ok(())
+}
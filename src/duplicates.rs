@@ -0,0 +1,89 @@
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::error::{AccountError, PolicyError};
+use crate::outcome::{OperationOutcome, Warning};
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAction {
+    /// Let the transfer through with a [`Warning::PossibleDuplicate`].
+    Warn,
+    /// Refuse with [`PolicyError::PossibleDuplicate`](crate::PolicyError::PossibleDuplicate).
+    Block,
+}
+
+/// Flags a transfer with the same payer, payee and amount as one booked
+/// within the last `window_seconds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuplicateCheck {
+    pub window_seconds: u64,
+    pub action: DuplicateAction,
+}
+
+impl DuplicateCheck {
+    pub fn new(window_seconds: u64, action: DuplicateAction) -> Self {
+        DuplicateCheck {
+            window_seconds,
+            action,
+        }
+    }
+}
+
+impl Bank {
+    pub fn duplicate_check(&self) -> Option<DuplicateCheck> {
+        self.duplicate_check
+    }
+
+    pub fn set_duplicate_check(&mut self, check: Option<DuplicateCheck>) {
+        self.duplicate_check = check;
+    }
+
+    /// Transfers even if the payment looks like a duplicate, for when the
+    /// holder has confirmed they meant to send it again.
+    pub fn transfer_allowing_duplicate(
+        &mut self,
+        from: impl Into<AccountRef>,
+        to: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<OperationOutcome<()>, AccountError> {
+        let from_id = self.id_of(from)?;
+        let to_id = self.id_of(to)?;
+        self.transfer_between(from_id, to_id, amount, true, false)
+    }
+}
+
+impl DuplicateCheck {
+    /// Checks a transfer `from` is about to make, returning a warning to
+    /// attach if it goes ahead.
+    pub(crate) fn check_transfer(
+        &self,
+        now: Timestamp,
+        from: &Account,
+        to_id: u32,
+        amount: i64,
+    ) -> Result<Option<Warning>, AccountError> {
+        let earliest = now.seconds().saturating_sub(self.window_seconds);
+        let previous = from
+            .history
+            .iter()
+            .rev()
+            .take_while(|txn| txn.timestamp.seconds() >= earliest)
+            .find(|txn| {
+                txn.kind == TransactionKind::TransferOut
+                    && txn.counterparty == Some(to_id)
+                    && txn.amount == -amount
+            });
+        match (previous, self.action) {
+            (None, _) => Ok(None),
+            (Some(txn), DuplicateAction::Warn) => Ok(Some(Warning::PossibleDuplicate {
+                previous_transaction: txn.id,
+            })),
+            (Some(txn), DuplicateAction::Block) => {
+                Err(AccountError::Policy(PolicyError::PossibleDuplicate {
+                    previous_transaction: txn.id,
+                }))
+            }
+        }
+    }
+}
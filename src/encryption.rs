@@ -0,0 +1,208 @@
+//! Authenticated encryption of bank snapshots with ChaCha20-Poly1305, keyed
+//! either by a passphrase (PBKDF2-HMAC-SHA256) or a 256-bit key file.
+//!
+//! The file layout is: magic, key mode, KDF iterations, salt, nonce,
+//! ciphertext, tag. Everything before the ciphertext is authenticated too.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+use crate::bank::Bank;
+use crate::crypto;
+use crate::snapshot::PersistError;
+use crate::uuid::random_u64;
+
+const MAGIC: &[u8; 8] = b"BANKENC1";
+const PASSPHRASE_ITERATIONS: u32 = 100_000;
+/// The most KDF iterations a file may ask for. The count is read before the
+/// tag can be checked, so a tampered header must not stall the load.
+const MAX_PASSPHRASE_ITERATIONS: u32 = 10 * PASSPHRASE_ITERATIONS;
+const HEADER_LEN: usize = 8 + 1 + 4 + 16 + 12;
+const TAG_LEN: usize = 16;
+
+/// A 256-bit key for [`Bank::save_encrypted`]. Its `Debug` output never
+/// shows the key itself.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        EncryptionKey(bytes)
+    }
+
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        fill_random(&mut bytes);
+        EncryptionKey(bytes)
+    }
+
+    /// Reads a key file holding the key as 64 hex digits.
+    pub fn from_key_file(path: impl AsRef<Path>) -> Result<Self, PersistError> {
+        let text = fs::read_to_string(path)?;
+        let hex = text.trim();
+        let invalid = || PersistError::Corrupt {
+            line: 1,
+            message: "key file must hold 64 hex digits".to_string(),
+        };
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(EncryptionKey(bytes))
+    }
+
+    pub fn write_key_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let hex: String = self.0.iter().map(|byte| format!("{:02x}", byte)).collect();
+        fs::write(path, hex + "\n")
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptionKey(..)")
+    }
+}
+
+/// What an encrypted snapshot is locked with.
+#[derive(Debug, Clone, Copy)]
+pub enum Secret<'a> {
+    Passphrase(&'a str),
+    Key(&'a EncryptionKey),
+}
+
+impl Secret<'_> {
+    fn mode(&self) -> u8 {
+        match self {
+            Secret::Key(_) => 0,
+            Secret::Passphrase(_) => 1,
+        }
+    }
+
+    fn derive(&self, salt: &[u8; 16], iterations: u32) -> [u8; 32] {
+        match self {
+            Secret::Key(key) => key.0,
+            Secret::Passphrase(passphrase) => {
+                crypto::pbkdf2_sha256(passphrase.as_bytes(), salt, iterations)
+            }
+        }
+    }
+}
+
+/// OS randomness where available, falling back to std's keyed hasher.
+fn fill_random(bytes: &mut [u8]) {
+    let from_os = File::open("/dev/urandom").and_then(|mut file| file.read_exact(bytes));
+    if from_os.is_err() {
+        for chunk in bytes.chunks_mut(8) {
+            chunk.copy_from_slice(&random_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+impl Bank {
+    /// Writes a snapshot encrypted and authenticated with `secret`, so
+    /// holder names and balances never reach the disk in plain text.
+    pub fn save_encrypted<W: Write>(&self, writer: &mut W, secret: Secret<'_>) -> io::Result<()> {
+        let mut plaintext = Vec::new();
+        self.save_snapshot(&mut plaintext)?;
+
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        let iterations = match secret {
+            Secret::Key(_) => 0,
+            Secret::Passphrase(_) => {
+                fill_random(&mut salt);
+                PASSPHRASE_ITERATIONS
+            }
+        };
+        fill_random(&mut nonce);
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(secret.mode());
+        header.extend_from_slice(&iterations.to_be_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce);
+
+        let key = secret.derive(&salt, iterations);
+        let tag = crypto::seal(&key, &nonce, &header, &mut plaintext);
+        writer.write_all(&header)?;
+        writer.write_all(&plaintext)?;
+        writer.write_all(&tag)?;
+        writer.flush()
+    }
+
+    /// Reads a snapshot written by [`Bank::save_encrypted`]. A wrong
+    /// passphrase or key and any tampering both fail with
+    /// [`PersistError::Decryption`].
+    pub fn load_encrypted<R: Read>(
+        mut reader: R,
+        secret: Secret<'_>,
+    ) -> Result<Bank, PersistError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        if data.len() < HEADER_LEN + TAG_LEN || !data.starts_with(MAGIC) {
+            return Err(PersistError::Corrupt {
+                line: 1,
+                message: "not an encrypted snapshot".to_string(),
+            });
+        }
+        if data[8] != secret.mode() {
+            return Err(PersistError::Decryption);
+        }
+        let iterations = u32::from_be_bytes(data[9..13].try_into().unwrap());
+        if iterations > MAX_PASSPHRASE_ITERATIONS {
+            return Err(PersistError::Decryption);
+        }
+        let salt: [u8; 16] = data[13..29].try_into().unwrap();
+        let nonce: [u8; 12] = data[29..HEADER_LEN].try_into().unwrap();
+        let tag: [u8; TAG_LEN] = data[data.len() - TAG_LEN..].try_into().unwrap();
+
+        let key = secret.derive(&salt, iterations);
+        let (header, rest) = data.split_at_mut(HEADER_LEN);
+        let ciphertext_len = rest.len() - TAG_LEN;
+        let ciphertext = &mut rest[..ciphertext_len];
+        if !crypto::open(&key, &nonce, header, ciphertext, &tag) {
+            return Err(PersistError::Decryption);
+        }
+        Bank::load_snapshot(BufReader::new(&ciphertext[..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Account;
+
+    fn bank() -> Bank {
+        let mut bank = Bank::new();
+        bank.add_account(Account::new(1, "Ann".to_string()));
+        bank.deposit(1, 1500).unwrap();
+        bank
+    }
+
+    #[test]
+    fn round_trips_with_a_key() {
+        let key = EncryptionKey::generate();
+        let mut file = Vec::new();
+        bank().save_encrypted(&mut file, Secret::Key(&key)).unwrap();
+        let loaded = Bank::load_encrypted(&file[..], Secret::Key(&key)).unwrap();
+        assert_eq!(loaded.get_account(1).unwrap().balance(), 1500);
+    }
+
+    #[test]
+    fn refuses_a_tampered_iteration_count_before_deriving() {
+        let mut file = Vec::new();
+        bank()
+            .save_encrypted(&mut file, Secret::Passphrase("secret"))
+            .unwrap();
+        file[9..13].copy_from_slice(&u32::MAX.to_be_bytes());
+        let loaded = Bank::load_encrypted(&file[..], Secret::Passphrase("secret"));
+        assert!(matches!(loaded, Err(PersistError::Decryption)));
+    }
+}
@@ -0,0 +1,397 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::bank::Bank;
+use crate::channels::DepositChannel;
+use crate::currency::CurrencyTotals;
+use crate::fx::FxExecution;
+use crate::interest::{accrue_tiered, breakdown_metadata};
+use crate::overdraft_interest::OVERDRAFT_INTEREST_MEMO;
+use crate::scheduler::ScheduledRun;
+use crate::statement::Statement;
+use crate::time::Date;
+use crate::transaction::TransactionKind;
+
+/// `accrued_interest` is kept in basis-point-days of the account currency's
+/// minor unit (cents, yen, fils); this many make one minor unit, so interest
+/// is always posted in whole minor units.
+pub(crate) const INTEREST_UNITS_PER_CENT: i128 = 10_000 * 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndOfDayStep {
+    PostInterest,
+    ApplyFees,
+    DetectDormancy,
+    ClearHolds,
+    ClearCheques,
+    PayPromotions,
+    RunSweeps,
+    ExecuteFxOrders,
+    RevalueUnits,
+    GenerateStatements,
+    SummaryReport,
+}
+
+impl EndOfDayStep {
+    /// Every step, in the order the pipeline runs them.
+    pub const ALL: [EndOfDayStep; 11] = [
+        EndOfDayStep::PostInterest,
+        EndOfDayStep::ApplyFees,
+        EndOfDayStep::DetectDormancy,
+        EndOfDayStep::ClearHolds,
+        EndOfDayStep::ClearCheques,
+        EndOfDayStep::PayPromotions,
+        EndOfDayStep::RunSweeps,
+        EndOfDayStep::ExecuteFxOrders,
+        EndOfDayStep::RevalueUnits,
+        EndOfDayStep::GenerateStatements,
+        EndOfDayStep::SummaryReport,
+    ];
+}
+
+/// Which end-of-day steps run. All steps are enabled by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndOfDayConfig {
+    disabled: Vec<EndOfDayStep>,
+}
+
+impl EndOfDayConfig {
+    pub fn is_enabled(&self, step: EndOfDayStep) -> bool {
+        !self.disabled.contains(&step)
+    }
+
+    pub fn enable(&mut self, step: EndOfDayStep) -> &mut Self {
+        self.disabled.retain(|disabled| *disabled != step);
+        self
+    }
+
+    pub fn disable(&mut self, step: EndOfDayStep) -> &mut Self {
+        if !self.disabled.contains(&step) {
+            self.disabled.push(step);
+        }
+        self
+    }
+
+    pub fn enabled_steps(&self) -> Vec<EndOfDayStep> {
+        EndOfDayStep::ALL
+            .into_iter()
+            .filter(|step| self.is_enabled(*step))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndOfDayReport {
+    pub date: Date,
+    pub steps_run: Vec<EndOfDayStep>,
+    /// Scheduled operations run during the day.
+    pub scheduled: Vec<ScheduledRun>,
+    pub interest_posted: CurrencyTotals,
+    /// Interest charged on overdrawn balances past their grace period.
+    pub overdraft_interest: CurrencyTotals,
+    pub fees_charged: CurrencyTotals,
+    pub fees_skipped: usize,
+    /// How much of the fees charged took balances below zero.
+    pub fees_overdrawn: CurrencyTotals,
+    /// Accounts found dormant today.
+    pub accounts_made_dormant: usize,
+    pub dormancy_fees: CurrencyTotals,
+    pub holds_released: usize,
+    /// Cheques credited, along with deposits other channels held to clear.
+    pub cheques_cleared: usize,
+    pub cheques_cleared_amount: CurrencyTotals,
+    pub promotions_paid: usize,
+    pub promotion_bonuses: CurrencyTotals,
+    pub sweeps_run: usize,
+    pub swept_amount: CurrencyTotals,
+    pub fx_executions: Vec<FxExecution>,
+    pub fx_orders_expired: usize,
+    /// Net gain (or loss) booked on unit-priced accounts.
+    pub revaluation: CurrencyTotals,
+    pub statements: Vec<Statement>,
+    pub summary: Option<String>,
+}
+
+impl EndOfDayReport {
+    fn new(date: Date) -> Self {
+        EndOfDayReport {
+            date,
+            steps_run: Vec::new(),
+            scheduled: Vec::new(),
+            interest_posted: CurrencyTotals::new(),
+            overdraft_interest: CurrencyTotals::new(),
+            fees_charged: CurrencyTotals::new(),
+            fees_skipped: 0,
+            fees_overdrawn: CurrencyTotals::new(),
+            accounts_made_dormant: 0,
+            dormancy_fees: CurrencyTotals::new(),
+            holds_released: 0,
+            cheques_cleared: 0,
+            cheques_cleared_amount: CurrencyTotals::new(),
+            promotions_paid: 0,
+            promotion_bonuses: CurrencyTotals::new(),
+            sweeps_run: 0,
+            swept_amount: CurrencyTotals::new(),
+            fx_executions: Vec::new(),
+            fx_orders_expired: 0,
+            revaluation: CurrencyTotals::new(),
+            statements: Vec::new(),
+            summary: None,
+        }
+    }
+}
+
+impl fmt::Display for EndOfDayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "End of day {}", self.date)?;
+        writeln!(
+            f,
+            "Scheduled operations run: {} ({} failed)",
+            self.scheduled.len(),
+            self.scheduled
+                .iter()
+                .filter(|run| run.result.is_err())
+                .count()
+        )?;
+        writeln!(f, "Interest posted: {}", self.interest_posted)?;
+        writeln!(f, "Overdraft interest charged: {}", self.overdraft_interest)?;
+        writeln!(
+            f,
+            "Fees charged: {} ({} skipped, {} overdrawn)",
+            self.fees_charged, self.fees_skipped, self.fees_overdrawn
+        )?;
+        writeln!(
+            f,
+            "Accounts made dormant: {} ({} dormancy fees)",
+            self.accounts_made_dormant, self.dormancy_fees
+        )?;
+        writeln!(f, "Holds released: {}", self.holds_released)?;
+        writeln!(
+            f,
+            "Cheques cleared: {} ({})",
+            self.cheques_cleared, self.cheques_cleared_amount
+        )?;
+        writeln!(
+            f,
+            "Promotions paid: {} ({})",
+            self.promotions_paid, self.promotion_bonuses
+        )?;
+        writeln!(f, "Sweeps run: {} ({})", self.sweeps_run, self.swept_amount)?;
+        writeln!(
+            f,
+            "FX orders executed: {} ({} expired)",
+            self.fx_executions.len(),
+            self.fx_orders_expired
+        )?;
+        writeln!(f, "Units revalued: {}", self.revaluation)?;
+        write!(f, "Statements generated: {}", self.statements.len())
+    }
+}
+
+impl Bank {
+    pub fn end_of_day_config(&self) -> &EndOfDayConfig {
+        &self.end_of_day_config
+    }
+
+    pub fn end_of_day_config_mut(&mut self) -> &mut EndOfDayConfig {
+        &mut self.end_of_day_config
+    }
+
+    /// Closes the current business day: runs every enabled step with the clock
+    /// at the last second of the day, then moves the clock to the next morning.
+    pub fn end_of_day(&mut self) -> EndOfDayReport {
+        let day_start = self.now.start_of_day();
+        self.now = self.now.end_of_day();
+        let mut report = EndOfDayReport::new(day_start.date());
+        report.scheduled = std::mem::take(&mut self.scheduler.ran);
+
+        for step in self.end_of_day_config.enabled_steps() {
+            match step {
+                EndOfDayStep::PostInterest => self.post_interest(&mut report),
+                EndOfDayStep::ApplyFees => self.apply_fees(&mut report),
+                EndOfDayStep::DetectDormancy => self.detect_dormancy(&mut report),
+                EndOfDayStep::ClearHolds => self.clear_holds(&mut report),
+                EndOfDayStep::ClearCheques => self.clear_cheques(&mut report),
+                EndOfDayStep::PayPromotions => {
+                    (report.promotions_paid, report.promotion_bonuses) = self.pay_promotions();
+                }
+                EndOfDayStep::RunSweeps => {
+                    (report.sweeps_run, report.swept_amount) = self.run_sweeps();
+                }
+                EndOfDayStep::ExecuteFxOrders => {
+                    let fx = self.execute_fx_orders();
+                    report.fx_executions = fx.executed;
+                    report.fx_orders_expired = fx.expired.len();
+                }
+                EndOfDayStep::RevalueUnits => report.revaluation = self.revalue_units(),
+                EndOfDayStep::GenerateStatements => {
+                    report.statements = self
+                        .sorted_account_ids()
+                        .into_iter()
+                        .map(|id| Statement::for_period(&self.accounts[&id], day_start, self.now))
+                        .filter(|statement| !statement.transactions.is_empty())
+                        .collect();
+                    self.notify_statements(&report.statements);
+                }
+                EndOfDayStep::SummaryReport => {
+                    report.summary = Some(format!("{}\n{}", report, self));
+                }
+            }
+            report.steps_run.push(step);
+        }
+
+        self.now = day_start.plus_days(1);
+        report
+    }
+
+    /// Fast-forwards the clock by `duration`, running the end-of-day
+    /// pipeline for every business day that finishes on the way, so
+    /// interest, fees, hold expiry, cheque clearing, sweeps and statements
+    /// happen in the same order as they would day by day. Scheduled
+    /// operations run at their due times in between. Returns one report
+    /// per day closed.
+    pub fn advance_time(&mut self, duration: Duration) -> Vec<EndOfDayReport> {
+        let target = self.now.plus(duration);
+        let mut reports = Vec::new();
+        while self.now.end_of_day() < target {
+            self.run_scheduled_until(self.now.end_of_day());
+            reports.push(self.end_of_day());
+        }
+        self.run_scheduled_until(target);
+        self.now = target;
+        reports
+    }
+
+    fn post_interest(&mut self, report: &mut EndOfDayReport) {
+        for id in self.sorted_account_ids() {
+            let charged = self
+                .accounts
+                .get_mut(&id)
+                .unwrap()
+                .accrue_overdraft_interest();
+            if charged > 0 {
+                self.record(
+                    id,
+                    TransactionKind::Interest,
+                    -charged,
+                    None,
+                    OVERDRAFT_INTEREST_MEMO,
+                );
+                let currency = self.accounts[&id].currency;
+                report.overdraft_interest.add(currency, charged);
+            }
+            let account = self.accounts.get_mut(&id).unwrap();
+            let mut breakdown = Vec::new();
+            if account.balance > 0 {
+                if account.interest_tiers.is_empty() {
+                    account.accrued_interest +=
+                        i128::from(account.balance) * i128::from(account.interest_rate_bps);
+                } else {
+                    breakdown = accrue_tiered(account.balance, &account.interest_tiers);
+                    account.accrued_interest +=
+                        breakdown.iter().map(|tier| tier.units).sum::<i128>();
+                }
+            }
+            let cents = account
+                .rounding
+                .divide(account.accrued_interest, INTEREST_UNITS_PER_CENT)
+                as i64;
+            if cents <= 0 {
+                continue;
+            }
+            let Some(new_balance) = account.balance.checked_add(cents) else {
+                continue;
+            };
+            account.balance = new_balance;
+            account.accrued_interest -= i128::from(cents) * INTEREST_UNITS_PER_CENT;
+            let txn_id = self.record(id, TransactionKind::Interest, cents, None, "");
+            if !breakdown.is_empty() {
+                self.amend_transaction(id, txn_id, |txn| {
+                    txn.metadata = breakdown_metadata(&breakdown);
+                });
+            }
+            self.withhold_interest_tax(id, cents, txn_id);
+            let currency = self.accounts[&id].currency;
+            report.interest_posted.add(currency, cents);
+        }
+    }
+
+    fn apply_fees(&mut self, report: &mut EndOfDayReport) {
+        if !self.now.date().is_last_day_of_month() {
+            return;
+        }
+        for id in self.sorted_account_ids() {
+            let account = self.accounts.get_mut(&id).unwrap();
+            let (fee, currency) = (account.monthly_fee, account.currency);
+            if fee <= 0 {
+                continue;
+            }
+            let Some(overdrawn) = account.charge_fee(fee) else {
+                report.fees_skipped += 1;
+                continue;
+            };
+            self.record(
+                id,
+                TransactionKind::Fee,
+                -fee,
+                None,
+                "Monthly maintenance fee",
+            );
+            report.fees_charged.add(currency, fee);
+            report.fees_overdrawn.add(currency, overdrawn);
+        }
+    }
+
+    fn clear_holds(&mut self, report: &mut EndOfDayReport) {
+        self.end_guardianships();
+        let now = self.now;
+        for account in self.accounts.values_mut() {
+            let before = account.holds.len();
+            account.holds.retain(|hold| hold.expires_at > now);
+            report.holds_released += before - account.holds.len();
+            // Withdrawals still waiting for a co-signer lapse with their hold.
+            account
+                .pending_withdrawals
+                .retain(|request| request.expires_at > now);
+        }
+    }
+
+    fn clear_cheques(&mut self, report: &mut EndOfDayReport) {
+        let now = self.now;
+        for id in self.sorted_account_ids() {
+            let account = self.accounts.get_mut(&id).unwrap();
+            let (cleared, pending) = account
+                .pending_cheques
+                .drain(..)
+                .partition::<Vec<_>, _>(|cheque| cheque.clears_at <= now);
+            account.pending_cheques = pending;
+            for cheque in cleared {
+                let account = self.accounts.get_mut(&id).unwrap();
+                let Some(new_balance) = account.balance.checked_add(cheque.amount) else {
+                    account.pending_cheques.push(cheque);
+                    continue;
+                };
+                account.balance = new_balance;
+                let (kind, memo) = match cheque.channel {
+                    DepositChannel::Cheque => (
+                        TransactionKind::ChequeDeposit,
+                        format!("Cheque {}", cheque.id),
+                    ),
+                    channel => (
+                        TransactionKind::Deposit,
+                        format!("Cleared {} deposit {}", channel, cheque.id),
+                    ),
+                };
+                let txn_id = self.record(id, kind, cheque.amount, None, &memo);
+                self.amend_transaction(id, txn_id, |txn| {
+                    txn.metadata
+                        .insert("channel".to_string(), cheque.channel.code().to_string());
+                });
+                self.apply_deduction_orders(id, cheque.amount);
+                report.cheques_cleared += 1;
+                let currency = self.accounts[&id].currency;
+                report.cheques_cleared_amount.add(currency, cheque.amount);
+            }
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Why an operation failed, grouped by what went wrong: the request itself,
+//! a rule of the bank, the state the bank is in, or the storage under it.
+//!
+//! Every level is `#[non_exhaustive]`, so matches outside this crate need a
+//! catch-all arm and keep compiling as variants are added. Underlying
+//! causes, such as a holder name's broken rules or an I/O error, are
+//! reachable through [`Error::source`].
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use crate::currency::Currency;
+use crate::locale::{Locale, Localize};
+use crate::snapshot::PersistError;
+use crate::validation::ValidationError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AccountError {
+    /// The request is malformed, whatever the state of the bank.
+    Validation(InputError),
+    /// A rule of the bank or the account refused the operation.
+    Policy(PolicyError),
+    /// The bank is not in a state the operation applies to, such as the
+    /// account or item not existing.
+    State(StateError),
+    /// The storage under the bank failed.
+    Storage(StorageError),
+}
+
+/// The validation errors: input that is wrong in itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InputError {
+    NegativeAmount,
+    /// Refused under [`crate::ZeroAmountPolicy::Reject`] or a strict
+    /// [`crate::ValidationMode`].
+    ZeroAmount,
+    AmountOverflow,
+    SameAccount,
+    InvalidRate,
+    /// Adjustments must say why they were made and who made them.
+    MissingAuditDetails,
+    /// The holder name breaks the bank's holder validation.
+    InvalidHolder(ValidationError),
+    /// Tags and categories need some text.
+    EmptyTag,
+    /// Documents can be at most `limit` bytes.
+    DocumentTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// The accounts transferred or planned between aren't all in one
+    /// currency.
+    MixedCurrencies,
+}
+
+/// Operations a limit, control or approval rule refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PolicyError {
+    /// `available` already includes any overdraft the holder opted into;
+    /// `unused_overdraft` is facility that would apply after opting in.
+    /// All three are in `currency`, the account's.
+    InsufficientFunds {
+        requested: i64,
+        available: i64,
+        unused_overdraft: i64,
+        currency: Currency,
+    },
+    /// A matching transfer was booked within the duplicate-check window.
+    PossibleDuplicate { previous_transaction: u64 },
+    /// The account was locked after a chargeback.
+    AccountLocked,
+    /// The account is dormant and lets no money out until it is
+    /// reactivated.
+    AccountDormant,
+    /// The transfer would exceed today's limit for this payee.
+    PayeeLimitExceeded { remaining: i64, currency: Currency },
+    /// The withdrawal is above the co-signer threshold; it is pending as
+    /// `request_id` with its amount held.
+    CoSignatureRequired { request_id: u64 },
+    /// Only the co-signer named on the account can approve.
+    WrongCoSigner,
+    /// The minor's spending would pass the daily cap their guardian set.
+    SpendingCapExceeded { remaining: i64, currency: Currency },
+    /// Only the minor's guardian can change their controls or approve.
+    NotGuardian,
+    /// The debit would take the holder's overdrafts across all their
+    /// accounts in `currency` past the bank's exposure limit.
+    ExposureLimitExceeded {
+        exposure: i64,
+        resulting: i64,
+        limit: i64,
+        currency: Currency,
+    },
+    /// The account needs both holders to approve a change of holder; it is
+    /// pending as `request_id`.
+    OwnershipApprovalRequired { request_id: u64 },
+    /// Only the current or the new holder can approve a change of holder.
+    NotOwnershipParty,
+}
+
+/// Operations on something that isn't there, or isn't in a state they
+/// apply to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StateError {
+    AccountNotFound,
+    /// An account with this id or reference is already in the bank.
+    AccountExists,
+    HoldNotFound,
+    NotEnrolledInRewards,
+    InsufficientRewards,
+    DeductionOrderNotFound,
+    GoalNotFound,
+    SweepRuleNotFound,
+    PromotionNotFound,
+    /// The account holds fund units but its price source has no price.
+    PriceUnavailable,
+    TransactionNotFound,
+    /// Only deposits can be disputed, and each only once.
+    NotDisputable,
+    /// The transaction has no open dispute.
+    DisputeNotFound,
+    /// No such pending withdrawal, or it lapsed before approval.
+    WithdrawalRequestNotFound,
+    FxOrderNotFound,
+    ScheduledOperationNotFound,
+    /// No such pending change of holder.
+    OwnershipTransferNotFound,
+    /// No account in the bank has this holder.
+    HolderNotFound,
+    DocumentNotFound,
+}
+
+/// A failure reading or writing the bank's files.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum StorageError {
+    Io(Arc<io::Error>),
+    Corrupt {
+        line: usize,
+        message: String,
+    },
+    /// Encrypted data failed authentication.
+    Decryption,
+    /// The document's contents are missing or no longer match its hash.
+    DocumentAltered {
+        document_id: u64,
+    },
+}
+
+/// I/O errors are equal when their kind and message are.
+impl PartialEq for StorageError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (StorageError::Io(a), StorageError::Io(b)) => {
+                a.kind() == b.kind() && a.to_string() == b.to_string()
+            }
+            (
+                StorageError::Corrupt { line, message },
+                StorageError::Corrupt {
+                    line: other_line,
+                    message: other_message,
+                },
+            ) => line == other_line && message == other_message,
+            (StorageError::Decryption, StorageError::Decryption) => true,
+            (
+                StorageError::DocumentAltered { document_id },
+                StorageError::DocumentAltered {
+                    document_id: other_id,
+                },
+            ) => document_id == other_id,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for StorageError {}
+
+macro_rules! display_localized {
+    ($($error:ty),*) => {
+        $(
+            impl fmt::Display for $error {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str(&self.localize(Locale::English))
+                }
+            }
+        )*
+    };
+}
+
+display_localized!(
+    AccountError,
+    InputError,
+    PolicyError,
+    StateError,
+    StorageError
+);
+
+/// Transparent over its category: it shows the category's message, so the
+/// source is the category's own cause.
+impl Error for AccountError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AccountError::Validation(error) => error.source(),
+            AccountError::Policy(error) => error.source(),
+            AccountError::State(error) => error.source(),
+            AccountError::Storage(error) => error.source(),
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::InvalidHolder(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Error for PolicyError {}
+
+impl Error for StateError {}
+
+impl Error for StorageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StorageError::Io(error) => Some(error.as_ref()),
+            StorageError::Corrupt { .. }
+            | StorageError::Decryption
+            | StorageError::DocumentAltered { .. } => None,
+        }
+    }
+}
+
+impl From<InputError> for AccountError {
+    fn from(error: InputError) -> Self {
+        AccountError::Validation(error)
+    }
+}
+
+impl From<PolicyError> for AccountError {
+    fn from(error: PolicyError) -> Self {
+        AccountError::Policy(error)
+    }
+}
+
+impl From<StateError> for AccountError {
+    fn from(error: StateError) -> Self {
+        AccountError::State(error)
+    }
+}
+
+impl From<StorageError> for AccountError {
+    fn from(error: StorageError) -> Self {
+        AccountError::Storage(error)
+    }
+}
+
+impl From<ValidationError> for AccountError {
+    fn from(error: ValidationError) -> Self {
+        AccountError::Validation(InputError::InvalidHolder(error))
+    }
+}
+
+impl From<PersistError> for AccountError {
+    fn from(error: PersistError) -> Self {
+        AccountError::Storage(error.into())
+    }
+}
+
+impl From<io::Error> for AccountError {
+    fn from(error: io::Error) -> Self {
+        AccountError::Storage(error.into())
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(error: io::Error) -> Self {
+        StorageError::Io(Arc::new(error))
+    }
+}
+
+impl From<PersistError> for StorageError {
+    fn from(error: PersistError) -> Self {
+        match error {
+            PersistError::Io(error) => StorageError::Io(Arc::new(error)),
+            PersistError::Corrupt { line, message } => StorageError::Corrupt { line, message },
+            PersistError::Decryption => StorageError::Decryption,
+        }
+    }
+}
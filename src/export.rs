@@ -0,0 +1,166 @@
+//! Transaction history exports for data-analysis pipelines.
+
+use std::io::{self, Write};
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::json;
+use crate::snapshot::kind_code;
+use crate::transaction::Transaction;
+
+/// The version of the exported record layout. It goes up whenever a column
+/// is added, removed or changes meaning, so pipelines can tell layouts
+/// apart. Every JSON Lines record carries it; Parquet files keep it in
+/// their key-value metadata.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Rows written per chunk, unless the caller picks another size.
+pub const DEFAULT_EXPORT_CHUNK: usize = 10_000;
+
+/// What an export wrote.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub rows: usize,
+    /// How many chunks the rows were written in; for Parquet, the number of
+    /// row groups.
+    pub chunks: usize,
+}
+
+impl Bank {
+    /// Every booked entry in account id order, oldest first within an
+    /// account.
+    fn export_rows(&self) -> impl Iterator<Item = (&Account, &Transaction)> + '_ {
+        self.sorted_account_ids().into_iter().flat_map(|id| {
+            let account = &self.accounts[&id];
+            account.history.iter().map(move |txn| (account, txn))
+        })
+    }
+
+    /// Writes the transaction history as JSON Lines, one object per entry
+    /// with `schema_version`, `account_id`, `holder`, `currency`,
+    /// `transaction_id`, `timestamp` (Unix seconds), `kind`, `amount`,
+    /// `balance_after` (in the account currency's minor unit),
+    /// `counterparty`, `category` and `memo`. Absent values are `null`.
+    ///
+    /// Lines are buffered and written `chunk_rows` at a time, so memory use
+    /// stays flat however long the history is.
+    pub fn export_jsonl<W: Write>(
+        &self,
+        writer: &mut W,
+        chunk_rows: usize,
+    ) -> io::Result<ExportSummary> {
+        let chunk_rows = chunk_rows.max(1);
+        let mut summary = ExportSummary::default();
+        let mut chunk = String::new();
+        let mut in_chunk = 0;
+        for (account, txn) in self.export_rows() {
+            chunk.push_str(&format!(
+                "{{\"schema_version\":{},\"account_id\":{},\"holder\":{},\"currency\":{},\"transaction_id\":{},\"timestamp\":{},\"kind\":{},\"amount\":{},\"balance_after\":{},\"counterparty\":{},\"category\":{},\"memo\":{}}}\n",
+                EXPORT_SCHEMA_VERSION,
+                account.id,
+                json::string(&account.holder),
+                json::string(account.currency.code()),
+                txn.id,
+                txn.timestamp.seconds(),
+                json::string(kind_code(txn.kind)),
+                txn.amount,
+                txn.balance_after,
+                txn.counterparty
+                    .map_or("null".to_string(), |id| id.to_string()),
+                txn.category
+                    .as_deref()
+                    .map_or("null".to_string(), json::string),
+                json::string(&txn.memo)
+            ));
+            in_chunk += 1;
+            summary.rows += 1;
+            if in_chunk == chunk_rows {
+                writer.write_all(chunk.as_bytes())?;
+                chunk.clear();
+                in_chunk = 0;
+                summary.chunks += 1;
+            }
+        }
+        if in_chunk > 0 {
+            writer.write_all(chunk.as_bytes())?;
+            summary.chunks += 1;
+        }
+        writer.flush()?;
+        Ok(summary)
+    }
+
+    /// Writes the transaction history as a Parquet file with the same
+    /// columns as [`Bank::export_jsonl`] (less `schema_version`, which is in
+    /// the file metadata), one row group per `chunk_rows` entries.
+    /// Integers are INT64 and text is UTF-8; `counterparty` and `category`
+    /// are optional columns.
+    #[cfg(feature = "parquet")]
+    pub fn export_parquet<W: Write>(
+        &self,
+        writer: W,
+        chunk_rows: usize,
+    ) -> io::Result<ExportSummary> {
+        use crate::parquet::{ColumnSpec, ColumnType, ColumnValues, ParquetWriter};
+
+        let column = |name, kind, optional| ColumnSpec {
+            name,
+            kind,
+            optional,
+        };
+        let schema = vec![
+            column("account_id", ColumnType::Int64, false),
+            column("holder", ColumnType::Utf8, false),
+            column("currency", ColumnType::Utf8, false),
+            column("transaction_id", ColumnType::Int64, false),
+            column("timestamp", ColumnType::Int64, false),
+            column("kind", ColumnType::Utf8, false),
+            column("amount", ColumnType::Int64, false),
+            column("balance_after", ColumnType::Int64, false),
+            column("counterparty", ColumnType::Int64, true),
+            column("category", ColumnType::Utf8, true),
+            column("memo", ColumnType::Utf8, false),
+        ];
+        let width = schema.len();
+        let mut parquet = ParquetWriter::new(writer, schema)?;
+        let chunk_rows = chunk_rows.max(1);
+        let mut summary = ExportSummary::default();
+        let mut columns = vec![ColumnValues::default(); width];
+        let mut in_chunk = 0;
+        for (account, txn) in self.export_rows() {
+            columns[0].push_int(i64::from(account.id));
+            columns[1].push_text(&account.holder);
+            columns[2].push_text(account.currency.code());
+            columns[3].push_int(txn.id as i64);
+            columns[4].push_int(txn.timestamp.seconds() as i64);
+            columns[5].push_text(kind_code(txn.kind));
+            columns[6].push_int(txn.amount);
+            columns[7].push_int(txn.balance_after);
+            match txn.counterparty {
+                Some(id) => columns[8].push_int(i64::from(id)),
+                None => columns[8].push_null(),
+            }
+            match &txn.category {
+                Some(category) => columns[9].push_text(category),
+                None => columns[9].push_null(),
+            }
+            columns[10].push_text(&txn.memo);
+            in_chunk += 1;
+            summary.rows += 1;
+            if in_chunk == chunk_rows {
+                parquet.write_row_group(&columns)?;
+                columns = vec![ColumnValues::default(); width];
+                in_chunk = 0;
+                summary.chunks += 1;
+            }
+        }
+        if in_chunk > 0 {
+            parquet.write_row_group(&columns)?;
+            summary.chunks += 1;
+        }
+        parquet.finish(
+            &[("schema_version", EXPORT_SCHEMA_VERSION.to_string())],
+            concat!("bank ", env!("CARGO_PKG_VERSION")),
+        )?;
+        Ok(summary)
+    }
+}
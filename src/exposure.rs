@@ -0,0 +1,143 @@
+//! A bank-wide cap on how much one holder may borrow across all their
+//! accounts. Overdrafts are the only borrowing the bank offers, so a
+//! holder's exposure is what their accounts are overdrawn by in total.
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::error::{AccountError, PolicyError};
+
+/// A holder's exposure limit and what their accounts outside a given set
+/// are overdrawn by, so changes to that set can be checked while it is
+/// borrowed apart from the bank.
+pub(crate) struct ExposureCheck {
+    limit: i64,
+    holder: String,
+    currency: Currency,
+    elsewhere: i64,
+}
+
+impl ExposureCheck {
+    /// Refuses `changes` to the accounts left out of the check, as
+    /// `(account, amount)` pairs, if they would take the holder past the
+    /// limit. Changes that leave a holder already past it no worse off are
+    /// allowed.
+    pub(crate) fn check(&self, changes: &[(&Account, i64)]) -> Result<(), AccountError> {
+        let mut exposure = self.elsewhere;
+        let mut resulting = self.elsewhere;
+        for (account, change) in changes {
+            if account.holder != self.holder || account.currency != self.currency {
+                continue;
+            }
+            exposure = exposure.saturating_add(account.overdrawn());
+            let balance = account.balance.saturating_add(*change);
+            resulting = resulting.saturating_add(balance.min(0).saturating_neg());
+        }
+        if resulting > self.limit && resulting > exposure {
+            return Err(AccountError::Policy(PolicyError::ExposureLimitExceeded {
+                exposure,
+                resulting,
+                limit: self.limit,
+                currency: self.currency,
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    pub fn holder_exposure_limit(&self) -> Option<i64> {
+        self.holder_exposure_limit
+    }
+
+    /// Caps each holder's total overdraft across their accounts in any one
+    /// currency, in its minor units. Debits that would take a holder past
+    /// it are refused; fees and interest the bank charges are not.
+    pub fn set_holder_exposure_limit(&mut self, limit: Option<i64>) {
+        self.holder_exposure_limit = limit;
+    }
+
+    /// How far `holder`'s accounts are overdrawn in total. Holders are
+    /// matched by name.
+    pub fn holder_exposure(&self, holder: &str) -> i64 {
+        self.accounts
+            .values()
+            .filter(|account| account.holder == holder)
+            .map(|account| account.overdrawn())
+            .sum()
+    }
+
+    /// The exposure check for changes to `ids`, against the first one's
+    /// holder in its currency, or `None` when no limit is set.
+    pub(crate) fn exposure_check(&self, ids: &[u32]) -> Option<ExposureCheck> {
+        let limit = self.holder_exposure_limit?;
+        let first = self.accounts.get(ids.first()?)?;
+        let elsewhere = self
+            .accounts
+            .values()
+            .filter(|account| {
+                account.holder == first.holder
+                    && account.currency == first.currency
+                    && !ids.contains(&account.id)
+            })
+            .fold(0i64, |total, account| {
+                total.saturating_add(account.overdrawn())
+            });
+        Some(ExposureCheck {
+            limit,
+            holder: first.holder.clone(),
+            currency: first.currency,
+            elsewhere,
+        })
+    }
+
+    /// Refuses `changes` to balances, as `(account, amount)` pairs, if they
+    /// would take the first account's holder past the exposure limit in
+    /// that account's currency.
+    pub(crate) fn check_exposure(&self, changes: &[(u32, i64)]) -> Result<(), AccountError> {
+        let ids: Vec<u32> = changes.iter().map(|(id, _)| *id).collect();
+        let Some(check) = self.exposure_check(&ids) else {
+            return Ok(());
+        };
+        let changes: Vec<(&Account, i64)> = changes
+            .iter()
+            .filter_map(|(id, change)| Some((self.accounts.get(id)?, *change)))
+            .collect();
+        check.check(&changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overdrawable(id: u32, holder: &str) -> Account {
+        let mut account = Account::new(id, holder.to_string());
+        account.set_overdraft_limit(500);
+        account.set_overdraft_opt_in(true);
+        account
+    }
+
+    #[test]
+    fn transfers_count_the_holders_other_accounts() {
+        let mut bank = Bank::new();
+        bank.add_account(overdrawable(1, "Ann"));
+        bank.add_account(overdrawable(2, "Ann"));
+        bank.add_account(Account::new(3, "Bob".to_string()));
+        bank.withdraw(2, 400).unwrap();
+        bank.set_holder_exposure_limit(Some(600));
+
+        let refused = bank.transfer(1, 3, 300).unwrap_err();
+        assert!(matches!(
+            refused,
+            AccountError::Policy(PolicyError::ExposureLimitExceeded {
+                exposure: 400,
+                resulting: 700,
+                limit: 600,
+                ..
+            })
+        ));
+        bank.transfer(1, 3, 200).unwrap();
+        assert_eq!(bank.holder_exposure("Ann"), 600);
+    }
+}
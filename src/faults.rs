@@ -0,0 +1,189 @@
+//! Wrappers that make storage misbehave on purpose, for exercising the
+//! persistence and recovery paths: snapshot writes that fail, reads that
+//! stall, and records that come back damaged.
+
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+/// Which faults a [`FaultyWriter`] or [`FaultyReader`] injects. The default
+/// injects none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultPlan {
+    /// Every `n`th call fails with an I/O error and transfers nothing.
+    pub fail_every: Option<usize>,
+    /// So do the first this many calls, like storage that recovers after
+    /// an outage.
+    pub fail_first: usize,
+    /// Report injected failures as timeouts, which a
+    /// [`RetryPolicy`](crate::RetryPolicy) retries, instead of as
+    /// permanent errors.
+    pub transient: bool,
+    /// Added before every call.
+    pub latency: Duration,
+    /// The zero-based line whose first byte is replaced with `?`, which is
+    /// enough to make a snapshot record unreadable.
+    pub corrupt_record: Option<usize>,
+}
+
+impl FaultPlan {
+    pub fn new() -> Self {
+        FaultPlan::default()
+    }
+
+    pub fn fail_every(mut self, calls: usize) -> Self {
+        self.fail_every = Some(calls.max(1));
+        self
+    }
+
+    pub fn fail_first(mut self, calls: usize) -> Self {
+        self.fail_first = calls;
+        self
+    }
+
+    pub fn transient(mut self) -> Self {
+        self.transient = true;
+        self
+    }
+
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn corrupt_record(mut self, line: usize) -> Self {
+        self.corrupt_record = Some(line);
+        self
+    }
+}
+
+/// Tracks where a byte stream is so one record can be damaged, however the
+/// stream is split into calls.
+#[derive(Debug, Clone, Copy, Default)]
+struct Faults {
+    plan: FaultPlan,
+    calls: usize,
+    failures: usize,
+    line: usize,
+    at_line_start: bool,
+}
+
+impl Faults {
+    fn new(plan: FaultPlan) -> Self {
+        Faults {
+            plan,
+            at_line_start: true,
+            ..Faults::default()
+        }
+    }
+
+    /// Counts a call, sleeping and failing as planned.
+    fn call(&mut self) -> io::Result<()> {
+        if !self.plan.latency.is_zero() {
+            thread::sleep(self.plan.latency);
+        }
+        self.calls += 1;
+        let fails = self.calls <= self.plan.fail_first
+            || self
+                .plan
+                .fail_every
+                .is_some_and(|n| self.calls.is_multiple_of(n));
+        if !fails {
+            return Ok(());
+        }
+        self.failures += 1;
+        match self.plan.transient {
+            true => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "injected transient storage failure",
+            )),
+            false => Err(io::Error::other("injected storage failure")),
+        }
+    }
+
+    fn corrupt(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            if self.at_line_start && self.plan.corrupt_record == Some(self.line) && *byte != b'\n' {
+                *byte = b'?';
+            }
+            self.at_line_start = *byte == b'\n';
+            if self.at_line_start {
+                self.line += 1;
+            }
+        }
+    }
+}
+
+/// A [`Write`] that passes through to `inner` apart from the faults in its
+/// plan.
+#[derive(Debug)]
+pub struct FaultyWriter<W> {
+    inner: W,
+    faults: Faults,
+}
+
+impl<W: Write> FaultyWriter<W> {
+    pub fn new(inner: W, plan: FaultPlan) -> Self {
+        FaultyWriter {
+            inner,
+            faults: Faults::new(plan),
+        }
+    }
+
+    /// How many calls have failed on purpose so far.
+    pub fn injected_failures(&self) -> usize {
+        self.faults.failures
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for FaultyWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.faults.call()?;
+        let mut bytes = buf.to_vec();
+        self.faults.corrupt(&mut bytes);
+        self.inner.write_all(&bytes)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A [`Read`] that passes through from `inner` apart from the faults in its
+/// plan. Wrap it in a [`std::io::BufReader`] to load a snapshot from it.
+#[derive(Debug)]
+pub struct FaultyReader<R> {
+    inner: R,
+    faults: Faults,
+}
+
+impl<R: Read> FaultyReader<R> {
+    pub fn new(inner: R, plan: FaultPlan) -> Self {
+        FaultyReader {
+            inner,
+            faults: Faults::new(plan),
+        }
+    }
+
+    pub fn injected_failures(&self) -> usize {
+        self.faults.failures
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for FaultyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.faults.call()?;
+        let read = self.inner.read(buf)?;
+        self.faults.corrupt(&mut buf[..read]);
+        Ok(read)
+    }
+}
@@ -0,0 +1,66 @@
+use crate::account::Account;
+
+/// What happens when a fee is due and the available balance can't cover it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeeShortfall {
+    /// Leave the fee uncharged.
+    #[default]
+    Skip,
+    /// Charge it anyway, taking the balance negative if need be. The part
+    /// of the negative balance a fee caused is tracked as fee debt, apart
+    /// from overdraft the holder drew on themselves.
+    Overdraw,
+}
+
+impl Account {
+    pub fn fee_shortfall(&self) -> FeeShortfall {
+        self.fee_shortfall
+    }
+
+    pub fn set_fee_shortfall(&mut self, policy: FeeShortfall) {
+        self.fee_shortfall = policy;
+    }
+
+    /// How far the balance is below zero, in cents.
+    pub fn overdrawn(&self) -> i64 {
+        self.balance.min(0).saturating_neg()
+    }
+
+    /// The part of [`Account::overdrawn`] caused by fees charged under
+    /// [`FeeShortfall::Overdraw`]. Money coming in pays it off before any
+    /// other overdraft.
+    pub fn fee_debt(&self) -> i64 {
+        self.fee_debt
+    }
+
+    /// The part of [`Account::overdrawn`] the holder ran up themselves.
+    pub fn customer_overdraft(&self) -> i64 {
+        self.overdrawn() - self.fee_debt
+    }
+
+    /// Takes `fee` off the balance if the available balance covers it or
+    /// the shortfall policy allows overdrawing. Returns how much of the fee
+    /// went into fee debt, or `None` if the fee was not charged.
+    pub(crate) fn charge_fee(&mut self, fee: i64) -> Option<i64> {
+        if self.available_balance() < fee && self.fee_shortfall == FeeShortfall::Skip {
+            return None;
+        }
+        let overdrawn = self.overdrawn();
+        self.balance = self.balance.checked_sub(fee)?;
+        let debt = self.overdrawn() - overdrawn;
+        self.fee_debt += debt;
+        Some(debt)
+    }
+
+    /// Keeps fee debt in step with a booked entry: credits pay it off
+    /// first, and it never exceeds what the account is overdrawn by.
+    pub(crate) fn settle_fee_debt(&mut self, amount: i64) {
+        if self.fee_debt == 0 {
+            return;
+        }
+        if amount > 0 {
+            self.fee_debt = (self.fee_debt - amount).max(0);
+        }
+        self.fee_debt = self.fee_debt.min(self.overdrawn());
+    }
+}
@@ -0,0 +1,253 @@
+//! Unit-priced accounts: the balance is a holding of fund units valued at a
+//! unit price that moves over time, like a simple investment account.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::chain::reseal;
+use crate::currency::CurrencyTotals;
+use crate::error::{AccountError, InputError, StateError};
+use crate::ids::allocate;
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// Unit holdings are counted in millionths of a unit.
+pub const UNIT_SCALE: i64 = 1_000_000;
+
+/// Quotes the price of one fund's units.
+pub trait PriceSource: fmt::Debug + Send + Sync {
+    /// The price of one whole unit in cents at `at`, or `None` if there is no
+    /// price yet. Prices of zero or less count as no price.
+    fn unit_price(&self, at: Timestamp) -> Option<i64>;
+}
+
+/// The same price forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedPrice(pub i64);
+
+impl PriceSource for FixedPrice {
+    fn unit_price(&self, _at: Timestamp) -> Option<i64> {
+        Some(self.0)
+    }
+}
+
+/// A list of prices, each in force from its timestamp until the next one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriceHistory {
+    prices: BTreeMap<Timestamp, i64>,
+}
+
+impl PriceHistory {
+    pub fn new() -> Self {
+        PriceHistory::default()
+    }
+
+    pub fn with_price(mut self, from: Timestamp, price: i64) -> Self {
+        self.prices.insert(from, price);
+        self
+    }
+}
+
+impl PriceSource for PriceHistory {
+    fn unit_price(&self, at: Timestamp) -> Option<i64> {
+        self.prices
+            .range(..=at)
+            .next_back()
+            .map(|(_, price)| *price)
+    }
+}
+
+/// The fund units behind a unit-priced account's balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitHolding {
+    pub fund: String,
+    /// Millionths of a unit; see [`UNIT_SCALE`].
+    pub units: i64,
+    /// The unit price the balance was last valued at, in cents.
+    pub unit_price: i64,
+}
+
+impl UnitHolding {
+    fn value_at(&self, price: i64) -> i128 {
+        i128::from(self.units) * i128::from(price) / i128::from(UNIT_SCALE)
+    }
+}
+
+fn units_for(amount: i64, price: i64, round_up: bool) -> i64 {
+    let scaled = i128::from(amount) * i128::from(UNIT_SCALE);
+    let price = i128::from(price);
+    let units = if round_up {
+        (scaled + price - 1) / price
+    } else {
+        scaled / price
+    };
+    i64::try_from(units).unwrap_or(i64::MAX)
+}
+
+/// The bank's price sources as of one moment, borrowed apart from its
+/// accounts so an account can be revalued while the bank holds it.
+pub(crate) struct Quotes<'a> {
+    sources: &'a HashMap<String, Arc<dyn PriceSource>>,
+    at: Timestamp,
+}
+
+impl<'a> Quotes<'a> {
+    pub(crate) fn new(sources: &'a HashMap<String, Arc<dyn PriceSource>>, at: Timestamp) -> Self {
+        Quotes { sources, at }
+    }
+
+    fn price(&self, fund: &str) -> Option<i64> {
+        self.sources
+            .get(fund)
+            .and_then(|source| source.unit_price(self.at))
+            .filter(|price| *price > 0)
+    }
+}
+
+impl Account {
+    /// Values the account's units at the price `quotes` give, booking any
+    /// change as an entry numbered by `next_id`. Does nothing for accounts
+    /// that hold no units.
+    pub(crate) fn revalue(
+        &mut self,
+        quotes: &Quotes<'_>,
+        next_id: impl FnOnce() -> u64,
+    ) -> Result<i64, AccountError> {
+        let Some(holding) = self.units.as_ref() else {
+            return Ok(0);
+        };
+        let price = quotes
+            .price(&holding.fund)
+            .ok_or(AccountError::State(StateError::PriceUnavailable))?;
+        let previous = holding.unit_price;
+        if price == previous {
+            return Ok(0);
+        }
+        let change = i64::try_from(holding.value_at(price) - holding.value_at(previous))
+            .map_err(|_| AccountError::Validation(InputError::AmountOverflow))?;
+        let (fund, units) = (holding.fund.clone(), holding.units);
+
+        self.balance = self
+            .balance
+            .checked_add(change)
+            .ok_or(AccountError::Validation(InputError::AmountOverflow))?;
+        if let Some(holding) = self.units.as_mut() {
+            holding.unit_price = price;
+        }
+        if change != 0 {
+            self.book(
+                next_id(),
+                TransactionKind::Revaluation,
+                change,
+                None,
+                &fund,
+                quotes.at,
+            );
+            let index = self.history.len() - 1;
+            let metadata = &mut self.history[index].metadata;
+            metadata.insert("units".to_string(), units.to_string());
+            metadata.insert("previous_unit_price".to_string(), previous.to_string());
+            metadata.insert("unit_price".to_string(), price.to_string());
+            reseal(&mut self.history, index);
+        }
+        Ok(change)
+    }
+}
+
+impl Bank {
+    /// Quotes `fund` from `source`. Sources are runtime configuration and
+    /// are not saved in snapshots; prices are in the bank's current minor
+    /// unit.
+    pub fn set_price_source(&mut self, fund: &str, source: impl PriceSource + 'static) {
+        self.price_sources
+            .insert(fund.to_string(), Arc::new(source));
+    }
+
+    /// The current price of one unit of `fund`, in cents.
+    pub fn unit_price(&self, fund: &str) -> Option<i64> {
+        Quotes::new(&self.price_sources, self.now).price(fund)
+    }
+
+    /// Turns the account into a holding of `fund`, buying units with its
+    /// current balance. An account already holding another fund is valued
+    /// at today's price and switched over.
+    ///
+    /// From then on deposits buy units and withdrawals sell them at the
+    /// current price, while other credits and debits trade at the price of
+    /// the last valuation. Price moves are booked as revaluation entries, so
+    /// gains and losses show up in statements.
+    pub fn hold_units(
+        &mut self,
+        account: impl Into<AccountRef>,
+        fund: &str,
+    ) -> Result<(), AccountError> {
+        let id = self.resolve(account)?;
+        let price = self
+            .unit_price(fund)
+            .ok_or(AccountError::State(StateError::PriceUnavailable))?;
+        self.revalue(id)?;
+        let account = self.accounts.get_mut(&id).unwrap();
+        account.units = Some(UnitHolding {
+            fund: fund.to_string(),
+            units: units_for(account.balance.max(0), price, false),
+            unit_price: price,
+        });
+        Ok(())
+    }
+
+    /// Values every unit-priced account at today's prices and returns the
+    /// net gain (or loss) booked in each currency. Accounts whose fund has
+    /// no price are left at their last valuation.
+    pub fn revalue_units(&mut self) -> CurrencyTotals {
+        let mut booked = CurrencyTotals::new();
+        for id in self.sorted_account_ids() {
+            if let Ok(change) = self.revalue(id) {
+                booked.add(self.accounts[&id].currency, change);
+            }
+        }
+        booked
+    }
+
+    /// Books the change in the value of an account's units since its last
+    /// valuation. Does nothing for accounts that hold no units, or don't
+    /// exist.
+    pub(crate) fn revalue(&mut self, account_id: u32) -> Result<i64, AccountError> {
+        let quotes = Quotes::new(&self.price_sources, self.now);
+        let Some(account) = self.accounts.get_mut(&account_id) else {
+            return Ok(0);
+        };
+        account.revalue(&quotes, || {
+            allocate(&mut self.id_generator, &mut self.next_transaction_id)
+        })
+    }
+
+    /// Buys or sells units for an entry that has just been booked, at the
+    /// holding's last valuation price. Debits that empty the account sell
+    /// every unit.
+    pub(crate) fn trade_units(&mut self, account_id: u32, txn_id: u64, amount: i64) {
+        let Some(account) = self.accounts.get_mut(&account_id) else {
+            return;
+        };
+        let balance = account.balance;
+        let Some(holding) = account.units.as_mut() else {
+            return;
+        };
+        let price = holding.unit_price;
+        let traded = if amount > 0 {
+            units_for(amount, price, false)
+        } else if balance <= 0 {
+            -holding.units
+        } else {
+            -units_for(-amount, price, true).min(holding.units)
+        };
+        holding.units += traded;
+        self.amend_transaction(account_id, txn_id, |txn| {
+            txn.metadata.insert("units".to_string(), traded.to_string());
+            txn.metadata
+                .insert("unit_price".to_string(), price.to_string());
+        });
+    }
+}
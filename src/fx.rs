@@ -0,0 +1,275 @@
+//! Standing currency conversion orders: move money between accounts held in
+//! different currencies once the exchange rate reaches a trigger.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::error::{AccountError, InputError, StateError};
+use crate::remainders::RoundingSource;
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// Exchange rates are given in millionths: 1.085 is `1_085_000`.
+pub const RATE_SCALE: i64 = 1_000_000;
+
+/// Quotes exchange rates.
+pub trait RateSource: fmt::Debug + Send + Sync {
+    /// How many units of `to` one unit of `from` buys at `at`, in
+    /// millionths (see [`RATE_SCALE`]), or `None` if there is no quote.
+    /// Rates of zero or less count as no quote.
+    fn rate(&self, from: Currency, to: Currency, at: Timestamp) -> Option<i64>;
+}
+
+/// Quoted rates per currency pair, each in force from its timestamp until
+/// the next one for the same pair.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateHistory {
+    rates: BTreeMap<(Currency, Currency), BTreeMap<Timestamp, i64>>,
+}
+
+impl RateHistory {
+    pub fn new() -> Self {
+        RateHistory::default()
+    }
+
+    pub fn with_rate(mut self, from: Currency, to: Currency, at: Timestamp, rate: i64) -> Self {
+        self.rates.entry((from, to)).or_default().insert(at, rate);
+        self
+    }
+}
+
+impl RateSource for RateHistory {
+    fn rate(&self, from: Currency, to: Currency, at: Timestamp) -> Option<i64> {
+        self.rates
+            .get(&(from, to))?
+            .range(..=at)
+            .next_back()
+            .map(|(_, rate)| *rate)
+    }
+}
+
+/// Converts `from_account`'s `amount` into `to_account`'s currency once the
+/// rate reaches `trigger_rate` or better.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FxOrder {
+    pub id: u64,
+    pub from_account: u32,
+    pub to_account: u32,
+    /// In the minor unit of the source account's currency.
+    pub amount: i64,
+    /// In millionths; see [`RATE_SCALE`].
+    pub trigger_rate: i64,
+    /// The order lapses if it has not been carried out by then.
+    pub expires_at: Timestamp,
+}
+
+/// How an [`FxOrder`] was carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FxExecution {
+    pub order_id: u64,
+    pub from: Currency,
+    pub to: Currency,
+    /// The rate the conversion was done at, in millionths.
+    pub rate: i64,
+    pub debited: i64,
+    pub credited: i64,
+    pub executed_at: Timestamp,
+    pub debit_transaction: u64,
+    pub credit_transaction: u64,
+}
+
+impl fmt::Display for FxExecution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FX order {}: {} {} -> {} {} at {}",
+            self.order_id,
+            self.debited,
+            self.from,
+            self.credited,
+            self.to,
+            format_rate(self.rate)
+        )
+    }
+}
+
+/// The result of [`Bank::execute_fx_orders`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FxOrderReport {
+    pub executed: Vec<FxExecution>,
+    pub expired: Vec<FxOrder>,
+    /// Orders whose rate was reached but which could not be carried out,
+    /// e.g. for lack of funds. They stay open.
+    pub failed: Vec<(u64, AccountError)>,
+}
+
+fn format_rate(rate: i64) -> String {
+    format!("{}.{:06}", rate / RATE_SCALE, rate % RATE_SCALE)
+}
+
+/// `amount` in `from`'s minor unit converted at `rate` into `to`'s minor
+/// unit, rounded down, with what was rounded off as `(parts, parts per
+/// unit)`.
+fn convert(amount: i64, rate: i64, from: Currency, to: Currency) -> Option<(i64, i128, i128)> {
+    let scaled = i128::from(amount) * i128::from(rate) * 10_i128.pow(to.minor_units());
+    let divisor = i128::from(RATE_SCALE) * 10_i128.pow(from.minor_units());
+    let converted = i64::try_from(scaled / divisor).ok()?;
+    Some((converted, scaled % divisor, divisor))
+}
+
+impl Bank {
+    /// Quotes exchange rates for FX orders. Like price sources, the source
+    /// is runtime configuration and is not saved in snapshots.
+    pub fn set_rate_source(&mut self, source: impl RateSource + 'static) {
+        self.rate_source = Some(Arc::new(source));
+    }
+
+    /// The current rate from `from` to `to`, in millionths.
+    pub fn exchange_rate(&self, from: Currency, to: Currency) -> Option<i64> {
+        if from == to {
+            return Some(RATE_SCALE);
+        }
+        self.rate_source
+            .as_ref()
+            .and_then(|source| source.rate(from, to, self.now))
+            .filter(|rate| *rate > 0)
+    }
+
+    /// Places a standing order to convert `amount` from one account into
+    /// another once the rate reaches `trigger_rate`. Orders are checked at
+    /// the end of every day, or whenever [`Bank::execute_fx_orders`] is
+    /// called; nothing is held in the meantime.
+    pub fn place_fx_order(
+        &mut self,
+        from: impl Into<AccountRef>,
+        to: impl Into<AccountRef>,
+        amount: i64,
+        trigger_rate: i64,
+        expires_at: Timestamp,
+    ) -> Result<u64, AccountError> {
+        if amount <= 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        if trigger_rate <= 0 {
+            return Err(AccountError::Validation(InputError::InvalidRate));
+        }
+        let from_account = self.resolve(from)?;
+        let to_account = self.resolve(to)?;
+        if from_account == to_account {
+            return Err(AccountError::Validation(InputError::SameAccount));
+        }
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        self.fx_orders.push(FxOrder {
+            id,
+            from_account,
+            to_account,
+            amount,
+            trigger_rate,
+            expires_at,
+        });
+        Ok(id)
+    }
+
+    pub fn fx_orders(&self) -> &[FxOrder] {
+        &self.fx_orders
+    }
+
+    pub fn cancel_fx_order(&mut self, order_id: u64) -> Result<FxOrder, AccountError> {
+        let index = self
+            .fx_orders
+            .iter()
+            .position(|order| order.id == order_id)
+            .ok_or(AccountError::State(StateError::FxOrderNotFound))?;
+        Ok(self.fx_orders.remove(index))
+    }
+
+    /// Carries out every open order whose rate has been reached, in the
+    /// order they were placed, and drops those past their deadline.
+    pub fn execute_fx_orders(&mut self) -> FxOrderReport {
+        let mut report = FxOrderReport::default();
+        let now = self.now;
+        let (expired, open) = std::mem::take(&mut self.fx_orders)
+            .into_iter()
+            .partition::<Vec<_>, _>(|order| order.expires_at <= now);
+        report.expired = expired;
+        for order in open {
+            match self.execute_fx_order(&order) {
+                Ok(Some(execution)) => report.executed.push(execution),
+                Ok(None) => self.fx_orders.push(order),
+                Err(error) => {
+                    report.failed.push((order.id, error));
+                    self.fx_orders.push(order);
+                }
+            }
+        }
+        report
+    }
+
+    /// Converts `order` if the current rate reaches its trigger.
+    fn execute_fx_order(&mut self, order: &FxOrder) -> Result<Option<FxExecution>, AccountError> {
+        let (Some(from), Some(to)) = (
+            self.accounts.get(&order.from_account),
+            self.accounts.get(&order.to_account),
+        ) else {
+            return Err(AccountError::State(StateError::AccountNotFound));
+        };
+        let (from_currency, to_currency) = (from.currency, to.currency);
+        let Some(rate) = self.exchange_rate(from_currency, to_currency) else {
+            return Ok(None);
+        };
+        if rate < order.trigger_rate {
+            return Ok(None);
+        }
+        let (credited, remainder, parts) = convert(order.amount, rate, from_currency, to_currency)
+            .ok_or(AccountError::Validation(InputError::AmountOverflow))?;
+        if to.balance.checked_add(credited).is_none() {
+            return Err(AccountError::Validation(InputError::AmountOverflow));
+        }
+        self.accounts
+            .get_mut(&order.from_account)
+            .unwrap()
+            .withdraw(order.amount)?;
+        self.accounts
+            .get_mut(&order.to_account)
+            .unwrap()
+            .deposit(credited)?;
+        let memo = format!(
+            "FX order {} at {} {}/{}",
+            order.id,
+            format_rate(rate),
+            from_currency,
+            to_currency
+        );
+        let debit_transaction = self.record(
+            order.from_account,
+            TransactionKind::TransferOut,
+            -order.amount,
+            Some(order.to_account),
+            &memo,
+        );
+        let credit_transaction = self.record(
+            order.to_account,
+            TransactionKind::TransferIn,
+            credited,
+            Some(order.from_account),
+            &memo,
+        );
+        self.track_remainder(to_currency, RoundingSource::Fx, remainder, parts);
+        Ok(Some(FxExecution {
+            order_id: order.id,
+            from: from_currency,
+            to: to_currency,
+            rate,
+            debited: order.amount,
+            credited,
+            executed_at: self.now,
+            debit_transaction,
+            credit_transaction,
+        }))
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError, StateError};
+use crate::receipt::Receipt;
+use crate::time::{Timestamp, SECONDS_PER_DAY};
+
+/// How far back deposits count towards the projected saving rate.
+const RECENT_RATE_DAYS: u64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavingsGoal {
+    pub id: u64,
+    pub name: String,
+    pub target: i64,
+    pub deadline: Timestamp,
+    pub created_at: Timestamp,
+    /// Deposits tagged to this goal, oldest first.
+    pub contributions: Vec<(Timestamp, i64)>,
+}
+
+impl SavingsGoal {
+    pub fn saved(&self) -> i64 {
+        self.contributions.iter().map(|(_, amount)| amount).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoalProgress {
+    pub goal_id: u64,
+    pub name: String,
+    pub target: i64,
+    pub saved: i64,
+    pub deadline: Timestamp,
+    /// When the target will be reached at the recent deposit rate; `None`
+    /// if nothing has been saved recently.
+    pub projected_completion: Option<Timestamp>,
+}
+
+impl GoalProgress {
+    pub fn percent(&self) -> f64 {
+        if self.target <= 0 {
+            return 100.0;
+        }
+        (self.saved as f64 / self.target as f64 * 100.0).min(100.0)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.saved >= self.target
+    }
+
+    pub fn on_track(&self) -> bool {
+        self.is_complete()
+            || self
+                .projected_completion
+                .is_some_and(|completion| completion <= self.deadline)
+    }
+}
+
+impl fmt::Display for GoalProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: ${:.2} of ${:.2} ({:.1}%), due {}",
+            self.name,
+            self.saved as f64 / 100.0,
+            self.target as f64 / 100.0,
+            self.percent(),
+            self.deadline.date()
+        )?;
+        match self.projected_completion {
+            _ if self.is_complete() => write!(f, ", complete"),
+            Some(completion) => write!(f, ", projected {}", completion.date()),
+            None => write!(f, ", no recent deposits"),
+        }
+    }
+}
+
+impl Bank {
+    pub fn add_savings_goal(
+        &mut self,
+        account: impl Into<AccountRef>,
+        name: &str,
+        target: i64,
+        deadline: Timestamp,
+    ) -> Result<u64, AccountError> {
+        if target < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let id = self.resolve(account)?;
+        let goal_id = self.next_item_id;
+        self.next_item_id += 1;
+        let created_at = self.now;
+        self.accounts.get_mut(&id).unwrap().goals.push(SavingsGoal {
+            id: goal_id,
+            name: name.to_string(),
+            target,
+            deadline,
+            created_at,
+            contributions: Vec::new(),
+        });
+        Ok(goal_id)
+    }
+
+    pub fn savings_goals(&self, account: impl Into<AccountRef>) -> &[SavingsGoal] {
+        self.get_account(account)
+            .map(|account| account.goals.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn remove_savings_goal(
+        &mut self,
+        account: impl Into<AccountRef>,
+        goal_id: u64,
+    ) -> Result<SavingsGoal, AccountError> {
+        let id = self.resolve(account)?;
+        let goals = &mut self.accounts.get_mut(&id).unwrap().goals;
+        let index = goals
+            .iter()
+            .position(|goal| goal.id == goal_id)
+            .ok_or(AccountError::State(StateError::GoalNotFound))?;
+        Ok(goals.remove(index))
+    }
+
+    /// Deposits `amount` and counts it towards the goal. The deposit is
+    /// tagged with the goal id in its metadata.
+    pub fn deposit_to_goal(
+        &mut self,
+        account: impl Into<AccountRef>,
+        goal_id: u64,
+        amount: i64,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        if !self.accounts[&id]
+            .goals
+            .iter()
+            .any(|goal| goal.id == goal_id)
+        {
+            return Err(AccountError::State(StateError::GoalNotFound));
+        }
+        let receipt = self.deposit(id, amount)?;
+        let now = self.now;
+        if let Some(txn_id) = receipt.transaction_id {
+            self.amend_transaction(id, txn_id, |txn| {
+                txn.metadata.insert("goal".to_string(), goal_id.to_string());
+            });
+        }
+        let account = self.accounts.get_mut(&id).unwrap();
+        if let Some(goal) = account.goals.iter_mut().find(|goal| goal.id == goal_id) {
+            goal.contributions.push((now, amount));
+        }
+        Ok(receipt)
+    }
+
+    /// Progress towards a goal, projecting completion from the goal's
+    /// deposits over the last 30 days.
+    pub fn goal_progress(
+        &self,
+        account: impl Into<AccountRef>,
+        goal_id: u64,
+    ) -> Result<GoalProgress, AccountError> {
+        let id = self.resolve(account)?;
+        let goal = self.accounts[&id]
+            .goals
+            .iter()
+            .find(|goal| goal.id == goal_id)
+            .ok_or(AccountError::State(StateError::GoalNotFound))?;
+        let saved = goal.saved();
+
+        let window_start = self
+            .now
+            .seconds()
+            .saturating_sub(RECENT_RATE_DAYS * SECONDS_PER_DAY)
+            .max(goal.created_at.seconds());
+        let elapsed = self
+            .now
+            .seconds()
+            .saturating_sub(window_start)
+            .max(SECONDS_PER_DAY);
+        let recent: i64 = goal
+            .contributions
+            .iter()
+            .filter(|(time, _)| time.seconds() >= window_start)
+            .map(|(_, amount)| amount)
+            .sum();
+        let projected_completion = if saved >= goal.target {
+            Some(self.now)
+        } else if recent > 0 {
+            let remaining = (goal.target - saved) as u128;
+            let seconds = remaining * u128::from(elapsed) / recent as u128;
+            Some(Timestamp(self.now.seconds().saturating_add(seconds as u64)))
+        } else {
+            None
+        };
+
+        Ok(GoalProgress {
+            goal_id,
+            name: goal.name.clone(),
+            target: goal.target,
+            saved,
+            deadline: goal.deadline,
+            projected_completion,
+        })
+    }
+}
@@ -0,0 +1,259 @@
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::cosign::CoSigner;
+use crate::error::{AccountError, InputError, PolicyError, StateError};
+use crate::receipt::Receipt;
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// Controls a guardian account holds over a minor's account until the
+/// minor takes full control.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Guardianship {
+    pub guardian_id: u32,
+    /// Most the minor may withdraw or transfer out per day, in cents.
+    pub daily_spending_cap: Option<i64>,
+    /// Withdrawals and transfers above this many cents wait for the
+    /// guardian's approval.
+    pub approval_threshold: Option<i64>,
+    /// Approval requests not answered within this many days lapse.
+    pub approval_expiry_days: u32,
+    /// From this time the controls no longer apply.
+    pub full_control_at: Timestamp,
+}
+
+impl Guardianship {
+    pub fn new(guardian_id: u32, full_control_at: Timestamp) -> Self {
+        Guardianship {
+            guardian_id,
+            daily_spending_cap: None,
+            approval_threshold: None,
+            approval_expiry_days: 7,
+            full_control_at,
+        }
+    }
+
+    pub fn daily_spending_cap(mut self, cap: i64) -> Self {
+        self.daily_spending_cap = Some(cap);
+        self
+    }
+
+    pub fn approval_above(mut self, threshold: i64, expiry_days: u32) -> Self {
+        self.approval_threshold = Some(threshold);
+        self.approval_expiry_days = expiry_days;
+        self
+    }
+
+    pub fn is_active(&self, now: Timestamp) -> bool {
+        now < self.full_control_at
+    }
+
+    /// The guardian as the co-signer of withdrawals and transfers above the
+    /// threshold.
+    pub(crate) fn approver(&self) -> Option<CoSigner> {
+        let threshold = self.approval_threshold?;
+        Some(CoSigner::new(
+            &format!("guardian account {}", self.guardian_id),
+            threshold,
+            self.approval_expiry_days,
+        ))
+    }
+}
+
+impl Account {
+    pub fn guardianship(&self) -> Option<&Guardianship> {
+        self.guardianship.as_ref()
+    }
+
+    /// How much was withdrawn or transferred out on the day of `now`.
+    fn spent_on(&self, now: Timestamp) -> i64 {
+        let start = now.start_of_day();
+        self.history
+            .iter()
+            .rev()
+            .take_while(|txn| txn.timestamp >= start)
+            .filter(|txn| {
+                matches!(
+                    txn.kind,
+                    TransactionKind::Withdrawal | TransactionKind::TransferOut
+                )
+            })
+            .map(|txn| -txn.amount)
+            .sum()
+    }
+
+    /// The guardian who must approve withdrawals and transfers above their
+    /// threshold, while the guardianship lasts.
+    pub(crate) fn guardian_approver(&self, now: Timestamp) -> Option<CoSigner> {
+        self.guardianship
+            .as_ref()
+            .filter(|guardianship| guardianship.is_active(now))
+            .and_then(Guardianship::approver)
+    }
+
+    /// Refuses spending that would take a minor past today's cap.
+    pub(crate) fn check_spending_cap(
+        &self,
+        now: Timestamp,
+        amount: i64,
+    ) -> Result<(), AccountError> {
+        let Some(cap) = self
+            .guardianship
+            .as_ref()
+            .filter(|guardianship| guardianship.is_active(now))
+            .and_then(|guardianship| guardianship.daily_spending_cap)
+        else {
+            return Ok(());
+        };
+        let remaining = (cap - self.spent_on(now)).max(0);
+        if amount > remaining {
+            return Err(AccountError::Policy(PolicyError::SpendingCapExceeded {
+                remaining,
+                currency: self.currency,
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    /// Puts `minor` under `guardianship`. The guardian account must exist
+    /// and differ from the minor's.
+    pub fn set_guardianship(
+        &mut self,
+        minor: impl Into<AccountRef>,
+        guardianship: Guardianship,
+    ) -> Result<(), AccountError> {
+        let minor_id = self.resolve(minor)?;
+        if !self.accounts.contains_key(&guardianship.guardian_id) {
+            return Err(AccountError::State(StateError::AccountNotFound));
+        }
+        if guardianship.guardian_id == minor_id {
+            return Err(AccountError::Validation(InputError::SameAccount));
+        }
+        self.accounts.get_mut(&minor_id).unwrap().guardianship = Some(guardianship);
+        Ok(())
+    }
+
+    /// Changes the minor's daily spending cap; only the guardian may.
+    pub fn set_spending_cap(
+        &mut self,
+        minor: impl Into<AccountRef>,
+        guardian: impl Into<AccountRef>,
+        cap: Option<i64>,
+    ) -> Result<(), AccountError> {
+        self.guardianship_mut(minor, guardian)?.daily_spending_cap = cap;
+        Ok(())
+    }
+
+    /// Changes the amount above which the minor's withdrawals and transfers
+    /// need approval; only the guardian may.
+    pub fn set_approval_threshold(
+        &mut self,
+        minor: impl Into<AccountRef>,
+        guardian: impl Into<AccountRef>,
+        threshold: Option<i64>,
+    ) -> Result<(), AccountError> {
+        self.guardianship_mut(minor, guardian)?.approval_threshold = threshold;
+        Ok(())
+    }
+
+    /// Carries out a minor's pending withdrawal or transfer once the
+    /// guardian approves it, as [`Bank::approve_withdrawal`] does for a
+    /// co-signer. The daily spending cap does not apply to approved
+    /// requests.
+    pub fn approve_as_guardian(
+        &mut self,
+        minor: impl Into<AccountRef>,
+        request_id: u64,
+        guardian: impl Into<AccountRef>,
+    ) -> Result<Receipt, AccountError> {
+        let minor_id = self.resolve(minor)?;
+        self.guardianship_mut(minor_id, guardian)?;
+        let request = self.take_pending_withdrawal(minor_id, request_id)?;
+        self.carry_out(minor_id, request)
+    }
+
+    /// The minor's guardianship while it lasts, if `guardian` holds it.
+    fn guardianship_mut(
+        &mut self,
+        minor: impl Into<AccountRef>,
+        guardian: impl Into<AccountRef>,
+    ) -> Result<&mut Guardianship, AccountError> {
+        let minor_id = self.resolve(minor)?;
+        let guardian_id = self.resolve(guardian)?;
+        let now = self.now;
+        self.accounts
+            .get_mut(&minor_id)
+            .unwrap()
+            .guardianship
+            .as_mut()
+            .filter(|guardianship| {
+                guardianship.is_active(now) && guardianship.guardian_id == guardian_id
+            })
+            .ok_or(AccountError::Policy(PolicyError::NotGuardian))
+    }
+
+    /// Hands full control to minors whose guardianship has ended. Their
+    /// requests awaiting the guardian are dropped and the amounts released,
+    /// unless the account also has a co-signer who can still approve them.
+    pub(crate) fn end_guardianships(&mut self) {
+        let now = self.now;
+        for account in self.accounts.values_mut() {
+            if account
+                .guardianship
+                .as_ref()
+                .is_none_or(|guardianship| guardianship.is_active(now))
+            {
+                continue;
+            }
+            account.guardianship = None;
+            if account.co_signer.is_none() {
+                for request in std::mem::take(&mut account.pending_withdrawals) {
+                    account.holds.retain(|hold| hold.id != request.id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_wards_transfers_wait_for_the_guardian_like_withdrawals() {
+        let mut bank = Bank::new();
+        bank.add_account(Account::new(1, "Parent".to_string()));
+        bank.add_account(Account::new(2, "Child".to_string()));
+        bank.add_account(Account::new(3, "Shop".to_string()));
+        bank.deposit(2, 10_000).unwrap();
+        let full_control_at = bank.now().plus_days(365);
+        bank.set_guardianship(
+            2,
+            Guardianship::new(1, full_control_at).approval_above(1_000, 7),
+        )
+        .unwrap();
+
+        let awaiting = |error: AccountError| {
+            matches!(
+                error,
+                AccountError::Policy(PolicyError::CoSignatureRequired { .. })
+            )
+        };
+        assert!(awaiting(bank.withdraw(2, 5_000).unwrap_err()));
+        assert!(awaiting(bank.transfer(2, 3, 5_000).unwrap_err()));
+        let requests: Vec<u64> = bank
+            .get_account(2)
+            .unwrap()
+            .pending_withdrawals()
+            .iter()
+            .map(|request| request.id)
+            .collect();
+        assert_eq!(bank.get_account(3).unwrap().balance(), 0);
+
+        bank.approve_as_guardian(2, requests[1], 1).unwrap();
+        assert_eq!(bank.get_account(3).unwrap().balance(), 5_000);
+        assert_eq!(bank.get_account(2).unwrap().spendable_balance(), 0);
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bank::Bank;
+use crate::uuid::random_u64;
+
+/// Hands out transaction ids. Swap in a time-based generator so ids stay
+/// unique across simulation nodes that are later merged.
+pub trait IdGenerator: fmt::Debug + Send {
+    fn next_id(&mut self) -> u64;
+}
+
+/// 1, 2, 3, ... — what a bank uses unless told otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequentialIds {
+    next: u64,
+}
+
+impl SequentialIds {
+    pub fn starting_at(first: u64) -> Self {
+        SequentialIds { next: first }
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&mut self) -> u64 {
+        let id = self.next;
+        self.next += 1;
+        id
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Milliseconds since 2024-01-01, the epoch of [`SnowflakeIds`].
+const SNOWFLAKE_EPOCH_MILLIS: u64 = 1_704_067_200_000;
+const SNOWFLAKE_NODE_BITS: u32 = 10;
+const SNOWFLAKE_SEQUENCE_BITS: u32 = 12;
+
+/// Snowflake-style ids: 41 bits of milliseconds, a 10-bit node number and
+/// a 12-bit per-millisecond sequence. Unique across up to 1024 nodes, and
+/// increasing on each node even if its clock steps backwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnowflakeIds {
+    node: u64,
+    last_millis: u64,
+    sequence: u64,
+}
+
+impl SnowflakeIds {
+    /// `node` is taken modulo 1024.
+    pub fn new(node: u16) -> Self {
+        SnowflakeIds {
+            node: u64::from(node) & ((1 << SNOWFLAKE_NODE_BITS) - 1),
+            last_millis: 0,
+            sequence: 0,
+        }
+    }
+}
+
+impl IdGenerator for SnowflakeIds {
+    fn next_id(&mut self) -> u64 {
+        let millis = unix_millis().saturating_sub(SNOWFLAKE_EPOCH_MILLIS);
+        if millis > self.last_millis {
+            self.last_millis = millis;
+            self.sequence = 0;
+        } else {
+            self.sequence += 1;
+            if self.sequence >> SNOWFLAKE_SEQUENCE_BITS != 0 {
+                // Out of sequence numbers: borrow the next millisecond.
+                self.last_millis += 1;
+                self.sequence = 0;
+            }
+        }
+        (self.last_millis << (SNOWFLAKE_NODE_BITS + SNOWFLAKE_SEQUENCE_BITS))
+            | (self.node << SNOWFLAKE_SEQUENCE_BITS)
+            | self.sequence
+    }
+}
+
+/// UUIDv7-style ids folded into 64 bits: 48 bits of Unix milliseconds and
+/// 16 random bits, incremented instead of redrawn within a millisecond so
+/// ids keep increasing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeOrderedIds {
+    last: u64,
+}
+
+impl TimeOrderedIds {
+    pub fn new() -> Self {
+        TimeOrderedIds::default()
+    }
+}
+
+impl IdGenerator for TimeOrderedIds {
+    fn next_id(&mut self) -> u64 {
+        let candidate = (unix_millis() << 16) | (random_u64() & 0xffff);
+        self.last = candidate.max(self.last + 1);
+        self.last
+    }
+}
+
+/// Draws from `generator` if one is set, otherwise from the sequential
+/// `counter`. A free function so callers can hold other field borrows.
+pub(crate) fn allocate(generator: &mut Option<Box<dyn IdGenerator>>, counter: &mut u64) -> u64 {
+    match generator {
+        Some(generator) => generator.next_id(),
+        None => {
+            let id = *counter;
+            *counter += 1;
+            id
+        }
+    }
+}
+
+impl Bank {
+    /// Uses `generator` for every transaction booked from now on. The
+    /// generator itself is not saved in snapshots; set it again after
+    /// loading.
+    pub fn set_id_generator(&mut self, generator: impl IdGenerator + 'static) {
+        self.id_generator = Some(Box::new(generator));
+    }
+
+    /// Goes back to sequential ids, continuing after the last one handed out
+    /// sequentially.
+    pub fn clear_id_generator(&mut self) {
+        self.id_generator = None;
+    }
+
+    pub(crate) fn allocate_transaction_id(&mut self) -> u64 {
+        allocate(&mut self.id_generator, &mut self.next_transaction_id)
+    }
+}
@@ -0,0 +1,624 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::bank::Bank;
+use crate::chain::reseal;
+use crate::error::AccountError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchOp {
+    Deposit { account: u32, amount: i64 },
+    Withdraw { account: u32, amount: i64 },
+    Transfer { from: u32, to: u32, amount: i64 },
+}
+
+impl BatchOp {
+    fn accounts(&self) -> (u32, Option<u32>) {
+        match *self {
+            BatchOp::Deposit { account, .. } | BatchOp::Withdraw { account, .. } => (account, None),
+            BatchOp::Transfer { from, to, .. } => (from, Some(to)),
+        }
+    }
+
+    pub(crate) fn amount_mut(&mut self) -> &mut i64 {
+        match self {
+            BatchOp::Deposit { amount, .. }
+            | BatchOp::Withdraw { amount, .. }
+            | BatchOp::Transfer { amount, .. } => amount,
+        }
+    }
+}
+
+/// One parsed row of an import file, remembering where it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOperation {
+    pub line: usize,
+    pub op: BatchOp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportFailure {
+    pub line: usize,
+    pub error: AccountError,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportReport {
+    pub applied: usize,
+    pub failed: Vec<ImportFailure>,
+    /// Groups of accounts that could be processed independently.
+    pub partitions: usize,
+    pub threads: usize,
+    pub elapsed: Duration,
+}
+
+impl ImportReport {
+    /// Operations processed per second, successful or not.
+    pub fn throughput(&self) -> f64 {
+        let processed = (self.applied + self.failed.len()) as f64;
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            processed / seconds
+        } else {
+            processed
+        }
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Imported {} operations ({} failed) across {} partitions on {} threads in {:.3}s ({:.0} ops/s)",
+            self.applied,
+            self.failed.len(),
+            self.partitions,
+            self.threads,
+            self.elapsed.as_secs_f64(),
+            self.throughput()
+        )
+    }
+}
+
+/// What applying a batch would do, from [`Bank::preview_batch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchPreview {
+    pub operations: usize,
+    /// See [`batch_value`].
+    pub total_value: i64,
+    pub failed: Vec<ImportFailure>,
+    /// Every account whose balance would change, as its id and the
+    /// balances before and after.
+    pub balances: Vec<(u32, i64, i64)>,
+}
+
+impl fmt::Display for BatchPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Batch of {} operations moving {} ({} would fail)",
+            self.operations,
+            self.total_value,
+            self.failed.len()
+        )?;
+        for (account, before, after) in &self.balances {
+            write!(f, "\n  {:<8} {:>12} -> {:>12}", account, before, after)?;
+        }
+        for failure in &self.failed {
+            write!(f, "\n  line {}: {}", failure.line, failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// The total of every operation's amount, whichever way it moves money;
+/// what the CLI compares with its confirmation threshold.
+pub fn batch_value(operations: &[BatchOperation]) -> i64 {
+    operations
+        .iter()
+        .map(|operation| match operation.op {
+            BatchOp::Deposit { amount, .. }
+            | BatchOp::Withdraw { amount, .. }
+            | BatchOp::Transfer { amount, .. } => amount.saturating_abs(),
+        })
+        .fold(0, i64::saturating_add)
+}
+
+/// Parses `kind,account,amount[,to]` rows (amounts in cents). A header row
+/// and blank lines are skipped.
+pub fn parse_csv<R: BufRead>(reader: R) -> Result<Vec<BatchOperation>, ImportError> {
+    let mut operations = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.map_err(|error| ImportError {
+            line: line_number,
+            message: error.to_string(),
+        })?;
+        let line = line.trim();
+        if line.is_empty() || (line_number == 1 && line.starts_with("kind")) {
+            continue;
+        }
+        let op = parse_row(line).map_err(|message| ImportError {
+            line: line_number,
+            message,
+        })?;
+        operations.push(BatchOperation {
+            line: line_number,
+            op,
+        });
+    }
+    Ok(operations)
+}
+
+fn parse_row(line: &str) -> Result<BatchOp, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let field = |index: usize, name: &str| {
+        fields
+            .get(index)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| format!("missing {}", name))
+    };
+    let account = field(1, "account")?
+        .parse::<u32>()
+        .map_err(|_| "invalid account".to_string())?;
+    let amount = field(2, "amount")?
+        .parse::<i64>()
+        .map_err(|_| "invalid amount".to_string())?;
+    match field(0, "kind")?.to_ascii_lowercase().as_str() {
+        "deposit" => Ok(BatchOp::Deposit { account, amount }),
+        "withdraw" | "withdrawal" => Ok(BatchOp::Withdraw { account, amount }),
+        "transfer" => {
+            let to = field(3, "destination account")?
+                .parse::<u32>()
+                .map_err(|_| "invalid destination account".to_string())?;
+            Ok(BatchOp::Transfer {
+                from: account,
+                to,
+                amount,
+            })
+        }
+        other => Err(format!("unknown operation '{}'", other)),
+    }
+}
+
+/// A set of accounts no operation outside the group touches, and the
+/// indices of the operations on them in their original order.
+struct Partition {
+    accounts: Vec<u32>,
+    operations: Vec<usize>,
+}
+
+/// Splits operations into partitions over disjoint sets of accounts. Each
+/// `links` pair (e.g. a deduction order's account and beneficiary) is kept
+/// together, like the two sides of a transfer.
+fn partition(operations: &[BatchOperation], links: &[(u32, u32)]) -> Vec<Partition> {
+    fn find(parent: &mut HashMap<u32, u32>, id: u32) -> u32 {
+        let mut root = *parent.entry(id).or_insert(id);
+        while parent[&root] != root {
+            root = parent[&root];
+        }
+        let mut node = id;
+        while node != root {
+            let next = parent[&node];
+            parent.insert(node, root);
+            node = next;
+        }
+        root
+    }
+    fn union(parent: &mut HashMap<u32, u32>, first: u32, second: u32) {
+        let first = find(parent, first);
+        let second = find(parent, second);
+        if first != second {
+            parent.insert(second, first);
+        }
+    }
+
+    let mut parent: HashMap<u32, u32> = HashMap::new();
+    for operation in operations {
+        match operation.op.accounts() {
+            (first, Some(second)) => union(&mut parent, first, second),
+            (first, None) => {
+                find(&mut parent, first);
+            }
+        }
+    }
+    // Repeat until stable so chains of linked accounts end up together.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &(first, second) in links {
+            if !parent.contains_key(&first) && !parent.contains_key(&second) {
+                continue;
+            }
+            if find(&mut parent, first) != find(&mut parent, second) {
+                union(&mut parent, first, second);
+                changed = true;
+            }
+        }
+    }
+
+    let mut partitions: HashMap<u32, Partition> = HashMap::new();
+    let ids: Vec<u32> = parent.keys().copied().collect();
+    for id in ids {
+        let root = find(&mut parent, id);
+        partitions
+            .entry(root)
+            .or_insert_with(|| Partition {
+                accounts: Vec::new(),
+                operations: Vec::new(),
+            })
+            .accounts
+            .push(id);
+    }
+    for (index, operation) in operations.iter().enumerate() {
+        let root = find(&mut parent, operation.op.accounts().0);
+        if let Some(partition) = partitions.get_mut(&root) {
+            partition.operations.push(index);
+        }
+    }
+    let mut partitions: Vec<Partition> = partitions.into_values().collect();
+    partitions.sort_by_key(|partition| std::cmp::Reverse(partition.operations.len()));
+    partitions
+}
+
+/// How much of each moved account's history predates the split.
+type ShardBaseline = HashMap<u32, (usize, usize)>;
+
+/// The index of an operation and how applying it went.
+type OperationResult = (usize, Result<(), AccountError>);
+
+impl Bank {
+    /// Applies `operations` using up to `threads` worker threads.
+    ///
+    /// Operations are split into partitions that touch disjoint sets of
+    /// accounts (a transfer or deduction order joins its two accounts). Each
+    /// partition runs in file order on a single worker, so the result matches
+    /// applying the file serially; idle workers claim the next partition.
+    pub fn import_parallel(
+        &mut self,
+        operations: &[BatchOperation],
+        threads: usize,
+    ) -> ImportReport {
+        let started = Instant::now();
+        let mut links: Vec<(u32, u32)> = self
+            .deduction_orders
+            .iter()
+            .map(|order| (order.account_id, order.beneficiary_id))
+            .collect();
+        // Transfers may pay tax, so their senders share the tax account's
+        // partition.
+        if let (Some(_), Some(tax_account)) = (&self.tax_policy, self.tax_account) {
+            for operation in operations {
+                if let BatchOp::Transfer { from, .. } = operation.op {
+                    links.push((from, tax_account));
+                }
+            }
+        }
+        // The exposure limit counts all of a holder's accounts, so they
+        // share a partition.
+        if self.holder_exposure_limit.is_some() {
+            let mut first_of_holder: HashMap<&str, u32> = HashMap::new();
+            for account in self.accounts.values() {
+                let first = *first_of_holder
+                    .entry(account.holder.as_str())
+                    .or_insert(account.id);
+                if first != account.id {
+                    links.push((first, account.id));
+                }
+            }
+        }
+        let partitions = partition(operations, &links);
+        let threads = threads.clamp(1, partitions.len().max(1));
+
+        let shards: Vec<Mutex<(Bank, ShardBaseline)>> = partitions
+            .iter()
+            .map(|partition| {
+                Mutex::new(self.split_off(&partition.accounts, partition.operations.len()))
+            })
+            .collect();
+        let results: Vec<Mutex<Vec<OperationResult>>> =
+            partitions.iter().map(|_| Mutex::new(Vec::new())).collect();
+        let next_partition = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let index = next_partition.fetch_add(1, Ordering::Relaxed);
+                    let Some(partition) = partitions.get(index) else {
+                        break;
+                    };
+                    let mut shard = shards[index].lock().unwrap();
+                    let mut outcomes = results[index].lock().unwrap();
+                    for &op_index in &partition.operations {
+                        let outcome = shard.0.apply_batch_op(operations[op_index].op);
+                        outcomes.push((op_index, outcome));
+                    }
+                });
+            }
+        });
+        for shard in shards {
+            let (shard, baseline) = shard.into_inner().unwrap();
+            self.absorb(shard, &baseline);
+        }
+
+        let mut outcomes: Vec<OperationResult> = results
+            .into_iter()
+            .flat_map(|outcomes| outcomes.into_inner().unwrap())
+            .collect();
+        outcomes.sort_by_key(|(op_index, _)| *op_index);
+        let mut report = ImportReport {
+            applied: 0,
+            failed: Vec::new(),
+            partitions: partitions.len(),
+            threads,
+            elapsed: Duration::ZERO,
+        };
+        for (op_index, outcome) in outcomes {
+            match outcome {
+                Ok(()) => report.applied += 1,
+                Err(error) => report.failed.push(ImportFailure {
+                    line: operations[op_index].line,
+                    error,
+                }),
+            }
+        }
+        report.elapsed = started.elapsed();
+        report
+    }
+
+    /// Applies `operations` in order inside [`Bank::transact`] and rolls
+    /// them all back, reporting what they would have done. The bank is
+    /// left as it was, apart from ids a custom
+    /// [`IdGenerator`](crate::IdGenerator) handed out.
+    pub fn preview_batch(&mut self, operations: &[BatchOperation]) -> BatchPreview {
+        let ids: BTreeSet<u32> = operations
+            .iter()
+            .flat_map(|operation| {
+                let (first, second) = operation.op.accounts();
+                std::iter::once(first).chain(second)
+            })
+            .filter(|id| self.accounts.contains_key(id))
+            .collect();
+        let before: Vec<i64> = ids.iter().map(|id| self.accounts[id].balance).collect();
+        let rolled_back = self.transact(|savepoint| {
+            let mut failed = Vec::new();
+            for operation in operations {
+                let outcome = match operation.op {
+                    BatchOp::Deposit { account, amount } => {
+                        savepoint.deposit(account, amount).map(|_| ())
+                    }
+                    BatchOp::Withdraw { account, amount } => {
+                        savepoint.withdraw(account, amount).map(|_| ())
+                    }
+                    BatchOp::Transfer { from, to, amount } => {
+                        savepoint.transfer(from, to, amount).map(|_| ())
+                    }
+                };
+                if let Err(error) = outcome {
+                    failed.push(ImportFailure {
+                        line: operation.line,
+                        error,
+                    });
+                }
+            }
+            let after: Vec<i64> = ids
+                .iter()
+                .map(|id| savepoint.bank().accounts[id].balance)
+                .collect();
+            Err::<(), _>((failed, after))
+        });
+        let Err((failed, after)) = rolled_back else {
+            unreachable!("the preview always rolls back");
+        };
+        BatchPreview {
+            operations: operations.len(),
+            total_value: batch_value(operations),
+            failed,
+            balances: ids
+                .into_iter()
+                .zip(before.into_iter().zip(after))
+                .filter(|(_, (before, after))| before != after)
+                .map(|(id, (before, after))| (id, before, after))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn apply_batch_op(&mut self, op: BatchOp) -> Result<(), AccountError> {
+        match op {
+            BatchOp::Deposit { account, amount } => self.deposit(account, amount).map(|_| ()),
+            BatchOp::Withdraw { account, amount } => self.withdraw(account, amount).map(|_| ()),
+            BatchOp::Transfer { from, to, amount } => self.transfer(from, to, amount).map(|_| ()),
+        }
+    }
+
+    /// Moves the given accounts and their per-account state into a new bank
+    /// that shares this bank's clock and configuration. The shard gets the
+    /// next `operations` item ids to itself: a batch operation hands out at
+    /// most one, for a co-signature request, so ids stay unique across
+    /// shards.
+    fn split_off(&mut self, ids: &[u32], operations: usize) -> (Bank, ShardBaseline) {
+        let mut shard = Bank::starting_at(self.now);
+        shard.next_item_id = self.next_item_id;
+        self.next_item_id += operations as u64;
+        shard.end_of_day_config = self.end_of_day_config.clone();
+        shard.locale = self.locale;
+        shard.time_format = self.time_format.clone();
+        shard.duplicate_check = self.duplicate_check;
+        shard.price_sources = self.price_sources.clone();
+        shard.rate_source = self.rate_source.clone();
+        shard.tax_policy = self.tax_policy.clone();
+        shard.tax_account = self.tax_account;
+        shard.zero_amount_policy = self.zero_amount_policy;
+        shard.validation_mode = self.validation_mode;
+        shard.overflow_policy = self.overflow_policy;
+        shard.payee_limits = self.payee_limits.clone();
+        shard.payees = self.payees.clone();
+        shard.holder_exposure_limit = self.holder_exposure_limit;
+        self.pass_notifications_from(&mut shard);
+        let mut baseline = ShardBaseline::new();
+        for id in ids {
+            let mut lengths = (0, 0);
+            if let Some(account) = self.accounts.remove(id) {
+                lengths.0 = account.history.len();
+                shard.references.insert(account.reference, *id);
+                shard.accounts.insert(*id, account);
+            }
+            if let Some(rewards) = self.rewards.remove(id) {
+                lengths.1 = rewards.entries.len();
+                shard.rewards.insert(*id, rewards);
+            }
+            baseline.insert(*id, lengths);
+        }
+        let (moved, kept) = self
+            .deduction_orders
+            .drain(..)
+            .partition(|order| ids.contains(&order.account_id));
+        self.deduction_orders = kept;
+        shard.deduction_orders = moved;
+        (shard, baseline)
+    }
+
+    /// Merges a shard back, renumbering the transactions it booked so they
+    /// follow this bank's, in the order the shard booked them, and sends
+    /// the notifications the shard held for it.
+    fn absorb(&mut self, mut shard: Bank, baseline: &ShardBaseline) {
+        self.next_item_id = self.next_item_id.max(shard.next_item_id);
+        self.notify_passed_on(&mut shard);
+        let mut booked: Vec<u64> = shard
+            .accounts
+            .values()
+            .flat_map(|account| {
+                let (history_len, _) = baseline.get(&account.id).copied().unwrap_or_default();
+                account.history[history_len..].iter().map(|txn| txn.id)
+            })
+            .collect();
+        booked.sort_unstable();
+        let renumbered: HashMap<u64, u64> = booked
+            .into_iter()
+            .map(|old| (old, self.allocate_transaction_id()))
+            .collect();
+        for mut account in shard.accounts.into_values() {
+            let id = account.id;
+            let (history_len, _) = baseline.get(&id).copied().unwrap_or_default();
+            for txn in &mut account.history[history_len..] {
+                txn.id = renumbered[&txn.id];
+            }
+            reseal(&mut account.history, history_len);
+            self.accounts.insert(id, account);
+        }
+        for (id, mut rewards) in shard.rewards {
+            let (_, entries_len) = baseline.get(&id).copied().unwrap_or_default();
+            for entry in &mut rewards.entries[entries_len..] {
+                if let Some(&new_id) = renumbered.get(&entry.transaction_id) {
+                    entry.transaction_id = new_id;
+                }
+            }
+            self.rewards.insert(id, rewards);
+        }
+        self.deduction_orders.extend(shard.deduction_orders);
+        self.deduction_orders.sort_by_key(|order| order.id);
+        // Each shard started from this bank's payees, so the earliest date
+        // seen for a pair is when it was first added.
+        for (pair, added_at) in shard.payees {
+            self.payees
+                .entry(pair)
+                .and_modify(|earliest| *earliest = (*earliest).min(added_at))
+                .or_insert(added_at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::account::Account;
+    use crate::notify::{Notification, NotificationEvent, NotificationPreferences, Notifier};
+    use crate::template::Template;
+
+    #[derive(Debug, Clone, Default)]
+    struct Subjects(Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Subjects {
+        fn send(&mut self, notification: &Notification) -> std::io::Result<()> {
+            self.0.lock().unwrap().push(notification.subject.clone());
+            Ok(())
+        }
+    }
+
+    fn notifying_bank(subjects: &Subjects) -> Bank {
+        let mut bank = Bank::new();
+        for id in 1..=4 {
+            bank.add_account(Account::new(id, format!("Holder {}", id)));
+            bank.set_notification_preferences(
+                &format!("Holder {}", id),
+                NotificationPreferences::new().email("holder@example.com"),
+            );
+        }
+        bank.set_notifier(subjects.clone());
+        bank.set_large_withdrawal_threshold(Some(5_000));
+        bank.set_notification_template(
+            NotificationEvent::LargeWithdrawal,
+            Template::parse("big {{amount}}").unwrap(),
+            Template::parse("").unwrap(),
+        );
+        bank
+    }
+
+    #[test]
+    fn parallel_imports_send_the_notifications_a_serial_one_does() {
+        let operations: Vec<BatchOperation> = [
+            BatchOp::Deposit {
+                account: 1,
+                amount: 10_000,
+            },
+            BatchOp::Deposit {
+                account: 3,
+                amount: 10_000,
+            },
+            BatchOp::Withdraw {
+                account: 1,
+                amount: 5_000,
+            },
+            BatchOp::Withdraw {
+                account: 3,
+                amount: 100,
+            },
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(line, op)| BatchOperation { line: line + 1, op })
+        .collect();
+
+        let serial = Subjects::default();
+        let mut bank = notifying_bank(&serial);
+        for operation in &operations {
+            bank.apply_batch_op(operation.op).unwrap();
+        }
+        let parallel = Subjects::default();
+        let report = notifying_bank(&parallel).import_parallel(&operations, 2);
+
+        assert!(report.failed.is_empty());
+        assert_eq!(*serial.0.lock().unwrap(), ["big $50.00"]);
+        assert_eq!(*parallel.0.lock().unwrap(), *serial.0.lock().unwrap());
+    }
+}
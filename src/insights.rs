@@ -0,0 +1,210 @@
+//! Personal-finance views of a holder's spending: where the money went,
+//! how much a day, the biggest expense and how each month compares with
+//! the one before.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::locale::{Locale, Localize};
+use crate::time::{Date, Timestamp};
+use crate::transaction::{Transaction, TransactionKind};
+
+/// Spending in one category over the period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategorySpend {
+    pub category: String,
+    pub spent: i64,
+    pub count: usize,
+}
+
+/// Spending in one calendar month, counting only the days in the period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonthlySpend {
+    /// The first day of the month.
+    pub month: Date,
+    pub spent: i64,
+    /// The change from the month before, in basis points; `None` for the
+    /// first month and after a month with no spending.
+    pub change_bps: Option<i64>,
+}
+
+/// What a holder spent in one currency. Amounts are positive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendingInsights {
+    pub currency: Currency,
+    pub total_spent: i64,
+    /// The total over every day of the period, spending or not.
+    pub average_daily_spend: i64,
+    /// Biggest first.
+    pub categories: Vec<CategorySpend>,
+    pub biggest_expense: Option<Transaction>,
+    /// Every month the period touches, oldest first.
+    pub months: Vec<MonthlySpend>,
+}
+
+impl SpendingInsights {
+    /// The `count` categories with the most spending.
+    pub fn top_categories(&self, count: usize) -> &[CategorySpend] {
+        &self.categories[..count.min(self.categories.len())]
+    }
+
+    /// The last month's change from the month before, in basis points.
+    pub fn month_over_month_bps(&self) -> Option<i64> {
+        self.months.last().and_then(|month| month.change_bps)
+    }
+}
+
+/// A holder's spending over `[period_start, period_end]`, one
+/// [`SpendingInsights`] per currency they spent in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Insights {
+    pub holder: String,
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+    pub by_currency: Vec<SpendingInsights>,
+}
+
+impl fmt::Display for Insights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
+
+impl Bank {
+    /// Analyses what the accounts whose holder is exactly `holder` spent
+    /// over `period`, inclusive. Withdrawals, fees and transfers to anyone
+    /// but the holder count as spending; moving money between their own
+    /// accounts does not. Uncategorised spending is grouped as "fees",
+    /// "transfers" or "uncategorized" by its kind. Days and months after
+    /// the bank's current time don't count towards the averages.
+    pub fn insights(&self, holder: &str, period: (Timestamp, Timestamp)) -> Insights {
+        let (period_start, period_end) = period;
+        let elapsed_end = period_end.min(self.now).max(period_start);
+        let own: Vec<u32> = self
+            .sorted_account_ids()
+            .into_iter()
+            .filter(|id| self.accounts[id].holder == holder)
+            .collect();
+
+        let mut spending: BTreeMap<Currency, Vec<&Transaction>> = BTreeMap::new();
+        for id in &own {
+            let account = &self.accounts[id];
+            let expenses = account
+                .history_between(period_start, period_end)
+                .into_iter()
+                .filter(|txn| is_spending(txn, &own));
+            spending
+                .entry(account.currency)
+                .or_default()
+                .extend(expenses);
+        }
+
+        let days = elapsed_end.day_number() - period_start.day_number() + 1;
+        let by_currency = spending
+            .into_iter()
+            .filter(|(_, expenses)| !expenses.is_empty())
+            .map(|(currency, expenses)| {
+                summarize(currency, &expenses, days, period_start, elapsed_end)
+            })
+            .collect();
+        Insights {
+            holder: holder.to_string(),
+            period_start,
+            period_end,
+            by_currency,
+        }
+    }
+}
+
+fn is_spending(txn: &Transaction, own: &[u32]) -> bool {
+    txn.amount < 0
+        && match txn.kind {
+            TransactionKind::Withdrawal | TransactionKind::Fee => true,
+            TransactionKind::TransferOut => txn.counterparty.is_none_or(|to| !own.contains(&to)),
+            _ => false,
+        }
+}
+
+/// The category spending is reported under.
+pub(crate) fn category_of(txn: &Transaction) -> &str {
+    match (&txn.category, txn.kind) {
+        (Some(category), _) => category,
+        (None, TransactionKind::Fee) => "fees",
+        (None, TransactionKind::TransferOut) => "transfers",
+        (None, _) => "uncategorized",
+    }
+}
+
+fn first_of_month(timestamp: Timestamp) -> Date {
+    let date = timestamp.date();
+    Date::new(date.year, date.month, 1)
+}
+
+fn next_month(month: Date) -> Date {
+    match month.month {
+        12 => Date::new(month.year + 1, 1, 1),
+        _ => Date::new(month.year, month.month + 1, 1),
+    }
+}
+
+fn summarize(
+    currency: Currency,
+    expenses: &[&Transaction],
+    days: u64,
+    period_start: Timestamp,
+    elapsed_end: Timestamp,
+) -> SpendingInsights {
+    let total_spent: i64 = expenses.iter().map(|txn| -txn.amount).sum();
+
+    let mut by_category: HashMap<&str, (i64, usize)> = HashMap::new();
+    let mut by_month: HashMap<Date, i64> = HashMap::new();
+    for txn in expenses {
+        let entry = by_category.entry(category_of(txn)).or_default();
+        entry.0 -= txn.amount;
+        entry.1 += 1;
+        *by_month.entry(first_of_month(txn.timestamp)).or_default() -= txn.amount;
+    }
+    let mut categories: Vec<CategorySpend> = by_category
+        .into_iter()
+        .map(|(category, (spent, count))| CategorySpend {
+            category: category.to_string(),
+            spent,
+            count,
+        })
+        .collect();
+    categories.sort_by(|a, b| {
+        b.spent
+            .cmp(&a.spent)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+
+    let mut months: Vec<MonthlySpend> = Vec::new();
+    let (mut month, last) = (first_of_month(period_start), first_of_month(elapsed_end));
+    while month <= last {
+        let spent = by_month.get(&month).copied().unwrap_or(0);
+        let change_bps = months
+            .last()
+            .filter(|previous| previous.spent > 0)
+            .map(|previous| (spent - previous.spent) * 10_000 / previous.spent);
+        months.push(MonthlySpend {
+            month,
+            spent,
+            change_bps,
+        });
+        month = next_month(month);
+    }
+
+    SpendingInsights {
+        currency,
+        total_spent,
+        average_daily_spend: total_spent / days as i64,
+        categories,
+        biggest_expense: expenses
+            .iter()
+            .min_by_key(|txn| (txn.amount, txn.timestamp))
+            .map(|txn| (*txn).clone()),
+        months,
+    }
+}
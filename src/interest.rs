@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use crate::eod::INTEREST_UNITS_PER_CENT;
+
+/// One band of a tiered rate: the part of the balance up to `up_to` cents
+/// (and above the previous tier's bound) earns `rate_bps`. The last tier
+/// usually has no upper bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterestTier {
+    pub up_to: Option<i64>,
+    pub rate_bps: u32,
+}
+
+impl InterestTier {
+    pub fn up_to(up_to: i64, rate_bps: u32) -> Self {
+        InterestTier {
+            up_to: Some(up_to),
+            rate_bps,
+        }
+    }
+
+    pub fn above(rate_bps: u32) -> Self {
+        InterestTier {
+            up_to: None,
+            rate_bps,
+        }
+    }
+}
+
+/// How much of a day's accrual came from one tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TierAccrual {
+    pub(crate) portion: i64,
+    pub(crate) rate_bps: u32,
+    pub(crate) units: i128,
+}
+
+/// Splits a positive balance across `tiers`, which must be sorted by bound.
+/// Anything above the last bounded tier earns nothing.
+pub(crate) fn accrue_tiered(balance: i64, tiers: &[InterestTier]) -> Vec<TierAccrual> {
+    let mut accruals = Vec::with_capacity(tiers.len());
+    let mut floor = 0;
+    for tier in tiers {
+        let ceiling = tier.up_to.map_or(balance, |up_to| up_to.min(balance));
+        let portion = (ceiling - floor).max(0);
+        accruals.push(TierAccrual {
+            portion,
+            rate_bps: tier.rate_bps,
+            units: i128::from(portion) * i128::from(tier.rate_bps),
+        });
+        match tier.up_to {
+            Some(up_to) if up_to < balance => floor = floor.max(up_to),
+            _ => break,
+        }
+    }
+    accruals
+}
+
+/// Describes an accrual breakdown as transaction metadata, with the accrued
+/// interest in fractional cents.
+pub(crate) fn breakdown_metadata(accruals: &[TierAccrual]) -> BTreeMap<String, String> {
+    let mut metadata = BTreeMap::new();
+    for (index, accrual) in accruals.iter().enumerate() {
+        let tier = index + 1;
+        let accrued = accrual.units * 10_000 / INTEREST_UNITS_PER_CENT;
+        metadata.insert(
+            format!("tier.{}.portion", tier),
+            accrual.portion.to_string(),
+        );
+        metadata.insert(
+            format!("tier.{}.rate_bps", tier),
+            accrual.rate_bps.to_string(),
+        );
+        metadata.insert(
+            format!("tier.{}.accrued", tier),
+            format!("{}.{:04}", accrued / 10_000, accrued % 10_000),
+        );
+    }
+    metadata
+}
+
+/// One day's accrual on `balance` in interest units, using the tiers if
+/// there are any and the flat rate otherwise.
+pub(crate) fn daily_accrual(balance: i64, rate_bps: u32, tiers: &[InterestTier]) -> i128 {
+    if balance <= 0 {
+        0
+    } else if tiers.is_empty() {
+        i128::from(balance) * i128::from(rate_bps)
+    } else {
+        accrue_tiered(balance, tiers)
+            .iter()
+            .map(|tier| tier.units)
+            .sum()
+    }
+}
@@ -0,0 +1,72 @@
+//! The balance arithmetic every account operation rests on, written against
+//! `core` alone so it builds without the standard library
+//! (`--no-default-features`) for embedded targets and sandboxes.
+
+use core::fmt;
+
+/// Why a balance change was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerError {
+    NegativeAmount,
+    /// The new balance would not fit in an `i64`.
+    Overflow,
+    /// The amount is more than the account may draw on.
+    InsufficientFunds {
+        requested: i64,
+        spendable: i64,
+    },
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::NegativeAmount => write!(f, "Amount must not be negative"),
+            LedgerError::Overflow => write!(f, "Balance would overflow"),
+            LedgerError::InsufficientFunds {
+                requested,
+                spendable,
+            } => write!(
+                f,
+                "Requested {} but only {} can be spent",
+                requested, spendable
+            ),
+        }
+    }
+}
+
+/// The balance after crediting `amount`.
+pub fn credit(balance: i64, amount: i64) -> Result<i64, LedgerError> {
+    if amount < 0 {
+        return Err(LedgerError::NegativeAmount);
+    }
+    balance.checked_add(amount).ok_or(LedgerError::Overflow)
+}
+
+/// The balance after debiting `amount`, which may not exceed `spendable`.
+pub fn debit(balance: i64, spendable: i64, amount: i64) -> Result<i64, LedgerError> {
+    if amount < 0 {
+        return Err(LedgerError::NegativeAmount);
+    }
+    if spendable < amount {
+        return Err(LedgerError::InsufficientFunds {
+            requested: amount,
+            spendable,
+        });
+    }
+    balance.checked_sub(amount).ok_or(LedgerError::Overflow)
+}
+
+/// The balance minus the funds reserved by `holds`.
+pub fn available(balance: i64, holds: impl IntoIterator<Item = i64>) -> i64 {
+    balance - holds.into_iter().sum::<i64>()
+}
+
+/// What a debit may draw on: `available` plus the overdraft facility when
+/// the holder has opted into overdraft coverage.
+pub fn spendable(available: i64, overdraft_limit: i64, overdraft_opt_in: bool) -> i64 {
+    if overdraft_opt_in {
+        available + overdraft_limit
+    } else {
+        available
+    }
+}
@@ -0,0 +1,374 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod ledger;
+
+// Everything below needs the standard library.
+#[cfg(feature = "std")]
+mod access;
+#[cfg(feature = "std")]
+mod account;
+#[cfg(feature = "std")]
+mod actor;
+#[cfg(feature = "std")]
+mod adjustments;
+#[cfg(feature = "std")]
+mod archive;
+#[cfg(feature = "std")]
+mod audit_log;
+#[cfg(feature = "std")]
+mod auth;
+#[cfg(feature = "std")]
+mod bank;
+#[cfg(feature = "std")]
+mod chain;
+#[cfg(feature = "std")]
+mod channels;
+#[cfg(feature = "std")]
+mod checkpoint;
+#[cfg(feature = "std")]
+mod compare;
+#[cfg(feature = "std")]
+mod confirm;
+#[cfg(feature = "std")]
+mod cosign;
+#[cfg(feature = "std")]
+#[cfg_attr(not(feature = "encryption"), allow(dead_code))]
+mod crypto;
+#[cfg(feature = "std")]
+mod currency;
+#[cfg(feature = "std")]
+mod daemon;
+#[cfg(feature = "std")]
+pub mod datasets;
+#[cfg(feature = "std")]
+mod deductions;
+#[cfg(feature = "std")]
+mod disputes;
+#[cfg(feature = "std")]
+mod documents;
+#[cfg(feature = "std")]
+mod dormancy;
+#[cfg(feature = "std")]
+mod driver;
+#[cfg(feature = "std")]
+mod duplicates;
+#[cfg(feature = "encryption")]
+mod encryption;
+#[cfg(feature = "std")]
+mod eod;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod export;
+#[cfg(feature = "std")]
+mod exposure;
+#[cfg(feature = "std")]
+mod faults;
+#[cfg(feature = "std")]
+mod fee_debt;
+#[cfg(feature = "std")]
+mod funds;
+#[cfg(feature = "std")]
+mod fx;
+#[cfg(feature = "std")]
+mod goals;
+#[cfg(feature = "std")]
+mod guardian;
+#[cfg(feature = "std")]
+mod ids;
+#[cfg(feature = "std")]
+mod import;
+#[cfg(feature = "std")]
+mod insights;
+#[cfg(feature = "std")]
+mod interest;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod locale;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+mod money;
+#[cfg(feature = "std")]
+mod notify;
+#[cfg(feature = "std")]
+mod outcome;
+#[cfg(feature = "std")]
+mod overdraft_interest;
+#[cfg(feature = "std")]
+mod overflow;
+#[cfg(feature = "std")]
+mod ownership;
+#[cfg(feature = "parquet")]
+mod parquet;
+#[cfg(feature = "std")]
+mod partition;
+#[cfg(feature = "std")]
+mod payees;
+#[cfg(feature = "std")]
+mod payroll;
+#[cfg(feature = "std")]
+mod planner;
+#[cfg(feature = "std")]
+mod portfolio;
+#[cfg(feature = "std")]
+mod projection;
+#[cfg(feature = "std")]
+mod promotions;
+#[cfg(feature = "std")]
+mod receipt;
+#[cfg(feature = "std")]
+mod reconciliation;
+#[cfg(feature = "std")]
+mod recovery;
+#[cfg(feature = "std")]
+mod redenomination;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod remainders;
+#[cfg(feature = "std")]
+mod replica;
+#[cfg(feature = "std")]
+mod retention;
+#[cfg(feature = "std")]
+mod retry;
+#[cfg(feature = "std")]
+mod rewards;
+#[cfg(feature = "std")]
+mod rng;
+#[cfg(feature = "std")]
+mod rounding;
+#[cfg(feature = "std")]
+mod savepoint;
+#[cfg(feature = "std")]
+mod scheduler;
+#[cfg(feature = "std")]
+mod search;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod statement;
+#[cfg(feature = "std")]
+mod statement_reconciliation;
+#[cfg(feature = "std")]
+mod store;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+mod structuring;
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(feature = "std")]
+mod sweeps;
+#[cfg(feature = "std")]
+mod tagging;
+#[cfg(feature = "std")]
+mod tax;
+#[cfg(feature = "std")]
+mod template;
+#[cfg(feature = "std")]
+pub mod test_fixtures;
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(feature = "std")]
+mod time;
+#[cfg(feature = "std")]
+mod time_format;
+#[cfg(feature = "std")]
+mod transaction;
+#[cfg(feature = "std")]
+mod uuid;
+#[cfg(feature = "std")]
+mod validation;
+#[cfg(feature = "std")]
+mod wal;
+#[cfg(feature = "std")]
+mod wizard;
+#[cfg(feature = "std")]
+mod zero_amount;
+
+#[cfg(feature = "std")]
+pub use access::{AccessError, Action, Role, Session};
+#[cfg(feature = "std")]
+pub use account::{Account, AccountRef, Hold, PendingCheque};
+#[cfg(feature = "std")]
+pub use actor::{ActorError, BankActor, BankHandle};
+#[cfg(feature = "std")]
+pub use archive::AccountArchive;
+#[cfg(feature = "std")]
+pub use audit_log::{RebuildError, RebuildReport};
+#[cfg(feature = "std")]
+pub use auth::{AuthError, SessionToken};
+#[cfg(feature = "std")]
+pub use bank::Bank;
+#[cfg(feature = "std")]
+pub use chain::{ChainBreak, ChainHash};
+#[cfg(feature = "std")]
+pub use channels::{ChannelDeposit, ChannelLine, ChannelPolicy, ChannelReport, DepositChannel};
+#[cfg(feature = "std")]
+pub use checkpoint::{CheckpointConfig, Simulation, SimulationEvent};
+#[cfg(feature = "std")]
+pub use compare::{BankComparison, Mismatch};
+#[cfg(feature = "std")]
+pub use confirm::Confirmation;
+#[cfg(feature = "std")]
+pub use cosign::{CoSigner, PendingWithdrawal};
+#[cfg(feature = "std")]
+pub use currency::{Currency, CurrencyTotals};
+#[cfg(feature = "std")]
+pub use daemon::{Daemon, DaemonConfig};
+#[cfg(feature = "std")]
+pub use deductions::{DeductionOrder, DeductionStatus};
+#[cfg(feature = "std")]
+pub use disputes::{Dispute, DisputeStatus};
+#[cfg(feature = "std")]
+pub use documents::{Document, DocumentKind, DocumentOwner, DocumentStorage};
+#[cfg(feature = "std")]
+pub use dormancy::{DormancyPolicy, DormancyReport, DormantAccount};
+#[cfg(feature = "std")]
+pub use driver::{SimulationDriver, SimulationSpeed};
+#[cfg(feature = "std")]
+pub use duplicates::{DuplicateAction, DuplicateCheck};
+#[cfg(feature = "encryption")]
+pub use encryption::{EncryptionKey, Secret};
+#[cfg(feature = "std")]
+pub use eod::{EndOfDayConfig, EndOfDayReport, EndOfDayStep};
+#[cfg(feature = "std")]
+pub use error::{AccountError, InputError, PolicyError, StateError, StorageError};
+#[cfg(feature = "std")]
+pub use export::{ExportSummary, DEFAULT_EXPORT_CHUNK, EXPORT_SCHEMA_VERSION};
+#[cfg(feature = "std")]
+pub use faults::{FaultPlan, FaultyReader, FaultyWriter};
+#[cfg(feature = "std")]
+pub use fee_debt::FeeShortfall;
+#[cfg(feature = "std")]
+pub use funds::{FixedPrice, PriceHistory, PriceSource, UnitHolding, UNIT_SCALE};
+#[cfg(feature = "std")]
+pub use fx::{FxExecution, FxOrder, FxOrderReport, RateHistory, RateSource, RATE_SCALE};
+#[cfg(feature = "std")]
+pub use goals::{GoalProgress, SavingsGoal};
+#[cfg(feature = "std")]
+pub use guardian::Guardianship;
+#[cfg(feature = "std")]
+pub use ids::{IdGenerator, SequentialIds, SnowflakeIds, TimeOrderedIds};
+#[cfg(feature = "std")]
+pub use import::{
+    batch_value, parse_csv, BatchOp, BatchOperation, BatchPreview, ImportError, ImportFailure,
+    ImportReport,
+};
+#[cfg(feature = "std")]
+pub use insights::{CategorySpend, Insights, MonthlySpend, SpendingInsights};
+#[cfg(feature = "std")]
+pub use interest::InterestTier;
+#[cfg(feature = "std")]
+pub use locale::{Locale, Localize};
+#[cfg(feature = "std")]
+pub use metrics::Metrics;
+#[cfg(feature = "std")]
+pub use money::{Money, MoneyParseError};
+#[cfg(feature = "smtp")]
+pub use notify::SmtpNotifier;
+#[cfg(feature = "std")]
+pub use notify::{
+    Channel, FileNotifier, Notification, NotificationEvent, NotificationPreferences, Notifier,
+    StdoutNotifier,
+};
+#[cfg(feature = "std")]
+pub use outcome::{OperationOutcome, Warning};
+#[cfg(feature = "std")]
+pub use overdraft_interest::{GraceAccrual, OverdraftInterest};
+#[cfg(feature = "std")]
+pub use overflow::OverflowPolicy;
+#[cfg(feature = "std")]
+pub use ownership::OwnershipTransfer;
+#[cfg(feature = "std")]
+pub use payees::{PayeeLimitStage, PayeeLimits};
+#[cfg(feature = "std")]
+pub use payroll::{PayrollPayment, PayrollRun, SALARY_CATEGORY};
+#[cfg(feature = "std")]
+pub use planner::{PlannedTransfer, TransferGoal, TransferPlan};
+#[cfg(feature = "std")]
+pub use portfolio::{NetWorthPoint, Portfolio};
+#[cfg(feature = "std")]
+pub use projection::{
+    BalanceProjection, ProjectedMonth, Projection, ProjectionAssumptions, RecurringFlow,
+};
+#[cfg(feature = "std")]
+pub use promotions::{Promotion, PromotionAward};
+#[cfg(feature = "std")]
+pub use receipt::Receipt;
+#[cfg(feature = "std")]
+pub use reconciliation::{Invoice, MatchKind, PaymentMatch, ReconciliationReport};
+#[cfg(feature = "std")]
+pub use recovery::Remediation;
+#[cfg(feature = "std")]
+pub use redenomination::{RedenominationLine, RedenominationReport};
+#[cfg(feature = "std")]
+pub use registry::{
+    BankRegistry, NetSettlement, RegistryEndOfDay, RegistryError, Settlement, TenantConfig,
+};
+#[cfg(feature = "std")]
+pub use remainders::{RemainderLine, RoundingAudit, RoundingSource};
+#[cfg(feature = "std")]
+pub use replica::ReadReplica;
+#[cfg(feature = "std")]
+pub use retention::{PruneReport, RetentionPolicy};
+#[cfg(feature = "std")]
+pub use retry::{is_transient, Attempt, RetryError, RetryPolicy};
+#[cfg(feature = "std")]
+pub use rewards::{
+    RewardEarning, RewardEntry, RewardMatch, RewardRule, RewardsAccount, RewardsProgram,
+    RewardsStatement,
+};
+#[cfg(feature = "std")]
+pub use rng::SeededRng;
+#[cfg(feature = "std")]
+pub use rounding::RoundingPolicy;
+#[cfg(feature = "std")]
+pub use savepoint::Savepoint;
+#[cfg(feature = "std")]
+pub use scheduler::{ScheduledOperation, ScheduledRun};
+#[cfg(feature = "std")]
+pub use search::{SearchHit, SearchIndex};
+#[cfg(feature = "std")]
+pub use snapshot::PersistError;
+#[cfg(feature = "std")]
+pub use statement::{Statement, StatementDiff, TransactionChange};
+#[cfg(feature = "std")]
+pub use statement_reconciliation::{
+    AmountMismatch, ExtraEntry, MissingEntry, StatementLine, StatementReconciliation,
+};
+#[cfg(feature = "std")]
+pub use store::AccountStorage;
+#[cfg(feature = "std")]
+pub use stream::{StreamOp, StreamProcessor, StreamReport, StreamRow};
+#[cfg(feature = "std")]
+pub use structuring::{StructuringAlert, StructuringReport, StructuringRule};
+#[cfg(feature = "std")]
+pub use summary::{AccountSummary, SummaryQuery};
+#[cfg(feature = "std")]
+pub use sweeps::{SweepKind, SweepRule};
+#[cfg(feature = "std")]
+pub use tagging::{TagChange, TagEdit, TransactionFilter};
+#[cfg(feature = "std")]
+pub use tax::{FlatTax, TaxPolicy, TaxRemittance};
+#[cfg(feature = "std")]
+pub use template::{OutputFormat, Template, TemplateContext, TemplateError, TemplateValue};
+#[cfg(feature = "std")]
+pub use time::{Date, Timestamp, SECONDS_PER_DAY};
+#[cfg(feature = "std")]
+pub use time_format::{TimeFormat, TimeZone};
+#[cfg(feature = "std")]
+pub use transaction::{Transaction, TransactionKind};
+#[cfg(feature = "std")]
+pub use uuid::{ParseUuidError, Uuid};
+#[cfg(feature = "std")]
+pub use validation::{HolderValidation, ValidationError, ValidationMode, ValidationRule};
+#[cfg(feature = "std")]
+pub use wal::{LoggedEntry, WalEntry, WriteAheadLog};
+#[cfg(feature = "std")]
+pub use wizard::{AccountKind, AccountWizard, NewAccount};
+#[cfg(feature = "std")]
+pub use zero_amount::ZeroAmountPolicy;
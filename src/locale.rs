@@ -0,0 +1,1100 @@
+use std::fmt;
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::currency::{Currency, CurrencyTotals};
+use crate::error::{AccountError, InputError, PolicyError, StateError, StorageError};
+use crate::insights::{category_of, Insights, MonthlySpend};
+use crate::outcome::Warning;
+use crate::portfolio::Portfolio;
+use crate::rounding::RoundingPolicy;
+use crate::statement::Statement;
+use crate::time_format::TimeFormat;
+use crate::transaction::{Transaction, TransactionKind};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    Georgian,
+}
+
+impl Locale {
+    /// The catalog entry for `key`, falling back to English.
+    pub fn text(self, key: &str) -> &'static str {
+        let catalog: &[(&str, &'static str)] = match self {
+            Locale::English => ENGLISH,
+            Locale::German => GERMAN,
+            Locale::Georgian => GEORGIAN,
+        };
+        lookup(catalog, key)
+            .or_else(|| lookup(ENGLISH, key))
+            .unwrap_or("")
+    }
+
+    /// Renders `key`, substituting `{0}`, `{1}`, ... with `args`.
+    pub fn format(self, key: &str, args: &[&dyn fmt::Display]) -> String {
+        let template = self.text(key);
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let placeholder = after
+                .find('}')
+                .and_then(|close| Some((close, after[..close].parse::<usize>().ok()?)));
+            match placeholder.and_then(|(close, index)| Some((close, args.get(index)?))) {
+                Some((close, arg)) => {
+                    rendered.push_str(&arg.to_string());
+                    rest = &after[close + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = after;
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    /// Formats cents with two decimals in this locale's conventions.
+    pub fn money(self, cents: i64) -> String {
+        self.money_with(cents, 2, RoundingPolicy::Truncate)
+    }
+
+    pub fn money_with(self, cents: i64, precision: u32, rounding: RoundingPolicy) -> String {
+        self.money_units(cents, Currency::USD, precision, rounding)
+    }
+
+    /// Formats an amount in `currency`'s minor units at its usual precision.
+    pub fn money_in(self, amount: i64, currency: Currency) -> String {
+        self.money_units(
+            amount,
+            currency,
+            currency.minor_units(),
+            RoundingPolicy::Truncate,
+        )
+    }
+
+    /// Like [`Locale::money_with`], for amounts in `currency`'s minor units.
+    /// The currency's symbol is used where it has one, its code otherwise.
+    pub fn money_units(
+        self,
+        amount: i64,
+        currency: Currency,
+        precision: u32,
+        rounding: RoundingPolicy,
+    ) -> String {
+        let number = rounding.format_units(amount, currency.minor_units(), precision);
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", number.as_str()),
+        };
+        match (self, currency.symbol()) {
+            (Locale::English, Some(symbol)) => format!("{}{}{}", sign, symbol, digits),
+            (Locale::English, None) => format!("{}{} {}", sign, currency, digits),
+            (Locale::German | Locale::Georgian, symbol) => format!(
+                "{}{} {}",
+                sign,
+                digits.replace('.', ","),
+                symbol.unwrap_or(currency.code())
+            ),
+        }
+    }
+}
+
+fn lookup(catalog: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    catalog
+        .iter()
+        .find(|(candidate, _)| *candidate == key)
+        .map(|(_, text)| *text)
+}
+
+/// Renders a value's user-facing text in a given locale.
+pub trait Localize {
+    fn localize(&self, locale: Locale) -> String;
+
+    /// Like [`Localize::localize`], writing timestamps and dates in `time`.
+    fn localize_with(&self, locale: Locale, time: &TimeFormat) -> String {
+        let _ = time;
+        self.localize(locale)
+    }
+}
+
+impl Localize for AccountError {
+    fn localize(&self, locale: Locale) -> String {
+        match self {
+            AccountError::Validation(error) => error.localize(locale),
+            AccountError::Policy(error) => error.localize(locale),
+            AccountError::State(error) => error.localize(locale),
+            AccountError::Storage(error) => error.localize(locale),
+        }
+    }
+}
+
+impl Localize for InputError {
+    fn localize(&self, locale: Locale) -> String {
+        let key = match self {
+            InputError::NegativeAmount => "error.negative_amount",
+            InputError::ZeroAmount => "error.zero_amount",
+            InputError::AmountOverflow => "error.amount_overflow",
+            InputError::SameAccount => "error.same_account",
+            InputError::InvalidRate => "error.invalid_rate",
+            InputError::MissingAuditDetails => "error.missing_audit_details",
+            InputError::InvalidHolder(_) => "error.invalid_holder",
+            InputError::EmptyTag => "error.empty_tag",
+            InputError::MixedCurrencies => "error.mixed_currencies",
+            InputError::DocumentTooLarge { size, limit } => {
+                return locale.format("error.document_too_large", &[size, limit])
+            }
+        };
+        locale.text(key).to_string()
+    }
+}
+
+impl Localize for PolicyError {
+    fn localize(&self, locale: Locale) -> String {
+        let key = match self {
+            PolicyError::InsufficientFunds { .. } => "error.insufficient_funds",
+            PolicyError::PossibleDuplicate { .. } => "error.possible_duplicate",
+            PolicyError::AccountLocked => "error.account_locked",
+            PolicyError::AccountDormant => "error.account_dormant",
+            PolicyError::PayeeLimitExceeded { .. } => "error.payee_limit_exceeded",
+            PolicyError::CoSignatureRequired { .. } => "error.co_signature_required",
+            PolicyError::WrongCoSigner => "error.wrong_co_signer",
+            PolicyError::SpendingCapExceeded { .. } => "error.spending_cap_exceeded",
+            PolicyError::NotGuardian => "error.not_guardian",
+            PolicyError::ExposureLimitExceeded {
+                exposure,
+                resulting,
+                limit,
+                currency,
+            } => {
+                let money = |amount: &i64| locale.money_in(*amount, *currency);
+                return locale.format(
+                    "error.exposure_limit_exceeded",
+                    &[&money(exposure), &money(resulting), &money(limit)],
+                );
+            }
+            PolicyError::OwnershipApprovalRequired { .. } => "error.ownership_approval_required",
+            PolicyError::NotOwnershipParty => "error.not_ownership_party",
+        };
+        locale.text(key).to_string()
+    }
+}
+
+impl Localize for StateError {
+    fn localize(&self, locale: Locale) -> String {
+        let key = match self {
+            StateError::AccountNotFound => "error.account_not_found",
+            StateError::AccountExists => "error.account_exists",
+            StateError::HoldNotFound => "error.hold_not_found",
+            StateError::NotEnrolledInRewards => "error.not_enrolled_in_rewards",
+            StateError::InsufficientRewards => "error.insufficient_rewards",
+            StateError::DeductionOrderNotFound => "error.deduction_order_not_found",
+            StateError::GoalNotFound => "error.goal_not_found",
+            StateError::SweepRuleNotFound => "error.sweep_rule_not_found",
+            StateError::PromotionNotFound => "error.promotion_not_found",
+            StateError::PriceUnavailable => "error.price_unavailable",
+            StateError::TransactionNotFound => "error.transaction_not_found",
+            StateError::NotDisputable => "error.not_disputable",
+            StateError::DisputeNotFound => "error.dispute_not_found",
+            StateError::WithdrawalRequestNotFound => "error.withdrawal_request_not_found",
+            StateError::FxOrderNotFound => "error.fx_order_not_found",
+            StateError::ScheduledOperationNotFound => "error.scheduled_operation_not_found",
+            StateError::OwnershipTransferNotFound => "error.ownership_transfer_not_found",
+            StateError::HolderNotFound => "error.holder_not_found",
+            StateError::DocumentNotFound => "error.document_not_found",
+        };
+        locale.text(key).to_string()
+    }
+}
+
+impl Localize for StorageError {
+    fn localize(&self, locale: Locale) -> String {
+        match self {
+            StorageError::Io(error) => locale.format("error.storage_io", &[error]),
+            StorageError::Corrupt { line, message } => {
+                locale.format("error.storage_corrupt", &[line, message])
+            }
+            StorageError::Decryption => locale.text("error.storage_decryption").to_string(),
+            StorageError::DocumentAltered { document_id } => {
+                locale.format("error.document_altered", &[document_id])
+            }
+        }
+    }
+}
+
+impl Localize for Warning {
+    fn localize(&self, locale: Locale) -> String {
+        match self {
+            Warning::BelowMinimumBalance {
+                balance,
+                minimum,
+                currency,
+            } => locale.format(
+                "warning.below_minimum_balance",
+                &[
+                    &locale.money_in(*balance, *currency),
+                    &locale.money_in(*minimum, *currency),
+                ],
+            ),
+            Warning::UnusualSpend {
+                amount,
+                typical,
+                currency,
+            } => locale.format(
+                "warning.unusual_spend",
+                &[
+                    &locale.money_in(*amount, *currency),
+                    &locale.money_in(*typical, *currency),
+                ],
+            ),
+            Warning::PossibleDuplicate {
+                previous_transaction,
+            } => locale.format("warning.possible_duplicate", &[previous_transaction]),
+            Warning::ZeroAmount => locale.text("warning.zero_amount").to_string(),
+            Warning::SelfTransfer => locale.text("warning.self_transfer").to_string(),
+            Warning::AmountSaturated {
+                requested,
+                credited,
+                currency,
+            } => locale.format(
+                "warning.amount_saturated",
+                &[
+                    &locale.money_in(*requested, *currency),
+                    &locale.money_in(*credited, *currency),
+                ],
+            ),
+        }
+    }
+}
+
+impl Localize for TransactionKind {
+    fn localize(&self, locale: Locale) -> String {
+        let key = match self {
+            TransactionKind::OpeningBalance => "kind.opening_balance",
+            TransactionKind::Deposit => "kind.deposit",
+            TransactionKind::Withdrawal => "kind.withdrawal",
+            TransactionKind::TransferIn => "kind.transfer_in",
+            TransactionKind::TransferOut => "kind.transfer_out",
+            TransactionKind::ChequeDeposit => "kind.cheque_deposit",
+            TransactionKind::Interest => "kind.interest",
+            TransactionKind::Fee => "kind.fee",
+            TransactionKind::Reward => "kind.reward",
+            TransactionKind::Redenomination => "kind.redenomination",
+            TransactionKind::Adjustment => "kind.adjustment",
+            TransactionKind::Settlement => "kind.settlement",
+            TransactionKind::Revaluation => "kind.revaluation",
+            TransactionKind::Chargeback => "kind.chargeback",
+            TransactionKind::OwnershipTransfer => "kind.ownership_transfer",
+            TransactionKind::RoundingRemainder => "kind.rounding_remainder",
+        };
+        locale.text(key).to_string()
+    }
+}
+
+impl Transaction {
+    /// The entry as one line, its amounts in `currency`, the account's.
+    pub fn localize_in(&self, locale: Locale, time: &TimeFormat, currency: Currency) -> String {
+        let sign = if self.amount < 0 { "-" } else { "+" };
+        let mut line = locale.format(
+            "transaction.line",
+            &[
+                &time.timestamp(self.timestamp),
+                &self.id,
+                &self.kind.localize(locale),
+                &sign,
+                &locale.money_in(self.amount.saturating_abs(), currency),
+                &locale.money_in(self.balance_after, currency),
+            ],
+        );
+        if let Some(counterparty) = self.counterparty {
+            line.push(' ');
+            line.push_str(&locale.format("transaction.counterparty", &[&counterparty]));
+        }
+        if let Some(category) = &self.category {
+            line.push_str(&format!(" [{}]", category));
+        }
+        if !self.memo.is_empty() {
+            line.push_str(&format!(" - {}", self.memo));
+        }
+        line
+    }
+}
+
+/// An entry on its own does not know its account's currency, so it is
+/// shown in dollars; [`Transaction::localize_in`] takes the currency.
+impl Localize for Transaction {
+    fn localize(&self, locale: Locale) -> String {
+        self.localize_with(locale, &TimeFormat::default())
+    }
+
+    fn localize_with(&self, locale: Locale, time: &TimeFormat) -> String {
+        self.localize_in(locale, time, Currency::USD)
+    }
+}
+
+impl Localize for Account {
+    fn localize(&self, locale: Locale) -> String {
+        let balance = locale.money_units(
+            self.balance,
+            self.currency,
+            self.display_precision,
+            self.rounding,
+        );
+        locale.format("account.summary", &[&self.id, &self.holder, &balance])
+    }
+}
+
+impl Localize for Bank {
+    fn localize(&self, locale: Locale) -> String {
+        let totals = self.balances_by_currency().localize(locale);
+        locale.format("bank.total", &[&totals])
+    }
+}
+
+impl Localize for Statement {
+    fn localize(&self, locale: Locale) -> String {
+        self.localize_with(locale, &TimeFormat::default())
+    }
+
+    fn localize_with(&self, locale: Locale, time: &TimeFormat) -> String {
+        let mut lines = vec![
+            locale.format(
+                "statement.header",
+                &[
+                    &self.account_id,
+                    &self.holder,
+                    &time.date(self.period_start.date()),
+                    &time.date(self.period_end.date()),
+                ],
+            ),
+            locale.format(
+                "statement.opening",
+                &[&locale.money_in(self.opening_balance, self.currency)],
+            ),
+        ];
+        for txn in &self.transactions {
+            lines.push(format!(
+                "  {}",
+                txn.localize_in(locale, time, self.currency)
+            ));
+        }
+        lines.push(locale.format(
+            "statement.closing",
+            &[&locale.money_in(self.closing_balance, self.currency)],
+        ));
+        lines.join("\n")
+    }
+}
+
+impl Localize for CurrencyTotals {
+    /// Each currency's total, or a plain zero when there are none.
+    fn localize(&self, locale: Locale) -> String {
+        if self.is_empty() {
+            return "0".to_string();
+        }
+        self.iter()
+            .map(|(currency, total)| locale.money_in(total, currency))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Localize for Portfolio {
+    fn localize(&self, locale: Locale) -> String {
+        let mut lines =
+            vec![locale.format("portfolio.header", &[&self.holder, &self.accounts.len()])];
+        for (currency, balance) in &self.balances {
+            let balance = locale.money_in(*balance, *currency);
+            lines.push(locale.format("portfolio.balance", &[currency, &balance]));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Localize for Insights {
+    fn localize(&self, locale: Locale) -> String {
+        let mut lines = vec![locale.format(
+            "insights.header",
+            &[
+                &self.holder,
+                &self.period_start.date(),
+                &self.period_end.date(),
+            ],
+        )];
+        if self.by_currency.is_empty() {
+            lines.push(locale.text("insights.none").to_string());
+        }
+        for spending in &self.by_currency {
+            let money = |amount| locale.money_in(amount, spending.currency);
+            lines.push(locale.format(
+                "insights.total",
+                &[
+                    &spending.currency,
+                    &money(spending.total_spent),
+                    &money(spending.average_daily_spend),
+                ],
+            ));
+            for category in spending.top_categories(5) {
+                lines.push(locale.format(
+                    "insights.category",
+                    &[&category.category, &money(category.spent), &category.count],
+                ));
+            }
+            if let Some(txn) = &spending.biggest_expense {
+                lines.push(locale.format(
+                    "insights.biggest",
+                    &[
+                        &money(-txn.amount),
+                        &txn.timestamp.date(),
+                        &category_of(txn),
+                    ],
+                ));
+            }
+            for month in &spending.months {
+                lines.push(localize_month(locale, month, &money(month.spent)));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+fn localize_month(locale: Locale, month: &MonthlySpend, spent: &str) -> String {
+    let name = format!("{}-{:02}", month.month.year, month.month.month);
+    match month.change_bps {
+        Some(bps) => {
+            let sign = if bps < 0 { '-' } else { '+' };
+            let change = format!("{}{}.{:02}%", sign, bps.abs() / 100, bps.abs() % 100);
+            locale.format("insights.month_change", &[&name, &spent, &change])
+        }
+        None => locale.format("insights.month", &[&name, &spent]),
+    }
+}
+
+impl Bank {
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Localizes `value` in this bank's configured locale and time format.
+    pub fn localized(&self, value: &dyn Localize) -> String {
+        value.localize_with(self.locale, &self.time_format)
+    }
+
+    /// The account table from [`Bank::summary_table_in`], without color.
+    pub fn summary_in(&self, locale: Locale) -> String {
+        self.summary_table_in(locale, false)
+    }
+}
+
+const ENGLISH: &[(&str, &str)] = &[
+    ("error.negative_amount", "Amount cannot be negative"),
+    ("error.insufficient_funds", "Insufficient funds"),
+    ("error.amount_overflow", "Amount overflow"),
+    ("error.account_not_found", "Account not found"),
+    ("error.account_exists", "Account already exists"),
+    (
+        "error.co_signature_required",
+        "Withdrawal is waiting for the co-signer",
+    ),
+    (
+        "error.withdrawal_request_not_found",
+        "Withdrawal request not found or expired",
+    ),
+    (
+        "error.wrong_co_signer",
+        "Only the account's co-signer can approve",
+    ),
+    ("error.spending_cap_exceeded", "Daily spending cap reached"),
+    (
+        "error.not_guardian",
+        "Only the account's guardian can do this",
+    ),
+    ("error.fx_order_not_found", "FX order not found"),
+    (
+        "error.scheduled_operation_not_found",
+        "Scheduled operation not found",
+    ),
+    (
+        "error.exposure_limit_exceeded",
+        "The holder's overdrafts would reach {1}, over the limit of {2} (now {0})",
+    ),
+    ("error.invalid_holder", "Holder name is not valid"),
+    (
+        "error.ownership_approval_required",
+        "Both holders must approve the ownership transfer",
+    ),
+    (
+        "error.ownership_transfer_not_found",
+        "Ownership transfer not found",
+    ),
+    ("error.holder_not_found", "No account has this holder"),
+    ("error.document_not_found", "Document not found"),
+    (
+        "error.document_too_large",
+        "The document is {0} bytes, over the limit of {1}",
+    ),
+    (
+        "error.document_altered",
+        "Document {0} is missing or no longer matches its hash",
+    ),
+    (
+        "error.not_ownership_party",
+        "Only the current or the new holder can approve",
+    ),
+    ("error.storage_io", "Storage failed: {0}"),
+    ("error.storage_corrupt", "Corrupt data at line {0}: {1}"),
+    (
+        "error.storage_decryption",
+        "Could not decrypt: wrong passphrase or key, or modified data",
+    ),
+    ("error.hold_not_found", "Hold not found"),
+    (
+        "error.not_enrolled_in_rewards",
+        "Account is not enrolled in rewards",
+    ),
+    ("error.insufficient_rewards", "Insufficient rewards balance"),
+    (
+        "error.same_account",
+        "Source and destination account are the same",
+    ),
+    ("error.invalid_rate", "Rate is out of range"),
+    (
+        "error.deduction_order_not_found",
+        "Deduction order not found",
+    ),
+    ("error.possible_duplicate", "Possible duplicate payment"),
+    ("error.goal_not_found", "Savings goal not found"),
+    ("error.sweep_rule_not_found", "Sweep rule not found"),
+    ("error.promotion_not_found", "Promotion not found"),
+    (
+        "error.missing_audit_details",
+        "An adjustment needs a reason and an actor",
+    ),
+    ("error.empty_tag", "A tag or category cannot be empty"),
+    (
+        "error.mixed_currencies",
+        "The accounts are not all in the same currency",
+    ),
+    (
+        "error.price_unavailable",
+        "No unit price is available for the fund",
+    ),
+    ("error.transaction_not_found", "Transaction not found"),
+    (
+        "error.not_disputable",
+        "Only an undisputed deposit can be disputed",
+    ),
+    (
+        "error.dispute_not_found",
+        "No open dispute for this transaction",
+    ),
+    ("error.account_locked", "Account is locked"),
+    (
+        "error.account_dormant",
+        "Account is dormant; reactivate it to take money out",
+    ),
+    ("error.zero_amount", "Amount must not be zero"),
+    (
+        "error.payee_limit_exceeded",
+        "Daily transfer limit for this payee reached",
+    ),
+    (
+        "warning.below_minimum_balance",
+        "Balance {0} is below the minimum of {1}",
+    ),
+    (
+        "warning.unusual_spend",
+        "Amount {0} exceeds your typical spend of {1}",
+    ),
+    (
+        "warning.possible_duplicate",
+        "Possible duplicate of transaction #{0}",
+    ),
+    ("warning.zero_amount", "Amount was zero; nothing was booked"),
+    (
+        "warning.self_transfer",
+        "Transfer was to the same account; nothing was moved",
+    ),
+    (
+        "warning.amount_saturated",
+        "Only {1} of {0} fitted in the balance and was credited",
+    ),
+    ("kind.opening_balance", "Opening balance"),
+    ("kind.deposit", "Deposit"),
+    ("kind.withdrawal", "Withdrawal"),
+    ("kind.transfer_in", "Transfer in"),
+    ("kind.transfer_out", "Transfer out"),
+    ("kind.cheque_deposit", "Cheque deposit"),
+    ("kind.interest", "Interest"),
+    ("kind.fee", "Fee"),
+    ("kind.reward", "Reward"),
+    ("kind.redenomination", "Redenomination"),
+    ("kind.adjustment", "Adjustment"),
+    ("kind.settlement", "External settlement"),
+    ("kind.revaluation", "Revaluation"),
+    ("kind.chargeback", "Chargeback"),
+    ("kind.ownership_transfer", "Ownership transfer"),
+    ("kind.rounding_remainder", "Rounding remainder"),
+    ("transaction.line", "{0} #{1} {2} {3}{4} (balance {5})"),
+    ("transaction.counterparty", "account {0}"),
+    ("account.summary", "Account {0} ({1}) has a balance of {2}"),
+    ("bank.total", "Bank total balance: {0}"),
+    ("table.id", "ID"),
+    ("table.holder", "Holder"),
+    ("table.status", "Status"),
+    ("table.currency", "Currency"),
+    ("table.balance", "Balance"),
+    ("status.active", "Active"),
+    ("status.overdrawn", "Overdrawn"),
+    ("status.below_minimum", "Below minimum"),
+    ("status.funds_held", "Funds held"),
+    (
+        "statement.header",
+        "Statement for account {0} ({1}) from {2} to {3}",
+    ),
+    ("statement.opening", "Opening balance: {0}"),
+    ("statement.closing", "Closing balance: {0}"),
+    ("portfolio.header", "Portfolio of {0}: {1} accounts"),
+    ("portfolio.balance", "Combined {0} balance: {1}"),
+    (
+        "insights.header",
+        "Spending insights for {0} from {1} to {2}",
+    ),
+    ("insights.none", "No spending"),
+    ("insights.total", "{0}: spent {1}, {2} a day on average"),
+    ("insights.category", "  {0}: {1} over {2} payments"),
+    ("insights.biggest", "  Biggest expense: {0} on {1} ({2})"),
+    ("insights.month", "  {0}: {1}"),
+    (
+        "insights.month_change",
+        "  {0}: {1} ({2} on the month before)",
+    ),
+];
+
+const GERMAN: &[(&str, &str)] = &[
+    (
+        "error.negative_amount",
+        "Der Betrag darf nicht negativ sein",
+    ),
+    ("error.insufficient_funds", "Unzureichende Deckung"),
+    ("error.amount_overflow", "Betragsüberlauf"),
+    ("error.account_not_found", "Konto nicht gefunden"),
+    ("error.account_exists", "Konto existiert bereits"),
+    (
+        "error.co_signature_required",
+        "Auszahlung wartet auf den Mitunterzeichner",
+    ),
+    (
+        "error.withdrawal_request_not_found",
+        "Auszahlungsanfrage nicht gefunden oder abgelaufen",
+    ),
+    (
+        "error.wrong_co_signer",
+        "Nur der Mitunterzeichner des Kontos kann freigeben",
+    ),
+    ("error.spending_cap_exceeded", "Tägliches Ausgabenlimit erreicht"),
+    ("error.not_guardian", "Nur der Vormund des Kontos kann das tun"),
+    ("error.fx_order_not_found", "Devisenauftrag nicht gefunden"),
+    (
+        "error.scheduled_operation_not_found",
+        "Geplanter Vorgang nicht gefunden",
+    ),
+    (
+        "error.exposure_limit_exceeded",
+        "Die Überziehungen des Inhabers würden {1} erreichen, über dem Limit von {2} (derzeit {0})",
+    ),
+    ("error.invalid_holder", "Ungültiger Inhabername"),
+    (
+        "error.ownership_approval_required",
+        "Beide Inhaber müssen den Inhaberwechsel freigeben",
+    ),
+    (
+        "error.ownership_transfer_not_found",
+        "Inhaberwechsel nicht gefunden",
+    ),
+    ("error.holder_not_found", "Kein Konto hat diesen Inhaber"),
+    ("error.document_not_found", "Dokument nicht gefunden"),
+    (
+        "error.document_too_large",
+        "Das Dokument hat {0} Bytes, über dem Limit von {1}",
+    ),
+    (
+        "error.document_altered",
+        "Dokument {0} fehlt oder passt nicht mehr zu seinem Hash",
+    ),
+    (
+        "error.not_ownership_party",
+        "Nur der bisherige oder der neue Inhaber kann freigeben",
+    ),
+    ("error.storage_io", "Speicherfehler: {0}"),
+    ("error.storage_corrupt", "Beschädigte Daten in Zeile {0}: {1}"),
+    (
+        "error.storage_decryption",
+        "Entschlüsselung fehlgeschlagen: falsches Passwort oder falscher Schlüssel, oder veränderte Daten",
+    ),
+    ("error.hold_not_found", "Sperre nicht gefunden"),
+    (
+        "error.not_enrolled_in_rewards",
+        "Konto nimmt nicht am Prämienprogramm teil",
+    ),
+    (
+        "error.insufficient_rewards",
+        "Unzureichendes Prämienguthaben",
+    ),
+    ("error.same_account", "Quell- und Zielkonto sind identisch"),
+    (
+        "error.invalid_rate",
+        "Der Satz liegt außerhalb des zulässigen Bereichs",
+    ),
+    (
+        "error.deduction_order_not_found",
+        "Abzugsauftrag nicht gefunden",
+    ),
+    ("error.possible_duplicate", "Mögliche doppelte Zahlung"),
+    ("error.goal_not_found", "Sparziel nicht gefunden"),
+    ("error.sweep_rule_not_found", "Sweep-Regel nicht gefunden"),
+    ("error.promotion_not_found", "Aktion nicht gefunden"),
+    (
+        "error.missing_audit_details",
+        "Eine Korrektur braucht einen Grund und einen Verantwortlichen",
+    ),
+    (
+        "error.empty_tag",
+        "Ein Schlagwort oder eine Kategorie darf nicht leer sein",
+    ),
+    (
+        "error.mixed_currencies",
+        "Die Konten lauten nicht alle auf dieselbe Währung",
+    ),
+    (
+        "error.price_unavailable",
+        "Für den Fonds ist kein Anteilspreis verfügbar",
+    ),
+    ("error.transaction_not_found", "Buchung nicht gefunden"),
+    (
+        "error.not_disputable",
+        "Nur eine noch nicht beanstandete Einzahlung kann beanstandet werden",
+    ),
+    (
+        "error.dispute_not_found",
+        "Für diese Buchung gibt es keine offene Beanstandung",
+    ),
+    ("error.account_locked", "Konto ist gesperrt"),
+    (
+        "error.account_dormant",
+        "Konto ist ruhend; reaktivieren Sie es, um Geld abzuheben",
+    ),
+    ("error.zero_amount", "Betrag darf nicht null sein"),
+    (
+        "error.payee_limit_exceeded",
+        "Tageslimit für diesen Empfänger erreicht",
+    ),
+    (
+        "warning.below_minimum_balance",
+        "Kontostand {0} liegt unter dem Mindestbetrag von {1}",
+    ),
+    (
+        "warning.unusual_spend",
+        "Betrag {0} übersteigt Ihre üblichen Ausgaben von {1}",
+    ),
+    (
+        "warning.possible_duplicate",
+        "Mögliches Duplikat von Buchung #{0}",
+    ),
+    (
+        "warning.zero_amount",
+        "Betrag war null; nichts wurde gebucht",
+    ),
+    (
+        "warning.self_transfer",
+        "Überweisung auf dasselbe Konto; nichts wurde bewegt",
+    ),
+    (
+        "warning.amount_saturated",
+        "Nur {1} von {0} passten in den Kontostand und wurden gutgeschrieben",
+    ),
+    ("kind.opening_balance", "Eröffnungssaldo"),
+    ("kind.deposit", "Einzahlung"),
+    ("kind.withdrawal", "Auszahlung"),
+    ("kind.transfer_in", "Eingehende Überweisung"),
+    ("kind.transfer_out", "Ausgehende Überweisung"),
+    ("kind.cheque_deposit", "Scheckeinreichung"),
+    ("kind.interest", "Zinsen"),
+    ("kind.fee", "Gebühr"),
+    ("kind.reward", "Prämie"),
+    ("kind.redenomination", "Redenominierung"),
+    ("kind.adjustment", "Korrektur"),
+    ("kind.settlement", "Externe Verrechnung"),
+    ("kind.revaluation", "Neubewertung"),
+    ("kind.chargeback", "Rückbuchung"),
+    ("kind.ownership_transfer", "Inhaberwechsel"),
+    ("kind.rounding_remainder", "Rundungsrest"),
+    ("transaction.line", "{0} #{1} {2} {3}{4} (Saldo {5})"),
+    ("transaction.counterparty", "Konto {0}"),
+    (
+        "account.summary",
+        "Konto {0} ({1}) hat einen Kontostand von {2}",
+    ),
+    ("bank.total", "Gesamtguthaben der Bank: {0}"),
+    ("table.id", "ID"),
+    ("table.holder", "Inhaber"),
+    ("table.status", "Status"),
+    ("table.currency", "Währung"),
+    ("table.balance", "Saldo"),
+    ("status.active", "Aktiv"),
+    ("status.overdrawn", "Überzogen"),
+    ("status.below_minimum", "Unter Mindestsaldo"),
+    ("status.funds_held", "Beträge gesperrt"),
+    (
+        "statement.header",
+        "Kontoauszug für Konto {0} ({1}) vom {2} bis {3}",
+    ),
+    ("statement.opening", "Anfangssaldo: {0}"),
+    ("statement.closing", "Endsaldo: {0}"),
+    ("portfolio.header", "Portfolio von {0}: {1} Konten"),
+    ("portfolio.balance", "Gesamtguthaben in {0}: {1}"),
+    (
+        "insights.header",
+        "Ausgabenanalyse für {0} vom {1} bis {2}",
+    ),
+    ("insights.none", "Keine Ausgaben"),
+    (
+        "insights.total",
+        "{0}: {1} ausgegeben, durchschnittlich {2} pro Tag",
+    ),
+    ("insights.category", "  {0}: {1} in {2} Zahlungen"),
+    ("insights.biggest", "  Größte Ausgabe: {0} am {1} ({2})"),
+    ("insights.month", "  {0}: {1}"),
+    ("insights.month_change", "  {0}: {1} ({2} zum Vormonat)"),
+];
+
+const GEORGIAN: &[(&str, &str)] = &[
+    ("error.negative_amount", "თანხა არ შეიძლება იყოს უარყოფითი"),
+    ("error.insufficient_funds", "არასაკმარისი თანხა"),
+    ("error.amount_overflow", "თანხის გადავსება"),
+    ("error.account_not_found", "ანგარიში ვერ მოიძებნა"),
+    ("error.account_exists", "ანგარიში უკვე არსებობს"),
+    (
+        "error.co_signature_required",
+        "გატანა ელოდება თანახელმომწერის დადასტურებას",
+    ),
+    (
+        "error.withdrawal_request_not_found",
+        "გატანის მოთხოვნა ვერ მოიძებნა ან ვადა გაუვიდა",
+    ),
+    (
+        "error.wrong_co_signer",
+        "დადასტურება მხოლოდ ანგარიშის თანახელმომწერს შეუძლია",
+    ),
+    (
+        "error.spending_cap_exceeded",
+        "ხარჯვის დღიური ლიმიტი ამოწურულია",
+    ),
+    (
+        "error.not_guardian",
+        "ამის გაკეთება მხოლოდ ანგარიშის მეურვეს შეუძლია",
+    ),
+    (
+        "error.fx_order_not_found",
+        "ვალუტის კონვერტაციის ბრძანება ვერ მოიძებნა",
+    ),
+    (
+        "error.scheduled_operation_not_found",
+        "დაგეგმილი ოპერაცია ვერ მოიძებნა",
+    ),
+    (
+        "error.exposure_limit_exceeded",
+        "მფლობელის ოვერდრაფტები მიაღწევდა {1}-ს, ლიმიტზე {2} მეტს (ამჟამად {0})",
+    ),
+    ("error.invalid_holder", "მფლობელის სახელი არასწორია"),
+    (
+        "error.ownership_approval_required",
+        "მფლობელის შეცვლა ორივე მფლობელმა უნდა დაადასტუროს",
+    ),
+    (
+        "error.ownership_transfer_not_found",
+        "მფლობელის შეცვლის მოთხოვნა ვერ მოიძებნა",
+    ),
+    (
+        "error.holder_not_found",
+        "ამ მფლობელის ანგარიში არ არსებობს",
+    ),
+    ("error.document_not_found", "დოკუმენტი ვერ მოიძებნა"),
+    (
+        "error.document_too_large",
+        "დოკუმენტი {0} ბაიტია, ლიმიტზე {1} მეტი",
+    ),
+    (
+        "error.document_altered",
+        "დოკუმენტი {0} აკლია ან აღარ ემთხვევა თავის ჰეშს",
+    ),
+    (
+        "error.not_ownership_party",
+        "დადასტურება მხოლოდ ამჟამინდელ ან ახალ მფლობელს შეუძლია",
+    ),
+    ("error.storage_io", "შენახვა ვერ მოხერხდა: {0}"),
+    (
+        "error.storage_corrupt",
+        "დაზიანებული მონაცემები ხაზზე {0}: {1}",
+    ),
+    (
+        "error.storage_decryption",
+        "გაშიფვრა ვერ მოხერხდა: არასწორი პაროლი ან გასაღები, ან შეცვლილი მონაცემები",
+    ),
+    ("error.hold_not_found", "დაბლოკვა ვერ მოიძებნა"),
+    (
+        "error.not_enrolled_in_rewards",
+        "ანგარიში არ არის ჩართული ჯილდოების პროგრამაში",
+    ),
+    (
+        "error.insufficient_rewards",
+        "ჯილდოების ბალანსი არასაკმარისია",
+    ),
+    (
+        "error.same_account",
+        "გამგზავნი და მიმღები ანგარიში ერთი და იგივეა",
+    ),
+    ("error.invalid_rate", "განაკვეთი დასაშვებ ფარგლებს გარეთაა"),
+    (
+        "error.deduction_order_not_found",
+        "დაქვითვის ბრძანება ვერ მოიძებნა",
+    ),
+    ("error.possible_duplicate", "შესაძლო დუბლირებული გადახდა"),
+    ("error.goal_not_found", "დაზოგვის მიზანი ვერ მოიძებნა"),
+    ("error.sweep_rule_not_found", "გადატანის წესი ვერ მოიძებნა"),
+    ("error.promotion_not_found", "აქცია ვერ მოიძებნა"),
+    (
+        "error.missing_audit_details",
+        "კორექტირებას სჭირდება მიზეზი და შემსრულებელი",
+    ),
+    ("error.empty_tag", "ტეგი ან კატეგორია ცარიელი ვერ იქნება"),
+    ("error.mixed_currencies", "ანგარიშები ერთ ვალუტაში არ არის"),
+    (
+        "error.price_unavailable",
+        "ფონდის წილის ფასი ხელმისაწვდომი არ არის",
+    ),
+    ("error.transaction_not_found", "ტრანზაქცია ვერ მოიძებნა"),
+    (
+        "error.not_disputable",
+        "გასაჩივრება შესაძლებელია მხოლოდ გაუსაჩივრებელი შენატანის",
+    ),
+    (
+        "error.dispute_not_found",
+        "ამ ტრანზაქციაზე ღია დავა არ არსებობს",
+    ),
+    ("error.account_locked", "ანგარიში დაბლოკილია"),
+    (
+        "error.account_dormant",
+        "ანგარიში უმოქმედოა; თანხის გასატანად გააქტიურეთ იგი",
+    ),
+    ("error.zero_amount", "თანხა არ უნდა იყოს ნული"),
+    (
+        "error.payee_limit_exceeded",
+        "ამ მიმღებზე გადარიცხვის დღიური ლიმიტი ამოწურულია",
+    ),
+    (
+        "warning.below_minimum_balance",
+        "ბალანსი {0} ნაკლებია მინიმალურ {1}-ზე",
+    ),
+    (
+        "warning.unusual_spend",
+        "თანხა {0} აღემატება თქვენს ჩვეულ ხარჯს ({1})",
+    ),
+    (
+        "warning.possible_duplicate",
+        "შესაძლო დუბლიკატი: ტრანზაქცია #{0}",
+    ),
+    ("warning.zero_amount", "თანხა ნული იყო; არაფერი გატარებულა"),
+    (
+        "warning.self_transfer",
+        "გადარიცხვა იმავე ანგარიშზე იყო; თანხა არ გადაადგილებულა",
+    ),
+    (
+        "warning.amount_saturated",
+        "{0}-დან ბალანსში მხოლოდ {1} დაეტია და ჩაირიცხა",
+    ),
+    ("kind.opening_balance", "საწყისი ბალანსი"),
+    ("kind.deposit", "შეტანა"),
+    ("kind.withdrawal", "გატანა"),
+    ("kind.transfer_in", "შემოსული გადარიცხვა"),
+    ("kind.transfer_out", "გასული გადარიცხვა"),
+    ("kind.cheque_deposit", "ჩეკის შეტანა"),
+    ("kind.interest", "პროცენტი"),
+    ("kind.fee", "საკომისიო"),
+    ("kind.reward", "ჯილდო"),
+    ("kind.redenomination", "დენომინაცია"),
+    ("kind.adjustment", "კორექტირება"),
+    ("kind.settlement", "გარე ანგარიშსწორება"),
+    ("kind.revaluation", "გადაფასება"),
+    ("kind.chargeback", "დაბრუნება"),
+    ("kind.ownership_transfer", "მფლობელის შეცვლა"),
+    ("kind.rounding_remainder", "დამრგვალების ნაშთი"),
+    ("transaction.line", "{0} #{1} {2} {3}{4} (ბალანსი {5})"),
+    ("transaction.counterparty", "ანგარიში {0}"),
+    ("account.summary", "ანგარიში {0} ({1}) — ბალანსი: {2}"),
+    ("bank.total", "ბანკის ჯამური ბალანსი: {0}"),
+    ("table.id", "ID"),
+    ("table.holder", "მფლობელი"),
+    ("table.status", "სტატუსი"),
+    ("table.currency", "ვალუტა"),
+    ("table.balance", "ბალანსი"),
+    ("status.active", "აქტიური"),
+    ("status.overdrawn", "გადახარჯული"),
+    ("status.below_minimum", "მინიმუმზე დაბალი"),
+    ("status.funds_held", "თანხა დაბლოკილია"),
+    (
+        "statement.header",
+        "ამონაწერი ანგარიშისთვის {0} ({1}) {2}-დან {3}-მდე",
+    ),
+    ("statement.opening", "საწყისი ბალანსი: {0}"),
+    ("statement.closing", "საბოლოო ბალანსი: {0}"),
+    ("portfolio.header", "{0}-ის პორტფელი: {1} ანგარიში"),
+    ("portfolio.balance", "ჯამური ბალანსი ({0}): {1}"),
+    ("insights.header", "{0}-ის ხარჯების ანალიზი {1}-დან {2}-მდე"),
+    ("insights.none", "ხარჯები არ არის"),
+    ("insights.total", "{0}: დახარჯულია {1}, დღეში საშუალოდ {2}"),
+    ("insights.category", "  {0}: {1}, {2} გადახდა"),
+    ("insights.biggest", "  უდიდესი ხარჯი: {0}, {1} ({2})"),
+    ("insights.month", "  {0}: {1}"),
+    (
+        "insights.month_change",
+        "  {0}: {1} ({2} წინა თვესთან შედარებით)",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timestamp;
+
+    #[test]
+    fn statements_show_amounts_in_the_accounts_currency() {
+        let mut bank = Bank::new();
+        let mut account = Account::new(1, "Ann".to_string());
+        account.set_currency(Currency::JPY);
+        bank.add_account(account);
+        bank.deposit(1, 1_000).unwrap();
+        let account = bank.get_account(1).unwrap();
+        let statement = Statement::for_period(account, Timestamp(0), Timestamp(u64::MAX));
+
+        let text = statement.localize(Locale::English);
+        assert!(account.localize(Locale::English).contains("¥1000"));
+        assert!(text.contains("+¥1000 (balance ¥1000)"), "{}", text);
+        assert!(!text.contains('$'), "{}", text);
+        let context = statement.template_context(Locale::English);
+        let rendered = crate::template::Template::parse("{{closing_balance}}")
+            .unwrap()
+            .render(&context, crate::template::OutputFormat::Text)
+            .unwrap();
+        assert_eq!(rendered, "¥1000");
+    }
+}
@@ -1,177 +1,520 @@
-use std::collections::HashMap;
-use std::fmt;
-use std::error::Error;
+//! A walk through the bank's features, plus account administration.
+//!
+//! Usage: `bank [--format json|table|plain]` runs the demo; `bank
+//! new-account STATE_FILE` opens an account in the snapshot at
+//! `STATE_FILE` through an interactive wizard, creating the file if there
+//! is none yet.
+//!
+//! `bank seed [STATE_FILE] [--accounts N] [--history-days N] [--seed N]
+//! [--start YYYY-MM-DD]` writes a new snapshot (`bank.snapshot` by
+//! default) of randomly generated accounts with salaries, rent and everyday
+//! spending, for exploring the reports. History ends today unless
+//! `--start` is given; the same seed, start and sizes always give the same
+//! bank. An existing file is never overwritten.
+//!
+//! `bank chargeback STATE_FILE TRANSACTION` reverses a deposit, disputing
+//! it first if it isn't already, and `bank import STATE_FILE CSV_FILE`
+//! applies a batch of `kind,account,amount[,to]` rows. Both show what they
+//! would do and ask before changing anything: a chargeback always, an
+//! import when it moves more than `--confirm-above` cents (1000000 by
+//! default). `--yes` skips the question.
+//!
+//! `bank completions bash|zsh|fish` prints a shell completion script and
+//! `bank man` a man page, both generated from the command definitions in
+//! `cli.rs` that the usage text comes from too. For example,
+//! `bank completions bash > /etc/bash_completion.d/bank` or
+//! `bank man > /usr/local/share/man/man1/bank.1`.
+//!
+//! `--format` applies to every command that reports on a bank. `plain` is the default, `table`
+//! lists accounts as an aligned table, and `json` prints one JSON object
+//! with a `schema_version` for scripts. With `json` the wizard's prompts go
+//! to standard error so standard output stays parseable.
 
-#[derive(Debug)]
-struct Account {
-    id: u32,
-    balance: i64,
-    holder: String,
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bank::datasets::{self, DatasetProfile};
+use bank::{
+    batch_value, parse_csv, Account, AccountWizard, Bank, Confirmation, Date, DisputeStatus,
+    Timestamp,
+};
+
+mod cli;
+
+const DEFAULT_SEED_FILE: &str = "bank.snapshot";
+
+/// Imports moving more than this many cents ask for confirmation unless
+/// `--confirm-above` says otherwise.
+const DEFAULT_CONFIRM_ABOVE: i64 = 1_000_000;
+
+/// Bumped whenever a field is removed or changes meaning in the JSON
+/// output; new fields may be added without bumping it.
+const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Plain,
+    Table,
+    Json,
 }
 
-impl Account {
-    fn new(id: u32, holder: String) -> Self {
-        Account {
-            id,
-            holder,
-            balance: 0,
+impl Format {
+    fn parse(value: &str) -> Result<Format, String> {
+        match value {
+            "plain" => Ok(Format::Plain),
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "unknown format '{}'; use json, table or plain",
+                value
+            )),
         }
     }
+}
 
-    fn summary(&self) -> String {
-        format!("{}", self)
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
     }
+}
 
-    fn deposit(&mut self, amount: i64) -> Result<i64, AccountError> {
-        if amount < 0 {
-            return Err(AccountError::NegativeAmount);
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut format = Format::Plain;
+    let mut positional = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let value = args.next().ok_or("--format needs json, table or plain")?;
+                format = Format::parse(&value)?;
+            }
+            _ if arg.starts_with("--format=") => {
+                format = Format::parse(&arg["--format=".len()..])?;
+            }
+            _ => positional.push(arg),
         }
-        self.balance = self.balance.checked_add(amount).ok_or(AccountError::AmountOverflow)?;
-        Ok(self.balance)
     }
 
-    fn withdraw(&mut self, amount: i64) -> Result<i64, AccountError> {
-        if amount < 0 {
-            return Err(AccountError::NegativeAmount);
+    let mut positional = positional.into_iter();
+    match positional.next().as_deref() {
+        None => demo(format),
+        Some("new-account") => {
+            let path = positional.next().ok_or("new-account needs a STATE_FILE")?;
+            if let Some(arg) = positional.next() {
+                return Err(format!("unexpected argument '{}'", arg).into());
+            }
+            new_account(&path, format)
         }
-        if self.balance < amount {
-            return Err(AccountError::InsufficientFunds);
+        Some("seed") => seed(positional, format),
+        Some("chargeback") => chargeback(positional, format),
+        Some("import") => import(positional, format),
+        Some("completions") => {
+            let shell = positional
+                .next()
+                .ok_or("completions needs bash, zsh or fish")?;
+            let script = cli::completions(&shell)
+                .ok_or(format!("unknown shell '{}'; use bash, zsh or fish", shell))?;
+            print!("{}", script);
+            Ok(())
         }
-        self.balance -= amount;
-        Ok(self.balance)
-    }
-}
-
-impl fmt::Display for Account {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let balance_dollars = self.balance as f64 / 100.0;
-        write!(
-            f,
-            "Account {} ({}) has a balance of ${:.2}",
-            self.id, self.holder, balance_dollars
-        )
+        Some("man") => {
+            print!("{}", cli::man_page());
+            Ok(())
+        }
+        Some("-h" | "--help") => {
+            println!("{}", cli::usage());
+            Ok(())
+        }
+        Some(command) => Err(format!("unknown command '{}'\n{}", command, cli::usage()).into()),
     }
 }
 
-#[derive(Debug)]
-enum AccountError {
-    NegativeAmount,
-    InsufficientFunds,
-    AmountOverflow,
-    AccountNotFound,
-}
-
-impl fmt::Display for AccountError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            AccountError::NegativeAmount => write!(f, "Amount cannot be negative"),
-            AccountError::InsufficientFunds => write!(f, "Insufficient funds"),
-            AccountError::AmountOverflow => write!(f, "Amount overflow"),
-            AccountError::AccountNotFound => write!(f, "Account not found"),
+fn new_account(path: &str, format: Format) -> Result<(), Box<dyn Error>> {
+    let mut bank = match File::open(path) {
+        Ok(file) => Bank::load_snapshot(BufReader::new(file))?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Bank::new(),
+        Err(error) => return Err(error.into()),
+    };
+    let new_account = match format {
+        Format::Json => AccountWizard::new(io::stdin().lock(), io::stderr()).run(&bank)?,
+        _ => AccountWizard::new(io::stdin().lock(), io::stdout()).run(&bank)?,
+    };
+    let Some(new_account) = new_account else {
+        match format {
+            Format::Json => println!(
+                "{{\"schema_version\":{},\"command\":\"new-account\",\"state_file\":{},\"account\":null}}",
+                JSON_SCHEMA_VERSION,
+                json_string(path)
+            ),
+            _ => println!("Nothing was created."),
+        }
+        return Ok(());
+    };
+    let id = new_account.id;
+    bank.add_validated_account(new_account.into_account())?;
+
+    save(&bank, path)?;
+    match format {
+        Format::Plain => println!("Opened account {} in {}.", id, path),
+        Format::Table => {
+            println!("Opened account {} in {}.", id, path);
+            println!("{}", bank.summary_table(false));
+        }
+        Format::Json => {
+            let account = bank
+                .account_summaries()
+                .into_iter()
+                .find(|summary| summary.id == id)
+                .ok_or("the new account is missing")?;
+            println!(
+                "{{\"schema_version\":{},\"command\":\"new-account\",\"state_file\":{},\"account\":{}}}",
+                JSON_SCHEMA_VERSION,
+                json_string(path),
+                account.to_json()
+            );
         }
     }
+    Ok(())
 }
 
-impl Error for AccountError {}
-
-#[derive(Debug)]
-struct Bank {
-    accounts: HashMap<u32, Account>,
-}
-
-impl Bank {
-    fn new() -> Self {
-        Bank {
-            accounts: HashMap::new(),
+fn seed(mut args: impl Iterator<Item = String>, format: Format) -> Result<(), Box<dyn Error>> {
+    let mut path = None;
+    let mut accounts = 100;
+    let mut history_days = 365;
+    let mut seed = None;
+    let mut start = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--accounts" => accounts = value("--accounts")?.parse()?,
+            "--history-days" => history_days = value("--history-days")?.parse()?,
+            "--seed" => seed = Some(value("--seed")?.parse()?),
+            "--start" => {
+                let date = value("--start")?;
+                start = Some(
+                    Date::parse(&date).ok_or(format!("invalid date '{}'; use YYYY-MM-DD", date))?,
+                );
+            }
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
         }
     }
-
-    fn add_account(&mut self, account: Account) {
-        self.accounts.insert(account.id, account);
+    let path = path.unwrap_or_else(|| DEFAULT_SEED_FILE.to_string());
+    if fs::metadata(&path).is_ok() {
+        return Err(format!("{} already exists; pick another STATE_FILE", path).into());
     }
-
-    fn total_balance(&self) -> i64 {
-        self.accounts.values().map(|account| account.balance).sum()
+    let seed = match seed {
+        Some(seed) => seed,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+    };
+    let start = start.unwrap_or_else(|| {
+        let today = Timestamp::now().date();
+        Date::from_days_since_epoch(today.days_since_epoch() - i64::from(history_days))
+    });
+
+    let profile = DatasetProfile::new(accounts, 0)
+        .days(history_days)
+        .starting(start)
+        .seed(seed);
+    let bank = datasets::generate(&profile);
+    save(&bank, &path)?;
+
+    let transactions: usize = bank
+        .account_summaries()
+        .iter()
+        .filter_map(|summary| bank.get_account(summary.id))
+        .map(|account| account.history().len())
+        .sum();
+    match format {
+        Format::Plain | Format::Table => {
+            println!(
+                "Seeded {} accounts with {} transactions from {} in {} (seed {}).",
+                bank.len(),
+                transactions,
+                start,
+                path,
+                seed
+            );
+            if format == Format::Table {
+                println!("{}", bank.summary_table(false));
+            }
+        }
+        Format::Json => println!(
+            "{{\"schema_version\":{},\"command\":\"seed\",\"state_file\":{},\"seed\":{},\"start\":{},\"history_days\":{},\"transactions\":{},\"accounts\":{}}}",
+            JSON_SCHEMA_VERSION,
+            json_string(&path),
+            seed,
+            json_string(&start.to_string()),
+            history_days,
+            transactions,
+            bank.summary_json()
+        ),
     }
+    Ok(())
+}
 
-    fn summary(&self) -> String {
-        self.accounts
-            .values()
-            .map(|account| account.summary())
-            .collect::<Vec<String>>()
-            .join("\n")
+fn chargeback(args: impl Iterator<Item = String>, format: Format) -> Result<(), Box<dyn Error>> {
+    let mut operands = Vec::new();
+    let mut yes = false;
+    for arg in args {
+        match arg.as_str() {
+            "--yes" | "-y" => yes = true,
+            _ if operands.len() < 2 && !arg.starts_with('-') => operands.push(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
+        }
     }
-
-    fn get_account_mut(&mut self, id: u32) -> Option<&mut Account> {
-        self.accounts.get_mut(&id)
+    let [path, transaction] = <[String; 2]>::try_from(operands)
+        .map_err(|_| "chargeback needs a STATE_FILE and a TRANSACTION")?;
+    let transaction: u64 = transaction.trim_start_matches('#').parse()?;
+    let mut bank = load(&path)?;
+
+    let txn = bank
+        .transaction(transaction)
+        .ok_or(format!("there is no transaction #{}", transaction))?;
+    let account = bank
+        .get_account(txn.account_id)
+        .ok_or("the transaction's account is missing")?;
+    let preview = format!(
+        "Charge back transaction #{}: {} of {} on {} to account {} ({}).\nBalance {} -> {}.",
+        transaction,
+        txn.kind,
+        account.format_amount(txn.amount),
+        txn.timestamp.date(),
+        account.id(),
+        account.holder(),
+        account.format_amount(account.balance()),
+        account.format_amount(account.balance() - txn.amount)
+    );
+    let dispute = bank
+        .disputes(txn.account_id)
+        .iter()
+        .find(|dispute| dispute.transaction_id == transaction);
+    let disputed = match dispute.map(|dispute| dispute.status) {
+        None => false,
+        Some(DisputeStatus::Open) => true,
+        Some(_) => {
+            return Err(format!(
+                "transaction #{} was already disputed and settled",
+                transaction
+            )
+            .into())
+        }
+    };
+    if !yes && !confirm(&preview, format)? {
+        return nothing_changed("chargeback", &path, format);
     }
-
-    fn get_account(&self, id: u32) -> Option<&Account> {
-        self.accounts.get(&id)
+    if !disputed {
+        bank.dispute(transaction)?;
     }
+    let entry = bank.chargeback(transaction)?;
+    save(&bank, &path)?;
+    match format {
+        Format::Json => println!(
+            "{{\"schema_version\":{},\"command\":\"chargeback\",\"state_file\":{},\"transaction\":{},\"chargeback\":{}}}",
+            JSON_SCHEMA_VERSION,
+            json_string(&path),
+            transaction,
+            entry
+        ),
+        _ => println!(
+            "Charged back transaction #{} as #{} in {}.",
+            transaction, entry, path
+        ),
+    }
+    Ok(())
+}
 
-    fn transfer(&mut self, from_id: u32, to_id: u32, amount: i64) -> Result<(), AccountError> {
-        if amount < 0 {
-            return Err(AccountError::NegativeAmount);
-        }
-
-        if !self.accounts.contains_key(&from_id) {
-            return Err(AccountError::AccountNotFound);
+fn import(mut args: impl Iterator<Item = String>, format: Format) -> Result<(), Box<dyn Error>> {
+    let mut operands = Vec::new();
+    let mut yes = false;
+    let mut confirm_above = DEFAULT_CONFIRM_ABOVE;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--yes" | "-y" => yes = true,
+            "--confirm-above" => {
+                confirm_above = args
+                    .next()
+                    .ok_or("--confirm-above needs a value")?
+                    .parse()?;
+            }
+            _ if operands.len() < 2 && !arg.starts_with('-') => operands.push(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg).into()),
         }
-        if !self.accounts.contains_key(&to_id) {
-            return Err(AccountError::AccountNotFound);
+    }
+    let [path, csv] = <[String; 2]>::try_from(operands)
+        .map_err(|_| "import needs a STATE_FILE and a CSV_FILE")?;
+    let mut bank = load(&path)?;
+    let operations = parse_csv(BufReader::new(File::open(&csv)?))?;
+
+    if !yes && batch_value(&operations) > confirm_above {
+        let preview = bank.preview_batch(&operations);
+        if !confirm(&preview.to_string(), format)? {
+            return nothing_changed("import", &path, format);
         }
-
-        if from_id == to_id {
-            return Ok(());
+    }
+    let threads = thread::available_parallelism().map_or(1, usize::from);
+    let report = bank.import_parallel(&operations, threads);
+    save(&bank, &path)?;
+    match format {
+        Format::Json => {
+            let failures: Vec<String> = report
+                .failed
+                .iter()
+                .map(|failure| {
+                    format!(
+                        "{{\"line\":{},\"error\":{},\"remediation\":{}}}",
+                        failure.line,
+                        json_string(&failure.error.to_string()),
+                        json_string(failure.error.suggest().code())
+                    )
+                })
+                .collect();
+            println!(
+                "{{\"schema_version\":{},\"command\":\"import\",\"state_file\":{},\"applied\":{},\"failed\":{},\"failures\":[{}]}}",
+                JSON_SCHEMA_VERSION,
+                json_string(&path),
+                report.applied,
+                report.failed.len(),
+                failures.join(",")
+            );
         }
-
-        {
-            let from_account = self.accounts.get(&from_id).unwrap();
-            if from_account.balance < amount {
-                return Err(AccountError::InsufficientFunds);
+        _ => {
+            println!("{}", report);
+            for failure in &report.failed {
+                println!("  line {}: {}", failure.line, failure.error);
             }
         }
+    }
+    Ok(())
+}
 
-        {
-            let from_account = self.accounts.get_mut(&from_id).unwrap();
-            from_account.withdraw(amount)?;
-        }
-
-        {
-            let to_account = self.accounts.get_mut(&to_id).unwrap();
-            to_account.deposit(amount)?;
-        }
-
-        Ok(())
+/// Shows `preview` and asks whether to go ahead, on standard error when
+/// standard output is JSON.
+fn confirm(preview: &str, format: Format) -> io::Result<bool> {
+    match format {
+        Format::Json => Confirmation::new(io::stdin().lock(), io::stderr()).ask(preview),
+        _ => Confirmation::new(io::stdin().lock(), io::stdout()).ask(preview),
     }
 }
 
-impl fmt::Display for Bank {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let total_balance_dollars = self.total_balance() as f64 / 100.0;
-        write!(f, "Bank total balance: ${:.2}", total_balance_dollars)
+fn nothing_changed(command: &str, path: &str, format: Format) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => println!(
+            "{{\"schema_version\":{},\"command\":{},\"state_file\":{},\"confirmed\":false}}",
+            JSON_SCHEMA_VERSION,
+            json_string(command),
+            json_string(path)
+        ),
+        _ => println!("Nothing was changed."),
     }
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn load(path: &str) -> Result<Bank, Box<dyn Error>> {
+    Ok(Bank::load_snapshot(BufReader::new(File::open(path)?))?)
+}
+
+/// Writes the snapshot to a temporary file and renames it into place.
+fn save(bank: &Bank, path: &str) -> Result<(), Box<dyn Error>> {
+    let mut snapshot = Vec::new();
+    bank.save_snapshot(&mut snapshot)?;
+    let temporary = format!("{}.tmp", path);
+    fs::write(&temporary, snapshot)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+fn demo(format: Format) -> Result<(), Box<dyn Error>> {
     let mut bank = Bank::new();
 
     let mut account1 = Account::new(1, String::from("Giorgi"));
     let mut account2 = Account::new(2, String::from("QioJI"));
 
-    account1.deposit(50000)?; 
-    account1.withdraw(25000)?; 
+    account1.set_minimum_balance(Some(20000));
+    account2.set_interest_rate_bps(150);
+
+    account1.deposit(50000)?;
+    account1.withdraw(25000)?;
 
-    account2.deposit(30000)?; 
+    account2.deposit(30000)?;
 
     bank.add_account(account1);
     bank.add_account(account2);
 
-    bank.transfer(1, 2, 10000)?; 
+    let outcome = bank.transfer(1, 2, 10000)?;
+    let receipt = bank.deposit(2, 2500)?;
+    let declined = bank.withdraw(1, 1_000_000).err();
+    bank.deposit_cheque(2, 5000, 0)?;
+    let report = bank.end_of_day();
+
+    if format == Format::Json {
+        let warnings: Vec<String> = outcome
+            .warnings
+            .iter()
+            .map(|warning| json_string(&warning.to_string()))
+            .collect();
+        let declined = match &declined {
+            Some(error) => format!(
+                "{{\"error\":{},\"remediation\":{}}}",
+                json_string(&error.to_string()),
+                json_string(error.suggest().code())
+            ),
+            None => "null".to_string(),
+        };
+        println!(
+            "{{\"schema_version\":{},\"command\":\"demo\",\"transfer_warnings\":[{}],\"receipt\":{},\"declined_withdrawal\":{},\"accounts\":{}}}",
+            JSON_SCHEMA_VERSION,
+            warnings.join(","),
+            receipt.to_json(),
+            declined,
+            bank.summary_json()
+        );
+        return Ok(());
+    }
+
+    for warning in &outcome.warnings {
+        println!("Warning: {}", warning);
+    }
+    println!("{}", receipt);
+    if let Some(error) = declined {
+        println!("{}: {}", error, error.suggest());
+    }
+    println!("{}", report);
 
-    println!("{}", bank.summary());
-    println!("{}", bank);
+    match format {
+        Format::Table => println!("{}", bank.summary_table(false)),
+        _ => {
+            println!("{}", bank.summary());
+            println!("{}", bank);
+        }
+    }
 
     Ok(())
 }
+
+/// Quotes and escapes `value` as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the operation latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 9] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations at or below each bound in [`LATENCY_BUCKETS`].
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    /// By (operation, outcome).
+    operations: BTreeMap<(&'static str, &'static str), u64>,
+    latency: BTreeMap<&'static str, Histogram>,
+}
+
+/// Operation counts and latencies of a [`crate::BankActor`], and how many
+/// commands are waiting for it, rendered in the Prometheus text format by
+/// [`Metrics::render`].
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+    queued: AtomicUsize,
+    peak_queued: AtomicUsize,
+}
+
+impl Metrics {
+    /// Counts one `operation` ending in `outcome` after `latency`.
+    pub fn record(&self, operation: &'static str, outcome: &'static str, latency: Duration) {
+        let mut counters = self
+            .counters
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        *counters.operations.entry((operation, outcome)).or_default() += 1;
+        let histogram = counters.latency.entry(operation).or_default();
+        let seconds = latency.as_secs_f64();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    /// Commands waiting for the bank task, whether queued or blocked on a
+    /// full queue.
+    pub fn queue_depth(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// The deepest the queue has been.
+    pub fn peak_queue_depth(&self) -> usize {
+        self.peak_queued.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn enqueued(&self) {
+        let depth = self.queued.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak_queued.fetch_max(depth, Ordering::SeqCst);
+    }
+
+    pub(crate) fn dequeued(&self) {
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub fn operations(&self, operation: &str, outcome: &str) -> u64 {
+        let counters = self
+            .counters
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        counters
+            .operations
+            .get(&(operation, outcome))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The counters and histograms, plus the balance and account count
+    /// gauges when `gauges` is given as `(total_balance, accounts)`.
+    pub fn render(&self, gauges: Option<(i64, usize)>) -> String {
+        let counters = self
+            .counters
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let mut text = String::new();
+        text.push_str("# HELP bank_operations_total Operations by type and outcome.\n");
+        text.push_str("# TYPE bank_operations_total counter\n");
+        for ((operation, outcome), count) in &counters.operations {
+            let _ = writeln!(
+                text,
+                "bank_operations_total{{operation=\"{}\",outcome=\"{}\"}} {}",
+                operation, outcome, count
+            );
+        }
+        text.push_str(
+            "# HELP bank_operation_duration_seconds Time from sending an operation to its reply.\n",
+        );
+        text.push_str("# TYPE bank_operation_duration_seconds histogram\n");
+        for (operation, histogram) in &counters.latency {
+            for (count, bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    text,
+                    "bank_operation_duration_seconds_bucket{{operation=\"{}\",le=\"{}\"}} {}",
+                    operation, bound, count
+                );
+            }
+            let _ = writeln!(
+                text,
+                "bank_operation_duration_seconds_bucket{{operation=\"{}\",le=\"+Inf\"}} {}",
+                operation, histogram.count
+            );
+            let _ = writeln!(
+                text,
+                "bank_operation_duration_seconds_sum{{operation=\"{}\"}} {}",
+                operation, histogram.sum
+            );
+            let _ = writeln!(
+                text,
+                "bank_operation_duration_seconds_count{{operation=\"{}\"}} {}",
+                operation, histogram.count
+            );
+        }
+        text.push_str("# HELP bank_queue_depth Commands waiting for the bank task.\n");
+        text.push_str("# TYPE bank_queue_depth gauge\n");
+        let _ = writeln!(text, "bank_queue_depth {}", self.queue_depth());
+        text.push_str("# HELP bank_queue_depth_peak The deepest the queue has been.\n");
+        text.push_str("# TYPE bank_queue_depth_peak gauge\n");
+        let _ = writeln!(text, "bank_queue_depth_peak {}", self.peak_queue_depth());
+        if let Some((total_balance, accounts)) = gauges {
+            text.push_str("# HELP bank_total_balance_cents Sum of every account balance.\n");
+            text.push_str("# TYPE bank_total_balance_cents gauge\n");
+            let _ = writeln!(text, "bank_total_balance_cents {}", total_balance);
+            text.push_str("# HELP bank_accounts Number of accounts.\n");
+            text.push_str("# TYPE bank_accounts gauge\n");
+            let _ = writeln!(text, "bank_accounts {}", accounts);
+        }
+        text
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::currency::Currency;
+use crate::locale::Locale;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoneyParseError {
+    Empty,
+    InvalidCharacter(char),
+    /// More digits after the decimal separator than the currency has minor
+    /// units.
+    TooManyDecimals {
+        allowed: u32,
+    },
+    /// A grouping separator that doesn't split the whole part into threes.
+    MisplacedSeparator,
+    Overflow,
+}
+
+impl fmt::Display for MoneyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoneyParseError::Empty => write!(f, "No amount entered"),
+            MoneyParseError::InvalidCharacter(ch) => write!(f, "Unexpected character '{}'", ch),
+            MoneyParseError::TooManyDecimals { allowed: 0 } => {
+                write!(f, "This currency has no decimal places")
+            }
+            MoneyParseError::TooManyDecimals { allowed } => {
+                write!(f, "Use at most {} decimal places", allowed)
+            }
+            MoneyParseError::MisplacedSeparator => write!(f, "Misplaced thousands separator"),
+            MoneyParseError::Overflow => write!(f, "Amount is too large"),
+        }
+    }
+}
+
+impl std::error::Error for MoneyParseError {}
+
+/// An amount in cents, parsed from or shown as what people type and read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(pub i64);
+
+impl Money {
+    pub fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    /// Parses an amount written the English way, e.g. `$1,234.56` or `-12.5`.
+    pub fn parse(input: &str) -> Result<Money, MoneyParseError> {
+        Money::parse_with(input, Locale::English)
+    }
+
+    /// Parses an amount using the separators of `locale`: `1,234.56` in
+    /// English, `1.234,56` in German and Georgian. A `$` sign may come before
+    /// or after the number, and the amount may be negated with a leading `-`
+    /// or by parentheses.
+    pub fn parse_with(input: &str, locale: Locale) -> Result<Money, MoneyParseError> {
+        Money::parse_in(input, locale, Currency::USD)
+    }
+
+    /// Parses an amount in `currency`, returning its minor units: `1.234`
+    /// is 1234 fils in BHD but refused in USD, and `1500` is 1500 yen. The
+    /// currency's symbol or code may come before or after the number, as
+    /// [`Locale::money_in`] writes them.
+    pub fn parse_in(
+        input: &str,
+        locale: Locale,
+        currency: Currency,
+    ) -> Result<Money, MoneyParseError> {
+        let minor_units = currency.minor_units();
+        let (group, decimal) = match locale {
+            Locale::English => (',', '.'),
+            Locale::German | Locale::Georgian => ('.', ','),
+        };
+        let mut text = input.trim();
+        let mut negative = false;
+        if let Some(inner) = text
+            .strip_prefix('(')
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            negative = true;
+            text = inner.trim();
+        }
+        if !negative {
+            if let Some(rest) = text.strip_prefix('-') {
+                negative = true;
+                text = rest.trim_start();
+            }
+        }
+        let marks = currency.symbol().into_iter().chain([currency.code()]);
+        for mark in marks {
+            if let Some(rest) = text.strip_prefix(mark) {
+                text = rest.trim_start();
+                break;
+            }
+            if let Some(rest) = text.strip_suffix(mark) {
+                text = rest.trim_end();
+                break;
+            }
+        }
+        if !negative {
+            if let Some(rest) = text.strip_prefix('-') {
+                negative = true;
+                text = rest.trim_start();
+            }
+        }
+        if text.is_empty() {
+            return Err(MoneyParseError::Empty);
+        }
+
+        let (whole, fraction) = match text.split_once(decimal) {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (text, ""),
+        };
+        if let Some(ch) = fraction.chars().find(|ch| !ch.is_ascii_digit()) {
+            return Err(MoneyParseError::InvalidCharacter(ch));
+        }
+        if fraction.len() > minor_units as usize {
+            return Err(MoneyParseError::TooManyDecimals {
+                allowed: minor_units,
+            });
+        }
+        if let Some(ch) = whole
+            .chars()
+            .find(|ch| !ch.is_ascii_digit() && *ch != group)
+        {
+            return Err(MoneyParseError::InvalidCharacter(ch));
+        }
+        if whole.contains(group) {
+            let mut groups = whole.split(group);
+            let first = groups.next().unwrap_or("");
+            if first.is_empty() || first.len() > 3 || groups.any(|rest| rest.len() != 3) {
+                return Err(MoneyParseError::MisplacedSeparator);
+            }
+        }
+        let digits: String = whole.chars().filter(|ch| *ch != group).collect();
+        if digits.is_empty() && fraction.is_empty() {
+            return Err(MoneyParseError::Empty);
+        }
+
+        let whole: i64 = if digits.is_empty() {
+            0
+        } else {
+            digits.parse().map_err(|_| MoneyParseError::Overflow)?
+        };
+        let fraction: i64 = if minor_units == 0 {
+            0
+        } else {
+            format!("{:0<width$}", fraction, width = minor_units as usize)
+                .parse()
+                .map_err(|_| MoneyParseError::Overflow)?
+        };
+        let cents = whole
+            .checked_mul(10_i64.pow(minor_units))
+            .and_then(|cents| cents.checked_add(fraction))
+            .ok_or(MoneyParseError::Overflow)?;
+        Ok(Money(if negative { -cents } else { cents }))
+    }
+}
+
+impl FromStr for Money {
+    type Err = MoneyParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Money::parse(input)
+    }
+}
+
+impl From<Money> for i64 {
+    fn from(money: Money) -> Self {
+        money.0
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Locale::English.money(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_what_the_formatter_writes() {
+        let currencies = [
+            Currency::USD,
+            Currency::EUR,
+            Currency::JPY,
+            Currency::BHD,
+            Currency::GEL,
+            Currency::new("CHF").unwrap(),
+        ];
+        for locale in [Locale::English, Locale::German, Locale::Georgian] {
+            for currency in currencies {
+                for amount in [0, 7, -250, 123_456_789] {
+                    let text = locale.money_in(amount, currency);
+                    assert_eq!(
+                        Money::parse_in(&text, locale, currency),
+                        Ok(Money(amount)),
+                        "{}",
+                        text
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_another_currencys_symbol() {
+        assert_eq!(
+            Money::parse_in("€5", Locale::English, Currency::USD),
+            Err(MoneyParseError::InvalidCharacter('€'))
+        );
+    }
+}
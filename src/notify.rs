@@ -0,0 +1,457 @@
+//! Holder notifications: events raised by the bank are matched against each
+//! holder's preferences, rendered through templates and handed to a
+//! pluggable [`Notifier`] transport.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::bank::Bank;
+use crate::snapshot::escape;
+use crate::statement::Statement;
+use crate::template::{OutputFormat, Template, TemplateContext};
+
+/// Withdrawals of at least this many cents notify the holder unless the
+/// bank sets its own threshold.
+const DEFAULT_LARGE_WITHDRAWAL: i64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotificationEvent {
+    /// `{{holder}}`, `{{account_id}}`, `{{amount}}`, `{{balance}}`
+    LargeWithdrawal,
+    /// `{{holder}}`, `{{account_id}}`, `{{period_end}}`,
+    /// `{{opening_balance}}`, `{{closing_balance}}`, `{{entries}}`
+    StatementReady,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Email,
+    Sms,
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Channel::Email => write!(f, "email"),
+            Channel::Sms => write!(f, "sms"),
+        }
+    }
+}
+
+/// How one holder wants to be reached. Holders without preferences are
+/// never notified.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NotificationPreferences {
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub muted: Vec<NotificationEvent>,
+}
+
+impl NotificationPreferences {
+    pub fn new() -> Self {
+        NotificationPreferences::default()
+    }
+
+    pub fn email(mut self, address: &str) -> Self {
+        self.email = Some(address.to_string());
+        self
+    }
+
+    pub fn sms(mut self, phone: &str) -> Self {
+        self.phone = Some(phone.to_string());
+        self
+    }
+
+    pub fn mute(mut self, event: NotificationEvent) -> Self {
+        self.muted.push(event);
+        self
+    }
+
+    fn destinations(&self) -> impl Iterator<Item = (Channel, &str)> {
+        let email = self.email.as_deref().map(|email| (Channel::Email, email));
+        let phone = self.phone.as_deref().map(|phone| (Channel::Sms, phone));
+        email.into_iter().chain(phone)
+    }
+}
+
+/// A rendered message ready for a transport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub holder: String,
+    pub channel: Channel,
+    pub address: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub trait Notifier: fmt::Debug + Send {
+    fn send(&mut self, notification: &Notification) -> io::Result<()>;
+}
+
+/// Prints every notification, for demos and debugging.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutNotifier;
+
+impl Notifier for StdoutNotifier {
+    fn send(&mut self, notification: &Notification) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        writeln!(
+            stdout,
+            "[{} to {}] {}\n{}",
+            notification.channel, notification.address, notification.subject, notification.body
+        )
+    }
+}
+
+/// Appends one tab-separated line per notification to a file.
+#[derive(Debug)]
+pub struct FileNotifier {
+    file: File,
+}
+
+impl FileNotifier {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileNotifier { file })
+    }
+}
+
+impl Notifier for FileNotifier {
+    fn send(&mut self, notification: &Notification) -> io::Result<()> {
+        writeln!(
+            self.file,
+            "{}\t{}\t{}\t{}",
+            notification.channel,
+            escape(&notification.address),
+            escape(&notification.subject),
+            escape(&notification.body)
+        )
+    }
+}
+
+/// Sends email notifications over plain SMTP. SMS notifications are
+/// refused with [`io::ErrorKind::Unsupported`].
+#[cfg(feature = "smtp")]
+#[derive(Debug, Clone)]
+pub struct SmtpNotifier {
+    server: String,
+    from: String,
+}
+
+#[cfg(feature = "smtp")]
+impl SmtpNotifier {
+    /// `server` is a `host:port` address.
+    pub fn new(server: &str, from: &str) -> Self {
+        SmtpNotifier {
+            server: server.to_string(),
+            from: from.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "smtp")]
+impl Notifier for SmtpNotifier {
+    fn send(&mut self, notification: &Notification) -> io::Result<()> {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpStream;
+
+        if notification.channel != Channel::Email {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "SMTP can only deliver email",
+            ));
+        }
+        let stream = TcpStream::connect(&self.server)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        let mut expect = |code: &str| -> io::Result<()> {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                reader.read_line(&mut line)?;
+                // Multi-line replies continue with "250-".
+                if line.len() < 4 || line.as_bytes()[3] != b'-' {
+                    break;
+                }
+            }
+            if line.starts_with(code) {
+                Ok(())
+            } else {
+                Err(io::Error::other(format!(
+                    "SMTP server replied: {}",
+                    line.trim()
+                )))
+            }
+        };
+        expect("220")?;
+        let dot_stuffed = notification.body.replace("\n.", "\n..");
+        let commands = [
+            ("HELO bank\r\n".to_string(), "250"),
+            (format!("MAIL FROM:<{}>\r\n", self.from), "250"),
+            (format!("RCPT TO:<{}>\r\n", notification.address), "250"),
+            ("DATA\r\n".to_string(), "354"),
+            (
+                format!(
+                    "From: {}\r\nTo: {}\r\nSubject: {}\r\n\r\n{}\r\n.\r\n",
+                    self.from, notification.address, notification.subject, dot_stuffed
+                ),
+                "250",
+            ),
+            ("QUIT\r\n".to_string(), "221"),
+        ];
+        for (command, code) in commands {
+            writer.write_all(command.as_bytes())?;
+            expect(code)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MessageTemplate {
+    subject: Template,
+    body: Template,
+}
+
+/// Everything the bank needs to notify holders. Runtime configuration only:
+/// none of it is saved in snapshots.
+#[derive(Debug)]
+pub(crate) struct Notifications {
+    notifier: Option<Box<dyn Notifier>>,
+    large_withdrawal: Option<i64>,
+    preferences: HashMap<String, NotificationPreferences>,
+    templates: BTreeMap<NotificationEvent, MessageTemplate>,
+    errors: Vec<io::Error>,
+    /// Notifications held back until the open savepoint commits.
+    deferred: Option<Vec<Notification>>,
+    /// In an import shard, the events the bank it was split from should
+    /// notify once the shard is merged back.
+    passed_on: Option<Vec<(NotificationEvent, String, TemplateContext)>>,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        let template = |subject: &str, body: &str| MessageTemplate {
+            subject: Template::parse(subject).expect("default template parses"),
+            body: Template::parse(body).expect("default template parses"),
+        };
+        let mut templates = BTreeMap::new();
+        templates.insert(
+            NotificationEvent::LargeWithdrawal,
+            template(
+                "Large withdrawal from account {{account_id}}",
+                "{{holder}}, {{amount}} was withdrawn from account {{account_id}}. \
+                 Your balance is now {{balance}}.",
+            ),
+        );
+        templates.insert(
+            NotificationEvent::StatementReady,
+            template(
+                "Your statement for {{period_end}} is ready",
+                "{{holder}}, your statement for account {{account_id}} lists {{entries}} \
+                 entries. Closing balance: {{closing_balance}}.",
+            ),
+        );
+        Notifications {
+            notifier: None,
+            large_withdrawal: Some(DEFAULT_LARGE_WITHDRAWAL),
+            preferences: HashMap::new(),
+            templates,
+            errors: Vec::new(),
+            deferred: None,
+            passed_on: None,
+        }
+    }
+}
+
+impl Bank {
+    pub fn set_notifier(&mut self, notifier: impl Notifier + 'static) {
+        self.notifications.notifier = Some(Box::new(notifier));
+    }
+
+    pub fn clear_notifier(&mut self) {
+        self.notifications.notifier = None;
+    }
+
+    pub fn large_withdrawal_threshold(&self) -> Option<i64> {
+        self.notifications.large_withdrawal
+    }
+
+    /// Withdrawals of at least `threshold` cents notify the holder; `None`
+    /// turns the event off for everyone.
+    pub fn set_large_withdrawal_threshold(&mut self, threshold: Option<i64>) {
+        self.notifications.large_withdrawal = threshold;
+    }
+
+    pub fn set_notification_preferences(
+        &mut self,
+        holder: &str,
+        preferences: NotificationPreferences,
+    ) {
+        self.notifications
+            .preferences
+            .insert(holder.to_string(), preferences);
+    }
+
+    pub fn notification_preferences(&self, holder: &str) -> Option<&NotificationPreferences> {
+        self.notifications.preferences.get(holder)
+    }
+
+    /// Replaces the subject and body templates used for `event`. The
+    /// variables available are listed on [`NotificationEvent`].
+    pub fn set_notification_template(
+        &mut self,
+        event: NotificationEvent,
+        subject: Template,
+        body: Template,
+    ) {
+        self.notifications
+            .templates
+            .insert(event, MessageTemplate { subject, body });
+    }
+
+    /// Delivery and template errors since the last call. Operations never
+    /// fail because a notification could not be sent.
+    pub fn take_notification_errors(&mut self) -> Vec<io::Error> {
+        std::mem::take(&mut self.notifications.errors)
+    }
+
+    pub(crate) fn notify_withdrawal(&mut self, account_id: u32, amount: i64) {
+        match self.notifications.large_withdrawal {
+            Some(threshold) if amount >= threshold => {}
+            _ => return,
+        }
+        let account = &self.accounts[&account_id];
+        let mut context = TemplateContext::new();
+        context
+            .set("holder", account.holder.clone())
+            .set("account_id", account_id.to_string())
+            .set("amount", self.locale.money_in(amount, account.currency))
+            .set(
+                "balance",
+                self.locale.money_in(account.balance, account.currency),
+            );
+        let holder = account.holder.clone();
+        self.notify(NotificationEvent::LargeWithdrawal, &holder, &context);
+    }
+
+    pub(crate) fn notify_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let mut context = TemplateContext::new();
+            context
+                .set("holder", statement.holder.clone())
+                .set("account_id", statement.account_id.to_string())
+                .set("period_end", statement.period_end.date().to_string())
+                .set(
+                    "opening_balance",
+                    self.locale
+                        .money_in(statement.opening_balance, statement.currency),
+                )
+                .set(
+                    "closing_balance",
+                    self.locale
+                        .money_in(statement.closing_balance, statement.currency),
+                )
+                .set("entries", statement.transactions.len().to_string());
+            self.notify(
+                NotificationEvent::StatementReady,
+                &statement.holder,
+                &context,
+            );
+        }
+    }
+
+    fn notify(&mut self, event: NotificationEvent, holder: &str, context: &TemplateContext) {
+        let notifications = &mut self.notifications;
+        if let Some(passed_on) = notifications.passed_on.as_mut() {
+            passed_on.push((event, holder.to_string(), context.clone()));
+            return;
+        }
+        let Some(notifier) = notifications.notifier.as_mut() else {
+            return;
+        };
+        let Some(preferences) = notifications.preferences.get(holder) else {
+            return;
+        };
+        if preferences.muted.contains(&event) {
+            return;
+        }
+        let Some(template) = notifications.templates.get(&event) else {
+            return;
+        };
+        let rendered = template
+            .subject
+            .render(context, OutputFormat::Text)
+            .and_then(|subject| Ok((subject, template.body.render(context, OutputFormat::Text)?)));
+        let (subject, body) = match rendered {
+            Ok(rendered) => rendered,
+            Err(error) => {
+                notifications
+                    .errors
+                    .push(io::Error::other(error.to_string()));
+                return;
+            }
+        };
+        for (channel, address) in preferences.destinations() {
+            let notification = Notification {
+                event,
+                holder: holder.to_string(),
+                channel,
+                address: address.to_string(),
+                subject: subject.clone(),
+                body: body.clone(),
+            };
+            if let Some(deferred) = notifications.deferred.as_mut() {
+                deferred.push(notification);
+            } else if let Err(error) = notifier.send(&notification) {
+                notifications.errors.push(error);
+            }
+        }
+    }
+
+    /// Has `shard`, a bank holding some of this one's accounts for a while,
+    /// keep the events this bank would notify for
+    /// [`Bank::notify_passed_on`] instead of sending anything itself.
+    pub(crate) fn pass_notifications_from(&self, shard: &mut Bank) {
+        shard.notifications.large_withdrawal = self.notifications.large_withdrawal;
+        if self.notifications.notifier.is_some() {
+            shard.notifications.passed_on = Some(Vec::new());
+        }
+    }
+
+    /// Notifies the events `shard` kept for this bank, in the order it saw
+    /// them.
+    pub(crate) fn notify_passed_on(&mut self, shard: &mut Bank) {
+        for (event, holder, context) in shard.notifications.passed_on.take().unwrap_or_default() {
+            self.notify(event, &holder, &context);
+        }
+    }
+
+    /// Holds notifications back until [`Bank::finish_deferring`].
+    pub(crate) fn defer_notifications(&mut self) {
+        self.notifications.deferred.get_or_insert_with(Vec::new);
+    }
+
+    /// Sends the held-back notifications, or drops them if `send` is false.
+    pub(crate) fn finish_deferring(&mut self, send: bool) {
+        let notifications = &mut self.notifications;
+        let Some(deferred) = notifications.deferred.take() else {
+            return;
+        };
+        let Some(notifier) = notifications.notifier.as_mut() else {
+            return;
+        };
+        if !send {
+            return;
+        }
+        for notification in deferred {
+            if let Err(error) = notifier.send(&notification) {
+                notifications.errors.push(error);
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+use crate::currency::Currency;
+use crate::locale::{Locale, Localize};
+
+/// A soft condition raised by an operation that still succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    BelowMinimumBalance {
+        balance: i64,
+        minimum: i64,
+        currency: Currency,
+    },
+    UnusualSpend {
+        amount: i64,
+        typical: i64,
+        currency: Currency,
+    },
+    PossibleDuplicate {
+        previous_transaction: u64,
+    },
+    /// The amount was zero, so nothing was booked.
+    ZeroAmount,
+    /// The transfer was to the account it came from, so nothing moved; the
+    /// attempt is booked as a zero-amount transfer out.
+    SelfTransfer,
+    /// Only `credited` of the amount fitted in the balance.
+    AmountSaturated {
+        requested: i64,
+        credited: i64,
+        currency: Currency,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
+
+/// The successful result of an operation together with any warnings it raised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationOutcome<T> {
+    pub value: T,
+    pub warnings: Vec<Warning>,
+}
+
+impl<T> OperationOutcome<T> {
+    pub fn new(value: T) -> Self {
+        OperationOutcome {
+            value,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn with_warnings(value: T, warnings: Vec<Warning>) -> Self {
+        OperationOutcome { value, warnings }
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> OperationOutcome<U> {
+        OperationOutcome {
+            value: f(self.value),
+            warnings: self.warnings,
+        }
+    }
+}
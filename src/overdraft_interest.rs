@@ -0,0 +1,142 @@
+//! Interest on overdrawn balances, with an interest-free grace period for
+//! holders who pay the overdraft back quickly.
+
+use crate::account::Account;
+use crate::eod::INTEREST_UNITS_PER_CENT;
+
+/// Memo on the entries that charge overdraft interest.
+pub(crate) const OVERDRAFT_INTEREST_MEMO: &str = "Overdraft interest";
+
+/// When interest starts counting once the grace period has run out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraceAccrual {
+    /// Interest is charged back to the first day overdrawn, as if there had
+    /// been no grace period.
+    #[default]
+    Retroactive,
+    /// Interest counts from this day of the overdraft on, the first day
+    /// being 1. `FromDay(grace_days + 1)` never charges the grace days.
+    FromDay(u32),
+}
+
+/// How an account is charged for being overdrawn.
+///
+/// Interest accrues daily on the overdrawn amount at `rate_bps` a year.
+/// An overdraft fully repaid within `grace_days` end-of-days costs nothing;
+/// one that lasts longer is charged from the day `accrual` says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverdraftInterest {
+    pub rate_bps: u32,
+    pub grace_days: u32,
+    pub accrual: GraceAccrual,
+}
+
+impl OverdraftInterest {
+    /// Charged from the first day, with no grace period.
+    pub fn new(rate_bps: u32) -> Self {
+        OverdraftInterest {
+            rate_bps,
+            grace_days: 0,
+            accrual: GraceAccrual::default(),
+        }
+    }
+
+    pub fn grace_days(mut self, days: u32) -> Self {
+        self.grace_days = days;
+        self
+    }
+
+    pub fn accrual(mut self, accrual: GraceAccrual) -> Self {
+        self.accrual = accrual;
+        self
+    }
+
+    fn first_charged_day(&self) -> u32 {
+        match self.accrual {
+            GraceAccrual::Retroactive => 1,
+            GraceAccrual::FromDay(day) => day.max(1),
+        }
+    }
+}
+
+/// Where the current overdraft stands. Amounts are in the same
+/// basis-point-day units as `accrued_interest`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct OverdraftAccrual {
+    /// End-of-days closed overdrawn in a row.
+    pub days: u32,
+    /// Accrued within the grace period; waived if the overdraft is repaid
+    /// in time.
+    pub deferred: i128,
+    /// Owed and not yet charged because it is less than a cent.
+    pub accrued: i128,
+}
+
+impl Account {
+    pub fn overdraft_interest(&self) -> Option<OverdraftInterest> {
+        self.overdraft_interest
+    }
+
+    /// Sets or removes overdraft interest. Removing it drops whatever the
+    /// current overdraft had accrued.
+    pub fn set_overdraft_interest(&mut self, policy: Option<OverdraftInterest>) {
+        if policy.is_none() {
+            self.overdraft_accrual = OverdraftAccrual::default();
+        }
+        self.overdraft_interest = policy;
+    }
+
+    /// How many end-of-days in a row the account has closed overdrawn.
+    pub fn overdrawn_days(&self) -> u32 {
+        self.overdraft_accrual.days
+    }
+
+    /// Whether the account is overdrawn and still inside its grace period.
+    pub fn in_overdraft_grace(&self) -> bool {
+        self.overdraft_interest.is_some_and(|policy| {
+            self.balance < 0 && self.overdraft_accrual.days < policy.grace_days
+        })
+    }
+
+    /// Interest accrued during the grace period, in whole cents. It is
+    /// charged only if the overdraft outlasts the grace period.
+    pub fn deferred_overdraft_interest(&self) -> i64 {
+        (self.overdraft_accrual.deferred / INTEREST_UNITS_PER_CENT) as i64
+    }
+
+    /// Accrues one day of overdraft interest and takes whatever has come to
+    /// whole cents off the balance, returning the amount charged. A
+    /// balance back at zero or above ends the overdraft, waiving anything
+    /// deferred.
+    pub(crate) fn accrue_overdraft_interest(&mut self) -> i64 {
+        let Some(policy) = self.overdraft_interest else {
+            return 0;
+        };
+        let state = &mut self.overdraft_accrual;
+        if self.balance >= 0 {
+            *state = OverdraftAccrual::default();
+            return 0;
+        }
+        state.days += 1;
+        if state.days == policy.grace_days + 1 {
+            state.accrued += std::mem::take(&mut state.deferred);
+        }
+        if state.days >= policy.first_charged_day() {
+            let units = i128::from(self.balance).abs() * i128::from(policy.rate_bps);
+            match state.days <= policy.grace_days {
+                true => state.deferred += units,
+                false => state.accrued += units,
+            }
+        }
+        let cents = self.rounding.divide(state.accrued, INTEREST_UNITS_PER_CENT) as i64;
+        if cents <= 0 {
+            return 0;
+        }
+        let Some(balance) = self.balance.checked_sub(cents) else {
+            return 0;
+        };
+        self.balance = balance;
+        state.accrued -= i128::from(cents) * INTEREST_UNITS_PER_CENT;
+        cents
+    }
+}
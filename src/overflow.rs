@@ -0,0 +1,188 @@
+//! What a deposit or transfer does when the balance it credits would no
+//! longer fit in an `i64`.
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError};
+use crate::outcome::{OperationOutcome, Warning};
+use crate::transaction::Transaction;
+
+/// The metadata key holding, on each entry booked while an account's
+/// balance was promoted, the part of the balance above `i64::MAX`.
+pub(crate) const PROMOTED_KEY: &str = "promoted";
+
+/// How credits that would overflow a balance are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Refuse with [`InputError::AmountOverflow`].
+    #[default]
+    Reject,
+    /// Credit only what fits, with a [`Warning::AmountSaturated`].
+    Saturate,
+    /// Credit everything, carrying what doesn't fit in the booked balance
+    /// as a promoted `i128` part; see [`Account::exact_balance`].
+    Promote,
+}
+
+impl Bank {
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// Applies to deposits and incoming transfers. Interest, fees and
+    /// other postings refuse to overflow whatever the policy.
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.overflow_policy = policy;
+    }
+}
+
+impl Account {
+    /// The balance including any promoted part above `i64::MAX`.
+    pub fn exact_balance(&self) -> i128 {
+        i128::from(self.balance) + self.promoted
+    }
+
+    /// The part of the balance that doesn't fit in [`Account::balance`].
+    pub fn promoted_balance(&self) -> i128 {
+        self.promoted
+    }
+
+    /// Credits `amount` under `policy`. The outcome's value is the amount
+    /// actually credited, which is less than `amount` only when
+    /// saturating.
+    pub(crate) fn credit(
+        &mut self,
+        amount: i64,
+        policy: OverflowPolicy,
+    ) -> Result<OperationOutcome<i64>, AccountError> {
+        if amount < 0 || self.balance.checked_add(amount).is_some() {
+            return Ok(self.deposit(amount)?.map(|_| amount));
+        }
+        let room = i64::MAX - self.balance;
+        let credited = match policy {
+            OverflowPolicy::Reject => {
+                return Err(AccountError::Validation(InputError::AmountOverflow))
+            }
+            OverflowPolicy::Saturate => room,
+            OverflowPolicy::Promote => {
+                self.promoted += i128::from(amount - room);
+                amount
+            }
+        };
+        self.balance = i64::MAX;
+        let mut warnings = Vec::new();
+        if credited < amount {
+            warnings.push(Warning::AmountSaturated {
+                requested: amount,
+                credited,
+                currency: self.currency,
+            });
+        }
+        warnings.extend(self.balance_warnings());
+        Ok(OperationOutcome::with_warnings(credited, warnings))
+    }
+
+    /// Moves as much of the promoted part back into the booked balance as
+    /// debits have made room for.
+    pub(crate) fn absorb_promoted(&mut self) {
+        if self.promoted == 0 {
+            return;
+        }
+        let moved = self.promoted.min(i128::from(i64::MAX - self.balance));
+        self.balance += moved as i64;
+        self.promoted -= moved;
+    }
+
+    /// Splits an exact balance into the booked `i64` part and the promoted
+    /// rest.
+    pub(crate) fn set_exact_balance(&mut self, exact: i128) {
+        self.balance = exact.min(i128::from(i64::MAX)) as i64;
+        self.promoted = exact - i128::from(self.balance);
+    }
+}
+
+impl Transaction {
+    /// The balance this entry left, including any promoted part.
+    pub fn exact_balance_after(&self) -> i128 {
+        let promoted = self
+            .metadata
+            .get(PROMOTED_KEY)
+            .and_then(|value| value.parse::<i128>().ok())
+            .unwrap_or(0);
+        i128::from(self.balance_after) + promoted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEAR_MAX: i64 = i64::MAX - 10;
+
+    /// Account 1 has room for 10 more; account 2 holds 1000.
+    fn bank(policy: OverflowPolicy) -> Bank {
+        let mut bank = Bank::new();
+        bank.add_account(Account::new(1, "Ann".to_string()));
+        bank.add_account(Account::new(2, "Bob".to_string()));
+        bank.deposit(1, NEAR_MAX).unwrap();
+        bank.deposit(2, 1_000).unwrap();
+        bank.set_overflow_policy(policy);
+        bank
+    }
+
+    fn balance(bank: &Bank, id: u32) -> i64 {
+        bank.get_account(id).unwrap().balance()
+    }
+
+    #[test]
+    fn reject_refuses_deposits_and_transfers_that_overflow() {
+        let mut bank = bank(OverflowPolicy::Reject);
+        let overflow = AccountError::Validation(InputError::AmountOverflow);
+
+        assert_eq!(bank.deposit(1, 100).unwrap_err(), overflow);
+        assert_eq!(bank.transfer(2, 1, 100).unwrap_err(), overflow);
+        assert_eq!((balance(&bank, 1), balance(&bank, 2)), (NEAR_MAX, 1_000));
+        bank.deposit(1, 10).unwrap();
+        assert_eq!(balance(&bank, 1), i64::MAX);
+    }
+
+    #[test]
+    fn saturate_credits_what_fits_and_warns() {
+        let mut bank = bank(OverflowPolicy::Saturate);
+        let saturated = Warning::AmountSaturated {
+            requested: 100,
+            credited: 10,
+            currency: Default::default(),
+        };
+
+        let receipt = bank.deposit(1, 100).unwrap();
+        assert_eq!(receipt.amount, 10);
+        assert!(receipt.warnings.contains(&saturated));
+        assert_eq!(balance(&bank, 1), i64::MAX);
+
+        bank.withdraw(1, 10).unwrap();
+        let outcome = bank.transfer(2, 1, 100).unwrap();
+        assert!(outcome.warnings.contains(&saturated));
+        assert_eq!((balance(&bank, 1), balance(&bank, 2)), (i64::MAX, 990));
+    }
+
+    #[test]
+    fn promote_keeps_the_excess_and_gives_it_back_after_debits() {
+        let mut bank = bank(OverflowPolicy::Promote);
+
+        bank.deposit(1, 100).unwrap();
+        let account = bank.get_account(1).unwrap();
+        assert_eq!(account.balance(), i64::MAX);
+        assert_eq!(account.promoted_balance(), 90);
+        assert_eq!(account.exact_balance(), i128::from(i64::MAX) + 90);
+        let last = account.history().last().unwrap();
+        assert_eq!(last.exact_balance_after(), account.exact_balance());
+
+        bank.transfer(2, 1, 100).unwrap();
+        assert_eq!(bank.get_account(1).unwrap().promoted_balance(), 190);
+        bank.withdraw(1, 500).unwrap();
+        let account = bank.get_account(1).unwrap();
+        assert_eq!(account.exact_balance(), i128::from(i64::MAX) - 310);
+        assert_eq!(account.promoted_balance(), 0);
+    }
+}
@@ -0,0 +1,141 @@
+//! Handing an account to a new holder without closing it, keeping its
+//! history and recording the change in the ledger.
+
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError, PolicyError, StateError};
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// A change of holder waiting for both the current and the new holder to
+/// approve it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnershipTransfer {
+    pub id: u64,
+    pub new_holder: String,
+    pub requested_at: Timestamp,
+    pub approved_by_holder: bool,
+    pub approved_by_new_holder: bool,
+}
+
+impl Account {
+    /// Whether a change of holder waits for both parties to approve it.
+    pub fn ownership_approval_required(&self) -> bool {
+        self.ownership_approval_required
+    }
+
+    pub fn set_ownership_approval_required(&mut self, required: bool) {
+        self.ownership_approval_required = required;
+    }
+
+    pub fn pending_ownership_transfer(&self) -> Option<&OwnershipTransfer> {
+        self.pending_ownership_transfer.as_ref()
+    }
+}
+
+impl Bank {
+    /// Makes `new_holder` the account's holder, keeping its balance and
+    /// history, and books a zero-amount entry recording both names. Returns
+    /// the entry's id.
+    ///
+    /// The name is checked against the bank's holder validation. If the
+    /// account requires approval, the change is kept as a pending request
+    /// instead, replacing any earlier one, and refused with
+    /// [`PolicyError::OwnershipApprovalRequired`] until both holders
+    /// approve it through [`Bank::approve_ownership_transfer`].
+    pub fn transfer_ownership(
+        &mut self,
+        account: impl Into<AccountRef>,
+        new_holder: &str,
+    ) -> Result<u64, AccountError> {
+        let id = self.resolve(account)?;
+        let new_holder = self
+            .holder_validation
+            .validate(new_holder)
+            .map_err(InputError::InvalidHolder)?;
+        if !self.accounts[&id].ownership_approval_required {
+            return Ok(self.change_holder(id, new_holder, &[]));
+        }
+        let (request_id, now) = (self.next_item_id, self.now);
+        self.next_item_id += 1;
+        self.accounts
+            .get_mut(&id)
+            .unwrap()
+            .pending_ownership_transfer = Some(OwnershipTransfer {
+            id: request_id,
+            new_holder,
+            requested_at: now,
+            approved_by_holder: false,
+            approved_by_new_holder: false,
+        });
+        Err(AccountError::Policy(
+            PolicyError::OwnershipApprovalRequired { request_id },
+        ))
+    }
+
+    /// Records `party`'s approval of a pending change of holder; `party`
+    /// must be the current or the new holder. Once both have approved, the
+    /// change is made and the id of its entry returned.
+    pub fn approve_ownership_transfer(
+        &mut self,
+        account: impl Into<AccountRef>,
+        request_id: u64,
+        party: &str,
+    ) -> Result<Option<u64>, AccountError> {
+        let id = self.resolve(account)?;
+        let account = self.accounts.get_mut(&id).unwrap();
+        let request = account
+            .pending_ownership_transfer
+            .as_mut()
+            .filter(|request| request.id == request_id)
+            .ok_or(AccountError::State(StateError::OwnershipTransferNotFound))?;
+        let party = party.trim();
+        let (is_holder, is_new_holder) = (party == account.holder, party == request.new_holder);
+        if !is_holder && !is_new_holder {
+            return Err(AccountError::Policy(PolicyError::NotOwnershipParty));
+        }
+        request.approved_by_holder |= is_holder;
+        request.approved_by_new_holder |= is_new_holder;
+        if !(request.approved_by_holder && request.approved_by_new_holder) {
+            return Ok(None);
+        }
+        let request = account.pending_ownership_transfer.take().unwrap();
+        let approvers = [account.holder.clone(), request.new_holder.clone()];
+        Ok(Some(self.change_holder(id, request.new_holder, &approvers)))
+    }
+
+    /// Drops a pending change of holder.
+    pub fn reject_ownership_transfer(
+        &mut self,
+        account: impl Into<AccountRef>,
+        request_id: u64,
+    ) -> Result<OwnershipTransfer, AccountError> {
+        let id = self.resolve(account)?;
+        let account = self.accounts.get_mut(&id).unwrap();
+        if account
+            .pending_ownership_transfer
+            .as_ref()
+            .is_none_or(|request| request.id != request_id)
+        {
+            return Err(AccountError::State(StateError::OwnershipTransferNotFound));
+        }
+        Ok(account.pending_ownership_transfer.take().unwrap())
+    }
+
+    fn change_holder(&mut self, id: u32, new_holder: String, approvers: &[String]) -> u64 {
+        let account = self.accounts.get_mut(&id).unwrap();
+        let previous = std::mem::replace(&mut account.holder, new_holder.clone());
+        account.pending_ownership_transfer = None;
+        let memo = format!("Ownership transferred from {} to {}", previous, new_holder);
+        let txn_id = self.record(id, TransactionKind::OwnershipTransfer, 0, None, &memo);
+        self.amend_transaction(id, txn_id, |txn| {
+            txn.metadata.insert("previous_holder".to_string(), previous);
+            txn.metadata.insert("new_holder".to_string(), new_holder);
+            if !approvers.is_empty() {
+                txn.metadata
+                    .insert("approved_by".to_string(), approvers.join(", "));
+            }
+        });
+        txn_id
+    }
+}
@@ -0,0 +1,331 @@
+//! A minimal Parquet writer: flat schemas of 64-bit integers and UTF-8
+//! strings, plain encoding, no compression, one data page per column chunk.
+//! Enough for analysis tools to read without pulling in a Parquet library.
+
+use std::io::{self, Write};
+
+const MAGIC: &[u8; 4] = b"PAR1";
+
+// Thrift compact protocol field types.
+const I32: u8 = 5;
+const I64: u8 = 6;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const STRUCT: u8 = 12;
+
+// Parquet enums.
+const TYPE_INT64: i32 = 2;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const REQUIRED: i32 = 0;
+const OPTIONAL: i32 = 1;
+const CONVERTED_UTF8: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const PAGE_DATA: i32 = 0;
+const CODEC_UNCOMPRESSED: i32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnType {
+    Int64,
+    Utf8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ColumnSpec {
+    pub name: &'static str,
+    pub kind: ColumnType,
+    pub optional: bool,
+}
+
+/// One column's values for a row group, already plain-encoded.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ColumnValues {
+    values: Vec<u8>,
+    /// Whether each row has a value; only written for optional columns.
+    present: Vec<bool>,
+    rows: usize,
+}
+
+impl ColumnValues {
+    pub fn push_int(&mut self, value: i64) {
+        self.values.extend_from_slice(&value.to_le_bytes());
+        self.present.push(true);
+        self.rows += 1;
+    }
+
+    pub fn push_text(&mut self, value: &str) {
+        self.values
+            .extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.values.extend_from_slice(value.as_bytes());
+        self.present.push(true);
+        self.rows += 1;
+    }
+
+    pub fn push_null(&mut self) {
+        self.present.push(false);
+        self.rows += 1;
+    }
+}
+
+/// Encodes structures in the Thrift compact protocol Parquet metadata uses.
+#[derive(Debug)]
+struct Thrift {
+    bytes: Vec<u8>,
+    /// The last field id written in each struct being encoded.
+    last_field: Vec<i16>,
+}
+
+impl Thrift {
+    fn new() -> Self {
+        Thrift {
+            bytes: Vec::new(),
+            last_field: vec![0],
+        }
+    }
+
+    fn varint(&mut self, value: u64) {
+        varint(&mut self.bytes, value);
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last_field.last_mut().expect("inside a struct");
+        let delta = id - *last;
+        *last = id;
+        if (1..=15).contains(&delta) {
+            self.bytes.push(((delta as u8) << 4) | kind);
+        } else {
+            self.bytes.push(kind);
+            self.varint(zigzag(i64::from(id)));
+        }
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, I32);
+        self.varint(zigzag(i64::from(value)));
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, I64);
+        self.varint(zigzag(value));
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, BINARY);
+        self.raw_binary(value);
+    }
+
+    fn raw_binary(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.bytes.extend_from_slice(value);
+    }
+
+    fn list(&mut self, id: i16, element: u8, len: usize) {
+        self.field(id, LIST);
+        if len < 15 {
+            self.bytes.push(((len as u8) << 4) | element);
+        } else {
+            self.bytes.push(0xf0 | element);
+            self.varint(len as u64);
+        }
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, STRUCT);
+        self.last_field.push(0);
+    }
+
+    /// Starts a struct that is an element of a list.
+    fn begin_element(&mut self) {
+        self.last_field.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.bytes.push(0);
+        self.last_field.pop();
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.bytes.push(0);
+        self.bytes
+    }
+}
+
+fn varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Definition levels of bit width 1 in the RLE/bit-packing hybrid, as runs
+/// of equal levels, prefixed with their length in bytes.
+fn definition_levels(present: &[bool]) -> Vec<u8> {
+    let mut runs = Vec::new();
+    let mut index = 0;
+    while index < present.len() {
+        let level = present[index];
+        let run = present[index..]
+            .iter()
+            .take_while(|other| **other == level)
+            .count();
+        varint(&mut runs, (run as u64) << 1);
+        runs.push(u8::from(level));
+        index += run;
+    }
+    let mut levels = (runs.len() as u32).to_le_bytes().to_vec();
+    levels.extend_from_slice(&runs);
+    levels
+}
+
+#[derive(Debug)]
+struct ChunkMeta {
+    offset: u64,
+    size: u64,
+    rows: usize,
+}
+
+/// Writes a Parquet file one row group at a time, so only the current
+/// group is held in memory.
+#[derive(Debug)]
+pub(crate) struct ParquetWriter<W: Write> {
+    writer: W,
+    schema: Vec<ColumnSpec>,
+    offset: u64,
+    row_groups: Vec<(usize, Vec<ChunkMeta>)>,
+}
+
+impl<W: Write> ParquetWriter<W> {
+    pub fn new(mut writer: W, schema: Vec<ColumnSpec>) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        Ok(ParquetWriter {
+            writer,
+            schema,
+            offset: MAGIC.len() as u64,
+            row_groups: Vec::new(),
+        })
+    }
+
+    /// Writes one row group; `columns` match the schema, in order, and hold
+    /// the same number of rows.
+    pub fn write_row_group(&mut self, columns: &[ColumnValues]) -> io::Result<()> {
+        let rows = columns.first().map_or(0, |column| column.rows);
+        if rows == 0 {
+            return Ok(());
+        }
+        let mut chunks = Vec::with_capacity(columns.len());
+        for (spec, column) in self.schema.iter().zip(columns) {
+            let mut page = Vec::new();
+            if spec.optional {
+                page.extend_from_slice(&definition_levels(&column.present));
+            }
+            page.extend_from_slice(&column.values);
+
+            let mut header = Thrift::new();
+            header.i32(1, PAGE_DATA);
+            header.i32(2, page.len() as i32);
+            header.i32(3, page.len() as i32);
+            header.begin_struct(5);
+            header.i32(1, column.rows as i32);
+            header.i32(2, ENCODING_PLAIN);
+            header.i32(3, ENCODING_RLE);
+            header.i32(4, ENCODING_RLE);
+            header.end_struct();
+            let header = header.finish();
+
+            self.writer.write_all(&header)?;
+            self.writer.write_all(&page)?;
+            let size = (header.len() + page.len()) as u64;
+            chunks.push(ChunkMeta {
+                offset: self.offset,
+                size,
+                rows: column.rows,
+            });
+            self.offset += size;
+        }
+        self.row_groups.push((rows, chunks));
+        Ok(())
+    }
+
+    /// Writes the footer with `metadata` as key-value pairs and returns the
+    /// underlying writer.
+    pub fn finish(mut self, metadata: &[(&str, String)], created_by: &str) -> io::Result<W> {
+        let mut footer = Thrift::new();
+        footer.i32(1, 1);
+        footer.list(2, STRUCT, self.schema.len() + 1);
+        footer.begin_element();
+        footer.binary(4, b"schema");
+        footer.i32(5, self.schema.len() as i32);
+        footer.end_struct();
+        for spec in &self.schema {
+            footer.begin_element();
+            footer.i32(
+                1,
+                match spec.kind {
+                    ColumnType::Int64 => TYPE_INT64,
+                    ColumnType::Utf8 => TYPE_BYTE_ARRAY,
+                },
+            );
+            footer.i32(3, if spec.optional { OPTIONAL } else { REQUIRED });
+            footer.binary(4, spec.name.as_bytes());
+            if spec.kind == ColumnType::Utf8 {
+                footer.i32(6, CONVERTED_UTF8);
+            }
+            footer.end_struct();
+        }
+        let total_rows: usize = self.row_groups.iter().map(|(rows, _)| rows).sum();
+        footer.i64(3, total_rows as i64);
+        footer.list(4, STRUCT, self.row_groups.len());
+        for (rows, chunks) in &self.row_groups {
+            footer.begin_element();
+            footer.list(1, STRUCT, chunks.len());
+            for (spec, chunk) in self.schema.iter().zip(chunks) {
+                footer.begin_element();
+                footer.i64(2, chunk.offset as i64);
+                footer.begin_struct(3);
+                footer.i32(
+                    1,
+                    match spec.kind {
+                        ColumnType::Int64 => TYPE_INT64,
+                        ColumnType::Utf8 => TYPE_BYTE_ARRAY,
+                    },
+                );
+                footer.list(2, I32, 2);
+                footer.varint(zigzag(i64::from(ENCODING_PLAIN)));
+                footer.varint(zigzag(i64::from(ENCODING_RLE)));
+                footer.list(3, BINARY, 1);
+                footer.raw_binary(spec.name.as_bytes());
+                footer.i32(4, CODEC_UNCOMPRESSED);
+                footer.i64(5, chunk.rows as i64);
+                footer.i64(6, chunk.size as i64);
+                footer.i64(7, chunk.size as i64);
+                footer.i64(9, chunk.offset as i64);
+                footer.end_struct();
+                footer.end_struct();
+            }
+            footer.i64(2, chunks.iter().map(|chunk| chunk.size).sum::<u64>() as i64);
+            footer.i64(3, *rows as i64);
+            footer.end_struct();
+        }
+        footer.list(5, STRUCT, metadata.len());
+        for (key, value) in metadata {
+            footer.begin_element();
+            footer.binary(1, key.as_bytes());
+            footer.binary(2, value.as_bytes());
+            footer.end_struct();
+        }
+        footer.binary(6, created_by.as_bytes());
+        let footer = footer.finish();
+
+        self.writer.write_all(&footer)?;
+        self.writer
+            .write_all(&(footer.len() as u32).to_le_bytes())?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
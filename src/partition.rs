@@ -0,0 +1,75 @@
+//! An account's history partitioned by the day each entry was booked on, so
+//! statements and reports over a period look only at that period's days
+//! instead of the whole history.
+
+use std::collections::BTreeMap;
+
+use crate::account::Account;
+use crate::time::Timestamp;
+use crate::transaction::Transaction;
+
+/// Positions in a history, grouped by UTC day. Positions within a day are
+/// ascending, and each is listed under the day of its entry's timestamp,
+/// whatever order the clock moved in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DayPartitions {
+    days: BTreeMap<u64, Vec<usize>>,
+}
+
+impl DayPartitions {
+    pub(crate) fn build(history: &[Transaction]) -> Self {
+        let mut partitions = DayPartitions::default();
+        for (position, txn) in history.iter().enumerate() {
+            partitions.insert(position, txn.timestamp);
+        }
+        partitions
+    }
+
+    /// Files the entry at `position`, which must come after every position
+    /// already filed.
+    pub(crate) fn insert(&mut self, position: usize, timestamp: Timestamp) {
+        self.days
+            .entry(timestamp.day_number())
+            .or_default()
+            .push(position);
+    }
+
+    /// Positions of the entries booked in `[from, to]`, in booking order.
+    fn positions(&self, history: &[Transaction], from: Timestamp, to: Timestamp) -> Vec<usize> {
+        if from > to {
+            return Vec::new();
+        }
+        let (first_day, last_day) = (from.day_number(), to.day_number());
+        let mut positions: Vec<usize> = self
+            .days
+            .range(first_day..=last_day)
+            .flat_map(|(&day, positions)| {
+                let whole_day = day != first_day && day != last_day;
+                positions.iter().copied().filter(move |&position| {
+                    whole_day || (from..=to).contains(&history[position].timestamp)
+                })
+            })
+            .collect();
+        // Already sorted unless the clock was set back across days.
+        positions.sort_unstable();
+        positions
+    }
+}
+
+impl Account {
+    /// The entries booked in `[from, to]`, in booking order. Only the days
+    /// in the period are looked at, so this stays fast on long histories.
+    pub fn history_between(&self, from: Timestamp, to: Timestamp) -> Vec<&Transaction> {
+        self.partitions
+            .positions(&self.history, from, to)
+            .into_iter()
+            .map(|position| &self.history[position])
+            .collect()
+    }
+
+    /// Refiles every entry after the history was changed other than by
+    /// booking onto its end.
+    pub(crate) fn repartition(&mut self) {
+        self.partitions = DayPartitions::build(&self.history);
+    }
+}
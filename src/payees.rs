@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError, PolicyError};
+use crate::time::{Timestamp, SECONDS_PER_DAY};
+use crate::transaction::TransactionKind;
+
+/// A daily cap on transfers to one payee that applies until the
+/// relationship is `until_day` days old.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayeeLimitStage {
+    pub until_day: u32,
+    pub daily_limit: i64,
+}
+
+/// Daily limits on transfers between a payer and a payee that relax as the
+/// relationship ages, e.g. at most $500 a day for the first week and $2,000 a
+/// day for the first month. Relationships older than the last stage are
+/// unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayeeLimits {
+    pub(crate) stages: Vec<PayeeLimitStage>,
+}
+
+impl PayeeLimits {
+    pub fn new() -> Self {
+        PayeeLimits::default()
+    }
+
+    /// Caps transfers at `daily_limit` cents a day until the relationship is
+    /// `until_day` days old.
+    pub fn stage(mut self, until_day: u32, daily_limit: i64) -> Self {
+        self.stages.push(PayeeLimitStage {
+            until_day,
+            daily_limit,
+        });
+        self.stages.sort_by_key(|stage| stage.until_day);
+        self
+    }
+
+    pub fn stages(&self) -> &[PayeeLimitStage] {
+        &self.stages
+    }
+
+    /// The daily limit for a relationship `age_days` old, if any.
+    pub fn daily_limit(&self, age_days: u32) -> Option<i64> {
+        self.stages
+            .iter()
+            .find(|stage| age_days < stage.until_day)
+            .map(|stage| stage.daily_limit)
+    }
+
+    /// What `from` may still send to `to_id` today, or `None` if unlimited.
+    pub(crate) fn allowance(
+        &self,
+        payees: &HashMap<(u32, u32), Timestamp>,
+        now: Timestamp,
+        from: &Account,
+        to_id: u32,
+    ) -> Option<i64> {
+        let since = payees.get(&(from.id, to_id)).copied().or_else(|| {
+            from.history
+                .iter()
+                .find(|txn| is_transfer_to(txn.kind, txn.counterparty, to_id))
+                .map(|txn| txn.timestamp)
+        });
+        let age_days = since
+            .map(|since| now.seconds().saturating_sub(since.seconds()) / SECONDS_PER_DAY)
+            .unwrap_or(0);
+        let limit = self.daily_limit(u32::try_from(age_days).unwrap_or(u32::MAX))?;
+        let today = now.start_of_day();
+        let sent: i64 = from
+            .history
+            .iter()
+            .rev()
+            .take_while(|txn| txn.timestamp >= today)
+            .filter(|txn| is_transfer_to(txn.kind, txn.counterparty, to_id))
+            .map(|txn| -txn.amount)
+            .sum();
+        Some((limit - sent).max(0))
+    }
+
+    /// Refuses a transfer that would take `from` past today's limit for
+    /// `to_id`.
+    pub(crate) fn check_transfer(
+        &self,
+        payees: &HashMap<(u32, u32), Timestamp>,
+        now: Timestamp,
+        from: &Account,
+        to_id: u32,
+        amount: i64,
+    ) -> Result<(), AccountError> {
+        match self.allowance(payees, now, from, to_id) {
+            Some(remaining) if amount > remaining => {
+                Err(AccountError::Policy(PolicyError::PayeeLimitExceeded {
+                    remaining,
+                    currency: from.currency,
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_transfer_to(kind: TransactionKind, counterparty: Option<u32>, to_id: u32) -> bool {
+    kind == TransactionKind::TransferOut && counterparty == Some(to_id)
+}
+
+impl Bank {
+    pub fn payee_limits(&self) -> Option<&PayeeLimits> {
+        self.payee_limits.as_ref()
+    }
+
+    pub fn set_payee_limits(&mut self, limits: Option<PayeeLimits>) {
+        self.payee_limits = limits;
+    }
+
+    /// Registers `to` as a payee of `from`, starting the relationship's
+    /// clock now. Pairs never added count from their first transfer.
+    pub fn add_payee(
+        &mut self,
+        from: impl Into<AccountRef>,
+        to: impl Into<AccountRef>,
+    ) -> Result<(), AccountError> {
+        let from_id = self.resolve(from)?;
+        let to_id = self.resolve(to)?;
+        if from_id == to_id {
+            return Err(AccountError::Validation(InputError::SameAccount));
+        }
+        self.payees.insert((from_id, to_id), self.now);
+        Ok(())
+    }
+
+    pub fn payee_added_at(&self, from: u32, to: u32) -> Option<Timestamp> {
+        self.payees.get(&(from, to)).copied()
+    }
+
+    /// What `from` may still send `to` today under the payee limits, or
+    /// `None` if the pair is unlimited.
+    pub fn payee_allowance(
+        &self,
+        from: impl Into<AccountRef>,
+        to: impl Into<AccountRef>,
+    ) -> Result<Option<i64>, AccountError> {
+        let from_id = self.resolve(from)?;
+        let to_id = self.resolve(to)?;
+        Ok(self.payee_limits.as_ref().and_then(|limits| {
+            limits.allowance(&self.payees, self.now, &self.accounts[&from_id], to_id)
+        }))
+    }
+}
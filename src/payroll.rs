@@ -0,0 +1,144 @@
+use std::fmt;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError, StateError};
+use crate::reconciliation::PAYMENT_REFERENCE;
+use crate::transaction::TransactionKind;
+
+/// The category payroll transactions are booked under on both sides.
+pub const SALARY_CATEGORY: &str = "salary";
+
+/// One employee's pay from a [`PayrollRun`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayrollPayment {
+    pub employee_id: u32,
+    pub amount: i64,
+    /// Quoted on both entries, and returned by
+    /// [`Transaction::payment_reference`](crate::Transaction::payment_reference).
+    pub reference: String,
+    pub debit_transaction: u64,
+    pub credit_transaction: u64,
+}
+
+/// The result of [`Bank::run_payroll`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayrollRun {
+    pub id: u64,
+    pub payer_id: u32,
+    pub total: i64,
+    pub payments: Vec<PayrollPayment>,
+}
+
+impl fmt::Display for PayrollRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Payroll run {} from account {}: {} payments, ${:.2}",
+            self.id,
+            self.payer_id,
+            self.payments.len(),
+            self.total as f64 / 100.0
+        )?;
+        for payment in &self.payments {
+            writeln!(
+                f,
+                "  {}  account {}  ${:.2}",
+                payment.reference,
+                payment.employee_id,
+                payment.amount as f64 / 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    /// Pays every `(employee account, amount)` entry from `payer` as one
+    /// unit. The payer must be able to fund the whole run up front, and if
+    /// any payment fails none is made.
+    ///
+    /// Each payment is an ordinary transfer, so every transfer rule
+    /// applies, with both entries booked under [`SALARY_CATEGORY`] with
+    /// `memo` and a reference of the form `PAYROLL-<run>-<n>`.
+    pub fn run_payroll(
+        &mut self,
+        payer: impl Into<AccountRef>,
+        entries: &[(u32, i64)],
+        memo: &str,
+    ) -> Result<PayrollRun, AccountError> {
+        let payer_id = self.resolve(payer)?;
+        let mut total: i64 = 0;
+        for &(employee_id, amount) in entries {
+            if amount < 0 {
+                return Err(AccountError::Validation(InputError::NegativeAmount));
+            }
+            if amount == 0 {
+                return Err(AccountError::Validation(InputError::ZeroAmount));
+            }
+            if employee_id == payer_id {
+                return Err(AccountError::Validation(InputError::SameAccount));
+            }
+            if !self.accounts.contains_key(&employee_id) {
+                return Err(AccountError::State(StateError::AccountNotFound));
+            }
+            total = total
+                .checked_add(amount)
+                .ok_or(AccountError::Validation(InputError::AmountOverflow))?;
+        }
+        let payer = &self.accounts[&payer_id];
+        if payer.spendable_balance() < total {
+            return Err(payer.insufficient_funds(total));
+        }
+
+        let booked = self.transact(|savepoint| {
+            let mut booked = Vec::with_capacity(entries.len());
+            for &(employee_id, amount) in entries {
+                savepoint.transfer(payer_id, employee_id, amount)?;
+                let latest = |account_id: u32, kind| {
+                    savepoint.bank().accounts[&account_id]
+                        .history
+                        .iter()
+                        .rev()
+                        .find(|txn| txn.kind == kind)
+                        .map_or(0, |txn| txn.id)
+                };
+                booked.push((
+                    latest(payer_id, TransactionKind::TransferOut),
+                    latest(employee_id, TransactionKind::TransferIn),
+                ));
+            }
+            Ok::<_, AccountError>(booked)
+        })?;
+
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        let mut payments = Vec::with_capacity(entries.len());
+        for (index, (&(employee_id, amount), (debit, credit))) in
+            entries.iter().zip(booked).enumerate()
+        {
+            let reference = format!("PAYROLL-{}-{}", id, index + 1);
+            for (account_id, txn_id) in [(payer_id, debit), (employee_id, credit)] {
+                self.amend_transaction(account_id, txn_id, |txn| {
+                    txn.memo = memo.to_string();
+                    txn.category = Some(SALARY_CATEGORY.to_string());
+                    txn.metadata
+                        .insert(PAYMENT_REFERENCE.to_string(), reference.clone());
+                });
+            }
+            payments.push(PayrollPayment {
+                employee_id,
+                amount,
+                reference,
+                debit_transaction: debit,
+                credit_transaction: credit,
+            });
+        }
+        Ok(PayrollRun {
+            id,
+            payer_id,
+            total,
+            payments,
+        })
+    }
+}
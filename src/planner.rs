@@ -0,0 +1,227 @@
+//! What-if planning of internal transfers: given the end state wanted,
+//! work out the transfers that reach it, to look at first or to carry out
+//! as one unit.
+
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError, PolicyError};
+
+/// The end state a [`TransferPlan`] works towards. Only available money
+/// moves: funds under a hold stay put.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferGoal {
+    /// The same available balance in every account listed, moving only
+    /// money already among them. Cents that don't divide evenly stay with
+    /// the accounts listed first.
+    Equalize(Vec<u32>),
+    /// At least `target` available in `account`, drawn from `sources`.
+    Fund {
+        account: u32,
+        target: i64,
+        sources: Vec<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedTransfer {
+    pub from: u32,
+    pub to: u32,
+    pub amount: i64,
+}
+
+/// The result of [`Bank::plan_transfers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferPlan {
+    pub transfers: Vec<PlannedTransfer>,
+    /// How far the transfers fall short of the goal; zero when they reach
+    /// it.
+    pub shortfall: i64,
+}
+
+impl TransferPlan {
+    pub fn is_complete(&self) -> bool {
+        self.shortfall == 0
+    }
+
+    pub fn total_moved(&self) -> i64 {
+        self.transfers.iter().map(|transfer| transfer.amount).sum()
+    }
+}
+
+impl fmt::Display for TransferPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Transfer plan: {} transfers moving {}",
+            self.transfers.len(),
+            self.total_moved()
+        )?;
+        for transfer in &self.transfers {
+            write!(
+                f,
+                "\n  {:<8} -> {:<8} {:>12}",
+                transfer.from, transfer.to, transfer.amount
+            )?;
+        }
+        if !self.is_complete() {
+            write!(f, "\n  Short by {}", self.shortfall)?;
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    /// Works out the fewest transfers that reach `goal` without touching
+    /// the bank. Funding draws on the sources with the most available
+    /// first; equalizing needs at most one transfer fewer than there are
+    /// accounts. All the accounts must be in the same currency.
+    pub fn plan_transfers(&self, goal: &TransferGoal) -> Result<TransferPlan, AccountError> {
+        match goal {
+            TransferGoal::Equalize(accounts) => self.plan_equalize(accounts),
+            TransferGoal::Fund {
+                account,
+                target,
+                sources,
+            } => self.plan_fund(*account, *target, sources),
+        }
+    }
+
+    /// Plans `goal` and makes the transfers as one unit with
+    /// [`Bank::transact`], returning the plan carried out. Refused with
+    /// [`PolicyError::InsufficientFunds`] when the plan falls short, and if
+    /// any transfer is refused none are made.
+    pub fn execute_transfers(&mut self, goal: &TransferGoal) -> Result<TransferPlan, AccountError> {
+        let plan = self.plan_transfers(goal)?;
+        if !plan.is_complete() {
+            let available = plan.total_moved();
+            let account = match goal {
+                TransferGoal::Equalize(accounts) => accounts.first(),
+                TransferGoal::Fund { account, .. } => Some(account),
+            };
+            return Err(AccountError::Policy(PolicyError::InsufficientFunds {
+                requested: available + plan.shortfall,
+                available,
+                unused_overdraft: 0,
+                currency: account
+                    .and_then(|id| self.accounts.get(id))
+                    .map(|account| account.currency)
+                    .unwrap_or_default(),
+            }));
+        }
+        self.transact(|savepoint| {
+            for transfer in &plan.transfers {
+                savepoint.transfer(transfer.from, transfer.to, transfer.amount)?;
+            }
+            Ok::<_, AccountError>(())
+        })?;
+        Ok(plan)
+    }
+
+    fn plan_equalize(&self, accounts: &[u32]) -> Result<TransferPlan, AccountError> {
+        let mut ids: Vec<u32> = Vec::new();
+        for id in accounts {
+            if !ids.contains(id) {
+                ids.push(*id);
+            }
+        }
+        let balances = self.available_balances(&ids)?;
+        let count = ids.len().max(1) as i64;
+        let total: i64 = balances.iter().sum();
+        let (share, remainder) = (total.div_euclid(count), total.rem_euclid(count));
+        // Positive for money an account has to give, negative for money it
+        // is owed.
+        let mut surplus: Vec<i64> = balances
+            .iter()
+            .enumerate()
+            .map(|(index, balance)| balance - share - i64::from((index as i64) < remainder))
+            .collect();
+        let mut transfers = Vec::new();
+        loop {
+            let giver = largest(&surplus, |surplus| surplus);
+            let taker = largest(&surplus, |surplus| -surplus);
+            let (Some(giver), Some(taker)) = (giver, taker) else {
+                break;
+            };
+            let amount = surplus[giver].min(-surplus[taker]);
+            surplus[giver] -= amount;
+            surplus[taker] += amount;
+            transfers.push(PlannedTransfer {
+                from: ids[giver],
+                to: ids[taker],
+                amount,
+            });
+        }
+        Ok(TransferPlan {
+            transfers,
+            shortfall: 0,
+        })
+    }
+
+    fn plan_fund(
+        &self,
+        account: u32,
+        target: i64,
+        sources: &[u32],
+    ) -> Result<TransferPlan, AccountError> {
+        if target < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let mut ids = vec![account];
+        for id in sources {
+            if !ids.contains(id) {
+                ids.push(*id);
+            }
+        }
+        let balances = self.available_balances(&ids)?;
+        let mut needed = (target - balances[0]).max(0);
+        let mut by_size: Vec<(u32, i64)> = ids[1..]
+            .iter()
+            .copied()
+            .zip(balances[1..].iter().copied())
+            .collect();
+        by_size.sort_by_key(|(_, available)| std::cmp::Reverse(*available));
+        let mut transfers = Vec::new();
+        for (from, available) in by_size {
+            if needed == 0 || available <= 0 {
+                break;
+            }
+            let amount = available.min(needed);
+            needed -= amount;
+            transfers.push(PlannedTransfer {
+                from,
+                to: account,
+                amount,
+            });
+        }
+        Ok(TransferPlan {
+            transfers,
+            shortfall: needed,
+        })
+    }
+
+    /// The available balances of `ids`, which must all exist and share one
+    /// currency.
+    fn available_balances(&self, ids: &[u32]) -> Result<Vec<i64>, AccountError> {
+        let mut currency = None;
+        let mut balances = Vec::with_capacity(ids.len());
+        for id in ids {
+            let account = &self.accounts[&self.resolve(*id)?];
+            if *currency.get_or_insert(account.currency) != account.currency {
+                return Err(AccountError::Validation(InputError::MixedCurrencies));
+            }
+            balances.push(account.available_balance());
+        }
+        Ok(balances)
+    }
+}
+
+/// The index with the largest positive `key`, the first on ties.
+fn largest(surplus: &[i64], key: impl Fn(i64) -> i64) -> Option<usize> {
+    surplus
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| key(**value) > 0)
+        .min_by_key(|(index, value)| (std::cmp::Reverse(key(**value)), *index))
+        .map(|(index, _)| index)
+}
@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::json;
+use crate::locale::{Locale, Localize};
+use crate::snapshot::kind_code;
+use crate::time::Timestamp;
+use crate::transaction::Transaction;
+
+/// A holder's combined net worth in one currency after a timeline entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetWorthPoint {
+    pub timestamp: Timestamp,
+    pub currency: Currency,
+    pub net_worth: i64,
+}
+
+/// Everything one holder has across their accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Portfolio {
+    pub holder: String,
+    pub accounts: Vec<u32>,
+    pub balances: BTreeMap<Currency, i64>,
+    /// Every transaction on the holder's accounts, oldest first.
+    pub timeline: Vec<Transaction>,
+    pub net_worth: Vec<NetWorthPoint>,
+}
+
+impl Portfolio {
+    pub fn to_json(&self) -> String {
+        let accounts: Vec<String> = self.accounts.iter().map(u32::to_string).collect();
+        let balances: Vec<String> = self
+            .balances
+            .iter()
+            .map(|(currency, balance)| format!("{}:{}", json::string(currency.code()), balance))
+            .collect();
+        let timeline: Vec<String> = self
+            .timeline
+            .iter()
+            .map(|txn| {
+                format!(
+                    "{{\"id\":{},\"account_id\":{},\"kind\":{},\"amount\":{},\"balance_after\":{},\"timestamp\":{},\"memo\":{}}}",
+                    txn.id,
+                    txn.account_id,
+                    json::string(kind_code(txn.kind)),
+                    txn.amount,
+                    txn.balance_after,
+                    txn.timestamp.seconds(),
+                    json::string(&txn.memo)
+                )
+            })
+            .collect();
+        let net_worth: Vec<String> = self
+            .net_worth
+            .iter()
+            .map(|point| {
+                format!(
+                    "{{\"timestamp\":{},\"currency\":{},\"net_worth\":{}}}",
+                    point.timestamp.seconds(),
+                    json::string(point.currency.code()),
+                    point.net_worth
+                )
+            })
+            .collect();
+        format!(
+            "{{\"holder\":{},\"accounts\":[{}],\"balances\":{{{}}},\"timeline\":[{}],\"net_worth\":[{}]}}",
+            json::string(&self.holder),
+            accounts.join(","),
+            balances.join(","),
+            timeline.join(","),
+            net_worth.join(",")
+        )
+    }
+}
+
+impl fmt::Display for Portfolio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
+
+impl Bank {
+    /// Gathers every account whose holder is exactly `holder`.
+    pub fn holder_portfolio(&self, holder: &str) -> Portfolio {
+        let accounts: Vec<u32> = self
+            .sorted_account_ids()
+            .into_iter()
+            .filter(|id| self.accounts[id].holder == holder)
+            .collect();
+
+        let mut balances = BTreeMap::new();
+        let mut timeline = Vec::new();
+        for id in &accounts {
+            let account = &self.accounts[id];
+            let balance = balances.entry(account.currency).or_insert(0i64);
+            *balance = balance.saturating_add(account.balance);
+            timeline.extend(account.history.iter().cloned());
+        }
+        timeline.sort_by_key(|txn| (txn.timestamp, txn.id));
+
+        let mut latest: HashMap<u32, i64> = HashMap::new();
+        let mut net_worth = Vec::with_capacity(timeline.len());
+        for txn in &timeline {
+            latest.insert(txn.account_id, txn.balance_after);
+            let currency = self.accounts[&txn.account_id].currency;
+            let total = latest
+                .iter()
+                .filter(|(id, _)| self.accounts[id].currency == currency)
+                .fold(0i64, |total, (_, balance)| total.saturating_add(*balance));
+            net_worth.push(NetWorthPoint {
+                timestamp: txn.timestamp,
+                currency,
+                net_worth: total,
+            });
+        }
+
+        Portfolio {
+            holder: holder.to_string(),
+            accounts,
+            balances,
+            timeline,
+            net_worth,
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::eod::INTEREST_UNITS_PER_CENT;
+use crate::error::AccountError;
+use crate::fee_debt::FeeShortfall;
+use crate::import::BatchOp;
+use crate::interest::daily_accrual;
+use crate::time::{Date, Timestamp, SECONDS_PER_DAY};
+
+/// Money that moves in (positive) or out (negative) on the same day every
+/// month. Days past the end of a short month fall on its last day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecurringFlow {
+    pub day_of_month: u32,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectionAssumptions {
+    pub recurring: Vec<RecurringFlow>,
+    /// Replaces the account's flat rate; tiers still apply if configured.
+    pub interest_rate_bps: Option<u32>,
+}
+
+impl ProjectionAssumptions {
+    pub fn with_flow(mut self, day_of_month: u32, amount: i64) -> Self {
+        self.recurring.push(RecurringFlow {
+            day_of_month,
+            amount,
+        });
+        self
+    }
+
+    pub fn with_interest_rate_bps(mut self, rate: u32) -> Self {
+        self.interest_rate_bps = Some(rate);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectedMonth {
+    pub year: i32,
+    pub month: u32,
+    pub opening_balance: i64,
+    pub inflows: i64,
+    pub outflows: i64,
+    pub interest: i64,
+    pub fees: i64,
+    /// Outgoing flows and fees that the balance couldn't cover.
+    pub missed_payments: usize,
+    pub closing_balance: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Projection {
+    pub account_id: u32,
+    pub months: Vec<ProjectedMonth>,
+}
+
+impl Projection {
+    pub fn final_balance(&self) -> Option<i64> {
+        self.months.last().map(|month| month.closing_balance)
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dollars = |cents: i64| format!("{:.2}", cents as f64 / 100.0);
+        writeln!(f, "Projection for account {}", self.account_id)?;
+        write!(
+            f,
+            "{:<8} {:>12} {:>12} {:>12} {:>10} {:>10} {:>12}",
+            "Month", "Opening", "In", "Out", "Interest", "Fees", "Closing"
+        )?;
+        for month in &self.months {
+            write!(
+                f,
+                "\n{:04}-{:02}  {:>12} {:>12} {:>12} {:>10} {:>10} {:>12}",
+                month.year,
+                month.month,
+                dollars(month.opening_balance),
+                dollars(month.inflows),
+                dollars(month.outflows),
+                dollars(month.interest),
+                dollars(month.fees),
+                dollars(month.closing_balance)
+            )?;
+            if month.missed_payments > 0 {
+                write!(f, " ({} missed)", month.missed_payments)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What an account will have to spend by a point in time once the items
+/// already in flight settle. See [`BalanceProjection::projected`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceProjection {
+    pub account_id: u32,
+    pub until: Timestamp,
+    pub booked: i64,
+    /// Reserved by holds.
+    pub held: i64,
+    /// Cheques that clear by `until` and will be credited.
+    pub clearing: i64,
+    /// Cheques still uncleared at `until`, not counted.
+    pub uncleared: i64,
+    /// Scheduled withdrawals and outgoing transfers due by `until`, every
+    /// run of a standing order included.
+    pub scheduled_debits: i64,
+    /// Overdraft the holder can draw on.
+    pub overdraft: i64,
+}
+
+impl BalanceProjection {
+    /// The booked balance less holds and scheduled debits, plus cheques
+    /// clearing in time.
+    pub fn projected(&self) -> i64 {
+        self.booked - self.held + self.clearing - self.scheduled_debits
+    }
+
+    /// Whether a debit of `amount` would still be covered at `until`,
+    /// overdraft included.
+    pub fn can_afford(&self, amount: i64) -> bool {
+        self.projected() + self.overdraft >= amount
+    }
+}
+
+impl fmt::Display for BalanceProjection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dollars = |cents: i64| {
+            let sign = if cents < 0 { "-" } else { "" };
+            format!("{}${:.2}", sign, cents.unsigned_abs() as f64 / 100.0)
+        };
+        writeln!(
+            f,
+            "Balance projection for account {} until {}",
+            self.account_id, self.until
+        )?;
+        writeln!(f, "  Booked:            {:>12}", dollars(self.booked))?;
+        writeln!(f, "  Held:              {:>12}", dollars(-self.held))?;
+        writeln!(f, "  Cheques clearing:  {:>12}", dollars(self.clearing))?;
+        writeln!(
+            f,
+            "  Scheduled debits:  {:>12}",
+            dollars(-self.scheduled_debits)
+        )?;
+        write!(f, "  Projected:         {:>12}", dollars(self.projected()))?;
+        if self.uncleared > 0 {
+            write!(
+                f,
+                "\n  ({} in cheques clear later)",
+                dollars(self.uncleared)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Account {
+    /// Nets holds and cheques clearing by `until` against the booked
+    /// balance. The account can't see the bank's schedule, so
+    /// [`Bank::projected_balance`] adds scheduled debits.
+    pub fn projected_balance(&self, until: Timestamp) -> BalanceProjection {
+        let (clearing, uncleared) = self
+            .pending_cheques
+            .iter()
+            .partition::<Vec<_>, _>(|cheque| cheque.clears_at <= until);
+        BalanceProjection {
+            account_id: self.id,
+            until,
+            booked: self.balance,
+            held: self.holds.iter().map(|hold| hold.amount).sum(),
+            clearing: clearing.iter().map(|cheque| cheque.amount).sum(),
+            uncleared: uncleared.iter().map(|cheque| cheque.amount).sum(),
+            scheduled_debits: 0,
+            overdraft: self.spendable_balance() - self.available_balance(),
+        }
+    }
+}
+
+impl Bank {
+    /// The account's balance `days` days from now once holds, cheques
+    /// clearing by then and scheduled debits due by then are netted
+    /// against it. Use it to answer "can I afford this?".
+    pub fn projected_balance(
+        &self,
+        account: impl Into<AccountRef>,
+        days: u32,
+    ) -> Result<BalanceProjection, AccountError> {
+        let id = self.resolve(account)?;
+        let until = self.now.plus_days(days);
+        let mut projection = self.accounts[&id].projected_balance(until);
+        for operation in self
+            .scheduled_operations()
+            .take_while(|operation| operation.due <= until)
+        {
+            let amount = match operation.op {
+                BatchOp::Withdraw { account, amount } if account == id => amount,
+                BatchOp::Transfer { from, amount, .. } if from == id => amount,
+                _ => continue,
+            };
+            let runs = match operation.repeat_days {
+                Some(every) => {
+                    let interval = u64::from(every) * SECONDS_PER_DAY;
+                    1 + (until.seconds() - operation.due.seconds()) / interval
+                }
+                None => 1,
+            };
+            projection.scheduled_debits = projection
+                .scheduled_debits
+                .saturating_add(amount.saturating_mul(runs as i64));
+        }
+        Ok(projection)
+    }
+
+    /// Projects an account's balance month by month without touching the
+    /// bank. Each day applies the recurring flows, then posts interest as
+    /// end of day would, charging the monthly fee on the month's last day.
+    /// The first row covers the rest of the current month.
+    pub fn project(
+        &self,
+        account: impl Into<AccountRef>,
+        months: u32,
+        assumptions: &ProjectionAssumptions,
+    ) -> Result<Projection, AccountError> {
+        let id = self.resolve(account)?;
+        let account = &self.accounts[&id];
+        let rate = assumptions
+            .interest_rate_bps
+            .unwrap_or(account.interest_rate_bps);
+        let spendable_extra = account.spendable_balance() - account.balance;
+
+        let mut balance = account.balance;
+        let mut accrued = account.accrued_interest;
+        let mut day = self.now.date();
+        let mut projection = Projection {
+            account_id: id,
+            months: Vec::with_capacity(months as usize),
+        };
+        for _ in 0..months {
+            let mut row = ProjectedMonth {
+                year: day.year,
+                month: day.month,
+                opening_balance: balance,
+                inflows: 0,
+                outflows: 0,
+                interest: 0,
+                fees: 0,
+                missed_payments: 0,
+                closing_balance: balance,
+            };
+            while day.month == row.month {
+                let days_in_month = day.days_in_month();
+                for flow in &assumptions.recurring {
+                    if flow.day_of_month.clamp(1, days_in_month) != day.day {
+                        continue;
+                    }
+                    if flow.amount >= 0 {
+                        balance = balance.saturating_add(flow.amount);
+                        row.inflows += flow.amount;
+                    } else if balance + spendable_extra >= -flow.amount {
+                        balance += flow.amount;
+                        row.outflows -= flow.amount;
+                    } else {
+                        row.missed_payments += 1;
+                    }
+                }
+
+                accrued += daily_accrual(balance, rate, &account.interest_tiers);
+                let cents = account.rounding.divide(accrued, INTEREST_UNITS_PER_CENT) as i64;
+                if cents > 0 {
+                    balance = balance.saturating_add(cents);
+                    accrued -= i128::from(cents) * INTEREST_UNITS_PER_CENT;
+                    row.interest += cents;
+                }
+
+                if day.is_last_day_of_month() && account.monthly_fee > 0 {
+                    if balance >= account.monthly_fee
+                        || account.fee_shortfall == FeeShortfall::Overdraw
+                    {
+                        balance -= account.monthly_fee;
+                        row.fees += account.monthly_fee;
+                    } else {
+                        row.missed_payments += 1;
+                    }
+                }
+                day = Date::from_days_since_epoch(day.days_since_epoch() + 1);
+            }
+            row.closing_balance = balance;
+            projection.months.push(row);
+        }
+        Ok(projection)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::currency::CurrencyTotals;
+use crate::error::{AccountError, InputError, StateError};
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// A bonus paid once to every account that deposits at least `min_deposit`
+/// in one go within `within_days` days of opening, e.g. $50 for a $1,000
+/// deposit in the first 30 days.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Promotion {
+    pub id: u64,
+    pub name: String,
+    pub min_deposit: i64,
+    pub within_days: u32,
+    pub bonus: i64,
+}
+
+impl Promotion {
+    /// The first deposit on `account` that qualifies for this promotion.
+    fn qualifying_deposit(&self, account: &Account) -> Option<u64> {
+        let deadline = account.opened_at?.plus_days(self.within_days);
+        account
+            .history
+            .iter()
+            .take_while(|txn| txn.timestamp <= deadline)
+            .find(|txn| {
+                matches!(
+                    txn.kind,
+                    TransactionKind::Deposit | TransactionKind::ChequeDeposit
+                ) && txn.amount >= self.min_deposit
+            })
+            .map(|txn| txn.id)
+    }
+}
+
+/// A promotion an account qualified for and was paid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromotionAward {
+    pub promotion_id: u64,
+    /// The deposit that qualified.
+    pub deposit_transaction: u64,
+    /// The entry that credited the bonus.
+    pub transaction_id: u64,
+    pub amount: i64,
+    pub awarded_at: Timestamp,
+}
+
+impl Bank {
+    /// Pays promotion bonuses out of `account`. Without a marketing account
+    /// no bonuses are paid.
+    pub fn set_marketing_account(
+        &mut self,
+        account: impl Into<AccountRef>,
+    ) -> Result<(), AccountError> {
+        self.marketing_account = Some(self.resolve(account)?);
+        Ok(())
+    }
+
+    pub fn clear_marketing_account(&mut self) {
+        self.marketing_account = None;
+    }
+
+    pub fn marketing_account(&self) -> Option<u32> {
+        self.marketing_account
+    }
+
+    /// Adds a promotion checked at the end of every day. Accounts opened
+    /// before it was added qualify too if their deposits do.
+    pub fn add_promotion(
+        &mut self,
+        name: &str,
+        min_deposit: i64,
+        within_days: u32,
+        bonus: i64,
+    ) -> Result<u64, AccountError> {
+        if min_deposit < 0 || bonus < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        self.promotions.push(Promotion {
+            id,
+            name: name.to_string(),
+            min_deposit,
+            within_days,
+            bonus,
+        });
+        Ok(id)
+    }
+
+    /// Stops a promotion. Bonuses already paid stay paid.
+    pub fn remove_promotion(&mut self, promotion_id: u64) -> Result<Promotion, AccountError> {
+        let index = self
+            .promotions
+            .iter()
+            .position(|promotion| promotion.id == promotion_id)
+            .ok_or(AccountError::State(StateError::PromotionNotFound))?;
+        Ok(self.promotions.remove(index))
+    }
+
+    pub fn promotions(&self) -> &[Promotion] {
+        &self.promotions
+    }
+
+    /// The promotions an account qualified for, in the order they were paid.
+    pub fn promotion_awards(&self, account_id: u32) -> &[PromotionAward] {
+        self.accounts
+            .get(&account_id)
+            .map(|account| account.promotion_awards.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Pays every bonus that is due, in account id order, while the marketing
+    /// account's available balance covers it. Bonuses it cannot cover yet are
+    /// paid on a later day. Returns how many were paid and the total.
+    pub(crate) fn pay_promotions(&mut self) -> (usize, CurrencyTotals) {
+        let mut paid = (0, CurrencyTotals::new());
+        let Some(marketing_id) = self.marketing_account else {
+            return paid;
+        };
+        for id in self.sorted_account_ids() {
+            if id == marketing_id {
+                continue;
+            }
+            for index in 0..self.promotions.len() {
+                let promotion = &self.promotions[index];
+                let account = &self.accounts[&id];
+                if account
+                    .promotion_awards
+                    .iter()
+                    .any(|award| award.promotion_id == promotion.id)
+                {
+                    continue;
+                }
+                let Some(deposit_transaction) = promotion.qualifying_deposit(account) else {
+                    continue;
+                };
+                let (promotion_id, bonus) = (promotion.id, promotion.bonus);
+                let memo = format!("Promotion: {}", promotion.name);
+                let [Some(marketing), Some(account)] =
+                    self.accounts.get_disjoint_mut([&marketing_id, &id])
+                else {
+                    return paid;
+                };
+                if marketing.available_balance() < bonus {
+                    continue;
+                }
+                let Some(new_balance) = account.balance.checked_add(bonus) else {
+                    continue;
+                };
+                account.balance = new_balance;
+                marketing.balance -= bonus;
+                self.record(
+                    marketing_id,
+                    TransactionKind::TransferOut,
+                    -bonus,
+                    Some(id),
+                    &memo,
+                );
+                let transaction_id = self.record(
+                    id,
+                    TransactionKind::TransferIn,
+                    bonus,
+                    Some(marketing_id),
+                    &memo,
+                );
+                let awarded_at = self.now;
+                self.accounts
+                    .get_mut(&id)
+                    .unwrap()
+                    .promotion_awards
+                    .push(PromotionAward {
+                        promotion_id,
+                        deposit_transaction,
+                        transaction_id,
+                        amount: bonus,
+                        awarded_at,
+                    });
+                paid.0 += 1;
+                paid.1.add(self.accounts[&id].currency, bonus);
+            }
+        }
+        paid
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::json;
+use crate::outcome::Warning;
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// What a deposit or withdrawal booked: enough to show the holder a
+/// confirmation without looking anything else up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub account_id: u32,
+    /// `None` when nothing was booked, e.g. for an ignored zero amount.
+    pub transaction_id: Option<u64>,
+    pub timestamp: Timestamp,
+    /// Signed as in the history: negative for money going out.
+    pub amount: i64,
+    /// The balance once everything the operation booked is applied.
+    pub balance: i64,
+    /// Fees and taxes booked on the account along with the entry.
+    pub fees: i64,
+    pub warnings: Vec<Warning>,
+}
+
+impl Receipt {
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    /// The receipt as a JSON object, the timestamp in Unix seconds and
+    /// `transaction_id` null when nothing was booked.
+    pub fn to_json(&self) -> String {
+        let warnings: Vec<String> = self
+            .warnings
+            .iter()
+            .map(|warning| json::string(&warning.to_string()))
+            .collect();
+        format!(
+            "{{\"account_id\":{},\"transaction_id\":{},\"timestamp\":{},\"amount\":{},\"balance\":{},\"fees\":{},\"warnings\":[{}]}}",
+            self.account_id,
+            self.transaction_id
+                .map_or_else(|| "null".to_string(), |id| id.to_string()),
+            self.timestamp.seconds(),
+            self.amount,
+            self.balance,
+            self.fees,
+            warnings.join(",")
+        )
+    }
+}
+
+impl fmt::Display for Receipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.transaction_id {
+            Some(id) => write!(
+                f,
+                "Transaction {} on account {} at {}: {}${:.2}",
+                id,
+                self.account_id,
+                self.timestamp,
+                if self.amount < 0 { "-" } else { "+" },
+                self.amount.unsigned_abs() as f64 / 100.0
+            )?,
+            None => write!(
+                f,
+                "Nothing booked on account {} at {}",
+                self.account_id, self.timestamp
+            )?,
+        }
+        if self.fees != 0 {
+            write!(f, ", fees ${:.2}", self.fees as f64 / 100.0)?;
+        }
+        write!(f, ", balance ${:.2}", self.balance as f64 / 100.0)?;
+        for warning in &self.warnings {
+            write!(f, "\n  warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+impl Bank {
+    /// The receipt for `transaction_id`, just booked on `account_id`. Fee
+    /// entries and taxes booked after it count towards its fees.
+    pub(crate) fn receipt(
+        &self,
+        account_id: u32,
+        transaction_id: Option<u64>,
+        warnings: Vec<Warning>,
+    ) -> Receipt {
+        let account = &self.accounts[&account_id];
+        let mut receipt = Receipt {
+            account_id,
+            transaction_id,
+            timestamp: self.now,
+            amount: 0,
+            balance: account.balance,
+            fees: 0,
+            warnings,
+        };
+        let booked = transaction_id.and_then(|id| {
+            let index = account.history.iter().rposition(|txn| txn.id == id)?;
+            Some(&account.history[index..])
+        });
+        if let Some([entry, rest @ ..]) = booked {
+            receipt.timestamp = entry.timestamp;
+            receipt.amount = entry.amount;
+            receipt.fees = rest
+                .iter()
+                .filter(|txn| txn.kind == TransactionKind::Fee || txn.metadata.contains_key("tax"))
+                .map(|txn| -txn.amount)
+                .sum();
+        }
+        receipt
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::error::AccountError;
+use crate::receipt::Receipt;
+use crate::transaction::{Transaction, TransactionKind};
+
+/// The metadata key a deposit's payment reference is stored under.
+pub(crate) const PAYMENT_REFERENCE: &str = "payment_reference";
+
+/// A payment the bank expects to receive, such as an open invoice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invoice {
+    pub id: String,
+    /// The account the payment should arrive in.
+    pub account_id: u32,
+    pub amount: i64,
+    /// The reference the payer was asked to quote, if any.
+    pub reference: Option<String>,
+}
+
+impl Invoice {
+    pub fn new(id: &str, account_id: u32, amount: i64) -> Self {
+        Invoice {
+            id: id.to_string(),
+            account_id,
+            amount,
+            reference: None,
+        }
+    }
+
+    pub fn reference(mut self, reference: &str) -> Self {
+        self.reference = Some(reference.to_string());
+        self
+    }
+}
+
+/// How an invoice was paired with a deposit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// The deposit quoted the invoice's reference.
+    Reference,
+    /// No deposit quoted the reference; one of a close enough amount was
+    /// taken instead.
+    Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentMatch {
+    pub invoice_id: String,
+    pub transaction_id: u64,
+    pub kind: MatchKind,
+    /// Received minus expected, within the tolerance.
+    pub difference: i64,
+}
+
+/// The result of [`Bank::reconcile`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconciliationReport {
+    pub matched: Vec<PaymentMatch>,
+    pub unmatched_invoices: Vec<Invoice>,
+    /// Deposits into the invoiced accounts that paid no invoice, by
+    /// transaction id.
+    pub unmatched_deposits: Vec<u64>,
+}
+
+impl fmt::Display for ReconciliationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Matched {} invoices; {} invoices and {} deposits unmatched",
+            self.matched.len(),
+            self.unmatched_invoices.len(),
+            self.unmatched_deposits.len()
+        )?;
+        for found in &self.matched {
+            let by = match found.kind {
+                MatchKind::Reference => "reference",
+                MatchKind::Amount => "amount",
+            };
+            writeln!(
+                f,
+                "  {} <- transaction {} by {} ({:+})",
+                found.invoice_id, found.transaction_id, by, found.difference
+            )?;
+        }
+        for invoice in &self.unmatched_invoices {
+            writeln!(f, "  {} unpaid ({})", invoice.id, invoice.amount)?;
+        }
+        for txn_id in &self.unmatched_deposits {
+            writeln!(f, "  transaction {} pays no invoice", txn_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl Transaction {
+    /// The payment reference quoted with a deposit, if any.
+    pub fn payment_reference(&self) -> Option<&str> {
+        self.metadata.get(PAYMENT_REFERENCE).map(String::as_str)
+    }
+}
+
+/// Payers retype references by hand, so case and spacing are ignored.
+fn same_reference(a: &str, b: &str) -> bool {
+    let normalize = |reference: &str| -> String {
+        reference
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    normalize(a) == normalize(b)
+}
+
+impl Bank {
+    /// Deposits `amount`, recording the payment reference the payer quoted.
+    pub fn deposit_with_reference(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        reference: &str,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        let receipt = self.deposit(id, amount)?;
+        if let Some(txn_id) = receipt.transaction_id {
+            self.amend_transaction(id, txn_id, |txn| {
+                txn.metadata
+                    .insert(PAYMENT_REFERENCE.to_string(), reference.to_string());
+            });
+        }
+        Ok(receipt)
+    }
+
+    /// Pairs each invoice with a deposit into its account. Deposits quoting
+    /// the invoice's reference are taken first; the rest are paired by
+    /// amount, preferring the closest and then the earliest. Either way the
+    /// amount may differ from the invoice by at most `tolerance` cents, and
+    /// each deposit pays at most one invoice.
+    pub fn reconcile(&self, invoices: &[Invoice], tolerance: i64) -> ReconciliationReport {
+        let deposits: Vec<&Transaction> = self
+            .sorted_account_ids()
+            .into_iter()
+            .filter(|id| invoices.iter().any(|invoice| invoice.account_id == *id))
+            .flat_map(|id| self.accounts[&id].history.iter())
+            .filter(|txn| {
+                matches!(
+                    txn.kind,
+                    TransactionKind::Deposit | TransactionKind::ChequeDeposit
+                )
+            })
+            .collect();
+        let mut used: HashSet<u64> = HashSet::new();
+        let mut found: Vec<Option<PaymentMatch>> = vec![None; invoices.len()];
+
+        for (slot, invoice) in found.iter_mut().zip(invoices) {
+            let Some(reference) = &invoice.reference else {
+                continue;
+            };
+            let paying = deposits.iter().find(|txn| {
+                txn.account_id == invoice.account_id
+                    && !used.contains(&txn.id)
+                    && (txn.amount - invoice.amount).abs() <= tolerance
+                    && txn
+                        .payment_reference()
+                        .is_some_and(|quoted| same_reference(quoted, reference))
+            });
+            if let Some(txn) = paying {
+                used.insert(txn.id);
+                *slot = Some(PaymentMatch {
+                    invoice_id: invoice.id.clone(),
+                    transaction_id: txn.id,
+                    kind: MatchKind::Reference,
+                    difference: txn.amount - invoice.amount,
+                });
+            }
+        }
+        for (slot, invoice) in found.iter_mut().zip(invoices) {
+            if slot.is_some() {
+                continue;
+            }
+            let paying = deposits
+                .iter()
+                .filter(|txn| {
+                    txn.account_id == invoice.account_id
+                        && !used.contains(&txn.id)
+                        && (txn.amount - invoice.amount).abs() <= tolerance
+                })
+                .min_by_key(|txn| (txn.amount - invoice.amount).abs());
+            if let Some(txn) = paying {
+                used.insert(txn.id);
+                *slot = Some(PaymentMatch {
+                    invoice_id: invoice.id.clone(),
+                    transaction_id: txn.id,
+                    kind: MatchKind::Amount,
+                    difference: txn.amount - invoice.amount,
+                });
+            }
+        }
+
+        let mut report = ReconciliationReport::default();
+        for (slot, invoice) in found.into_iter().zip(invoices) {
+            match slot {
+                Some(found) => report.matched.push(found),
+                None => report.unmatched_invoices.push(invoice.clone()),
+            }
+        }
+        report.unmatched_deposits = deposits
+            .iter()
+            .filter(|txn| !used.contains(&txn.id))
+            .map(|txn| txn.id)
+            .collect();
+        report
+    }
+}
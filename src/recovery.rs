@@ -0,0 +1,333 @@
+use std::fmt;
+
+use crate::currency::Currency;
+use crate::error::{AccountError, InputError, PolicyError, StateError};
+use crate::locale::Locale;
+
+/// A machine-readable next step for recovering from an [`AccountError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remediation {
+    /// Deposit at least `shortfall` more, or opt into overdraft coverage if
+    /// `overdraft_could_cover` is set.
+    AddFunds {
+        shortfall: i64,
+        overdraft_could_cover: bool,
+        currency: Currency,
+    },
+    UsePositiveAmount,
+    UseSmallerAmount,
+    CheckAccountId,
+    UseUnusedAccountId,
+    CheckItemId,
+    EnrollInRewards,
+    RedeemLess,
+    ChooseDifferentAccounts,
+    UseRateInRange {
+        min_bps: u32,
+        max_bps: u32,
+    },
+    /// Check the earlier transfer, then resend with the duplicate override
+    /// if the payment really is meant to go twice.
+    ConfirmDuplicate {
+        previous_transaction: u64,
+    },
+    ProvideAuditDetails,
+    RegisterPriceSource,
+    DisputeDepositsOnly,
+    ContactBank,
+    ReactivateAccount,
+    /// Send no more than `remaining` to this payee today.
+    StayWithinPayeeLimit {
+        remaining: i64,
+        currency: Currency,
+    },
+    /// Have the co-signer approve pending withdrawal `request_id`.
+    AwaitCoSigner {
+        request_id: u64,
+    },
+    ApproveAsCoSigner,
+    /// Spend no more than `remaining` from the minor's account today.
+    StayWithinSpendingCap {
+        remaining: i64,
+        currency: Currency,
+    },
+    ActAsGuardian,
+    /// Overdraw the holder's accounts by at most `headroom` more in total.
+    StayWithinExposureLimit {
+        headroom: i64,
+        currency: Currency,
+    },
+    UseValidHolderName,
+    UseNonEmptyTag,
+    /// Attach a document of at most `limit` bytes.
+    ShrinkDocument {
+        limit: usize,
+    },
+    CheckHolderName,
+    ChooseSameCurrency,
+    AwaitOwnershipApproval {
+        request_id: u64,
+    },
+    ApproveAsOwnershipParty,
+    CheckStorage,
+}
+
+impl Remediation {
+    /// A stable identifier for UIs to key their own messages off.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Remediation::AddFunds { .. } => "add_funds",
+            Remediation::UsePositiveAmount => "use_positive_amount",
+            Remediation::UseSmallerAmount => "use_smaller_amount",
+            Remediation::CheckAccountId => "check_account_id",
+            Remediation::UseUnusedAccountId => "use_unused_account_id",
+            Remediation::CheckItemId => "check_item_id",
+            Remediation::EnrollInRewards => "enroll_in_rewards",
+            Remediation::RedeemLess => "redeem_less",
+            Remediation::ChooseDifferentAccounts => "choose_different_accounts",
+            Remediation::UseRateInRange { .. } => "use_rate_in_range",
+            Remediation::ConfirmDuplicate { .. } => "confirm_duplicate",
+            Remediation::ProvideAuditDetails => "provide_audit_details",
+            Remediation::RegisterPriceSource => "register_price_source",
+            Remediation::DisputeDepositsOnly => "dispute_deposits_only",
+            Remediation::ContactBank => "contact_bank",
+            Remediation::ReactivateAccount => "reactivate_account",
+            Remediation::StayWithinPayeeLimit { .. } => "stay_within_payee_limit",
+            Remediation::AwaitCoSigner { .. } => "await_co_signer",
+            Remediation::ApproveAsCoSigner => "approve_as_co_signer",
+            Remediation::StayWithinSpendingCap { .. } => "stay_within_spending_cap",
+            Remediation::ActAsGuardian => "act_as_guardian",
+            Remediation::StayWithinExposureLimit { .. } => "stay_within_exposure_limit",
+            Remediation::UseValidHolderName => "use_valid_holder_name",
+            Remediation::UseNonEmptyTag => "use_non_empty_tag",
+            Remediation::ShrinkDocument { .. } => "shrink_document",
+            Remediation::CheckHolderName => "check_holder_name",
+            Remediation::ChooseSameCurrency => "choose_same_currency",
+            Remediation::AwaitOwnershipApproval { .. } => "await_ownership_approval",
+            Remediation::ApproveAsOwnershipParty => "approve_as_ownership_party",
+            Remediation::CheckStorage => "check_storage",
+        }
+    }
+}
+
+impl fmt::Display for Remediation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Remediation::AddFunds {
+                shortfall,
+                overdraft_could_cover,
+                currency,
+            } => {
+                write!(
+                    f,
+                    "Deposit at least {} more",
+                    Locale::English.money_in(*shortfall, *currency)
+                )?;
+                if *overdraft_could_cover {
+                    write!(f, " or opt into overdraft coverage")?;
+                }
+                Ok(())
+            }
+            Remediation::UsePositiveAmount => write!(f, "Enter a positive amount"),
+            Remediation::UseSmallerAmount => write!(f, "Use a smaller amount"),
+            Remediation::CheckAccountId => write!(f, "Check the account id or reference"),
+            Remediation::UseUnusedAccountId => {
+                write!(f, "Use an account id that is not taken yet")
+            }
+            Remediation::CheckItemId => write!(
+                f,
+                "Check the id of the hold, order, goal, rule or transaction"
+            ),
+            Remediation::EnrollInRewards => write!(f, "Enroll the account in a rewards program"),
+            Remediation::RedeemLess => write!(f, "Redeem no more than the rewards balance"),
+            Remediation::ChooseDifferentAccounts => {
+                write!(f, "Choose two different accounts")
+            }
+            Remediation::UseRateInRange { min_bps, max_bps } => write!(
+                f,
+                "Use a rate between {} and {} basis points",
+                min_bps, max_bps
+            ),
+            Remediation::ConfirmDuplicate {
+                previous_transaction,
+            } => write!(
+                f,
+                "Check transaction #{} and confirm if this payment is intended",
+                previous_transaction
+            ),
+            Remediation::ProvideAuditDetails => {
+                write!(
+                    f,
+                    "Give a reason and the name of whoever is making the change"
+                )
+            }
+            Remediation::RegisterPriceSource => {
+                write!(f, "Register a price source that quotes the fund")
+            }
+            Remediation::DisputeDepositsOnly => {
+                write!(f, "Dispute a deposit that has no dispute yet")
+            }
+            Remediation::ContactBank => write!(f, "Contact the bank to unlock the account"),
+            Remediation::ReactivateAccount => {
+                write!(f, "Ask the bank to reactivate the dormant account")
+            }
+            Remediation::StayWithinPayeeLimit {
+                remaining,
+                currency,
+            } => write!(
+                f,
+                "Send at most {} to this payee today",
+                Locale::English.money_in(*remaining, *currency)
+            ),
+            Remediation::AwaitCoSigner { request_id } => write!(
+                f,
+                "Ask the co-signer to approve withdrawal request #{}",
+                request_id
+            ),
+            Remediation::ApproveAsCoSigner => {
+                write!(f, "Approve as the co-signer named on the account")
+            }
+            Remediation::StayWithinSpendingCap {
+                remaining,
+                currency,
+            } => write!(
+                f,
+                "Spend at most {} from this account today",
+                Locale::English.money_in(*remaining, *currency)
+            ),
+            Remediation::ActAsGuardian => write!(f, "Have the account's guardian do this"),
+            Remediation::StayWithinExposureLimit { headroom, currency } => write!(
+                f,
+                "Overdraw the holder's accounts by at most {} more",
+                Locale::English.money_in(*headroom, *currency)
+            ),
+            Remediation::UseValidHolderName => {
+                write!(f, "Use a holder name the bank's validation accepts")
+            }
+            Remediation::UseNonEmptyTag => write!(f, "Give the tag or category some text"),
+            Remediation::ShrinkDocument { limit } => {
+                write!(f, "Attach a document of at most {} bytes", limit)
+            }
+            Remediation::CheckHolderName => {
+                write!(f, "Check the holder's name as it appears on their accounts")
+            }
+            Remediation::ChooseSameCurrency => {
+                write!(f, "Choose accounts held in the same currency")
+            }
+            Remediation::AwaitOwnershipApproval { request_id } => write!(
+                f,
+                "Ask both holders to approve ownership transfer #{}",
+                request_id
+            ),
+            Remediation::ApproveAsOwnershipParty => {
+                write!(f, "Approve as the current or the new holder")
+            }
+            Remediation::CheckStorage => {
+                write!(f, "Check the bank's storage and try again")
+            }
+        }
+    }
+}
+
+impl AccountError {
+    pub fn suggest(&self) -> Remediation {
+        match self {
+            AccountError::Validation(error) => match error {
+                InputError::NegativeAmount | InputError::ZeroAmount => {
+                    Remediation::UsePositiveAmount
+                }
+                InputError::AmountOverflow => Remediation::UseSmallerAmount,
+                InputError::SameAccount => Remediation::ChooseDifferentAccounts,
+                InputError::InvalidRate => Remediation::UseRateInRange {
+                    min_bps: 1,
+                    max_bps: 10_000,
+                },
+                InputError::MissingAuditDetails => Remediation::ProvideAuditDetails,
+                InputError::InvalidHolder(_) => Remediation::UseValidHolderName,
+                InputError::EmptyTag => Remediation::UseNonEmptyTag,
+                InputError::MixedCurrencies => Remediation::ChooseSameCurrency,
+                InputError::DocumentTooLarge { limit, .. } => {
+                    Remediation::ShrinkDocument { limit: *limit }
+                }
+            },
+            AccountError::Policy(error) => match error {
+                PolicyError::InsufficientFunds {
+                    requested,
+                    available,
+                    unused_overdraft,
+                    currency,
+                } => {
+                    let shortfall = requested - available;
+                    Remediation::AddFunds {
+                        shortfall,
+                        overdraft_could_cover: *unused_overdraft >= shortfall,
+                        currency: *currency,
+                    }
+                }
+                PolicyError::PossibleDuplicate {
+                    previous_transaction,
+                } => Remediation::ConfirmDuplicate {
+                    previous_transaction: *previous_transaction,
+                },
+                PolicyError::AccountLocked => Remediation::ContactBank,
+                PolicyError::AccountDormant => Remediation::ReactivateAccount,
+                PolicyError::PayeeLimitExceeded {
+                    remaining,
+                    currency,
+                } => Remediation::StayWithinPayeeLimit {
+                    remaining: *remaining,
+                    currency: *currency,
+                },
+                PolicyError::CoSignatureRequired { request_id } => Remediation::AwaitCoSigner {
+                    request_id: *request_id,
+                },
+                PolicyError::WrongCoSigner => Remediation::ApproveAsCoSigner,
+                PolicyError::SpendingCapExceeded {
+                    remaining,
+                    currency,
+                } => Remediation::StayWithinSpendingCap {
+                    remaining: *remaining,
+                    currency: *currency,
+                },
+                PolicyError::NotGuardian => Remediation::ActAsGuardian,
+                PolicyError::ExposureLimitExceeded {
+                    exposure,
+                    limit,
+                    currency,
+                    ..
+                } => Remediation::StayWithinExposureLimit {
+                    headroom: (limit - exposure).max(0),
+                    currency: *currency,
+                },
+                PolicyError::OwnershipApprovalRequired { request_id } => {
+                    Remediation::AwaitOwnershipApproval {
+                        request_id: *request_id,
+                    }
+                }
+                PolicyError::NotOwnershipParty => Remediation::ApproveAsOwnershipParty,
+            },
+            AccountError::State(error) => match error {
+                StateError::AccountNotFound => Remediation::CheckAccountId,
+                StateError::AccountExists => Remediation::UseUnusedAccountId,
+                StateError::HoldNotFound
+                | StateError::DeductionOrderNotFound
+                | StateError::GoalNotFound
+                | StateError::SweepRuleNotFound
+                | StateError::PromotionNotFound
+                | StateError::TransactionNotFound
+                | StateError::DisputeNotFound
+                | StateError::WithdrawalRequestNotFound
+                | StateError::FxOrderNotFound
+                | StateError::ScheduledOperationNotFound
+                | StateError::OwnershipTransferNotFound
+                | StateError::DocumentNotFound => Remediation::CheckItemId,
+                StateError::HolderNotFound => Remediation::CheckHolderName,
+                StateError::NotEnrolledInRewards => Remediation::EnrollInRewards,
+                StateError::InsufficientRewards => Remediation::RedeemLess,
+                StateError::PriceUnavailable => Remediation::RegisterPriceSource,
+                StateError::NotDisputable => Remediation::DisputeDepositsOnly,
+            },
+            AccountError::Storage(_) => Remediation::CheckStorage,
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::chain::reseal;
+use crate::error::{AccountError, InputError};
+use crate::overflow::PROMOTED_KEY;
+use crate::remainders::RoundingSource;
+use crate::rounding::RoundingPolicy;
+use crate::transaction::TransactionKind;
+
+/// How one account's balance came through a redenomination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedenominationLine {
+    pub account_id: u32,
+    pub old_balance: i64,
+    pub new_balance: i64,
+    /// What rounding gained or lost, in old minor units:
+    /// `old_balance - new_balance * factor`.
+    pub remainder: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedenominationReport {
+    pub factor: i64,
+    pub rounding: RoundingPolicy,
+    pub lines: Vec<RedenominationLine>,
+}
+
+impl RedenominationReport {
+    pub fn total_remainder(&self) -> i64 {
+        self.lines.iter().map(|line| line.remainder).sum()
+    }
+}
+
+impl fmt::Display for RedenominationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Redenominated {} accounts at {}:1 ({})",
+            self.lines.len(),
+            self.factor,
+            self.rounding
+        )?;
+        for line in &self.lines {
+            writeln!(
+                f,
+                "  Account {}: {} -> {} (remainder {})",
+                line.account_id, line.old_balance, line.new_balance, line.remainder
+            )?;
+        }
+        write!(f, "Total remainder: {}", self.total_remainder())
+    }
+}
+
+impl Bank {
+    /// Converts every amount in the bank to a new unit worth `factor` old
+    /// ones, e.g. 1000 for a 1000:1 redenomination.
+    ///
+    /// Balances are rounded with `rounding`, and each history is rewritten
+    /// so its running balances stay consistent in the new unit. Every
+    /// account also gets a zero-amount redenomination entry recording the
+    /// old balance and the rounding remainder.
+    pub fn redenominate(
+        &mut self,
+        factor: i64,
+        rounding: RoundingPolicy,
+    ) -> Result<RedenominationReport, AccountError> {
+        if factor <= 0 {
+            return Err(AccountError::Validation(InputError::InvalidRate));
+        }
+        let convert = |amount: i64| rounding.divide(i128::from(amount), i128::from(factor)) as i64;
+
+        let mut report = RedenominationReport {
+            factor,
+            rounding,
+            lines: Vec::new(),
+        };
+        for id in self.sorted_account_ids() {
+            let account = self.accounts.get_mut(&id).unwrap();
+            let old_balance = account.balance;
+            let old_exact = account.exact_balance();
+            account.set_exact_balance(rounding.divide(old_exact, i128::from(factor)));
+            account.minimum_balance = account.minimum_balance.map(convert);
+            account.withdrawal_total = convert(account.withdrawal_total);
+            account.accrued_interest =
+                rounding.divide(account.accrued_interest, i128::from(factor));
+            account.overdraft_accrual.deferred =
+                rounding.divide(account.overdraft_accrual.deferred, i128::from(factor));
+            account.overdraft_accrual.accrued =
+                rounding.divide(account.overdraft_accrual.accrued, i128::from(factor));
+            account.monthly_fee = convert(account.monthly_fee);
+            account.overdraft_limit = convert(account.overdraft_limit);
+            account.fee_debt = convert(account.fee_debt).min(account.overdrawn());
+            for tier in &mut account.interest_tiers {
+                tier.up_to = tier.up_to.map(convert);
+            }
+            if let Some(co_signer) = &mut account.co_signer {
+                co_signer.threshold = convert(co_signer.threshold);
+            }
+            if let Some(guardianship) = &mut account.guardianship {
+                guardianship.daily_spending_cap = guardianship.daily_spending_cap.map(convert);
+                guardianship.approval_threshold = guardianship.approval_threshold.map(convert);
+            }
+            for request in &mut account.pending_withdrawals {
+                request.amount = convert(request.amount);
+            }
+            for hold in &mut account.holds {
+                hold.amount = convert(hold.amount);
+            }
+            for cheque in &mut account.pending_cheques {
+                cheque.amount = convert(cheque.amount);
+            }
+            if let Some(holding) = &mut account.units {
+                holding.unit_price = convert(holding.unit_price);
+            }
+            for dispute in &mut account.disputes {
+                dispute.amount = convert(dispute.amount);
+            }
+            for award in &mut account.promotion_awards {
+                award.amount = convert(award.amount);
+            }
+            for goal in &mut account.goals {
+                goal.target = convert(goal.target);
+                for (_, amount) in &mut goal.contributions {
+                    *amount = convert(*amount);
+                }
+            }
+            // Convert running balances and derive amounts from them, so the
+            // history still adds up to the converted balance.
+            let convert_exact = |amount: i128| rounding.divide(amount, i128::from(factor));
+            let mut previous = account
+                .history
+                .first()
+                .map(|txn| convert_exact(txn.exact_balance_after() - i128::from(txn.amount)));
+            for txn in &mut account.history {
+                let exact_after = convert_exact(txn.exact_balance_after());
+                let balance_after = exact_after.min(i128::from(i64::MAX)) as i64;
+                txn.amount = (exact_after - previous.unwrap_or(0)) as i64;
+                txn.balance_after = balance_after;
+                match exact_after - i128::from(balance_after) {
+                    0 => txn.metadata.remove(PROMOTED_KEY),
+                    promoted => txn
+                        .metadata
+                        .insert(PROMOTED_KEY.to_string(), promoted.to_string()),
+                };
+                previous = Some(exact_after);
+            }
+            reseal(&mut account.history, 0);
+
+            let line = RedenominationLine {
+                account_id: id,
+                old_balance,
+                new_balance: account.balance,
+                remainder: (old_exact - account.exact_balance() * i128::from(factor)) as i64,
+            };
+            let memo = format!("Redenominated at {}:1", factor);
+            let txn_id = self.record(id, TransactionKind::Redenomination, 0, None, &memo);
+            self.amend_transaction(id, txn_id, |txn| {
+                txn.metadata
+                    .insert("old_balance".to_string(), old_balance.to_string());
+                txn.metadata
+                    .insert("remainder".to_string(), line.remainder.to_string());
+            });
+            report.lines.push(line);
+        }
+
+        for rewards in self.rewards.values_mut() {
+            rewards.cashback_balance = convert(rewards.cashback_balance);
+            rewards.cashback_remainder =
+                rounding.divide(rewards.cashback_remainder, i128::from(factor));
+            for entry in &mut rewards.entries {
+                entry.cashback = convert(entry.cashback);
+            }
+        }
+        if let Some(limits) = &mut self.payee_limits {
+            for stage in &mut limits.stages {
+                stage.daily_limit = convert(stage.daily_limit);
+            }
+        }
+        if let Some(limit) = &mut self.holder_exposure_limit {
+            *limit = convert(*limit);
+        }
+        if let Some(policy) = &mut self.dormancy_policy {
+            policy.monthly_fee = convert(policy.monthly_fee);
+        }
+        let threshold = self.large_withdrawal_threshold().map(convert);
+        self.set_large_withdrawal_threshold(threshold);
+        // A part of an old unit is a `factor`-th as big a part of a new one,
+        // so carried remainders stay exact. What was booked is in the
+        // rounding accounts' converted balances; the lines start over.
+        self.remainders = std::mem::take(&mut self.remainders)
+            .into_values()
+            .map(|mut line| {
+                line.denominator *= i128::from(factor);
+                line.total = line.carried;
+                line.posted = 0;
+                ((line.currency, line.source, line.denominator), line)
+            })
+            .collect();
+        for promotion in &mut self.promotions {
+            promotion.min_deposit = convert(promotion.min_deposit);
+            promotion.bonus = convert(promotion.bonus);
+        }
+        for rule in &mut self.sweep_rules {
+            rule.threshold = convert(rule.threshold);
+        }
+        for operation in self.scheduler.iter_mut() {
+            let amount = operation.op.amount_mut();
+            *amount = convert(*amount);
+        }
+        for order in &mut self.fx_orders {
+            order.amount = convert(order.amount);
+        }
+        for order in &mut self.deduction_orders {
+            order.target = convert(order.target);
+            order.collected = convert(order.collected);
+        }
+        for line in &report.lines {
+            let currency = self.accounts[&line.account_id].currency;
+            self.track_remainder(
+                currency,
+                RoundingSource::Redenomination,
+                i128::from(line.remainder),
+                i128::from(factor),
+            );
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Account;
+    use crate::dormancy::DormancyPolicy;
+    use crate::import::BatchOp;
+
+    #[test]
+    fn standing_orders_move_new_units_after_a_redenomination() {
+        let mut bank = Bank::new();
+        bank.add_account(Account::new(1, "Ann".to_string()));
+        bank.add_account(Account::new(2, "Bob".to_string()));
+        bank.deposit(1, 1_000_000).unwrap();
+        let due = bank.now().plus_days(1);
+        let order = bank
+            .schedule_every(
+                due,
+                30,
+                BatchOp::Transfer {
+                    from: 1,
+                    to: 2,
+                    amount: 100_000,
+                },
+            )
+            .unwrap();
+
+        bank.redenominate(1_000, RoundingPolicy::HalfUp).unwrap();
+
+        assert_eq!(
+            bank.scheduled_operation(order).unwrap().op,
+            BatchOp::Transfer {
+                from: 1,
+                to: 2,
+                amount: 100,
+            }
+        );
+        bank.advance_time(std::time::Duration::from_secs(2 * 86_400));
+        assert_eq!(bank.get_account(2).unwrap().balance(), 100);
+        assert_eq!(bank.get_account(1).unwrap().balance(), 900);
+    }
+
+    #[test]
+    fn bank_wide_amounts_are_rescaled_too() {
+        let mut bank = Bank::new();
+        bank.set_dormancy_policy(Some(DormancyPolicy::after_months(12).with_fee(5_000)));
+        bank.set_large_withdrawal_threshold(Some(100_000));
+        bank.set_holder_exposure_limit(Some(250_000));
+
+        bank.redenominate(1_000, RoundingPolicy::HalfUp).unwrap();
+
+        assert_eq!(bank.dormancy_policy().unwrap().monthly_fee, 5);
+        assert_eq!(bank.large_withdrawal_threshold(), Some(100));
+        assert_eq!(bank.holder_exposure_limit(), Some(250));
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::duplicates::DuplicateCheck;
+use crate::eod::{EndOfDayConfig, EndOfDayReport};
+use crate::error::{AccountError, InputError};
+use crate::locale::Locale;
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// Settings applied to one tenant's bank.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TenantConfig {
+    pub locale: Locale,
+    pub duplicate_check: Option<DuplicateCheck>,
+    pub end_of_day: EndOfDayConfig,
+    /// Whether this tenant may send or receive settlements at all.
+    pub external_transfers: bool,
+}
+
+/// Money that left one tenant's bank for another's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settlement {
+    pub id: u64,
+    pub from_tenant: String,
+    pub from_account: u32,
+    pub to_tenant: String,
+    pub to_account: u32,
+    pub amount: i64,
+    pub timestamp: Timestamp,
+    /// The [`NetSettlement`] that settled this between the two banks, once
+    /// netting has run.
+    pub netted_in: Option<u64>,
+}
+
+/// The one payment that settles every pending settlement between two
+/// tenants: what flowed one way less what flowed the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetSettlement {
+    pub id: u64,
+    /// The tenant that owes the net amount. When the flows cancel out, the
+    /// first of the two by name.
+    pub payer: String,
+    pub payee: String,
+    /// Never negative; zero when the flows cancel out.
+    pub amount: i64,
+    /// The total of the settlements netted, in both directions.
+    pub gross: i64,
+    /// The ids of the settlements netted, oldest first.
+    pub settlements: Vec<u64>,
+    pub timestamp: Timestamp,
+}
+
+/// One end of day across the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryEndOfDay {
+    pub reports: BTreeMap<String, EndOfDayReport>,
+    pub net_settlements: Vec<NetSettlement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    UnknownTenant(String),
+    TenantExists(String),
+    /// The named tenant does not allow external transfers.
+    ExternalTransfersDisabled(String),
+    /// Moves within one tenant are ordinary transfers, not settlements.
+    SameTenant,
+    Account {
+        tenant: String,
+        error: AccountError,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::UnknownTenant(name) => write!(f, "No tenant named '{}'", name),
+            RegistryError::TenantExists(name) => write!(f, "Tenant '{}' already exists", name),
+            RegistryError::ExternalTransfersDisabled(name) => {
+                write!(f, "Tenant '{}' does not allow external transfers", name)
+            }
+            RegistryError::SameTenant => {
+                write!(f, "Use a normal transfer within a single tenant")
+            }
+            RegistryError::Account { tenant, error } => write!(f, "{}: {}", tenant, error),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+struct Tenant {
+    bank: Bank,
+    config: TenantConfig,
+}
+
+/// Many independent banks in one process, looked up by name. Tenants never
+/// see each other's accounts; the only way money crosses between them is
+/// [`BankRegistry::settle`], which books it as an external settlement on
+/// both sides.
+#[derive(Default)]
+pub struct BankRegistry {
+    tenants: BTreeMap<String, Tenant>,
+    settlements: Vec<Settlement>,
+    next_settlement_id: u64,
+    net_settlements: Vec<NetSettlement>,
+    next_net_settlement_id: u64,
+}
+
+impl BankRegistry {
+    pub fn new() -> Self {
+        BankRegistry::default()
+    }
+
+    /// Adds a new empty bank under `name`.
+    pub fn create(&mut self, name: &str, config: TenantConfig) -> Result<&mut Bank, RegistryError> {
+        self.insert(name, Bank::new(), config)
+    }
+
+    /// Adds an existing bank under `name`, applying `config` to it.
+    pub fn insert(
+        &mut self,
+        name: &str,
+        mut bank: Bank,
+        config: TenantConfig,
+    ) -> Result<&mut Bank, RegistryError> {
+        if self.tenants.contains_key(name) {
+            return Err(RegistryError::TenantExists(name.to_string()));
+        }
+        apply(&mut bank, &config);
+        let tenant = self
+            .tenants
+            .entry(name.to_string())
+            .or_insert(Tenant { bank, config });
+        Ok(&mut tenant.bank)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Bank> {
+        self.tenants.remove(name).map(|tenant| tenant.bank)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Bank> {
+        self.tenants.get(name).map(|tenant| &tenant.bank)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Bank> {
+        self.tenants.get_mut(name).map(|tenant| &mut tenant.bank)
+    }
+
+    pub fn config(&self, name: &str) -> Option<&TenantConfig> {
+        self.tenants.get(name).map(|tenant| &tenant.config)
+    }
+
+    pub fn configure(&mut self, name: &str, config: TenantConfig) -> Result<(), RegistryError> {
+        let tenant = self
+            .tenants
+            .get_mut(name)
+            .ok_or_else(|| RegistryError::UnknownTenant(name.to_string()))?;
+        apply(&mut tenant.bank, &config);
+        tenant.config = config;
+        Ok(())
+    }
+
+    /// Tenant names in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tenants.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tenants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+
+    pub fn settlements(&self) -> &[Settlement] {
+        &self.settlements
+    }
+
+    /// Settlements not yet netted.
+    pub fn pending_settlements(&self) -> impl Iterator<Item = &Settlement> {
+        self.settlements
+            .iter()
+            .filter(|settlement| settlement.netted_in.is_none())
+    }
+
+    pub fn net_settlements(&self) -> &[NetSettlement] {
+        &self.net_settlements
+    }
+
+    /// Nets the pending settlements between each pair of tenants into a
+    /// single [`NetSettlement`], so the banks exchange one amount instead
+    /// of one per transfer. Customers' entries are untouched: they were
+    /// booked when each transfer was made. Returns the new net settlements,
+    /// by pair of tenant names.
+    pub fn net_pending_settlements(&mut self) -> Vec<NetSettlement> {
+        // (first tenant, second tenant) -> (first to second, second to first, ids)
+        let mut pairs: BTreeMap<(&str, &str), (i64, i64, Vec<usize>)> = BTreeMap::new();
+        for (index, settlement) in self.settlements.iter().enumerate() {
+            if settlement.netted_in.is_some() {
+                continue;
+            }
+            let (from, to) = (
+                settlement.from_tenant.as_str(),
+                settlement.to_tenant.as_str(),
+            );
+            let forward = from < to;
+            let pair = pairs
+                .entry(if forward { (from, to) } else { (to, from) })
+                .or_default();
+            if forward {
+                pair.0 += settlement.amount;
+            } else {
+                pair.1 += settlement.amount;
+            }
+            pair.2.push(index);
+        }
+
+        let mut netted = Vec::with_capacity(pairs.len());
+        for ((first, second), (forward, backward, indices)) in pairs {
+            let (payer, payee) = if forward >= backward {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            let timestamp = [first, second]
+                .iter()
+                .filter_map(|name| self.tenants.get(*name))
+                .map(|tenant| tenant.bank.now)
+                .max()
+                .unwrap_or_default();
+            self.next_net_settlement_id += 1;
+            netted.push((
+                NetSettlement {
+                    id: self.next_net_settlement_id,
+                    payer: payer.to_string(),
+                    payee: payee.to_string(),
+                    amount: (forward - backward).abs(),
+                    gross: forward + backward,
+                    settlements: indices
+                        .iter()
+                        .map(|&index| self.settlements[index].id)
+                        .collect(),
+                    timestamp,
+                },
+                indices,
+            ));
+        }
+        netted
+            .into_iter()
+            .map(|(net, indices)| {
+                for index in indices {
+                    self.settlements[index].netted_in = Some(net.id);
+                }
+                self.net_settlements.push(net.clone());
+                net
+            })
+            .collect()
+    }
+
+    /// Runs end of day in every tenant's bank, then nets the day's
+    /// settlements between them.
+    pub fn end_of_day(&mut self) -> RegistryEndOfDay {
+        let reports = self
+            .tenants
+            .iter_mut()
+            .map(|(name, tenant)| (name.clone(), tenant.bank.end_of_day()))
+            .collect();
+        RegistryEndOfDay {
+            reports,
+            net_settlements: self.net_pending_settlements(),
+        }
+    }
+
+    /// Moves `amount` from an account in one tenant to an account in
+    /// another. Both tenants must allow external transfers. Each side books
+    /// a settlement entry with no local counterparty; either the whole
+    /// move happens or nothing does.
+    pub fn settle(
+        &mut self,
+        from_tenant: &str,
+        from: impl Into<AccountRef>,
+        to_tenant: &str,
+        to: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<u64, RegistryError> {
+        if from_tenant == to_tenant {
+            return Err(RegistryError::SameTenant);
+        }
+        for name in [from_tenant, to_tenant] {
+            let tenant = self
+                .tenants
+                .get(name)
+                .ok_or_else(|| RegistryError::UnknownTenant(name.to_string()))?;
+            if !tenant.config.external_transfers {
+                return Err(RegistryError::ExternalTransfersDisabled(name.to_string()));
+            }
+        }
+        let account_error = |tenant: &str| {
+            let tenant = tenant.to_string();
+            move |error| RegistryError::Account { tenant, error }
+        };
+        if amount < 0 {
+            return Err(account_error(from_tenant)(AccountError::Validation(
+                InputError::NegativeAmount,
+            )));
+        }
+
+        let (mut source, mut target) = (None, None);
+        for (name, tenant) in self.tenants.iter_mut() {
+            if name == from_tenant {
+                source = Some(&mut tenant.bank);
+            } else if name == to_tenant {
+                target = Some(&mut tenant.bank);
+            }
+        }
+        let (Some(source), Some(target)) = (source, target) else {
+            unreachable!("both tenants were checked above");
+        };
+        let from_id = source.resolve(from).map_err(account_error(from_tenant))?;
+        let to_id = target.resolve(to).map_err(account_error(to_tenant))?;
+        if target.accounts[&to_id]
+            .balance
+            .checked_add(amount)
+            .is_none()
+        {
+            return Err(account_error(to_tenant)(AccountError::Validation(
+                InputError::AmountOverflow,
+            )));
+        }
+        source
+            .accounts
+            .get_mut(&from_id)
+            .unwrap()
+            .withdraw(amount)
+            .map_err(account_error(from_tenant))?;
+        target.accounts.get_mut(&to_id).unwrap().balance += amount;
+
+        self.next_settlement_id += 1;
+        let id = self.next_settlement_id;
+        let out_memo = format!("Settlement #{} to {}/{}", id, to_tenant, to_id);
+        let in_memo = format!("Settlement #{} from {}/{}", id, from_tenant, from_id);
+        book(source, from_id, -amount, &out_memo, id, to_tenant, to_id);
+        book(target, to_id, amount, &in_memo, id, from_tenant, from_id);
+        self.settlements.push(Settlement {
+            id,
+            from_tenant: from_tenant.to_string(),
+            from_account: from_id,
+            to_tenant: to_tenant.to_string(),
+            to_account: to_id,
+            amount,
+            timestamp: source.now,
+            netted_in: None,
+        });
+        Ok(id)
+    }
+}
+
+impl fmt::Debug for BankRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BankRegistry")
+            .field("tenants", &self.tenants.keys().collect::<Vec<_>>())
+            .field("settlements", &self.settlements.len())
+            .field("net_settlements", &self.net_settlements.len())
+            .finish()
+    }
+}
+
+fn apply(bank: &mut Bank, config: &TenantConfig) {
+    bank.locale = config.locale;
+    bank.duplicate_check = config.duplicate_check;
+    bank.end_of_day_config = config.end_of_day.clone();
+}
+
+fn book(
+    bank: &mut Bank,
+    account_id: u32,
+    amount: i64,
+    memo: &str,
+    settlement: u64,
+    tenant: &str,
+    counterparty: u32,
+) {
+    let txn_id = bank.record(account_id, TransactionKind::Settlement, amount, None, memo);
+    bank.amend_transaction(account_id, txn_id, |txn| {
+        txn.metadata
+            .insert("settlement".to_string(), settlement.to_string());
+        txn.metadata
+            .insert("counterparty_tenant".to_string(), tenant.to_string());
+        txn.metadata
+            .insert("counterparty_account".to_string(), counterparty.to_string());
+    });
+}
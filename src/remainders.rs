@@ -0,0 +1,216 @@
+//! Where rounding leftovers go. FX conversions rarely come out in whole
+//! minor units, and a redenomination rounds every balance; the fractions
+//! customers did not get, or got on top, collect here and are booked to a
+//! rounding account in whole units, so what the bank computed and what it
+//! booked always add up exactly.
+//!
+//! Interest needs none of this: the fraction of a cent left after a posting
+//! stays accrued on the account and is paid with the next one.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::error::AccountError;
+use crate::transaction::TransactionKind;
+
+/// The calculation a remainder came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RoundingSource {
+    Fx,
+    Redenomination,
+}
+
+impl RoundingSource {
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            RoundingSource::Fx => "fx",
+            RoundingSource::Redenomination => "redenomination",
+        }
+    }
+
+    pub(crate) fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "fx" => Some(RoundingSource::Fx),
+            "redenomination" => Some(RoundingSource::Redenomination),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RoundingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RoundingSource::Fx => "FX",
+            RoundingSource::Redenomination => "redenomination",
+        })
+    }
+}
+
+/// Remainders from one source in one currency, in parts of a minor unit.
+/// Positive amounts are what customers did not get; negative ones what
+/// they got on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemainderLine {
+    pub currency: Currency,
+    pub source: RoundingSource,
+    /// How many parts make one minor unit of `currency`.
+    pub denominator: i128,
+    /// Every remainder collected, in parts.
+    pub total: i128,
+    /// Whole minor units booked to the rounding account.
+    pub posted: i64,
+    /// Parts not yet booked: less than a unit, or waiting for a rounding
+    /// account to be set for the currency.
+    pub carried: i128,
+}
+
+impl RemainderLine {
+    /// Whether everything collected was either booked or is still carried.
+    pub fn is_balanced(&self) -> bool {
+        self.total == i128::from(self.posted) * self.denominator + self.carried
+    }
+}
+
+/// Every remainder the bank has collected and where it went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundingAudit {
+    pub accounts: BTreeMap<Currency, u32>,
+    pub lines: Vec<RemainderLine>,
+}
+
+impl RoundingAudit {
+    pub fn is_balanced(&self) -> bool {
+        self.lines.iter().all(RemainderLine::is_balanced)
+    }
+
+    /// Whole units booked to `currency`'s rounding account, from every source.
+    pub fn posted(&self, currency: Currency) -> i64 {
+        self.lines
+            .iter()
+            .filter(|line| line.currency == currency)
+            .map(|line| line.posted)
+            .sum()
+    }
+}
+
+impl fmt::Display for RoundingAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rounding remainders")?;
+        for line in &self.lines {
+            let account = match self.accounts.get(&line.currency) {
+                Some(id) => format!("account {}", id),
+                None => "no rounding account".to_string(),
+            };
+            write!(
+                f,
+                "\n  {} {}: {} units booked to {}, {}/{} carried",
+                line.currency, line.source, line.posted, account, line.carried, line.denominator
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Remainder lines by currency, source and denominator.
+pub(crate) type Remainders = BTreeMap<(Currency, RoundingSource, i128), RemainderLine>;
+
+impl Bank {
+    /// Makes `account` the rounding account for its currency, replacing any
+    /// earlier one, and books the whole units already carried to it.
+    /// Without one, remainders in the currency are still collected and
+    /// carried, ready for when it is set.
+    pub fn set_rounding_account(
+        &mut self,
+        account: impl Into<AccountRef>,
+    ) -> Result<(), AccountError> {
+        let id = self.resolve(account)?;
+        let currency = self.accounts[&id].currency;
+        self.rounding_accounts.insert(currency, id);
+        self.post_remainders(currency);
+        Ok(())
+    }
+
+    pub fn clear_rounding_account(&mut self, currency: Currency) {
+        self.rounding_accounts.remove(&currency);
+    }
+
+    pub fn rounding_account(&self, currency: Currency) -> Option<u32> {
+        self.rounding_accounts.get(&currency).copied()
+    }
+
+    pub fn rounding_audit(&self) -> RoundingAudit {
+        RoundingAudit {
+            accounts: self.rounding_accounts.clone(),
+            lines: self.remainders.values().cloned().collect(),
+        }
+    }
+
+    /// Collects `remainder` parts of a minor unit, `denominator` to a unit,
+    /// and books whatever now makes whole units.
+    pub(crate) fn track_remainder(
+        &mut self,
+        currency: Currency,
+        source: RoundingSource,
+        remainder: i128,
+        denominator: i128,
+    ) {
+        if remainder == 0 {
+            return;
+        }
+        let line = self
+            .remainders
+            .entry((currency, source, denominator))
+            .or_insert(RemainderLine {
+                currency,
+                source,
+                denominator,
+                total: 0,
+                posted: 0,
+                carried: 0,
+            });
+        line.total += remainder;
+        line.carried += remainder;
+        self.post_remainders(currency);
+    }
+
+    fn post_remainders(&mut self, currency: Currency) {
+        let Some(&account_id) = self.rounding_accounts.get(&currency) else {
+            return;
+        };
+        let whole: Vec<(RoundingSource, i128, i64)> = self
+            .remainders
+            .range((currency, RoundingSource::Fx, i128::MIN)..)
+            .take_while(|(key, _)| key.0 == currency)
+            .filter_map(|(&(_, source, denominator), line)| {
+                let units = i64::try_from(line.carried / denominator).ok()?;
+                (units != 0).then_some((source, denominator, units))
+            })
+            .collect();
+        for (source, denominator, units) in whole {
+            let Some(account) = self.accounts.get_mut(&account_id) else {
+                return;
+            };
+            let Some(balance) = account.balance.checked_add(units) else {
+                continue;
+            };
+            account.balance = balance;
+            let memo = format!("Rounding remainders from {}", source);
+            self.record(
+                account_id,
+                TransactionKind::RoundingRemainder,
+                units,
+                None,
+                &memo,
+            );
+            let line = self
+                .remainders
+                .get_mut(&(currency, source, denominator))
+                .unwrap();
+            line.posted += units;
+            line.carried -= i128::from(units) * denominator;
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! A read-only copy of an actor's bank, kept on its own thread for reports
+//! too slow to run on the bank task. The actor hands each logged operation
+//! to the replica as it applies it, without waiting, so the replica trails
+//! the bank by whatever it has not applied yet.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::actor::{ActorError, BankHandle};
+use crate::bank::Bank;
+use crate::time::Timestamp;
+use crate::wal::WalEntry;
+
+pub(crate) enum ReplicaEvent {
+    /// An operation the bank applied at `timestamp`.
+    Applied(Timestamp, WalEntry),
+    /// A snapshot of the whole bank, replacing the replica's state.
+    Reset(Vec<u8>),
+    Stop,
+}
+
+/// The actor's end of a replica's event channel.
+#[derive(Clone)]
+pub(crate) struct ReplicaFeed {
+    sender: Sender<ReplicaEvent>,
+    published: Arc<AtomicU64>,
+}
+
+impl ReplicaFeed {
+    /// Sends `event` without waiting; `false` once the replica is gone.
+    pub(crate) fn publish(&self, event: ReplicaEvent) -> bool {
+        self.published.fetch_add(1, Ordering::SeqCst);
+        self.sender.send(event).is_ok()
+    }
+}
+
+struct Shared {
+    bank: Mutex<Bank>,
+    applied: Mutex<u64>,
+    progressed: Condvar,
+}
+
+/// An eventually consistent copy of a [`BankActor`](crate::BankActor)'s
+/// bank. Account openings, deposits, withdrawals and transfers sent
+/// through its handles reach the replica in order; anything else done
+/// with [`BankHandle::call`], such as running end of day, shows up after
+/// [`ReadReplica::refresh`].
+pub struct ReadReplica {
+    shared: Arc<Shared>,
+    feed: ReplicaFeed,
+    handle: BankHandle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BankHandle {
+    /// Copies the bank as it is now into a [`ReadReplica`] and keeps it up
+    /// to date from then on.
+    pub fn spawn_replica(&self) -> Result<ReadReplica, ActorError> {
+        let (sender, receiver) = mpsc::channel();
+        let feed = ReplicaFeed {
+            sender,
+            published: Arc::new(AtomicU64::new(0)),
+        };
+        let snapshot = self.subscribe(feed.clone())?;
+        let bank = Bank::load_snapshot(&snapshot[..])
+            .map_err(|error| ActorError::Storage(error.into()))?;
+        let shared = Arc::new(Shared {
+            bank: Mutex::new(bank),
+            applied: Mutex::new(0),
+            progressed: Condvar::new(),
+        });
+        let thread = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || follow(&shared, receiver))
+        };
+        Ok(ReadReplica {
+            shared,
+            feed,
+            handle: self.clone(),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl ReadReplica {
+    /// Runs `f` against the replica. The bank task carries on meanwhile;
+    /// only the replica's own updates, and other reads, wait for `f` to
+    /// finish.
+    pub fn read<T>(&self, f: impl FnOnce(&Bank) -> T) -> T {
+        f(&self.shared.bank.lock().unwrap())
+    }
+
+    /// How many updates the bank has sent that the replica has not applied.
+    pub fn lag(&self) -> u64 {
+        let applied = *self.shared.applied.lock().unwrap();
+        self.feed.published.load(Ordering::SeqCst) - applied
+    }
+
+    /// Waits until everything the bank had sent when this was called is
+    /// applied.
+    pub fn catch_up(&self) {
+        let target = self.feed.published.load(Ordering::SeqCst);
+        let applied = self.shared.applied.lock().unwrap();
+        let _caught_up = self
+            .shared
+            .progressed
+            .wait_while(applied, |applied| *applied < target)
+            .unwrap();
+    }
+
+    /// Replaces the replica's state with a fresh snapshot, taken after the
+    /// commands already queued on the bank. Returns once the snapshot is
+    /// taken, not once it is applied; [`ReadReplica::catch_up`] waits for
+    /// that.
+    pub fn refresh(&self) -> Result<(), ActorError> {
+        self.handle.refresh_replica(self.feed.clone())
+    }
+}
+
+impl Drop for ReadReplica {
+    fn drop(&mut self) {
+        let _ = self.feed.sender.send(ReplicaEvent::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for ReadReplica {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadReplica")
+            .field("lag", &self.lag())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Applies events until the replica is dropped or the bank shuts down.
+/// Operations the bank refused are refused again here, as in replay.
+fn follow(shared: &Shared, receiver: Receiver<ReplicaEvent>) {
+    for event in receiver {
+        match event {
+            ReplicaEvent::Applied(timestamp, entry) => {
+                let mut bank = shared.bank.lock().unwrap();
+                bank.set_time(timestamp);
+                let _ = bank.apply_entry(&entry);
+            }
+            ReplicaEvent::Reset(snapshot) => {
+                if let Ok(bank) = Bank::load_snapshot(&snapshot[..]) {
+                    *shared.bank.lock().unwrap() = bank;
+                }
+            }
+            ReplicaEvent::Stop => break,
+        }
+        *shared.applied.lock().unwrap() += 1;
+        shared.progressed.notify_all();
+    }
+}
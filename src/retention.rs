@@ -0,0 +1,126 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::bank::Bank;
+use crate::chain::{reseal, ChainHash};
+use crate::snapshot::write_transaction;
+use crate::transaction::{Transaction, TransactionKind};
+
+/// How much history each account keeps. An entry is pruned once it falls
+/// outside either limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub keep_days: Option<u32>,
+    pub keep_entries: Option<usize>,
+}
+
+impl RetentionPolicy {
+    pub fn keep_days(days: u32) -> Self {
+        RetentionPolicy {
+            keep_days: Some(days),
+            keep_entries: None,
+        }
+    }
+
+    pub fn keep_entries(entries: usize) -> Self {
+        RetentionPolicy {
+            keep_days: None,
+            keep_entries: Some(entries),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub accounts: usize,
+    pub entries_pruned: usize,
+}
+
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Pruned {} entries from {} accounts",
+            self.entries_pruned, self.accounts
+        )
+    }
+}
+
+impl Bank {
+    /// Drops history outside `policy`, replacing each account's pruned
+    /// entries with one opening-balance marker carrying the balance forward,
+    /// so the remaining amounts still add up to the balance.
+    pub fn prune_history(&mut self, policy: &RetentionPolicy) -> PruneReport {
+        self.prune(policy, None)
+            .expect("pruning without an archive cannot fail")
+    }
+
+    /// Like [`Bank::prune_history`], but first writes every pruned entry to
+    /// `archive` in the snapshot `txn` format.
+    pub fn prune_history_with_archive<W: Write>(
+        &mut self,
+        policy: &RetentionPolicy,
+        archive: &mut W,
+    ) -> io::Result<PruneReport> {
+        let report = self.prune(policy, Some(archive))?;
+        archive.flush()?;
+        Ok(report)
+    }
+
+    fn prune(
+        &mut self,
+        policy: &RetentionPolicy,
+        mut archive: Option<&mut dyn Write>,
+    ) -> io::Result<PruneReport> {
+        let cutoff = policy.keep_days.map(|days| self.now.minus_days(days));
+        let mut report = PruneReport::default();
+        for id in self.sorted_account_ids() {
+            let account = self.accounts.get_mut(&id).unwrap();
+            let history = &account.history;
+            let mut prune = 0;
+            if let Some(keep) = policy.keep_entries {
+                prune = history.len().saturating_sub(keep);
+            }
+            if let Some(cutoff) = cutoff {
+                let old = history
+                    .iter()
+                    .take_while(|txn| txn.timestamp < cutoff)
+                    .count();
+                prune = prune.max(old);
+            }
+            // A lone marker left by an earlier pruning has nothing to roll up.
+            if prune == 0 || (prune == 1 && history[0].kind == TransactionKind::OpeningBalance) {
+                continue;
+            }
+
+            let pruned: Vec<Transaction> = account.history.drain(..prune).collect();
+            if let Some(archive) = archive.as_deref_mut() {
+                for txn in &pruned {
+                    write_transaction(archive, txn)?;
+                }
+            }
+            let last = pruned.last().unwrap();
+            let marker = Transaction {
+                id: last.id,
+                account_id: id,
+                kind: TransactionKind::OpeningBalance,
+                amount: last.balance_after,
+                balance_after: last.balance_after,
+                counterparty: None,
+                timestamp: last.timestamp,
+                memo: format!("Carried forward from {} entries", pruned.len()),
+                category: None,
+                tags: Default::default(),
+                rounding: last.rounding,
+                metadata: Default::default(),
+                chain_hash: ChainHash::GENESIS,
+            };
+            account.history.insert(0, marker);
+            reseal(&mut account.history, 0);
+            account.repartition();
+            report.accounts += 1;
+            report.entries_pruned += pruned.len();
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,227 @@
+//! Retrying storage operations that fail for reasons expected to pass:
+//! timeouts, interrupted calls, dropped connections.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufReader, Read, Write};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::bank::Bank;
+use crate::rng::SeededRng;
+use crate::snapshot::PersistError;
+
+/// How often and how patiently a failing storage operation is retried.
+///
+/// The wait before retry `n` is `initial_backoff * 2^(n - 1)`, capped at
+/// `max_backoff`. With jitter, each wait is instead drawn uniformly from
+/// between half of that and all of it, so processes that failed together
+/// don't all retry at the same instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Including the first try; 1 means no retries.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: bool,
+    /// Seeds the jitter, for reproducible waits; `None` seeds it from the
+    /// clock.
+    pub seed: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            jitter: true,
+            seed: None,
+        }
+    }
+}
+
+/// Whether an I/O error is worth retrying.
+pub fn is_transient(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ResourceBusy
+    )
+}
+
+/// One failed try.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attempt {
+    /// Counting from 1.
+    pub number: u32,
+    pub kind: io::ErrorKind,
+    pub message: String,
+    /// How long was waited before the next try; `None` for the last one.
+    pub backoff: Option<Duration>,
+}
+
+/// Every try of an operation failed. The last attempt failed either with a
+/// permanent error or with the attempts used up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryError {
+    pub attempts: Vec<Attempt>,
+}
+
+impl RetryError {
+    pub fn last(&self) -> &Attempt {
+        self.attempts.last().expect("a retry error has an attempt")
+    }
+
+    /// Whether the operation was given up on for running out of attempts
+    /// rather than for a permanent error.
+    pub fn exhausted(&self) -> bool {
+        is_transient(self.last().kind)
+    }
+}
+
+impl fmt::Display for RetryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let last = self.last();
+        match self.exhausted() {
+            true => write!(f, "Gave up after {} attempts", self.attempts.len())?,
+            false => write!(f, "Permanent error on attempt {}", last.number)?,
+        }
+        write!(f, ": {}", last.message)?;
+        for attempt in &self.attempts {
+            write!(f, "\n  attempt {}: {}", attempt.number, attempt.message)?;
+            if let Some(backoff) = attempt.backoff {
+                write!(f, " (retried after {}ms)", backoff.as_millis())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for RetryError {}
+
+impl From<RetryError> for io::Error {
+    /// Keeps the last attempt's kind, with the whole history as the inner
+    /// error.
+    fn from(error: RetryError) -> Self {
+        io::Error::new(error.last().kind, error)
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Tries once, never retrying.
+    pub fn none() -> Self {
+        RetryPolicy::new().max_attempts(1)
+    }
+
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The wait after failed attempt `number`, before jitter.
+    fn backoff_after(&self, number: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(number.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Runs `operation`, passing it the attempt number, until it succeeds,
+    /// fails with an error [`is_transient`] doesn't accept, or has been
+    /// tried `max_attempts` times.
+    pub fn run<T>(&self, mut operation: impl FnMut(u32) -> io::Result<T>) -> Result<T, RetryError> {
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        });
+        let mut rng = SeededRng::new(seed);
+        let mut attempts = Vec::new();
+        for number in 1..=self.max_attempts {
+            let error = match operation(number) {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            let kind = error.kind();
+            let mut attempt = Attempt {
+                number,
+                kind,
+                message: error.to_string(),
+                backoff: None,
+            };
+            if !is_transient(kind) || number == self.max_attempts {
+                attempts.push(attempt);
+                break;
+            }
+            let mut backoff = self.backoff_after(number);
+            if self.jitter {
+                let nanos = backoff.as_nanos().min(u128::from(u64::MAX)) as i64;
+                backoff = Duration::from_nanos(rng.range(nanos / 2, nanos) as u64);
+            }
+            attempt.backoff = Some(backoff);
+            attempts.push(attempt);
+            thread::sleep(backoff);
+        }
+        Err(RetryError { attempts })
+    }
+}
+
+impl Bank {
+    /// Saves a snapshot to the writer `open` returns, opening a fresh one
+    /// and starting over whenever a write fails transiently.
+    pub fn save_snapshot_with_retry<W: Write>(
+        &self,
+        policy: &RetryPolicy,
+        mut open: impl FnMut() -> io::Result<W>,
+    ) -> Result<(), RetryError> {
+        policy.run(|_| {
+            let mut writer = open()?;
+            self.save_snapshot(&mut writer)?;
+            writer.flush()
+        })
+    }
+
+    /// Loads a snapshot from the reader `open` returns, reopening it
+    /// whenever a read fails transiently. Corrupt data is never retried.
+    pub fn load_snapshot_with_retry<R: Read>(
+        policy: &RetryPolicy,
+        mut open: impl FnMut() -> io::Result<R>,
+    ) -> Result<Bank, RetryError> {
+        policy.run(|_| {
+            Bank::load_snapshot(BufReader::new(open()?)).map_err(|error| match error {
+                PersistError::Io(error) => error,
+                other => io::Error::new(io::ErrorKind::InvalidData, other.to_string()),
+            })
+        })
+    }
+}
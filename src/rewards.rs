@@ -0,0 +1,340 @@
+use std::fmt;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::error::{AccountError, InputError, StateError};
+use crate::locale::Locale;
+use crate::receipt::Receipt;
+use crate::time::{Date, Timestamp};
+use crate::transaction::TransactionKind;
+
+/// Which spends a reward rule applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RewardMatch {
+    /// Withdrawals tagged with this category.
+    Category(String),
+    /// Transfers to this account.
+    Payee(u32),
+    /// Every withdrawal and outgoing transfer.
+    AnySpend,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardEarning {
+    /// Cashback in basis points of the spend (100 = 1%).
+    Cashback { rate_bps: u32 },
+    /// Points per whole dollar spent.
+    Points { per_dollar: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardRule {
+    pub matches: RewardMatch,
+    pub earning: RewardEarning,
+}
+
+impl RewardRule {
+    pub fn new(matches: RewardMatch, earning: RewardEarning) -> Self {
+        RewardRule { matches, earning }
+    }
+
+    fn applies_to(&self, category: Option<&str>, payee: Option<u32>) -> bool {
+        match &self.matches {
+            RewardMatch::Category(wanted) => category == Some(wanted.as_str()),
+            RewardMatch::Payee(wanted) => payee == Some(*wanted),
+            RewardMatch::AnySpend => true,
+        }
+    }
+}
+
+/// The rules an account earns under. The first matching rule wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardsProgram {
+    pub rules: Vec<RewardRule>,
+    /// What one point is worth, in cents, when redeemed.
+    pub cents_per_point: u32,
+}
+
+impl RewardsProgram {
+    pub fn new(cents_per_point: u32) -> Self {
+        RewardsProgram {
+            rules: Vec::new(),
+            cents_per_point,
+        }
+    }
+
+    pub fn with_rule(mut self, rule: RewardRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+}
+
+/// An earning (positive) or redemption (negative) on a rewards balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardEntry {
+    pub transaction_id: u64,
+    pub timestamp: Timestamp,
+    pub cashback: i64,
+    pub points: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardsAccount {
+    pub(crate) program: RewardsProgram,
+    pub(crate) cashback_balance: i64,
+    pub(crate) points_balance: i64,
+    pub(crate) cashback_remainder: i128,
+    pub(crate) entries: Vec<RewardEntry>,
+}
+
+impl RewardsAccount {
+    pub fn program(&self) -> &RewardsProgram {
+        &self.program
+    }
+
+    pub fn cashback_balance(&self) -> i64 {
+        self.cashback_balance
+    }
+
+    pub fn points_balance(&self) -> i64 {
+        self.points_balance
+    }
+
+    pub fn entries(&self) -> &[RewardEntry] {
+        &self.entries
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewardsStatement {
+    pub account_id: u32,
+    pub year: i32,
+    pub month: u32,
+    /// The account's currency, which cashback is counted in.
+    pub currency: Currency,
+    pub earned_cashback: i64,
+    pub earned_points: i64,
+    pub redeemed_cashback: i64,
+    pub redeemed_points: i64,
+    pub closing_cashback: i64,
+    pub closing_points: i64,
+    pub entries: Vec<RewardEntry>,
+}
+
+impl fmt::Display for RewardsStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Rewards statement for account {} ({:04}-{:02})",
+            self.account_id, self.year, self.month
+        )?;
+        let money = |amount| Locale::English.money_in(amount, self.currency);
+        writeln!(
+            f,
+            "Earned: {} cashback, {} points",
+            money(self.earned_cashback),
+            self.earned_points
+        )?;
+        writeln!(
+            f,
+            "Redeemed: {} cashback, {} points",
+            money(self.redeemed_cashback),
+            self.redeemed_points
+        )?;
+        write!(
+            f,
+            "Balance: {} cashback, {} points",
+            money(self.closing_cashback),
+            self.closing_points
+        )
+    }
+}
+
+impl Bank {
+    pub fn enroll_rewards(
+        &mut self,
+        account: impl Into<AccountRef>,
+        program: RewardsProgram,
+    ) -> Result<(), AccountError> {
+        let id = self.resolve(account)?;
+        self.rewards.insert(
+            id,
+            RewardsAccount {
+                program,
+                cashback_balance: 0,
+                points_balance: 0,
+                cashback_remainder: 0,
+                entries: Vec::new(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn rewards(&self, account: impl Into<AccountRef>) -> Option<&RewardsAccount> {
+        let id = self.resolve(account).ok()?;
+        self.rewards.get(&id)
+    }
+
+    /// Moves `amount` cents of cashback into the main account.
+    pub fn redeem_cashback(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        if amount < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let rewards = self
+            .rewards
+            .get(&id)
+            .ok_or(AccountError::State(StateError::NotEnrolledInRewards))?;
+        if rewards.cashback_balance < amount {
+            return Err(AccountError::State(StateError::InsufficientRewards));
+        }
+        self.pay_redemption(id, amount, -amount, 0, "Cashback redemption")
+    }
+
+    /// Converts `points` into cash at the program's point value.
+    pub fn redeem_points(
+        &mut self,
+        account: impl Into<AccountRef>,
+        points: i64,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.resolve(account)?;
+        if points < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let rewards = self
+            .rewards
+            .get(&id)
+            .ok_or(AccountError::State(StateError::NotEnrolledInRewards))?;
+        if rewards.points_balance < points {
+            return Err(AccountError::State(StateError::InsufficientRewards));
+        }
+        let amount = points
+            .checked_mul(i64::from(rewards.program.cents_per_point))
+            .ok_or(AccountError::Validation(InputError::AmountOverflow))?;
+        self.pay_redemption(id, amount, 0, -points, "Points redemption")
+    }
+
+    pub fn rewards_statement(
+        &self,
+        account: impl Into<AccountRef>,
+        year: i32,
+        month: u32,
+    ) -> Result<RewardsStatement, AccountError> {
+        let id = self.resolve(account)?;
+        let rewards = self
+            .rewards
+            .get(&id)
+            .ok_or(AccountError::State(StateError::NotEnrolledInRewards))?;
+        let first_day = Date::new(year, month, 1);
+        let start = Timestamp::from_date(first_day);
+        let end = start.plus_days(first_day.days_in_month());
+        let mut statement = RewardsStatement {
+            account_id: id,
+            year,
+            month,
+            currency: self.accounts[&id].currency,
+            earned_cashback: 0,
+            earned_points: 0,
+            redeemed_cashback: 0,
+            redeemed_points: 0,
+            closing_cashback: 0,
+            closing_points: 0,
+            entries: Vec::new(),
+        };
+        for entry in rewards.entries.iter().filter(|entry| entry.timestamp < end) {
+            statement.closing_cashback += entry.cashback;
+            statement.closing_points += entry.points;
+            if entry.timestamp < start {
+                continue;
+            }
+            if entry.cashback >= 0 {
+                statement.earned_cashback += entry.cashback;
+            } else {
+                statement.redeemed_cashback -= entry.cashback;
+            }
+            if entry.points >= 0 {
+                statement.earned_points += entry.points;
+            } else {
+                statement.redeemed_points -= entry.points;
+            }
+            statement.entries.push(entry.clone());
+        }
+        Ok(statement)
+    }
+
+    /// Credits rewards for a booked spend if the account is enrolled.
+    pub(crate) fn earn_rewards(
+        &mut self,
+        id: u32,
+        transaction_id: u64,
+        amount: i64,
+        category: Option<&str>,
+        payee: Option<u32>,
+    ) {
+        let timestamp = self.now;
+        let Some(rewards) = self.rewards.get_mut(&id) else {
+            return;
+        };
+        let Some(rule) = rewards
+            .program
+            .rules
+            .iter()
+            .find(|rule| rule.applies_to(category, payee))
+        else {
+            return;
+        };
+        let (cashback, points) = match rule.earning {
+            RewardEarning::Cashback { rate_bps } => {
+                rewards.cashback_remainder += i128::from(amount) * i128::from(rate_bps);
+                let cents = rewards.cashback_remainder / 10_000;
+                rewards.cashback_remainder -= cents * 10_000;
+                (cents as i64, 0)
+            }
+            RewardEarning::Points { per_dollar } => (0, amount / 100 * i64::from(per_dollar)),
+        };
+        if cashback == 0 && points == 0 {
+            return;
+        }
+        rewards.cashback_balance += cashback;
+        rewards.points_balance += points;
+        rewards.entries.push(RewardEntry {
+            transaction_id,
+            timestamp,
+            cashback,
+            points,
+        });
+    }
+
+    fn pay_redemption(
+        &mut self,
+        id: u32,
+        amount: i64,
+        cashback: i64,
+        points: i64,
+        memo: &str,
+    ) -> Result<Receipt, AccountError> {
+        let account = self
+            .accounts
+            .get_mut(&id)
+            .ok_or(AccountError::State(StateError::AccountNotFound))?;
+        let outcome = account.deposit(amount)?;
+        let transaction_id = self.record(id, TransactionKind::Reward, amount, None, memo);
+        let timestamp = self.now;
+        if let Some(rewards) = self.rewards.get_mut(&id) {
+            rewards.cashback_balance += cashback;
+            rewards.points_balance += points;
+            rewards.entries.push(RewardEntry {
+                transaction_id,
+                timestamp,
+                cashback,
+                points,
+            });
+        }
+        Ok(self.receipt(id, Some(transaction_id), outcome.warnings))
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+
+/// How fractional cents are resolved when interest or fees are computed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RoundingPolicy {
+    /// Drop the fraction (round toward zero).
+    #[default]
+    Truncate,
+    /// Round halves away from zero.
+    HalfUp,
+    /// Banker's rounding: round halves to the nearest even value.
+    HalfEven,
+}
+
+impl RoundingPolicy {
+    /// The most decimal places amounts are formatted with; more would not
+    /// fit the largest balance once scaled.
+    pub const MAX_PRECISION: u32 = 18;
+
+    /// Divides `numerator` by `denominator`, rounding the quotient per policy.
+    pub fn divide(self, numerator: i128, denominator: i128) -> i128 {
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        if remainder == 0 {
+            return quotient;
+        }
+        let step = if (numerator < 0) != (denominator < 0) {
+            -1
+        } else {
+            1
+        };
+        let twice_remainder = remainder.abs() * 2;
+        let divisor = denominator.abs();
+        let round_away = match self {
+            RoundingPolicy::Truncate => false,
+            RoundingPolicy::HalfUp => twice_remainder >= divisor,
+            RoundingPolicy::HalfEven => {
+                twice_remainder > divisor || (twice_remainder == divisor && quotient % 2 != 0)
+            }
+        };
+        if round_away {
+            quotient + step
+        } else {
+            quotient
+        }
+    }
+
+    /// Formats an amount of cents as dollars with `precision` decimal places.
+    pub fn format_cents(self, cents: i64, precision: u32) -> String {
+        let number = self.format_units(cents, 2, precision);
+        match number.strip_prefix('-') {
+            Some(digits) => format!("-${}", digits),
+            None => format!("${}", number),
+        }
+    }
+
+    /// Formats `amount` counted in a currency's minor units, of which
+    /// `minor_units` decimal digits make one whole unit, with `precision`
+    /// decimal places, at most [`RoundingPolicy::MAX_PRECISION`]. The number
+    /// is written bare; [`Locale::money_units`](crate::Locale::money_units)
+    /// adds the currency.
+    pub fn format_units(self, amount: i64, minor_units: u32, precision: u32) -> String {
+        let precision = precision.min(RoundingPolicy::MAX_PRECISION);
+        let sign = if amount < 0 { "-" } else { "" };
+        let amount = i128::from(amount).abs();
+        let (units, decimals) = if precision >= minor_units {
+            let padding = 10_i128.pow(precision - minor_units);
+            (amount * padding, precision)
+        } else {
+            let scale = 10_i128.pow(minor_units - precision);
+            (self.divide(amount, scale), precision)
+        };
+        if decimals == 0 {
+            return format!("{}{}", sign, units);
+        }
+        let scale = 10_i128.pow(decimals);
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            units / scale,
+            units % scale,
+            width = decimals as usize
+        )
+    }
+}
+
+impl fmt::Display for RoundingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundingPolicy::Truncate => write!(f, "truncate"),
+            RoundingPolicy::HalfUp => write!(f, "round half up"),
+            RoundingPolicy::HalfEven => write!(f, "banker's rounding"),
+        }
+    }
+}
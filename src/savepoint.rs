@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use crate::account::{Account, AccountRef, Hold};
+use crate::bank::Bank;
+use crate::deductions::{DeductionOrder, DeductionStatus};
+use crate::error::AccountError;
+use crate::outcome::OperationOutcome;
+use crate::receipt::Receipt;
+use crate::rewards::RewardsAccount;
+
+/// The bank as seen from inside [`Bank::transact`]. Every operation made
+/// through it is kept if the closure succeeds and undone if it fails.
+///
+/// Only the accounts an operation touches are copied, the first time it
+/// touches them, so a savepoint costs nothing for the rest of the bank.
+#[derive(Debug)]
+pub struct Savepoint<'a> {
+    bank: &'a mut Bank,
+    accounts: HashMap<u32, Account>,
+    rewards: HashMap<u32, Option<RewardsAccount>>,
+    deduction_orders: Option<Vec<DeductionOrder>>,
+    next_transaction_id: u64,
+    next_item_id: u64,
+}
+
+impl Savepoint<'_> {
+    /// Read-only access to the bank, including what this savepoint has done
+    /// so far.
+    pub fn bank(&self) -> &Bank {
+        self.bank
+    }
+
+    pub fn deposit(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.bank.resolve(account)?;
+        self.save_with_beneficiaries(id);
+        self.bank.deposit(id, amount)
+    }
+
+    pub fn withdraw(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.bank.resolve(account)?;
+        self.save(id);
+        self.bank.withdraw(id, amount)
+    }
+
+    pub fn withdraw_in_category(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        category: &str,
+    ) -> Result<Receipt, AccountError> {
+        let id = self.bank.resolve(account)?;
+        self.save(id);
+        self.bank.withdraw_in_category(id, amount, category)
+    }
+
+    pub fn transfer(
+        &mut self,
+        from: impl Into<AccountRef>,
+        to: impl Into<AccountRef>,
+        amount: i64,
+    ) -> Result<OperationOutcome<()>, AccountError> {
+        let from_id = self.bank.resolve(from)?;
+        let to_id = self.bank.resolve(to)?;
+        self.save(from_id);
+        self.save(to_id);
+        self.save_tax_account();
+        self.bank.transfer(from_id, to_id, amount)
+    }
+
+    pub fn place_hold(
+        &mut self,
+        account: impl Into<AccountRef>,
+        amount: i64,
+        days: u32,
+        reason: &str,
+    ) -> Result<u64, AccountError> {
+        let id = self.bank.resolve(account)?;
+        self.save(id);
+        self.bank.place_hold(id, amount, days, reason)
+    }
+
+    pub fn release_hold(
+        &mut self,
+        account: impl Into<AccountRef>,
+        hold_id: u64,
+    ) -> Result<Hold, AccountError> {
+        let id = self.bank.resolve(account)?;
+        self.save(id);
+        self.bank.release_hold(id, hold_id)
+    }
+
+    /// Copies an account and its rewards before their first change.
+    fn save(&mut self, id: u32) {
+        if self.accounts.contains_key(&id) {
+            return;
+        }
+        if let Some(account) = self.bank.accounts.get(&id) {
+            self.accounts.insert(id, account.clone());
+        }
+        self.rewards.insert(id, self.bank.rewards.get(&id).cloned());
+    }
+
+    /// A taxed transfer also credits the tax account.
+    fn save_tax_account(&mut self) {
+        if let (Some(_), Some(tax_account)) = (&self.bank.tax_policy, self.bank.tax_account) {
+            self.save(tax_account);
+        }
+    }
+
+    /// A deposit can pass money on through deduction orders, so their
+    /// beneficiaries and the orders themselves are saved too.
+    fn save_with_beneficiaries(&mut self, id: u32) {
+        self.save(id);
+        let beneficiaries: Vec<u32> = self
+            .bank
+            .deduction_orders
+            .iter()
+            .filter(|order| order.account_id == id && order.status == DeductionStatus::Active)
+            .map(|order| order.beneficiary_id)
+            .collect();
+        if beneficiaries.is_empty() {
+            return;
+        }
+        for beneficiary in beneficiaries {
+            self.save(beneficiary);
+        }
+        if self.deduction_orders.is_none() {
+            self.deduction_orders = Some(self.bank.deduction_orders.clone());
+        }
+    }
+
+    fn roll_back(self) {
+        let bank = self.bank;
+        bank.accounts.extend(self.accounts);
+        for (id, rewards) in self.rewards {
+            match rewards {
+                Some(rewards) => bank.rewards.insert(id, rewards),
+                None => bank.rewards.remove(&id),
+            };
+        }
+        if let Some(orders) = self.deduction_orders {
+            bank.deduction_orders = orders;
+        }
+        bank.next_transaction_id = self.next_transaction_id;
+        bank.next_item_id = self.next_item_id;
+        bank.finish_deferring(false);
+    }
+}
+
+impl Bank {
+    /// Runs `operations` as one unit: if the closure returns an error, every
+    /// deposit, withdrawal, transfer and hold it made is undone and the bank
+    /// is left exactly as it was. Notifications are only sent once the
+    /// closure succeeds.
+    ///
+    /// Ids handed out by a custom [`IdGenerator`](crate::IdGenerator) are not
+    /// reused after a rollback.
+    pub fn transact<T, E>(
+        &mut self,
+        operations: impl FnOnce(&mut Savepoint<'_>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.defer_notifications();
+        let mut savepoint = Savepoint {
+            next_transaction_id: self.next_transaction_id,
+            next_item_id: self.next_item_id,
+            bank: self,
+            accounts: HashMap::new(),
+            rewards: HashMap::new(),
+            deduction_orders: None,
+        };
+        match operations(&mut savepoint) {
+            Ok(value) => {
+                savepoint.bank.finish_deferring(true);
+                Ok(value)
+            }
+            Err(error) => {
+                savepoint.roll_back();
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{BatchOp, BatchOperation};
+    use crate::tax::FlatTax;
+
+    fn taxed_bank() -> Bank {
+        let mut bank = Bank::new();
+        for id in 1..=3 {
+            bank.add_account(Account::new(id, format!("Holder {}", id)));
+        }
+        bank.deposit(1, 10_000).unwrap();
+        bank.set_tax_account(3).unwrap();
+        bank.set_tax_policy(FlatTax {
+            interest_bps: 0,
+            transfer_bps: 100,
+        });
+        bank
+    }
+
+    #[test]
+    fn rolling_back_a_taxed_transfer_takes_the_tax_back() {
+        let mut bank = taxed_bank();
+        let result = bank.transact(|savepoint| {
+            savepoint.transfer(1, 2, 5_000)?;
+            savepoint.withdraw(2, 1_000_000).map(|_| ())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(bank.total_balance(), 10_000);
+        let tax_account = bank.get_account(3).unwrap();
+        assert_eq!((tax_account.balance(), tax_account.history().len()), (0, 0));
+    }
+
+    #[test]
+    fn previews_leave_the_tax_account_alone() {
+        let mut bank = taxed_bank();
+        let preview = bank.preview_batch(&[BatchOperation {
+            line: 1,
+            op: BatchOp::Transfer {
+                from: 1,
+                to: 2,
+                amount: 5_000,
+            },
+        }]);
+
+        assert!(preview.failed.is_empty());
+        assert_eq!(bank.get_account(3).unwrap().balance(), 0);
+        assert_eq!(bank.total_balance(), 10_000);
+    }
+}
@@ -0,0 +1,205 @@
+//! Operations booked to run at a set time, such as standing orders.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError, StateError};
+use crate::import::BatchOp;
+use crate::time::Timestamp;
+
+/// A deposit, withdrawal or transfer waiting for its due time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledOperation {
+    pub id: u64,
+    pub due: Timestamp,
+    pub op: BatchOp,
+    /// Runs again this many days after each due time; `None` runs once.
+    pub repeat_days: Option<u32>,
+}
+
+/// One scheduled operation carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledRun {
+    pub id: u64,
+    pub due: Timestamp,
+    pub op: BatchOp,
+    pub result: Result<(), AccountError>,
+}
+
+/// Pending operations keyed by due time, then by id, so operations due at
+/// the same second run in the order they were scheduled. Scheduling,
+/// cancelling and rescheduling are O(log n).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scheduler {
+    queue: BTreeMap<(Timestamp, u64), ScheduledOperation>,
+    due_times: HashMap<u64, Timestamp>,
+    /// Runs since the last end of day, for its report.
+    pub ran: Vec<ScheduledRun>,
+}
+
+impl Scheduler {
+    pub fn insert(&mut self, operation: ScheduledOperation) {
+        if let Some(previous) = self.due_times.insert(operation.id, operation.due) {
+            self.queue.remove(&(previous, operation.id));
+        }
+        self.queue.insert((operation.due, operation.id), operation);
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<ScheduledOperation> {
+        let due = self.due_times.remove(&id)?;
+        self.queue.remove(&(due, id))
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ScheduledOperation> {
+        let due = self.due_times.get(&id)?;
+        self.queue.get(&(*due, id))
+    }
+
+    pub fn peek(&self) -> Option<&ScheduledOperation> {
+        self.queue.values().next()
+    }
+
+    /// Takes the earliest operation if it is due by `now`.
+    pub fn pop_due(&mut self, now: Timestamp) -> Option<ScheduledOperation> {
+        let (&(due, id), _) = self.queue.first_key_value()?;
+        if due > now {
+            return None;
+        }
+        self.due_times.remove(&id);
+        self.queue.remove(&(due, id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ScheduledOperation> {
+        self.queue.values()
+    }
+
+    /// Changing an operation's due time through this would break the
+    /// queue's order; use [`Scheduler::insert`] for that.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ScheduledOperation> {
+        self.queue.values_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.due_times.shrink_to_fit();
+        self.ran.shrink_to_fit();
+    }
+}
+
+impl Bank {
+    /// Schedules `op` to run once at `due`. Operations run when
+    /// [`Bank::advance_time`] passes their due time, or when
+    /// [`Bank::run_scheduled`] is called after it.
+    pub fn schedule(&mut self, due: Timestamp, op: BatchOp) -> Result<u64, AccountError> {
+        self.schedule_operation(due, op, None)
+    }
+
+    /// Schedules `op` as a standing order: first at `first_due`, then every
+    /// `every_days` days until cancelled.
+    pub fn schedule_every(
+        &mut self,
+        first_due: Timestamp,
+        every_days: u32,
+        op: BatchOp,
+    ) -> Result<u64, AccountError> {
+        self.schedule_operation(first_due, op, Some(every_days.max(1)))
+    }
+
+    fn schedule_operation(
+        &mut self,
+        due: Timestamp,
+        op: BatchOp,
+        repeat_days: Option<u32>,
+    ) -> Result<u64, AccountError> {
+        let (amount, accounts) = match op {
+            BatchOp::Deposit { account, amount } | BatchOp::Withdraw { account, amount } => {
+                (amount, [account, account])
+            }
+            BatchOp::Transfer { from, to, amount } => (amount, [from, to]),
+        };
+        if amount < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        if accounts.iter().any(|id| !self.accounts.contains_key(id)) {
+            return Err(AccountError::State(StateError::AccountNotFound));
+        }
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        self.scheduler.insert(ScheduledOperation {
+            id,
+            due,
+            op,
+            repeat_days,
+        });
+        Ok(id)
+    }
+
+    pub fn scheduled_operation(&self, id: u64) -> Option<&ScheduledOperation> {
+        self.scheduler.get(id)
+    }
+
+    /// Every pending operation, soonest first.
+    pub fn scheduled_operations(&self) -> impl Iterator<Item = &ScheduledOperation> {
+        self.scheduler.iter()
+    }
+
+    pub fn scheduled_count(&self) -> usize {
+        self.scheduler.len()
+    }
+
+    /// The operation that runs next.
+    pub fn next_scheduled(&self) -> Option<&ScheduledOperation> {
+        self.scheduler.peek()
+    }
+
+    pub fn cancel_scheduled(&mut self, id: u64) -> Result<ScheduledOperation, AccountError> {
+        self.scheduler
+            .remove(id)
+            .ok_or(AccountError::State(StateError::ScheduledOperationNotFound))
+    }
+
+    /// Moves a pending operation to `due`. A standing order's later runs
+    /// follow on from the new time.
+    pub fn reschedule(&mut self, id: u64, due: Timestamp) -> Result<(), AccountError> {
+        let mut operation = self
+            .scheduler
+            .remove(id)
+            .ok_or(AccountError::State(StateError::ScheduledOperationNotFound))?;
+        operation.due = due;
+        self.scheduler.insert(operation);
+        Ok(())
+    }
+
+    /// Runs every operation due by now, soonest first. A failed operation
+    /// is not retried, though a standing order still runs next time.
+    pub fn run_scheduled(&mut self) -> Vec<ScheduledRun> {
+        self.run_scheduled_until(self.now)
+    }
+
+    /// Runs every operation due by `until` with the clock at its due time,
+    /// leaving the clock at the last one run.
+    pub(crate) fn run_scheduled_until(&mut self, until: Timestamp) -> Vec<ScheduledRun> {
+        let mut runs = Vec::new();
+        while let Some(operation) = self.scheduler.pop_due(until) {
+            self.now = self.now.max(operation.due);
+            let result = self.apply_batch_op(operation.op);
+            if let Some(days) = operation.repeat_days {
+                self.scheduler.insert(ScheduledOperation {
+                    due: operation.due.plus_days(days),
+                    ..operation
+                });
+            }
+            runs.push(ScheduledRun {
+                id: operation.id,
+                due: operation.due,
+                op: operation.op,
+                result,
+            });
+        }
+        self.scheduler.ran.extend(runs.iter().cloned());
+        runs
+    }
+}
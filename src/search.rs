@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::bank::Bank;
+use crate::transaction::Transaction;
+
+/// Points for a query word found as a whole word, by where it was found.
+const CATEGORY_SCORE: u32 = 3;
+const MEMO_SCORE: u32 = 2;
+/// Points for a query word that only starts a word, e.g. `gro` in
+/// `groceries`.
+const PREFIX_SCORE: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Memo,
+    Category,
+}
+
+/// A transaction found by [`SearchIndex::search`], with how well it matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchHit<'a> {
+    pub score: u32,
+    pub transaction: &'a Transaction,
+}
+
+/// The words in every transaction's memo and category, mapped to the
+/// entries they appear in. Words are compared in lowercase and split on
+/// anything that is not a letter or digit.
+///
+/// The index borrows the bank, so build it again after booking more
+/// entries.
+#[derive(Debug, Clone)]
+pub struct SearchIndex<'a> {
+    words: BTreeMap<String, Vec<(&'a Transaction, Field)>>,
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+impl<'a> SearchIndex<'a> {
+    pub fn build(bank: &'a Bank) -> Self {
+        let mut words_index: BTreeMap<String, Vec<(&'a Transaction, Field)>> = BTreeMap::new();
+        for id in bank.sorted_account_ids() {
+            for txn in &bank.accounts[&id].history {
+                for word in words(&txn.memo) {
+                    words_index
+                        .entry(word)
+                        .or_default()
+                        .push((txn, Field::Memo));
+                }
+                for word in txn.category.iter().flat_map(|category| words(category)) {
+                    words_index
+                        .entry(word)
+                        .or_default()
+                        .push((txn, Field::Category));
+                }
+            }
+        }
+        SearchIndex { words: words_index }
+    }
+
+    /// Transactions containing any word of `query`, best match first and
+    /// newest first among equals. A whole word in the category scores
+    /// highest, then one in the memo, then a word that only starts with
+    /// the query word; each query word counts once per transaction.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'a>> {
+        let mut scores: HashMap<u64, (u32, &'a Transaction)> = HashMap::new();
+        for term in words(query) {
+            let mut best: HashMap<u64, (u32, &'a Transaction)> = HashMap::new();
+            for (word, postings) in self.words.range(term.clone()..) {
+                if !word.starts_with(&term) {
+                    break;
+                }
+                for &(txn, field) in postings {
+                    let score = match field {
+                        _ if *word != term => PREFIX_SCORE,
+                        Field::Category => CATEGORY_SCORE,
+                        Field::Memo => MEMO_SCORE,
+                    };
+                    let entry = best.entry(txn.id).or_insert((0, txn));
+                    entry.0 = entry.0.max(score);
+                }
+            }
+            for (id, (score, txn)) in best {
+                scores.entry(id).or_insert((0, txn)).0 += score;
+            }
+        }
+        let mut hits: Vec<SearchHit<'a>> = scores
+            .into_values()
+            .map(|(score, transaction)| SearchHit { score, transaction })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(b.transaction.timestamp.cmp(&a.transaction.timestamp))
+                .then(b.transaction.id.cmp(&a.transaction.id))
+        });
+        hits
+    }
+}
+
+impl Bank {
+    /// Searches every account's memos and categories; see
+    /// [`SearchIndex::search`]. Build a [`SearchIndex`] once instead when
+    /// running several searches over a large history.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        SearchIndex::build(self).search(query)
+    }
+}
@@ -0,0 +1,1503 @@
+//! A line-oriented, tab-separated text format for saving and restoring a
+//! whole bank.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::account::{Account, Hold, PendingCheque};
+use crate::auth::{parse_hex, parse_hex_bytes, to_hex, Credential, DEFAULT_SESSION_TTL};
+use crate::bank::Bank;
+use crate::channels::{ChannelPolicy, DepositChannel};
+use crate::cosign::{CoSigner, PendingWithdrawal};
+use crate::currency::Currency;
+use crate::deductions::{DeductionOrder, DeductionStatus};
+use crate::disputes::{Dispute, DisputeStatus};
+use crate::documents::{Document, DocumentKind, DocumentOwner, DocumentStorage};
+use crate::dormancy::DormancyPolicy;
+use crate::duplicates::{DuplicateAction, DuplicateCheck};
+use crate::eod::EndOfDayStep;
+use crate::fee_debt::FeeShortfall;
+use crate::funds::UnitHolding;
+use crate::fx::FxOrder;
+use crate::goals::SavingsGoal;
+use crate::guardian::Guardianship;
+use crate::import::BatchOp;
+use crate::interest::InterestTier;
+use crate::locale::Locale;
+use crate::overdraft_interest::{GraceAccrual, OverdraftAccrual, OverdraftInterest};
+use crate::overflow::OverflowPolicy;
+use crate::ownership::OwnershipTransfer;
+use crate::payees::{PayeeLimitStage, PayeeLimits};
+use crate::promotions::{Promotion, PromotionAward};
+use crate::remainders::{RemainderLine, RoundingSource};
+use crate::rewards::{
+    RewardEarning, RewardEntry, RewardMatch, RewardRule, RewardsAccount, RewardsProgram,
+};
+use crate::rounding::RoundingPolicy;
+use crate::scheduler::ScheduledOperation;
+use crate::sweeps::{SweepKind, SweepRule};
+use crate::tagging::{TagChange, TagEdit};
+use crate::time::Timestamp;
+use crate::time_format::{TimeFormat, TimeZone};
+use crate::transaction::{Transaction, TransactionKind};
+use crate::uuid::Uuid;
+use crate::validation::{HolderValidation, ValidationMode};
+use crate::zero_amount::ZeroAmountPolicy;
+
+const HEADER: &str = "bank-snapshot v1";
+
+#[derive(Debug)]
+pub enum PersistError {
+    Io(io::Error),
+    Corrupt {
+        line: usize,
+        message: String,
+    },
+    /// An encrypted snapshot failed authentication: the passphrase or key
+    /// is wrong, or the data was modified.
+    Decryption,
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Io(error) => write!(f, "I/O error: {}", error),
+            PersistError::Corrupt { line, message } => {
+                write!(f, "Corrupt data at line {}: {}", line, message)
+            }
+            PersistError::Decryption => {
+                write!(
+                    f,
+                    "Could not decrypt: wrong passphrase or key, or modified data"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::Io(error) => Some(error),
+            PersistError::Corrupt { .. } | PersistError::Decryption => None,
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(error: io::Error) -> Self {
+        PersistError::Io(error)
+    }
+}
+
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn optional<T: fmt::Display>(value: Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn optional_text(value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("+{}", escape(value)),
+        None => "-".to_string(),
+    }
+}
+
+pub(crate) fn kind_code(kind: TransactionKind) -> &'static str {
+    match kind {
+        TransactionKind::OpeningBalance => "opening",
+        TransactionKind::Deposit => "deposit",
+        TransactionKind::Withdrawal => "withdrawal",
+        TransactionKind::TransferIn => "transfer-in",
+        TransactionKind::TransferOut => "transfer-out",
+        TransactionKind::ChequeDeposit => "cheque",
+        TransactionKind::Interest => "interest",
+        TransactionKind::Fee => "fee",
+        TransactionKind::Reward => "reward",
+        TransactionKind::Redenomination => "redenomination",
+        TransactionKind::Adjustment => "adjustment",
+        TransactionKind::Settlement => "settlement",
+        TransactionKind::Revaluation => "revaluation",
+        TransactionKind::Chargeback => "chargeback",
+        TransactionKind::OwnershipTransfer => "ownership",
+        TransactionKind::RoundingRemainder => "rounding-remainder",
+    }
+}
+
+pub(crate) fn parse_kind(code: &str) -> Option<TransactionKind> {
+    Some(match code {
+        "opening" => TransactionKind::OpeningBalance,
+        "deposit" => TransactionKind::Deposit,
+        "withdrawal" => TransactionKind::Withdrawal,
+        "transfer-in" => TransactionKind::TransferIn,
+        "transfer-out" => TransactionKind::TransferOut,
+        "cheque" => TransactionKind::ChequeDeposit,
+        "interest" => TransactionKind::Interest,
+        "fee" => TransactionKind::Fee,
+        "reward" => TransactionKind::Reward,
+        "redenomination" => TransactionKind::Redenomination,
+        "adjustment" => TransactionKind::Adjustment,
+        "settlement" => TransactionKind::Settlement,
+        "revaluation" => TransactionKind::Revaluation,
+        "chargeback" => TransactionKind::Chargeback,
+        "ownership" => TransactionKind::OwnershipTransfer,
+        "rounding-remainder" => TransactionKind::RoundingRemainder,
+        _ => return None,
+    })
+}
+
+fn rounding_code(rounding: RoundingPolicy) -> &'static str {
+    match rounding {
+        RoundingPolicy::Truncate => "truncate",
+        RoundingPolicy::HalfUp => "half-up",
+        RoundingPolicy::HalfEven => "half-even",
+    }
+}
+
+fn fee_shortfall_code(policy: FeeShortfall) -> &'static str {
+    match policy {
+        FeeShortfall::Skip => "skip",
+        FeeShortfall::Overdraw => "overdraw",
+    }
+}
+
+fn parse_fee_shortfall(code: &str) -> Option<FeeShortfall> {
+    Some(match code {
+        "skip" => FeeShortfall::Skip,
+        "overdraw" => FeeShortfall::Overdraw,
+        _ => return None,
+    })
+}
+
+fn grace_accrual_code(accrual: GraceAccrual) -> String {
+    match accrual {
+        GraceAccrual::Retroactive => "retroactive".to_string(),
+        GraceAccrual::FromDay(day) => day.to_string(),
+    }
+}
+
+fn parse_grace_accrual(code: &str) -> Option<GraceAccrual> {
+    match code {
+        "retroactive" => Some(GraceAccrual::Retroactive),
+        day => day.parse().ok().map(GraceAccrual::FromDay),
+    }
+}
+
+fn parse_rounding(code: &str) -> Option<RoundingPolicy> {
+    Some(match code {
+        "truncate" => RoundingPolicy::Truncate,
+        "half-up" => RoundingPolicy::HalfUp,
+        "half-even" => RoundingPolicy::HalfEven,
+        _ => return None,
+    })
+}
+
+fn locale_code(locale: Locale) -> &'static str {
+    match locale {
+        Locale::English => "en",
+        Locale::German => "de",
+        Locale::Georgian => "ka",
+    }
+}
+
+fn parse_locale(code: &str) -> Option<Locale> {
+    Some(match code {
+        "en" => Locale::English,
+        "de" => Locale::German,
+        "ka" => Locale::Georgian,
+        _ => return None,
+    })
+}
+
+fn step_code(step: EndOfDayStep) -> &'static str {
+    match step {
+        EndOfDayStep::PostInterest => "interest",
+        EndOfDayStep::ApplyFees => "fees",
+        EndOfDayStep::DetectDormancy => "dormancy",
+        EndOfDayStep::ClearHolds => "holds",
+        EndOfDayStep::ClearCheques => "cheques",
+        EndOfDayStep::PayPromotions => "promotions",
+        EndOfDayStep::RunSweeps => "sweeps",
+        EndOfDayStep::ExecuteFxOrders => "fx-orders",
+        EndOfDayStep::RevalueUnits => "revalue",
+        EndOfDayStep::GenerateStatements => "statements",
+        EndOfDayStep::SummaryReport => "summary",
+    }
+}
+
+fn status_code(status: DeductionStatus) -> &'static str {
+    match status {
+        DeductionStatus::Active => "active",
+        DeductionStatus::Completed => "completed",
+        DeductionStatus::Cancelled => "cancelled",
+    }
+}
+
+fn parse_status(code: &str) -> Option<DeductionStatus> {
+    Some(match code {
+        "active" => DeductionStatus::Active,
+        "completed" => DeductionStatus::Completed,
+        "cancelled" => DeductionStatus::Cancelled,
+        _ => return None,
+    })
+}
+
+fn parse_precision(code: &str) -> Option<u32> {
+    code.parse()
+        .ok()
+        .filter(|precision| *precision <= RoundingPolicy::MAX_PRECISION)
+}
+
+fn zero_amount_code(policy: ZeroAmountPolicy) -> &'static str {
+    match policy {
+        ZeroAmountPolicy::Ignore => "ignore",
+        ZeroAmountPolicy::Record => "record",
+        ZeroAmountPolicy::Reject => "reject",
+    }
+}
+
+fn parse_zero_amount(code: &str) -> Option<ZeroAmountPolicy> {
+    Some(match code {
+        "ignore" => ZeroAmountPolicy::Ignore,
+        "record" => ZeroAmountPolicy::Record,
+        "reject" => ZeroAmountPolicy::Reject,
+        _ => return None,
+    })
+}
+
+fn validation_mode_code(mode: ValidationMode) -> &'static str {
+    match mode {
+        ValidationMode::Lenient => "lenient",
+        ValidationMode::Strict => "strict",
+    }
+}
+
+fn parse_validation_mode(code: &str) -> Option<ValidationMode> {
+    Some(match code {
+        "lenient" => ValidationMode::Lenient,
+        "strict" => ValidationMode::Strict,
+        _ => return None,
+    })
+}
+
+fn overflow_code(policy: OverflowPolicy) -> &'static str {
+    match policy {
+        OverflowPolicy::Reject => "reject",
+        OverflowPolicy::Saturate => "saturate",
+        OverflowPolicy::Promote => "promote",
+    }
+}
+
+fn parse_overflow(code: &str) -> Option<OverflowPolicy> {
+    Some(match code {
+        "reject" => OverflowPolicy::Reject,
+        "saturate" => OverflowPolicy::Saturate,
+        "promote" => OverflowPolicy::Promote,
+        _ => return None,
+    })
+}
+
+fn dispute_status_code(status: DisputeStatus) -> &'static str {
+    match status {
+        DisputeStatus::Open => "open",
+        DisputeStatus::Resolved => "resolved",
+        DisputeStatus::ChargedBack => "charged-back",
+    }
+}
+
+fn parse_dispute_status(code: &str) -> Option<DisputeStatus> {
+    Some(match code {
+        "open" => DisputeStatus::Open,
+        "resolved" => DisputeStatus::Resolved,
+        "charged-back" => DisputeStatus::ChargedBack,
+        _ => return None,
+    })
+}
+
+/// The tab-separated fields of one record, consumed left to right.
+pub(crate) struct Fields<'a> {
+    line: usize,
+    parts: std::str::Split<'a, char>,
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn new(line: usize, text: &'a str) -> Self {
+        Fields {
+            line,
+            parts: text.split('\t'),
+        }
+    }
+
+    pub(crate) fn corrupt(&self, message: impl Into<String>) -> PersistError {
+        PersistError::Corrupt {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn raw(&mut self) -> Result<&'a str, PersistError> {
+        let line = self.line;
+        self.parts.next().ok_or(PersistError::Corrupt {
+            line,
+            message: "missing field".to_string(),
+        })
+    }
+
+    pub(crate) fn text(&mut self) -> Result<String, PersistError> {
+        Ok(unescape(self.raw()?))
+    }
+
+    pub(crate) fn parse<T: FromStr>(&mut self) -> Result<T, PersistError> {
+        let raw = self.raw()?;
+        raw.parse()
+            .map_err(|_| self.corrupt(format!("invalid value '{}'", raw)))
+    }
+
+    pub(crate) fn optional<T: FromStr>(&mut self) -> Result<Option<T>, PersistError> {
+        match self.raw()? {
+            "-" => Ok(None),
+            raw => raw
+                .parse()
+                .map(Some)
+                .map_err(|_| self.corrupt(format!("invalid value '{}'", raw))),
+        }
+    }
+
+    pub(crate) fn optional_text(&mut self) -> Result<Option<String>, PersistError> {
+        let raw = self.raw()?;
+        match raw.strip_prefix('+') {
+            Some(value) => Ok(Some(unescape(value))),
+            None if raw == "-" => Ok(None),
+            None => Err(self.corrupt("invalid optional text")),
+        }
+    }
+
+    pub(crate) fn code<T>(&mut self, parse: fn(&str) -> Option<T>) -> Result<T, PersistError> {
+        let raw = self.raw()?;
+        parse(raw).ok_or_else(|| self.corrupt(format!("unknown code '{}'", raw)))
+    }
+}
+
+impl Bank {
+    /// Writes the complete state of the bank as a text snapshot.
+    pub fn save_snapshot<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", HEADER)?;
+        let disabled: Vec<&str> = EndOfDayStep::ALL
+            .into_iter()
+            .filter(|step| !self.end_of_day_config.is_enabled(*step))
+            .map(step_code)
+            .collect();
+        let disabled = if disabled.is_empty() {
+            "-".to_string()
+        } else {
+            disabled.join(",")
+        };
+        writeln!(
+            writer,
+            "bank\t{}\t{}\t{}\t{}\t{}",
+            self.now.seconds(),
+            self.next_transaction_id,
+            self.next_item_id,
+            locale_code(self.locale),
+            disabled
+        )?;
+        if let Some(check) = self.duplicate_check {
+            let action = match check.action {
+                DuplicateAction::Warn => "warn",
+                DuplicateAction::Block => "block",
+            };
+            writeln!(
+                writer,
+                "duplicate-check\t{}\t{}",
+                check.window_seconds, action
+            )?;
+        }
+        if self.time_format != TimeFormat::default() {
+            writeln!(
+                writer,
+                "time-format\t{}\t{}\t{}",
+                self.time_format.zone.offset_seconds(),
+                escape(&self.time_format.timestamp_pattern),
+                escape(&self.time_format.date_pattern)
+            )?;
+        }
+        if self.holder_validation != HolderValidation::default() {
+            writeln!(
+                writer,
+                "holder-validation\t{}\t{}",
+                self.holder_validation.max_length, self.holder_validation.normalize_whitespace
+            )?;
+        }
+        if self.zero_amount_policy != ZeroAmountPolicy::default() {
+            writeln!(
+                writer,
+                "zero-amount\t{}",
+                zero_amount_code(self.zero_amount_policy)
+            )?;
+        }
+        if self.validation_mode != ValidationMode::default() {
+            writeln!(
+                writer,
+                "validation-mode\t{}",
+                validation_mode_code(self.validation_mode)
+            )?;
+        }
+        if self.overflow_policy != OverflowPolicy::default() {
+            writeln!(writer, "overflow\t{}", overflow_code(self.overflow_policy))?;
+        }
+        if let Some(policy) = self.dormancy_policy {
+            writeln!(
+                writer,
+                "dormancy\t{}\t{}",
+                policy.inactive_months, policy.monthly_fee
+            )?;
+        }
+        if let Some(limit) = self.holder_exposure_limit {
+            writeln!(writer, "exposure-limit\t{}", limit)?;
+        }
+        if let Some(limits) = &self.payee_limits {
+            writeln!(writer, "payee-limits")?;
+            for stage in limits.stages() {
+                writeln!(
+                    writer,
+                    "payee-limit\t{}\t{}",
+                    stage.until_day, stage.daily_limit
+                )?;
+            }
+        }
+        let mut payees: Vec<_> = self.payees.iter().collect();
+        payees.sort_unstable();
+        for ((from, to), added_at) in payees {
+            writeln!(writer, "payee\t{}\t{}\t{}", from, to, added_at.seconds())?;
+        }
+        if self.wal_sequence != 0 {
+            writeln!(writer, "wal-sequence\t{}", self.wal_sequence)?;
+        }
+        if let Some(marketing) = self.marketing_account {
+            writeln!(writer, "marketing\t{}", marketing)?;
+        }
+        if let Some(tax_account) = self.tax_account {
+            writeln!(writer, "tax-account\t{}", tax_account)?;
+        }
+        for (currency, account) in &self.rounding_accounts {
+            writeln!(writer, "rounding-account\t{}\t{}", currency, account)?;
+        }
+        for (channel, policy) in &self.channel_policies {
+            writeln!(
+                writer,
+                "channel-policy\t{}\t{}",
+                channel.code(),
+                policy.clearing_days
+            )?;
+        }
+        for line in self.remainders.values() {
+            writeln!(
+                writer,
+                "remainder\t{}\t{}\t{}\t{}\t{}\t{}",
+                line.currency,
+                line.source.code(),
+                line.denominator,
+                line.total,
+                line.posted,
+                line.carried
+            )?;
+        }
+        if self.auth.session_ttl != DEFAULT_SESSION_TTL {
+            writeln!(writer, "session-ttl\t{}", self.auth.session_ttl)?;
+        }
+        let mut holders: Vec<&String> = self.auth.credentials.keys().collect();
+        holders.sort();
+        for holder in holders {
+            let credential = &self.auth.credentials[holder];
+            writeln!(
+                writer,
+                "credential\t{}\t{}\t{}",
+                escape(holder),
+                to_hex(&credential.salt),
+                to_hex(&credential.hash)
+            )?;
+        }
+        for promotion in &self.promotions {
+            writeln!(
+                writer,
+                "promotion\t{}\t{}\t{}\t{}\t{}",
+                promotion.id,
+                promotion.min_deposit,
+                promotion.within_days,
+                promotion.bonus,
+                escape(&promotion.name)
+            )?;
+        }
+        if let DocumentStorage::Directory(directory) = &self.document_storage {
+            writeln!(
+                writer,
+                "document-storage\t{}",
+                escape(&directory.to_string_lossy())
+            )?;
+        }
+        for document in self.documents.values() {
+            let owner = match &document.owner {
+                DocumentOwner::Holder(holder) => ("holder", escape(holder)),
+                DocumentOwner::Account(id) => ("account", id.to_string()),
+            };
+            let content = document.content.as_deref().map(to_hex);
+            writeln!(
+                writer,
+                "document\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                document.id,
+                owner.0,
+                owner.1,
+                document.kind.code(),
+                document.size,
+                document.sha256_hex(),
+                document.attached_at.seconds(),
+                optional(content),
+                escape(&document.name)
+            )?;
+        }
+        for id in self.sorted_account_ids() {
+            let account = &self.accounts[&id];
+            writeln!(
+                writer,
+                "account\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                account.id,
+                account.reference,
+                escape(&account.holder),
+                account.balance,
+                optional(account.minimum_balance),
+                account.withdrawal_count,
+                account.withdrawal_total,
+                account.interest_rate_bps,
+                account.accrued_interest,
+                account.monthly_fee,
+                account.overdraft_limit,
+                account.overdraft_opt_in,
+                account.display_precision,
+                rounding_code(account.rounding),
+                account.currency
+            )?;
+            for tier in &account.interest_tiers {
+                writeln!(
+                    writer,
+                    "interest-tier\t{}\t{}\t{}",
+                    id,
+                    optional(tier.up_to),
+                    tier.rate_bps
+                )?;
+            }
+            for (key, value) in &account.meta {
+                writeln!(
+                    writer,
+                    "account-meta\t{}\t{}\t{}",
+                    id,
+                    escape(key),
+                    escape(value)
+                )?;
+            }
+            if let Some(opened_at) = account.opened_at {
+                writeln!(writer, "opened\t{}\t{}", id, opened_at.seconds())?;
+            }
+            if account.dormant_since.is_some() || account.reactivated_at.is_some() {
+                writeln!(
+                    writer,
+                    "dormant\t{}\t{}\t{}",
+                    id,
+                    optional(account.dormant_since.map(Timestamp::seconds)),
+                    optional(account.reactivated_at.map(Timestamp::seconds))
+                )?;
+            }
+            if account.promoted != 0 {
+                writeln!(writer, "promoted\t{}\t{}", id, account.promoted)?;
+            }
+            if let Some(holding) = &account.units {
+                writeln!(
+                    writer,
+                    "units\t{}\t{}\t{}\t{}",
+                    id,
+                    escape(&holding.fund),
+                    holding.units,
+                    holding.unit_price
+                )?;
+            }
+            for goal in &account.goals {
+                writeln!(
+                    writer,
+                    "goal\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    goal.id,
+                    goal.target,
+                    goal.deadline.seconds(),
+                    goal.created_at.seconds(),
+                    escape(&goal.name)
+                )?;
+                for (time, amount) in &goal.contributions {
+                    writeln!(
+                        writer,
+                        "goal-contribution\t{}\t{}\t{}",
+                        id,
+                        time.seconds(),
+                        amount
+                    )?;
+                }
+            }
+            for hold in &account.holds {
+                writeln!(
+                    writer,
+                    "hold\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    hold.id,
+                    hold.amount,
+                    hold.expires_at.seconds(),
+                    escape(&hold.reason)
+                )?;
+            }
+            if account.fee_shortfall != FeeShortfall::Skip || account.fee_debt != 0 {
+                writeln!(
+                    writer,
+                    "fee-shortfall\t{}\t{}\t{}",
+                    id,
+                    fee_shortfall_code(account.fee_shortfall),
+                    account.fee_debt
+                )?;
+            }
+            if let Some(policy) = account.overdraft_interest {
+                let state = account.overdraft_accrual;
+                writeln!(
+                    writer,
+                    "overdraft-interest\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    policy.rate_bps,
+                    policy.grace_days,
+                    grace_accrual_code(policy.accrual),
+                    state.days,
+                    state.deferred,
+                    state.accrued
+                )?;
+            }
+            if let Some(co_signer) = &account.co_signer {
+                writeln!(
+                    writer,
+                    "co-signer\t{}\t{}\t{}\t{}",
+                    id,
+                    co_signer.threshold,
+                    co_signer.expiry_days,
+                    escape(&co_signer.name)
+                )?;
+            }
+            if let Some(guardianship) = &account.guardianship {
+                writeln!(
+                    writer,
+                    "guardianship\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    guardianship.guardian_id,
+                    optional(guardianship.daily_spending_cap),
+                    optional(guardianship.approval_threshold),
+                    guardianship.approval_expiry_days,
+                    guardianship.full_control_at.seconds()
+                )?;
+            }
+            if account.ownership_approval_required {
+                writeln!(writer, "ownership-approval\t{}", id)?;
+            }
+            if let Some(request) = &account.pending_ownership_transfer {
+                writeln!(
+                    writer,
+                    "ownership-transfer\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    request.id,
+                    request.requested_at.seconds(),
+                    request.approved_by_holder,
+                    request.approved_by_new_holder,
+                    escape(&request.new_holder)
+                )?;
+            }
+            for request in &account.pending_withdrawals {
+                writeln!(
+                    writer,
+                    "pending-withdrawal\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    request.id,
+                    request.amount,
+                    request.requested_at.seconds(),
+                    request.expires_at.seconds(),
+                    optional_text(request.category.as_deref()),
+                    optional(request.to)
+                )?;
+            }
+            for cheque in &account.pending_cheques {
+                writeln!(
+                    writer,
+                    "cheque\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    cheque.id,
+                    cheque.amount,
+                    cheque.clears_at.seconds(),
+                    cheque.channel.code()
+                )?;
+            }
+            for txn in &account.history {
+                write_transaction(writer, txn)?;
+            }
+            for dispute in &account.disputes {
+                writeln!(
+                    writer,
+                    "dispute\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    dispute.transaction_id,
+                    dispute.amount,
+                    dispute.hold_id,
+                    dispute.opened_at.seconds(),
+                    dispute_status_code(dispute.status)
+                )?;
+            }
+            for award in &account.promotion_awards {
+                writeln!(
+                    writer,
+                    "promotion-award\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    award.promotion_id,
+                    award.deposit_transaction,
+                    award.transaction_id,
+                    award.amount,
+                    award.awarded_at.seconds()
+                )?;
+            }
+            if let Some(rewards) = self.rewards.get(&id) {
+                writeln!(
+                    writer,
+                    "rewards\t{}\t{}\t{}\t{}\t{}",
+                    id,
+                    rewards.program.cents_per_point,
+                    rewards.cashback_balance,
+                    rewards.points_balance,
+                    rewards.cashback_remainder
+                )?;
+                for rule in &rewards.program.rules {
+                    let (match_kind, match_value) = match &rule.matches {
+                        RewardMatch::Category(category) => ("category", escape(category)),
+                        RewardMatch::Payee(payee) => ("payee", payee.to_string()),
+                        RewardMatch::AnySpend => ("any", "-".to_string()),
+                    };
+                    let (earning_kind, earning_value) = match rule.earning {
+                        RewardEarning::Cashback { rate_bps } => ("cashback", rate_bps),
+                        RewardEarning::Points { per_dollar } => ("points", per_dollar),
+                    };
+                    writeln!(
+                        writer,
+                        "reward-rule\t{}\t{}\t{}\t{}\t{}",
+                        id, match_kind, match_value, earning_kind, earning_value
+                    )?;
+                }
+                for entry in &rewards.entries {
+                    writeln!(
+                        writer,
+                        "reward-entry\t{}\t{}\t{}\t{}\t{}",
+                        id,
+                        entry.transaction_id,
+                        entry.timestamp.seconds(),
+                        entry.cashback,
+                        entry.points
+                    )?;
+                }
+            }
+        }
+        for order in &self.deduction_orders {
+            writeln!(
+                writer,
+                "deduction\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                order.id,
+                order.account_id,
+                order.beneficiary_id,
+                order.rate_bps,
+                order.target,
+                order.collected,
+                status_code(order.status)
+            )?;
+        }
+        for rule in &self.sweep_rules {
+            let kind = match rule.kind {
+                SweepKind::Excess => "excess",
+                SweepKind::TopUp => "top-up",
+            };
+            writeln!(
+                writer,
+                "sweep\t{}\t{}\t{}\t{}\t{}\t{}",
+                rule.id, kind, rule.account_id, rule.counterparty_id, rule.threshold, rule.paused
+            )?;
+        }
+        for order in &self.fx_orders {
+            writeln!(
+                writer,
+                "fx-order\t{}\t{}\t{}\t{}\t{}\t{}",
+                order.id,
+                order.from_account,
+                order.to_account,
+                order.amount,
+                order.trigger_rate,
+                order.expires_at.seconds()
+            )?;
+        }
+        for operation in self.scheduler.iter() {
+            let (kind, account, amount, to) = match operation.op {
+                BatchOp::Deposit { account, amount } => ("deposit", account, amount, None),
+                BatchOp::Withdraw { account, amount } => ("withdraw", account, amount, None),
+                BatchOp::Transfer { from, to, amount } => ("transfer", from, amount, Some(to)),
+            };
+            writeln!(
+                writer,
+                "scheduled\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                operation.id,
+                operation.due.seconds(),
+                optional(operation.repeat_days),
+                kind,
+                account,
+                amount,
+                optional(to)
+            )?;
+        }
+        for edit in &self.tag_edits {
+            let (change, value) = match &edit.change {
+                TagChange::AddTag(tag) => ("add", Some(tag.as_str())),
+                TagChange::RemoveTag(tag) => ("remove", Some(tag.as_str())),
+                TagChange::SetCategory(category) => ("category", category.as_deref()),
+            };
+            writeln!(
+                writer,
+                "tag-edit\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                edit.timestamp.seconds(),
+                edit.account_id,
+                edit.transaction_id,
+                change,
+                optional_text(value),
+                optional_text(edit.replaced_category.as_deref()),
+                escape(&edit.actor)
+            )?;
+        }
+        writeln!(writer, "end")
+    }
+
+    /// Rebuilds a bank from a snapshot written by [`Bank::save_snapshot`].
+    pub fn load_snapshot<R: BufRead>(reader: R) -> Result<Bank, PersistError> {
+        let mut bank = Bank::starting_at(Timestamp::default());
+        let mut lines = reader.lines().enumerate();
+        let header = lines.next().map(|(_, line)| line).transpose()?;
+        match header.as_deref() {
+            Some(HEADER) => {}
+            _ => {
+                return Err(PersistError::Corrupt {
+                    line: 1,
+                    message: "missing snapshot header".to_string(),
+                })
+            }
+        }
+        let mut finished = false;
+        for (index, line) in lines {
+            let line = line?;
+            let mut fields = Fields::new(index + 1, &line);
+            match fields.raw()? {
+                "bank" => {
+                    bank.now = Timestamp(fields.parse()?);
+                    bank.next_transaction_id = fields.parse()?;
+                    bank.next_item_id = fields.parse()?;
+                    bank.locale = fields.code(parse_locale)?;
+                    let disabled = fields.raw()?;
+                    for step in EndOfDayStep::ALL {
+                        if disabled.split(',').any(|code| code == step_code(step)) {
+                            bank.end_of_day_config.disable(step);
+                        }
+                    }
+                }
+                "duplicate-check" => {
+                    let window_seconds = fields.parse()?;
+                    let action = match fields.raw()? {
+                        "warn" => DuplicateAction::Warn,
+                        "block" => DuplicateAction::Block,
+                        _ => return Err(fields.corrupt("unknown duplicate action")),
+                    };
+                    bank.duplicate_check = Some(DuplicateCheck::new(window_seconds, action));
+                }
+                "exposure-limit" => {
+                    bank.holder_exposure_limit = Some(fields.parse()?);
+                }
+                "payee-limits" => {
+                    bank.payee_limits = Some(PayeeLimits::new());
+                }
+                "payee-limit" => {
+                    let stage = PayeeLimitStage {
+                        until_day: fields.parse()?,
+                        daily_limit: fields.parse()?,
+                    };
+                    bank.payee_limits
+                        .as_mut()
+                        .ok_or_else(|| fields.corrupt("payee limit before payee-limits"))?
+                        .stages
+                        .push(stage);
+                }
+                "payee" => {
+                    let pair = (fields.parse()?, fields.parse()?);
+                    bank.payees.insert(pair, Timestamp(fields.parse()?));
+                }
+                "wal-sequence" => bank.wal_sequence = fields.parse()?,
+                "marketing" => {
+                    bank.marketing_account = Some(fields.parse()?);
+                }
+                "tax-account" => bank.tax_account = Some(fields.parse()?),
+                "rounding-account" => {
+                    let currency = fields.code(Currency::new)?;
+                    bank.rounding_accounts.insert(currency, fields.parse()?);
+                }
+                "channel-policy" => {
+                    let channel = fields.code(DepositChannel::from_code)?;
+                    let policy = ChannelPolicy::clearing(fields.parse()?);
+                    bank.channel_policies.insert(channel, policy);
+                }
+                "remainder" => {
+                    let line = RemainderLine {
+                        currency: fields.code(Currency::new)?,
+                        source: fields.code(RoundingSource::from_code)?,
+                        denominator: fields.parse()?,
+                        total: fields.parse()?,
+                        posted: fields.parse()?,
+                        carried: fields.parse()?,
+                    };
+                    let key = (line.currency, line.source, line.denominator);
+                    bank.remainders.insert(key, line);
+                }
+                "session-ttl" => bank.auth.session_ttl = fields.parse()?,
+                "credential" => {
+                    let holder = fields.text()?;
+                    let credential = Credential {
+                        salt: fields.code(parse_hex::<16>)?,
+                        hash: fields.code(parse_hex::<32>)?,
+                    };
+                    bank.auth.credentials.insert(holder, credential);
+                }
+                "document-storage" => {
+                    bank.document_storage = DocumentStorage::Directory(fields.text()?.into());
+                }
+                "document" => {
+                    let id = fields.parse()?;
+                    let owner = match fields.raw()? {
+                        "holder" => DocumentOwner::Holder(fields.text()?),
+                        "account" => DocumentOwner::Account(fields.parse()?),
+                        _ => return Err(fields.corrupt("unknown document owner")),
+                    };
+                    let kind = fields.code(DocumentKind::from_code)?;
+                    let size = fields.parse()?;
+                    let sha256 = parse_hex(fields.raw()?)
+                        .ok_or_else(|| fields.corrupt("document hash is not valid hex"))?;
+                    let attached_at = Timestamp(fields.parse()?);
+                    let content = match fields.raw()? {
+                        "-" => None,
+                        hex => Some(parse_hex_bytes(hex).ok_or_else(|| {
+                            fields.corrupt("document contents are not valid hex")
+                        })?),
+                    };
+                    bank.documents.insert(
+                        id,
+                        Document {
+                            id,
+                            owner,
+                            kind,
+                            name: fields.text()?,
+                            size,
+                            sha256,
+                            attached_at,
+                            content,
+                        },
+                    );
+                }
+                "promotion" => {
+                    let promotion = Promotion {
+                        id: fields.parse()?,
+                        min_deposit: fields.parse()?,
+                        within_days: fields.parse()?,
+                        bonus: fields.parse()?,
+                        name: fields.text()?,
+                    };
+                    bank.promotions.push(promotion);
+                }
+                "time-format" => {
+                    let zone = TimeZone::from_offset_seconds(fields.parse()?)
+                        .ok_or_else(|| fields.corrupt("time zone offset out of range"))?;
+                    bank.time_format = TimeFormat::new()
+                        .zone(zone)
+                        .timestamps(&fields.text()?)
+                        .dates(&fields.text()?);
+                }
+                "holder-validation" => {
+                    bank.holder_validation = HolderValidation {
+                        max_length: fields.parse()?,
+                        normalize_whitespace: fields.parse()?,
+                    };
+                }
+                "zero-amount" => {
+                    bank.zero_amount_policy = fields.code(parse_zero_amount)?;
+                }
+                "validation-mode" => {
+                    bank.validation_mode = fields.code(parse_validation_mode)?;
+                }
+                "overflow" => bank.overflow_policy = fields.code(parse_overflow)?,
+                "dormancy" => {
+                    let policy = DormancyPolicy::after_months(fields.parse()?);
+                    bank.dormancy_policy = Some(policy.with_fee(fields.parse()?));
+                }
+                "account" => {
+                    let id = fields.parse()?;
+                    let reference: Uuid = fields.parse()?;
+                    let mut account = Account::new(id, fields.text()?);
+                    account.reference = reference;
+                    account.balance = fields.parse()?;
+                    account.minimum_balance = fields.optional()?;
+                    account.withdrawal_count = fields.parse()?;
+                    account.withdrawal_total = fields.parse()?;
+                    account.interest_rate_bps = fields.parse()?;
+                    account.accrued_interest = fields.parse()?;
+                    account.monthly_fee = fields.parse()?;
+                    account.overdraft_limit = fields.parse()?;
+                    account.overdraft_opt_in = fields.parse()?;
+                    account.display_precision = fields.code(parse_precision)?;
+                    account.rounding = fields.code(parse_rounding)?;
+                    account.currency = fields.code(Currency::new)?;
+                    bank.references.insert(reference, id);
+                    bank.accounts.insert(id, account);
+                }
+                "account-meta" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let key = fields.text()?;
+                    account.meta.insert(key, fields.text()?);
+                }
+                "interest-tier" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let tier = InterestTier {
+                        up_to: fields.optional()?,
+                        rate_bps: fields.parse()?,
+                    };
+                    account.interest_tiers.push(tier);
+                }
+                "opened" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.opened_at = Some(Timestamp(fields.parse()?));
+                }
+                "dormant" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.dormant_since = fields.optional()?.map(Timestamp);
+                    account.reactivated_at = fields.optional()?.map(Timestamp);
+                }
+                "promoted" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.promoted = fields.parse()?;
+                }
+                "units" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.units = Some(UnitHolding {
+                        fund: fields.text()?,
+                        units: fields.parse()?,
+                        unit_price: fields.parse()?,
+                    });
+                }
+                "txn-meta" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let key = fields.text()?;
+                    let value = fields.text()?;
+                    let txn = account
+                        .history
+                        .last_mut()
+                        .ok_or_else(|| fields.corrupt("metadata before any transaction"))?;
+                    txn.metadata.insert(key, value);
+                }
+                "txn-tag" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let tag = fields.text()?;
+                    let txn = account
+                        .history
+                        .last_mut()
+                        .ok_or_else(|| fields.corrupt("tag before any transaction"))?;
+                    txn.tags.insert(tag);
+                }
+                "goal" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let goal = SavingsGoal {
+                        id: fields.parse()?,
+                        target: fields.parse()?,
+                        deadline: Timestamp(fields.parse()?),
+                        created_at: Timestamp(fields.parse()?),
+                        name: fields.text()?,
+                        contributions: Vec::new(),
+                    };
+                    account.goals.push(goal);
+                }
+                "goal-contribution" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let contribution = (Timestamp(fields.parse()?), fields.parse()?);
+                    let goal = account
+                        .goals
+                        .last_mut()
+                        .ok_or_else(|| fields.corrupt("contribution before any goal"))?;
+                    goal.contributions.push(contribution);
+                }
+                "hold" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let hold = Hold {
+                        id: fields.parse()?,
+                        amount: fields.parse()?,
+                        expires_at: Timestamp(fields.parse()?),
+                        reason: fields.text()?,
+                    };
+                    account.holds.push(hold);
+                }
+                "fee-shortfall" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.fee_shortfall = fields.code(parse_fee_shortfall)?;
+                    account.fee_debt = fields.parse()?;
+                }
+                "overdraft-interest" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.overdraft_interest = Some(OverdraftInterest {
+                        rate_bps: fields.parse()?,
+                        grace_days: fields.parse()?,
+                        accrual: fields.code(parse_grace_accrual)?,
+                    });
+                    account.overdraft_accrual = OverdraftAccrual {
+                        days: fields.parse()?,
+                        deferred: fields.parse()?,
+                        accrued: fields.parse()?,
+                    };
+                }
+                "co-signer" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.co_signer = Some(CoSigner {
+                        threshold: fields.parse()?,
+                        expiry_days: fields.parse()?,
+                        name: fields.text()?,
+                    });
+                }
+                "guardianship" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.guardianship = Some(Guardianship {
+                        guardian_id: fields.parse()?,
+                        daily_spending_cap: fields.optional()?,
+                        approval_threshold: fields.optional()?,
+                        approval_expiry_days: fields.parse()?,
+                        full_control_at: Timestamp(fields.parse()?),
+                    });
+                }
+                "ownership-approval" => {
+                    account_mut(&mut bank, &mut fields)?.ownership_approval_required = true;
+                }
+                "ownership-transfer" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    account.pending_ownership_transfer = Some(OwnershipTransfer {
+                        id: fields.parse()?,
+                        requested_at: Timestamp(fields.parse()?),
+                        approved_by_holder: fields.parse()?,
+                        approved_by_new_holder: fields.parse()?,
+                        new_holder: fields.text()?,
+                    });
+                }
+                "pending-withdrawal" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let request = PendingWithdrawal {
+                        id: fields.parse()?,
+                        amount: fields.parse()?,
+                        requested_at: Timestamp(fields.parse()?),
+                        expires_at: Timestamp(fields.parse()?),
+                        category: fields.optional_text()?,
+                        to: fields.optional()?,
+                    };
+                    account.pending_withdrawals.push(request);
+                }
+                "dispute" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let dispute = Dispute {
+                        transaction_id: fields.parse()?,
+                        amount: fields.parse()?,
+                        hold_id: fields.parse()?,
+                        opened_at: Timestamp(fields.parse()?),
+                        status: fields.code(parse_dispute_status)?,
+                    };
+                    account.disputes.push(dispute);
+                }
+                "promotion-award" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let award = PromotionAward {
+                        promotion_id: fields.parse()?,
+                        deposit_transaction: fields.parse()?,
+                        transaction_id: fields.parse()?,
+                        amount: fields.parse()?,
+                        awarded_at: Timestamp(fields.parse()?),
+                    };
+                    account.promotion_awards.push(award);
+                }
+                "cheque" => {
+                    let account = account_mut(&mut bank, &mut fields)?;
+                    let cheque = PendingCheque {
+                        id: fields.parse()?,
+                        amount: fields.parse()?,
+                        clears_at: Timestamp(fields.parse()?),
+                        channel: fields.code(DepositChannel::from_code)?,
+                    };
+                    account.pending_cheques.push(cheque);
+                }
+                "txn" => {
+                    let txn = read_transaction(&mut fields)?;
+                    let account = bank
+                        .accounts
+                        .get_mut(&txn.account_id)
+                        .ok_or_else(|| fields.corrupt("transaction for unknown account"))?;
+                    account
+                        .partitions
+                        .insert(account.history.len(), txn.timestamp);
+                    account.history.push(txn);
+                }
+                "rewards" => {
+                    let id: u32 = fields.parse()?;
+                    let rewards = RewardsAccount {
+                        program: RewardsProgram::new(fields.parse()?),
+                        cashback_balance: fields.parse()?,
+                        points_balance: fields.parse()?,
+                        cashback_remainder: fields.parse()?,
+                        entries: Vec::new(),
+                    };
+                    bank.rewards.insert(id, rewards);
+                }
+                "reward-rule" => {
+                    let rewards = rewards_mut(&mut bank, &mut fields)?;
+                    let matches = match fields.raw()? {
+                        "category" => RewardMatch::Category(fields.text()?),
+                        "payee" => RewardMatch::Payee(fields.parse()?),
+                        "any" => {
+                            fields.raw()?;
+                            RewardMatch::AnySpend
+                        }
+                        _ => return Err(fields.corrupt("unknown reward match")),
+                    };
+                    let earning = match fields.raw()? {
+                        "cashback" => RewardEarning::Cashback {
+                            rate_bps: fields.parse()?,
+                        },
+                        "points" => RewardEarning::Points {
+                            per_dollar: fields.parse()?,
+                        },
+                        _ => return Err(fields.corrupt("unknown reward earning")),
+                    };
+                    rewards
+                        .program
+                        .rules
+                        .push(RewardRule::new(matches, earning));
+                }
+                "reward-entry" => {
+                    let rewards = rewards_mut(&mut bank, &mut fields)?;
+                    let entry = RewardEntry {
+                        transaction_id: fields.parse()?,
+                        timestamp: Timestamp(fields.parse()?),
+                        cashback: fields.parse()?,
+                        points: fields.parse()?,
+                    };
+                    rewards.entries.push(entry);
+                }
+                "deduction" => {
+                    let order = DeductionOrder {
+                        id: fields.parse()?,
+                        account_id: fields.parse()?,
+                        beneficiary_id: fields.parse()?,
+                        rate_bps: fields.parse()?,
+                        target: fields.parse()?,
+                        collected: fields.parse()?,
+                        status: fields.code(parse_status)?,
+                    };
+                    bank.deduction_orders.push(order);
+                }
+                "sweep" => {
+                    let id = fields.parse()?;
+                    let kind = match fields.raw()? {
+                        "excess" => SweepKind::Excess,
+                        "top-up" => SweepKind::TopUp,
+                        _ => return Err(fields.corrupt("unknown sweep kind")),
+                    };
+                    bank.sweep_rules.push(SweepRule {
+                        id,
+                        kind,
+                        account_id: fields.parse()?,
+                        counterparty_id: fields.parse()?,
+                        threshold: fields.parse()?,
+                        paused: fields.parse()?,
+                    });
+                }
+                "tag-edit" => {
+                    let timestamp = Timestamp(fields.parse()?);
+                    let (account_id, transaction_id) = (fields.parse()?, fields.parse()?);
+                    let kind = fields.raw()?;
+                    let value: Option<String> = fields.optional_text()?;
+                    let change = match (kind, value) {
+                        ("add", Some(tag)) => TagChange::AddTag(tag),
+                        ("remove", Some(tag)) => TagChange::RemoveTag(tag),
+                        ("category", category) => TagChange::SetCategory(category),
+                        _ => return Err(fields.corrupt("unknown tag change")),
+                    };
+                    bank.tag_edits.push(TagEdit {
+                        timestamp,
+                        account_id,
+                        transaction_id,
+                        change,
+                        replaced_category: fields.optional_text()?,
+                        actor: fields.text()?,
+                    });
+                }
+                "fx-order" => bank.fx_orders.push(FxOrder {
+                    id: fields.parse()?,
+                    from_account: fields.parse()?,
+                    to_account: fields.parse()?,
+                    amount: fields.parse()?,
+                    trigger_rate: fields.parse()?,
+                    expires_at: Timestamp(fields.parse()?),
+                }),
+                "scheduled" => {
+                    let id = fields.parse()?;
+                    let due = Timestamp(fields.parse()?);
+                    let repeat_days = fields.optional()?;
+                    let kind = fields.raw()?;
+                    let (account, amount, to) =
+                        (fields.parse()?, fields.parse()?, fields.optional()?);
+                    let op = match (kind, to) {
+                        ("deposit", None) => BatchOp::Deposit { account, amount },
+                        ("withdraw", None) => BatchOp::Withdraw { account, amount },
+                        ("transfer", Some(to)) => BatchOp::Transfer {
+                            from: account,
+                            to,
+                            amount,
+                        },
+                        _ => return Err(fields.corrupt("invalid scheduled operation")),
+                    };
+                    bank.scheduler.insert(ScheduledOperation {
+                        id,
+                        due,
+                        op,
+                        repeat_days,
+                    });
+                }
+                "end" => {
+                    finished = true;
+                    break;
+                }
+                other => return Err(fields.corrupt(format!("unknown record '{}'", other))),
+            }
+        }
+        if !finished {
+            return Err(PersistError::Corrupt {
+                line: 0,
+                message: "snapshot is truncated".to_string(),
+            });
+        }
+        Ok(bank)
+    }
+}
+
+pub(crate) fn write_transaction<W: Write + ?Sized>(
+    writer: &mut W,
+    txn: &Transaction,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "txn\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        txn.account_id,
+        txn.id,
+        kind_code(txn.kind),
+        txn.amount,
+        txn.balance_after,
+        optional(txn.counterparty),
+        txn.timestamp.seconds(),
+        rounding_code(txn.rounding),
+        optional_text(txn.category.as_deref()),
+        txn.chain_hash,
+        escape(&txn.memo)
+    )?;
+    for (key, value) in &txn.metadata {
+        writeln!(
+            writer,
+            "txn-meta\t{}\t{}\t{}",
+            txn.account_id,
+            escape(key),
+            escape(value)
+        )?;
+    }
+    for tag in &txn.tags {
+        writeln!(writer, "txn-tag\t{}\t{}", txn.account_id, escape(tag))?;
+    }
+    Ok(())
+}
+
+/// Reads the fields after the `txn` tag.
+pub(crate) fn read_transaction(fields: &mut Fields<'_>) -> Result<Transaction, PersistError> {
+    Ok(Transaction {
+        account_id: fields.parse()?,
+        id: fields.parse()?,
+        kind: fields.code(parse_kind)?,
+        amount: fields.parse()?,
+        balance_after: fields.parse()?,
+        counterparty: fields.optional()?,
+        timestamp: Timestamp(fields.parse()?),
+        rounding: fields.code(parse_rounding)?,
+        category: fields.optional_text()?,
+        chain_hash: fields.parse()?,
+        memo: fields.text()?,
+        metadata: BTreeMap::new(),
+        tags: BTreeSet::new(),
+    })
+}
+
+fn account_mut<'a>(
+    bank: &'a mut Bank,
+    fields: &mut Fields<'_>,
+) -> Result<&'a mut Account, PersistError> {
+    let id: u32 = fields.parse()?;
+    bank.accounts
+        .get_mut(&id)
+        .ok_or_else(|| fields.corrupt(format!("unknown account {}", id)))
+}
+
+fn rewards_mut<'a>(
+    bank: &'a mut Bank,
+    fields: &mut Fields<'_>,
+) -> Result<&'a mut RewardsAccount, PersistError> {
+    let id: u32 = fields.parse()?;
+    bank.rewards
+        .get_mut(&id)
+        .ok_or_else(|| fields.corrupt(format!("no rewards for account {}", id)))
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::account::Account;
+use crate::currency::Currency;
+use crate::locale::{Locale, Localize};
+use crate::time::Timestamp;
+use crate::transaction::Transaction;
+
+/// The transactions booked on one account over `[period_start, period_end]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub account_id: u32,
+    pub holder: String,
+    /// The account's currency, which every amount is in.
+    pub currency: Currency,
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+    pub opening_balance: i64,
+    pub closing_balance: i64,
+    pub transactions: Vec<Transaction>,
+}
+
+impl Statement {
+    pub fn for_period(account: &Account, period_start: Timestamp, period_end: Timestamp) -> Self {
+        let transactions: Vec<Transaction> = account
+            .history_between(period_start, period_end)
+            .into_iter()
+            .cloned()
+            .collect();
+        let opening_balance = match transactions.first() {
+            Some(first) => first.balance_after - first.amount,
+            None => account
+                .history
+                .iter()
+                .rev()
+                .find(|txn| txn.timestamp < period_start)
+                .map(|txn| txn.balance_after)
+                .unwrap_or(account.balance),
+        };
+        let closing_balance = transactions
+            .last()
+            .map(|txn| txn.balance_after)
+            .unwrap_or(opening_balance);
+        Statement {
+            account_id: account.id,
+            holder: account.holder.clone(),
+            currency: account.currency,
+            period_start,
+            period_end,
+            opening_balance,
+            closing_balance,
+            transactions,
+        }
+    }
+
+    pub fn net_change(&self) -> i64 {
+        self.closing_balance - self.opening_balance
+    }
+
+    /// What changed going from this statement to `other`, pairing
+    /// transactions by id. Useful to check that a replay or import
+    /// reproduced the original.
+    pub fn diff(&self, other: &Statement) -> StatementDiff {
+        let before: HashMap<u64, &Transaction> =
+            self.transactions.iter().map(|txn| (txn.id, txn)).collect();
+        let after: HashMap<u64, &Transaction> =
+            other.transactions.iter().map(|txn| (txn.id, txn)).collect();
+        let mut diff = StatementDiff {
+            balance_delta: other.closing_balance - self.closing_balance,
+            ..StatementDiff::default()
+        };
+        for txn in &self.transactions {
+            match after.get(&txn.id) {
+                None => diff.removed.push(txn.clone()),
+                Some(changed) if !same_entry(txn, changed) => {
+                    diff.changed.push(TransactionChange {
+                        before: txn.clone(),
+                        after: (*changed).clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.added = other
+            .transactions
+            .iter()
+            .filter(|txn| !before.contains_key(&txn.id))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+/// Compares everything but the chain hash, which changes for every entry
+/// after the first one that differs.
+fn same_entry(a: &Transaction, b: &Transaction) -> bool {
+    Transaction {
+        chain_hash: b.chain_hash,
+        ..a.clone()
+    } == *b
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionChange {
+    pub before: Transaction,
+    pub after: Transaction,
+}
+
+/// The result of [`Statement::diff`] or [`Account::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatementDiff {
+    pub added: Vec<Transaction>,
+    pub removed: Vec<Transaction>,
+    pub changed: Vec<TransactionChange>,
+    /// The second closing balance minus the first.
+    pub balance_delta: i64,
+}
+
+impl StatementDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.balance_delta == 0
+    }
+}
+
+impl fmt::Display for StatementDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences");
+        }
+        writeln!(
+            f,
+            "{} added, {} removed, {} changed; balance {:+}",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len(),
+            self.balance_delta
+        )?;
+        for txn in &self.added {
+            writeln!(f, "+ {}", txn)?;
+        }
+        for txn in &self.removed {
+            writeln!(f, "- {}", txn)?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {}", change.before)?;
+            writeln!(f, "  {}", change.after)?;
+        }
+        Ok(())
+    }
+}
+
+impl Account {
+    /// [`Statement::diff`] over the whole history of two copies of an
+    /// account, such as before and after a snapshot round trip.
+    pub fn diff(&self, other: &Account) -> StatementDiff {
+        let whole =
+            |account: &Account| Statement::for_period(account, Timestamp(0), Timestamp(u64::MAX));
+        whole(self).diff(&whole(other))
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
@@ -0,0 +1,326 @@
+//! Checking an account's ledger against a statement from outside the bank,
+//! such as a card processor's or correspondent bank's export.
+
+use std::fmt;
+use std::io::BufRead;
+
+use crate::account::Account;
+use crate::currency::Currency;
+use crate::import::ImportError;
+use crate::locale::Locale;
+use crate::money::Money;
+use crate::time::{Date, Timestamp};
+use crate::transaction::{Transaction, TransactionKind};
+
+/// Unmatched entries for the same amount at most this many days apart are
+/// suggested as the same movement booked on different dates.
+const SUGGESTION_WINDOW_DAYS: i64 = 5;
+
+/// One row of an external statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementLine {
+    /// The row's line number in the file.
+    pub line: usize,
+    pub date: Date,
+    /// In the account currency's minor unit, negative for money going out.
+    pub amount: i64,
+    pub description: String,
+}
+
+/// A statement row and a ledger entry on the same day and in the same
+/// direction, but for different amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountMismatch {
+    pub line: StatementLine,
+    pub transaction_id: u64,
+    pub ledger_amount: i64,
+}
+
+impl AmountMismatch {
+    /// Statement minus ledger.
+    pub fn difference(&self) -> i64 {
+        self.line.amount - self.ledger_amount
+    }
+}
+
+/// A statement row with no ledger entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingEntry {
+    pub line: StatementLine,
+    /// An unmatched ledger entry for the same amount a few days away,
+    /// likely the same movement booked on another date.
+    pub suggestion: Option<u64>,
+}
+
+/// A ledger entry the statement doesn't show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraEntry {
+    pub transaction_id: u64,
+    pub date: Date,
+    pub amount: i64,
+    /// The line number of an unmatched statement row for the same amount
+    /// a few days away.
+    pub suggestion: Option<usize>,
+}
+
+/// The result of [`Account::reconcile_csv`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementReconciliation {
+    pub account_id: u32,
+    pub currency: Currency,
+    /// `(statement line, transaction id)` pairs that agree exactly.
+    pub matched: Vec<(usize, u64)>,
+    pub amount_mismatches: Vec<AmountMismatch>,
+    pub missing: Vec<MissingEntry>,
+    pub extra: Vec<ExtraEntry>,
+}
+
+impl StatementReconciliation {
+    /// Whether the statement and the ledger agree entry for entry.
+    pub fn is_reconciled(&self) -> bool {
+        self.amount_mismatches.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+impl fmt::Display for StatementReconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let money = |amount| Locale::English.money_in(amount, self.currency);
+        write!(
+            f,
+            "Statement reconciliation for account {}: {} matched, {} amount mismatches, {} missing, {} extra",
+            self.account_id,
+            self.matched.len(),
+            self.amount_mismatches.len(),
+            self.missing.len(),
+            self.extra.len()
+        )?;
+        for mismatch in &self.amount_mismatches {
+            write!(
+                f,
+                "\n  line {} ({}, {}) vs transaction {} ({}): off by {}",
+                mismatch.line.line,
+                mismatch.line.date,
+                money(mismatch.line.amount),
+                mismatch.transaction_id,
+                money(mismatch.ledger_amount),
+                money(mismatch.difference())
+            )?;
+        }
+        for missing in &self.missing {
+            write!(
+                f,
+                "\n  line {} ({}, {}) not in the ledger",
+                missing.line.line,
+                missing.line.date,
+                money(missing.line.amount)
+            )?;
+            if let Some(txn_id) = missing.suggestion {
+                write!(f, "; maybe transaction {}", txn_id)?;
+            }
+        }
+        for extra in &self.extra {
+            write!(
+                f,
+                "\n  transaction {} ({}, {}) not on the statement",
+                extra.transaction_id,
+                extra.date,
+                money(extra.amount)
+            )?;
+            if let Some(line) = extra.suggestion {
+                write!(f, "; maybe line {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Account {
+    /// Compares the ledger with an external statement of
+    /// `date,amount[,description]` rows, dates as `YYYY-MM-DD` and amounts
+    /// in the account's currency (`-25.00`). A header row and blank lines
+    /// are skipped, and fields may be quoted.
+    ///
+    /// Only ledger entries between the statement's first and last dates
+    /// are compared. Rows matching an entry's date and amount are matched;
+    /// the rest are paired with a same-day entry in the same direction as
+    /// an amount mismatch, or else reported as missing or extra.
+    pub fn reconcile_csv<R: BufRead>(
+        &self,
+        reader: R,
+    ) -> Result<StatementReconciliation, ImportError> {
+        let lines = parse_statement(reader, self.currency)?;
+        let mut report = StatementReconciliation {
+            account_id: self.id,
+            currency: self.currency,
+            matched: Vec::new(),
+            amount_mismatches: Vec::new(),
+            missing: Vec::new(),
+            extra: Vec::new(),
+        };
+        let (Some(first), Some(last)) = (
+            lines.iter().map(|line| line.date).min(),
+            lines.iter().map(|line| line.date).max(),
+        ) else {
+            return Ok(report);
+        };
+        let entries: Vec<&Transaction> = self
+            .history_between(
+                Timestamp::from_date(first),
+                Timestamp::from_date(last).end_of_day(),
+            )
+            .into_iter()
+            .filter(|txn| txn.kind != TransactionKind::OpeningBalance && txn.amount != 0)
+            .collect();
+        let mut used = vec![false; entries.len()];
+
+        let mut unmatched = Vec::new();
+        for line in lines {
+            let exact = (0..entries.len()).find(|&index| {
+                !used[index]
+                    && entries[index].timestamp.date() == line.date
+                    && entries[index].amount == line.amount
+            });
+            match exact {
+                Some(index) => {
+                    used[index] = true;
+                    report.matched.push((line.line, entries[index].id));
+                }
+                None => unmatched.push(line),
+            }
+        }
+
+        let mut missing = Vec::new();
+        for line in unmatched {
+            let same_day = (0..entries.len())
+                .filter(|&index| {
+                    !used[index]
+                        && entries[index].timestamp.date() == line.date
+                        && entries[index].amount.signum() == line.amount.signum()
+                })
+                .min_by_key(|&index| (entries[index].amount - line.amount).abs());
+            match same_day {
+                Some(index) => {
+                    used[index] = true;
+                    report.amount_mismatches.push(AmountMismatch {
+                        line,
+                        transaction_id: entries[index].id,
+                        ledger_amount: entries[index].amount,
+                    });
+                }
+                None => missing.push(line),
+            }
+        }
+
+        let days_apart = |a: Date, b: Date| (a.days_since_epoch() - b.days_since_epoch()).abs();
+        let extra: Vec<&Transaction> = entries
+            .iter()
+            .zip(&used)
+            .filter(|(_, used)| !**used)
+            .map(|(txn, _)| *txn)
+            .collect();
+        report.missing = missing
+            .into_iter()
+            .map(|line| {
+                let suggestion = extra
+                    .iter()
+                    .filter(|txn| {
+                        txn.amount == line.amount
+                            && days_apart(txn.timestamp.date(), line.date) <= SUGGESTION_WINDOW_DAYS
+                    })
+                    .min_by_key(|txn| days_apart(txn.timestamp.date(), line.date))
+                    .map(|txn| txn.id);
+                MissingEntry { line, suggestion }
+            })
+            .collect();
+        report.extra = extra
+            .iter()
+            .map(|txn| {
+                let date = txn.timestamp.date();
+                let suggestion = report
+                    .missing
+                    .iter()
+                    .filter(|missing| {
+                        missing.line.amount == txn.amount
+                            && days_apart(missing.line.date, date) <= SUGGESTION_WINDOW_DAYS
+                    })
+                    .min_by_key(|missing| days_apart(missing.line.date, date))
+                    .map(|missing| missing.line.line);
+                ExtraEntry {
+                    transaction_id: txn.id,
+                    date,
+                    amount: txn.amount,
+                    suggestion,
+                }
+            })
+            .collect();
+        Ok(report)
+    }
+}
+
+fn parse_statement<R: BufRead>(
+    reader: R,
+    currency: Currency,
+) -> Result<Vec<StatementLine>, ImportError> {
+    let mut lines = Vec::new();
+    let mut seen_row = false;
+    for (index, line) in reader.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message: String| ImportError {
+            line: line_number,
+            message,
+        };
+        let line = line.map_err(|io| error(io.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_row(&line).map_err(error)?;
+        let is_header = !seen_row && fields[0].eq_ignore_ascii_case("date");
+        seen_row = true;
+        if is_header {
+            continue;
+        }
+        let date = Date::parse(&fields[0])
+            .ok_or_else(|| error(format!("invalid date '{}'", fields[0])))?;
+        let amount = match fields.get(1).filter(|amount| !amount.is_empty()) {
+            Some(amount) => Money::parse_in(amount, Locale::English, currency)
+                .map_err(|parse| error(format!("invalid amount '{}': {}", amount, parse)))?
+                .cents(),
+            None => return Err(error("missing amount".to_string())),
+        };
+        lines.push(StatementLine {
+            line: line_number,
+            date,
+            amount,
+            description: fields.get(2).cloned().unwrap_or_default(),
+        });
+    }
+    Ok(lines)
+}
+
+/// Splits a CSV row into trimmed fields, unquoting quoted ones.
+fn split_row(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            ch => field.push(ch),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".to_string());
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
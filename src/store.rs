@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::ops::Index;
+
+use crate::account::Account;
+use crate::bank::Bank;
+
+/// How a bank keeps its accounts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AccountStorage {
+    /// A hash map keyed by id: any ids, at the cost of hashing on every
+    /// lookup.
+    #[default]
+    Map,
+    /// A vector indexed by id, for simulations whose ids run densely from
+    /// one. Lookups are a bounds check and an index, and accounts sit next
+    /// to each other in memory; every id up to the largest takes a slot,
+    /// so sparse or very large ids waste memory.
+    Dense,
+}
+
+/// The accounts of a bank, behind the handful of map operations the rest
+/// of the crate uses.
+#[derive(Debug, Clone)]
+pub(crate) enum AccountStore {
+    Map(HashMap<u32, Account>),
+    Dense {
+        slots: Vec<Option<Account>>,
+        len: usize,
+    },
+}
+
+impl AccountStore {
+    pub fn new(storage: AccountStorage) -> Self {
+        match storage {
+            AccountStorage::Map => AccountStore::Map(HashMap::new()),
+            AccountStorage::Dense => AccountStore::Dense {
+                slots: Vec::new(),
+                len: 0,
+            },
+        }
+    }
+
+    pub fn storage(&self) -> AccountStorage {
+        match self {
+            AccountStore::Map(_) => AccountStorage::Map,
+            AccountStore::Dense { .. } => AccountStorage::Dense,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            AccountStore::Map(map) => map.len(),
+            AccountStore::Dense { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, id: &u32) -> Option<&Account> {
+        match self {
+            AccountStore::Map(map) => map.get(id),
+            AccountStore::Dense { slots, .. } => slots.get(*id as usize)?.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, id: &u32) -> Option<&mut Account> {
+        match self {
+            AccountStore::Map(map) => map.get_mut(id),
+            AccountStore::Dense { slots, .. } => slots.get_mut(*id as usize)?.as_mut(),
+        }
+    }
+
+    pub fn contains_key(&self, id: &u32) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Both accounts at once. Panics if the ids are the same, like
+    /// [`HashMap::get_disjoint_mut`].
+    pub fn get_disjoint_mut(&mut self, [first, second]: [&u32; 2]) -> [Option<&mut Account>; 2] {
+        match self {
+            AccountStore::Map(map) => map.get_disjoint_mut([first, second]),
+            AccountStore::Dense { slots, .. } => {
+                let (first, second) = (*first as usize, *second as usize);
+                assert_ne!(first, second, "account ids must differ");
+                let (low, high) = (first.min(second), first.max(second));
+                if high >= slots.len() {
+                    let low = slots.get_mut(low).and_then(Option::as_mut);
+                    return match first < second {
+                        true => [low, None],
+                        false => [None, low],
+                    };
+                }
+                let (head, tail) = slots.split_at_mut(high);
+                let (low, high) = (head[low].as_mut(), tail[0].as_mut());
+                match first < second {
+                    true => [low, high],
+                    false => [high, low],
+                }
+            }
+        }
+    }
+
+    pub fn insert(&mut self, id: u32, account: Account) -> Option<Account> {
+        match self {
+            AccountStore::Map(map) => map.insert(id, account),
+            AccountStore::Dense { slots, len } => {
+                let index = id as usize;
+                if index >= slots.len() {
+                    slots.resize_with(index + 1, || None);
+                }
+                let previous = slots[index].replace(account);
+                if previous.is_none() {
+                    *len += 1;
+                }
+                previous
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: &u32) -> Option<Account> {
+        match self {
+            AccountStore::Map(map) => map.remove(id),
+            AccountStore::Dense { slots, len } => {
+                let removed = slots.get_mut(*id as usize)?.take();
+                if removed.is_some() {
+                    *len -= 1;
+                }
+                removed
+            }
+        }
+    }
+
+    /// Account ids, in ascending order for dense storage and in no
+    /// particular order for a map.
+    pub fn keys(&self) -> Box<dyn Iterator<Item = &u32> + '_> {
+        Box::new(self.values().map(|account| &account.id))
+    }
+
+    pub fn values(&self) -> Box<dyn Iterator<Item = &Account> + '_> {
+        match self {
+            AccountStore::Map(map) => Box::new(map.values()),
+            AccountStore::Dense { slots, .. } => Box::new(slots.iter().flatten()),
+        }
+    }
+
+    pub fn values_mut(&mut self) -> Box<dyn Iterator<Item = &mut Account> + '_> {
+        match self {
+            AccountStore::Map(map) => Box::new(map.values_mut()),
+            AccountStore::Dense { slots, .. } => Box::new(slots.iter_mut().flatten()),
+        }
+    }
+
+    pub fn into_values(self) -> Box<dyn Iterator<Item = Account>> {
+        match self {
+            AccountStore::Map(map) => Box::new(map.into_values()),
+            AccountStore::Dense { slots, .. } => Box::new(slots.into_iter().flatten()),
+        }
+    }
+
+    /// Makes room for `additional` more accounts; with dense storage, for
+    /// ids up to `additional` past the current largest.
+    pub fn reserve(&mut self, additional: usize) {
+        match self {
+            AccountStore::Map(map) => map.reserve(additional),
+            AccountStore::Dense { slots, .. } => slots.reserve(additional),
+        }
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            AccountStore::Map(map) => map.shrink_to_fit(),
+            AccountStore::Dense { slots, .. } => {
+                while slots.last().is_some_and(Option::is_none) {
+                    slots.pop();
+                }
+                slots.shrink_to_fit();
+            }
+        }
+    }
+}
+
+impl Default for AccountStore {
+    fn default() -> Self {
+        AccountStore::new(AccountStorage::default())
+    }
+}
+
+impl Index<&u32> for AccountStore {
+    type Output = Account;
+
+    fn index(&self, id: &u32) -> &Account {
+        self.get(id).expect("no account with this id")
+    }
+}
+
+impl Extend<(u32, Account)> for AccountStore {
+    fn extend<I: IntoIterator<Item = (u32, Account)>>(&mut self, accounts: I) {
+        for (id, account) in accounts {
+            self.insert(id, account);
+        }
+    }
+}
+
+impl Bank {
+    /// Creates a bank keeping its accounts in `storage`.
+    pub fn with_storage(storage: AccountStorage) -> Self {
+        let mut bank = Bank::new();
+        bank.accounts = AccountStore::new(storage);
+        bank
+    }
+
+    pub fn storage(&self) -> AccountStorage {
+        self.accounts.storage()
+    }
+
+    /// Moves every account into `storage`, e.g. after loading a snapshot,
+    /// which always starts with a map.
+    pub fn set_storage(&mut self, storage: AccountStorage) {
+        if storage == self.accounts.storage() {
+            return;
+        }
+        let old = std::mem::replace(&mut self.accounts, AccountStore::new(storage));
+        self.accounts
+            .extend(old.into_values().map(|account| (account.id, account)));
+    }
+}
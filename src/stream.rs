@@ -0,0 +1,272 @@
+//! Applies a stream of client operations one row at a time, for inputs too
+//! large to load as a batch. Memory grows with the number of clients and the
+//! dispute window, never with the number of rows.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::account::{Account, Hold};
+use crate::error::{AccountError, PolicyError, StateError};
+use crate::money::Money;
+use crate::time::Timestamp;
+
+/// How many past deposits are remembered for disputes unless told otherwise.
+const DEFAULT_DISPUTE_WINDOW: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamOp {
+    Deposit(i64),
+    Withdrawal(i64),
+    Dispute,
+    Resolve,
+    Chargeback,
+}
+
+/// One `client,tx,type,amount` row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamRow {
+    pub client: u32,
+    pub tx: u64,
+    pub op: StreamOp,
+}
+
+impl StreamRow {
+    /// Parses a row; `amount` is only read for deposits and withdrawals.
+    pub fn parse(line: &str) -> Result<StreamRow, String> {
+        let mut fields = line.split(',').map(str::trim);
+        let mut field = |name: &str| {
+            fields
+                .next()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| format!("missing {}", name))
+        };
+        let client = field("client")?
+            .parse()
+            .map_err(|_| "invalid client".to_string())?;
+        let tx = field("tx")?.parse().map_err(|_| "invalid tx".to_string())?;
+        let kind = field("type")?.to_ascii_lowercase();
+        let mut amount = || -> Result<i64, String> {
+            let amount = Money::parse(field("amount")?).map_err(|error| error.to_string())?;
+            Ok(amount.cents())
+        };
+        let op = match kind.as_str() {
+            "deposit" => StreamOp::Deposit(amount()?),
+            "withdraw" | "withdrawal" => StreamOp::Withdrawal(amount()?),
+            "dispute" => StreamOp::Dispute,
+            "resolve" => StreamOp::Resolve,
+            "chargeback" => StreamOp::Chargeback,
+            other => return Err(format!("unknown operation '{}'", other)),
+        };
+        Ok(StreamRow { client, tx, op })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamReport {
+    pub rows: usize,
+    pub applied: usize,
+    /// Rows refused by the account, e.g. withdrawals without the funds or
+    /// disputes of unknown transactions.
+    pub rejected: usize,
+    pub malformed: usize,
+}
+
+impl fmt::Display for StreamReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Processed {} rows: {} applied, {} rejected, {} malformed",
+            self.rows, self.applied, self.rejected, self.malformed
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Client {
+    account: Account,
+    locked: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RememberedDeposit {
+    client: u32,
+    amount: i64,
+}
+
+/// Client accounts built up from a stream of rows.
+///
+/// Deposits are remembered so they can be disputed, but only the most recent
+/// `dispute_window` of them; older ones can no longer be disputed. A
+/// chargeback locks the client, and rows for locked clients are rejected.
+#[derive(Debug)]
+pub struct StreamProcessor {
+    clients: BTreeMap<u32, Client>,
+    deposits: HashMap<u64, RememberedDeposit>,
+    deposit_order: VecDeque<u64>,
+    disputed: HashMap<u64, RememberedDeposit>,
+    dispute_window: usize,
+    report: StreamReport,
+}
+
+impl Default for StreamProcessor {
+    fn default() -> Self {
+        StreamProcessor::with_dispute_window(DEFAULT_DISPUTE_WINDOW)
+    }
+}
+
+impl StreamProcessor {
+    pub fn new() -> Self {
+        StreamProcessor::default()
+    }
+
+    pub fn with_dispute_window(deposits: usize) -> Self {
+        StreamProcessor {
+            clients: BTreeMap::new(),
+            deposits: HashMap::new(),
+            deposit_order: VecDeque::new(),
+            disputed: HashMap::new(),
+            dispute_window: deposits,
+            report: StreamReport::default(),
+        }
+    }
+
+    /// Reads and applies every row. A header line starting with `client` and
+    /// blank lines are skipped; unparseable rows are counted and skipped.
+    pub fn process<R: BufRead>(&mut self, mut reader: R) -> io::Result<StreamReport> {
+        let mut line = String::new();
+        let mut line_number = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            line_number += 1;
+            let row = line.trim();
+            if row.is_empty() || (line_number == 1 && row.starts_with("client")) {
+                continue;
+            }
+            self.report.rows += 1;
+            match StreamRow::parse(row) {
+                Ok(row) => {
+                    let _ = self.apply(row);
+                }
+                Err(_) => self.report.malformed += 1,
+            }
+        }
+        Ok(self.report)
+    }
+
+    /// Applies one row, counting it as applied or rejected.
+    pub fn apply(&mut self, row: StreamRow) -> Result<(), AccountError> {
+        let result = self.apply_row(row);
+        match result {
+            Ok(()) => self.report.applied += 1,
+            Err(_) => self.report.rejected += 1,
+        }
+        result
+    }
+
+    pub fn report(&self) -> StreamReport {
+        self.report
+    }
+
+    /// Writes `client,available,held,total,locked` for every client, in id
+    /// order.
+    pub fn write_accounts<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "client,available,held,total,locked")?;
+        for (id, client) in &self.clients {
+            let account = &client.account;
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                id,
+                plain_amount(account.available_balance()),
+                plain_amount(account.held_amount()),
+                plain_amount(account.balance()),
+                client.locked
+            )?;
+        }
+        Ok(())
+    }
+
+    fn apply_row(&mut self, row: StreamRow) -> Result<(), AccountError> {
+        let client = self.clients.entry(row.client).or_insert_with(|| Client {
+            account: Account::new(row.client, format!("Client {}", row.client)),
+            locked: false,
+        });
+        if client.locked {
+            return Err(AccountError::Policy(PolicyError::AccountLocked));
+        }
+        let account = &mut client.account;
+        match row.op {
+            StreamOp::Deposit(amount) => {
+                if self.deposits.contains_key(&row.tx) || self.disputed.contains_key(&row.tx) {
+                    return Err(AccountError::Policy(PolicyError::PossibleDuplicate {
+                        previous_transaction: row.tx,
+                    }));
+                }
+                account.deposit(amount)?;
+                self.remember(row.tx, row.client, amount);
+            }
+            StreamOp::Withdrawal(amount) => {
+                account.withdraw(amount)?;
+            }
+            StreamOp::Dispute => {
+                let deposit = match self.deposits.get(&row.tx) {
+                    Some(deposit) if deposit.client == row.client => *deposit,
+                    Some(_) => return Err(AccountError::State(StateError::TransactionNotFound)),
+                    None if self.disputed.contains_key(&row.tx) => {
+                        return Err(AccountError::State(StateError::NotDisputable))
+                    }
+                    None => return Err(AccountError::State(StateError::TransactionNotFound)),
+                };
+                account.holds.push(Hold {
+                    id: row.tx,
+                    amount: deposit.amount,
+                    expires_at: Timestamp(u64::MAX),
+                    reason: format!("Dispute of transaction #{}", row.tx),
+                });
+                self.deposits.remove(&row.tx);
+                self.disputed.insert(row.tx, deposit);
+            }
+            StreamOp::Resolve | StreamOp::Chargeback => {
+                let deposit = match self.disputed.get(&row.tx) {
+                    Some(deposit) if deposit.client == row.client => *deposit,
+                    _ => return Err(AccountError::State(StateError::DisputeNotFound)),
+                };
+                self.disputed.remove(&row.tx);
+                account.holds.retain(|hold| hold.id != row.tx);
+                if row.op == StreamOp::Chargeback {
+                    account.balance -= deposit.amount;
+                    client.locked = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn remember(&mut self, tx: u64, client: u32, amount: i64) {
+        if self.dispute_window == 0 {
+            return;
+        }
+        while self.deposit_order.len() >= self.dispute_window {
+            match self.deposit_order.pop_front() {
+                Some(oldest) => {
+                    self.deposits.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        self.deposits
+            .insert(tx, RememberedDeposit { client, amount });
+        self.deposit_order.push_back(tx);
+    }
+}
+
+/// Cents as a plain decimal, e.g. `-12.50`.
+fn plain_amount(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+}
@@ -0,0 +1,187 @@
+//! Flags "structuring": splitting cash into several deposits just under a
+//! reporting threshold so that none of them has to be reported.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::time::Timestamp;
+use crate::transaction::{Transaction, TransactionKind};
+
+/// What counts as a structuring pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructuringRule {
+    /// Deposits of this many cents or more are reported anyway.
+    pub threshold: i64,
+    /// Deposits at most this far under the threshold count as "just
+    /// under" it.
+    pub band: i64,
+    pub window_days: u32,
+    /// How many such deposits within the window make a pattern.
+    pub min_deposits: usize,
+}
+
+impl StructuringRule {
+    /// Three or more deposits within 10% under `threshold` in seven days.
+    pub fn new(threshold: i64) -> Self {
+        StructuringRule {
+            threshold,
+            band: threshold / 10,
+            window_days: 7,
+            min_deposits: 3,
+        }
+    }
+
+    pub fn band(mut self, band: i64) -> Self {
+        self.band = band;
+        self
+    }
+
+    pub fn window_days(mut self, days: u32) -> Self {
+        self.window_days = days;
+        self
+    }
+
+    pub fn min_deposits(mut self, count: usize) -> Self {
+        self.min_deposits = count;
+        self
+    }
+
+    fn is_just_under(&self, amount: i64) -> bool {
+        amount < self.threshold && amount >= self.threshold.saturating_sub(self.band)
+    }
+}
+
+/// One holder's run of deposits just under the threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuringAlert {
+    pub holder: String,
+    /// The holder's accounts the deposits went into.
+    pub accounts: Vec<u32>,
+    /// Oldest first.
+    pub deposits: Vec<Transaction>,
+    pub total: i64,
+}
+
+impl StructuringAlert {
+    pub fn first_deposit(&self) -> Timestamp {
+        self.deposits[0].timestamp
+    }
+
+    pub fn last_deposit(&self) -> Timestamp {
+        self.deposits[self.deposits.len() - 1].timestamp
+    }
+}
+
+/// The result of [`Bank::structuring_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuringReport {
+    pub rule: StructuringRule,
+    pub alerts: Vec<StructuringAlert>,
+}
+
+impl fmt::Display for StructuringReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dollars = |cents: i64| format!("${:.2}", cents as f64 / 100.0);
+        writeln!(f, "Structuring report")?;
+        writeln!(
+            f,
+            "Rule: {} or more deposits between {} and {} within {} days",
+            self.rule.min_deposits,
+            dollars(self.rule.threshold - self.rule.band),
+            dollars(self.rule.threshold - 1),
+            self.rule.window_days
+        )?;
+        writeln!(f, "Reporting threshold: {}", dollars(self.rule.threshold))?;
+        if self.alerts.is_empty() {
+            return write!(f, "\nNo patterns found.");
+        }
+        for (index, alert) in self.alerts.iter().enumerate() {
+            let accounts: Vec<String> = alert.accounts.iter().map(u32::to_string).collect();
+            writeln!(
+                f,
+                "\nAlert {}: {} (accounts {})",
+                index + 1,
+                alert.holder,
+                accounts.join(", ")
+            )?;
+            writeln!(
+                f,
+                "  {} deposits totalling {} between {} and {}",
+                alert.deposits.len(),
+                dollars(alert.total),
+                alert.first_deposit(),
+                alert.last_deposit()
+            )?;
+            for txn in &alert.deposits {
+                writeln!(
+                    f,
+                    "    {}  account {:<6} {:>12}",
+                    txn.timestamp,
+                    txn.account_id,
+                    dollars(txn.amount)
+                )?;
+            }
+        }
+        write!(
+            f,
+            "\nAlerts: {}. A pattern is a reason to look closer, not proof of intent.",
+            self.alerts.len()
+        )
+    }
+}
+
+impl Bank {
+    /// Looks across each holder's accounts (by exact holder name) for
+    /// deposits just under `rule.threshold` bunched within the window.
+    /// Deposits in one alert are not counted again in another.
+    pub fn structuring_report(&self, rule: &StructuringRule) -> StructuringReport {
+        let mut by_holder: BTreeMap<&str, Vec<&Transaction>> = BTreeMap::new();
+        for id in self.sorted_account_ids() {
+            let account = &self.accounts[&id];
+            let deposits = account.history.iter().filter(|txn| {
+                matches!(
+                    txn.kind,
+                    TransactionKind::Deposit | TransactionKind::ChequeDeposit
+                ) && rule.is_just_under(txn.amount)
+            });
+            by_holder
+                .entry(account.holder.as_str())
+                .or_default()
+                .extend(deposits);
+        }
+
+        let mut alerts = Vec::new();
+        for (holder, mut deposits) in by_holder {
+            deposits.sort_by_key(|txn| (txn.timestamp, txn.id));
+            let mut start = 0;
+            while start < deposits.len() {
+                let window_end = deposits[start].timestamp.plus_days(rule.window_days);
+                let end = start
+                    + deposits[start..]
+                        .iter()
+                        .take_while(|txn| txn.timestamp < window_end)
+                        .count();
+                if end - start < rule.min_deposits.max(1) {
+                    start += 1;
+                    continue;
+                }
+                let run = &deposits[start..end];
+                let mut accounts: Vec<u32> = run.iter().map(|txn| txn.account_id).collect();
+                accounts.sort_unstable();
+                accounts.dedup();
+                alerts.push(StructuringAlert {
+                    holder: holder.to_string(),
+                    accounts,
+                    deposits: run.iter().map(|txn| (*txn).clone()).collect(),
+                    total: run.iter().map(|txn| txn.amount).sum(),
+                });
+                start = end;
+            }
+        }
+        StructuringReport {
+            rule: *rule,
+            alerts,
+        }
+    }
+}
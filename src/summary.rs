@@ -0,0 +1,265 @@
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::json;
+use crate::locale::Locale;
+use crate::time::Timestamp;
+use crate::uuid::Uuid;
+
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// One account's line in a machine-readable summary. Amounts are in cents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountSummary {
+    pub id: u32,
+    pub reference: Uuid,
+    pub holder: String,
+    pub currency: Currency,
+    pub balance: i64,
+    pub available_balance: i64,
+}
+
+impl AccountSummary {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"id\":{},\"reference\":{},\"holder\":{},\"currency\":{},\"balance\":{},\"available_balance\":{}}}",
+            self.id,
+            json::string(&self.reference.to_string()),
+            json::string(&self.holder),
+            json::string(self.currency.code()),
+            self.balance,
+            self.available_balance
+        )
+    }
+}
+
+/// Which accounts a summary includes. Every condition set must hold; the
+/// default query matches every account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SummaryQuery {
+    non_zero: bool,
+    above: Option<i64>,
+    below: Option<i64>,
+    currency: Option<Currency>,
+    modified: Option<(Timestamp, Timestamp)>,
+}
+
+impl SummaryQuery {
+    pub fn new() -> Self {
+        SummaryQuery::default()
+    }
+
+    /// Only accounts whose balance is not zero.
+    pub fn non_zero(mut self) -> Self {
+        self.non_zero = true;
+        self
+    }
+
+    /// Only balances strictly above `threshold` cents.
+    pub fn balance_above(mut self, threshold: i64) -> Self {
+        self.above = Some(threshold);
+        self
+    }
+
+    /// Only balances strictly below `threshold` cents.
+    pub fn balance_below(mut self, threshold: i64) -> Self {
+        self.below = Some(threshold);
+        self
+    }
+
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    /// Only accounts with an entry booked between `from` and `to`,
+    /// inclusive.
+    pub fn modified_between(mut self, from: Timestamp, to: Timestamp) -> Self {
+        self.modified = Some((from, to));
+        self
+    }
+
+    pub fn matches(&self, account: &Account) -> bool {
+        (!self.non_zero || account.balance != 0)
+            && self
+                .above
+                .is_none_or(|threshold| account.balance > threshold)
+            && self
+                .below
+                .is_none_or(|threshold| account.balance < threshold)
+            && self
+                .currency
+                .is_none_or(|currency| account.currency == currency)
+            && self
+                .modified
+                .is_none_or(|(from, to)| !account.history_between(from, to).is_empty())
+    }
+}
+
+const CSV_HEADER: &str = "id,reference,holder,currency,balance,available_balance";
+
+/// The locale key for an account's status column.
+fn status_key(account: &Account) -> &'static str {
+    if account.balance < 0 {
+        "status.overdrawn"
+    } else if account
+        .minimum_balance
+        .is_some_and(|minimum| account.balance < minimum)
+    {
+        "status.below_minimum"
+    } else if !account.holds.is_empty() {
+        "status.funds_held"
+    } else {
+        "status.active"
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Bank {
+    /// Every account's summary, ordered by id.
+    pub fn account_summaries(&self) -> Vec<AccountSummary> {
+        self.account_summaries_where(&SummaryQuery::new())
+    }
+
+    /// The summaries of the accounts matching `query`, ordered by id.
+    pub fn account_summaries_where(&self, query: &SummaryQuery) -> Vec<AccountSummary> {
+        self.matching_ids(query)
+            .into_iter()
+            .map(|id| {
+                let account = &self.accounts[&id];
+                AccountSummary {
+                    id,
+                    reference: account.reference,
+                    holder: account.holder.clone(),
+                    currency: account.currency,
+                    balance: account.balance,
+                    available_balance: account.available_balance(),
+                }
+            })
+            .collect()
+    }
+
+    /// The account summaries as a JSON array of objects.
+    pub fn summary_json(&self) -> String {
+        self.summary_json_where(&SummaryQuery::new())
+    }
+
+    pub fn summary_json_where(&self, query: &SummaryQuery) -> String {
+        let accounts: Vec<String> = self
+            .account_summaries_where(query)
+            .iter()
+            .map(AccountSummary::to_json)
+            .collect();
+        format!("[{}]", accounts.join(","))
+    }
+
+    /// Every account as an aligned table of id, holder, status, currency and
+    /// balance, ordered by id. With `color`, negative balances are printed
+    /// in red using ANSI escapes.
+    pub fn summary_table(&self, color: bool) -> String {
+        self.summary_table_in(self.locale, color)
+    }
+
+    pub fn summary_table_in(&self, locale: Locale, color: bool) -> String {
+        self.summary_table_where(&SummaryQuery::new(), locale, color)
+    }
+
+    /// The table from [`Bank::summary_table_in`], limited to the accounts
+    /// matching `query`.
+    pub fn summary_table_where(&self, query: &SummaryQuery, locale: Locale, color: bool) -> String {
+        let header = [
+            "table.id",
+            "table.holder",
+            "table.status",
+            "table.currency",
+            "table.balance",
+        ]
+        .map(|key| locale.text(key).to_string());
+        let mut rows = Vec::new();
+        let mut negative = Vec::new();
+        for id in self.matching_ids(query) {
+            let account = &self.accounts[&id];
+            rows.push([
+                id.to_string(),
+                account.holder.clone(),
+                locale.text(status_key(account)).to_string(),
+                account.currency.to_string(),
+                locale.money_units(
+                    account.balance,
+                    account.currency,
+                    account.display_precision,
+                    account.rounding,
+                ),
+            ]);
+            negative.push(account.balance < 0);
+        }
+
+        let mut widths = header.clone().map(|cell| cell.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let render = |cells: &[String; 5], red: bool| {
+            let line: Vec<String> = cells
+                .iter()
+                .zip(widths)
+                .enumerate()
+                .map(|(column, (cell, width))| {
+                    let padding = " ".repeat(width - cell.chars().count());
+                    // Ids and balances line up on the right.
+                    match column {
+                        0 => format!("{}{}", padding, cell),
+                        4 if red => format!("{}{}{}{}", padding, RED, cell, RESET),
+                        4 => format!("{}{}", padding, cell),
+                        _ => format!("{}{}", cell, padding),
+                    }
+                })
+                .collect();
+            line.join("  ").trim_end().to_string()
+        };
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        let mut lines = vec![render(&header, false), rule.join("  ")];
+        for (row, negative) in rows.iter().zip(negative) {
+            lines.push(render(row, color && negative));
+        }
+        lines.join("\n")
+    }
+
+    /// The account summaries as CSV with a header row.
+    pub fn summary_csv(&self) -> String {
+        self.summary_csv_where(&SummaryQuery::new())
+    }
+
+    pub fn summary_csv_where(&self, query: &SummaryQuery) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        for summary in self.account_summaries_where(query) {
+            csv.push_str(&format!(
+                "\n{},{},{},{},{},{}",
+                summary.id,
+                summary.reference,
+                csv_field(&summary.holder),
+                summary.currency,
+                summary.balance,
+                summary.available_balance
+            ));
+        }
+        csv.push('\n');
+        csv
+    }
+
+    fn matching_ids(&self, query: &SummaryQuery) -> Vec<u32> {
+        let mut ids = self.sorted_account_ids();
+        ids.retain(|id| query.matches(&self.accounts[id]));
+        ids
+    }
+}
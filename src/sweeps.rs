@@ -0,0 +1,126 @@
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::currency::CurrencyTotals;
+use crate::error::{AccountError, InputError, StateError};
+use crate::transaction::TransactionKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SweepKind {
+    /// Moves whatever `account` holds above `threshold` to `counterparty`.
+    Excess,
+    /// Brings `account` back up to `threshold` from `counterparty`.
+    TopUp,
+}
+
+/// A standing instruction run at the end of every day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SweepRule {
+    pub id: u64,
+    pub kind: SweepKind,
+    pub account_id: u32,
+    pub counterparty_id: u32,
+    pub threshold: i64,
+    pub paused: bool,
+}
+
+impl Bank {
+    pub fn add_sweep_rule(
+        &mut self,
+        kind: SweepKind,
+        account: impl Into<AccountRef>,
+        counterparty: impl Into<AccountRef>,
+        threshold: i64,
+    ) -> Result<u64, AccountError> {
+        if threshold < 0 {
+            return Err(AccountError::Validation(InputError::NegativeAmount));
+        }
+        let account_id = self.resolve(account)?;
+        let counterparty_id = self.resolve(counterparty)?;
+        if account_id == counterparty_id {
+            return Err(AccountError::Validation(InputError::SameAccount));
+        }
+        let id = self.next_item_id;
+        self.next_item_id += 1;
+        self.sweep_rules.push(SweepRule {
+            id,
+            kind,
+            account_id,
+            counterparty_id,
+            threshold,
+            paused: false,
+        });
+        Ok(id)
+    }
+
+    pub fn sweep_rules(&self) -> &[SweepRule] {
+        &self.sweep_rules
+    }
+
+    pub fn set_sweep_paused(&mut self, rule_id: u64, paused: bool) -> Result<(), AccountError> {
+        let rule = self
+            .sweep_rules
+            .iter_mut()
+            .find(|rule| rule.id == rule_id)
+            .ok_or(AccountError::State(StateError::SweepRuleNotFound))?;
+        rule.paused = paused;
+        Ok(())
+    }
+
+    pub fn remove_sweep_rule(&mut self, rule_id: u64) -> Result<SweepRule, AccountError> {
+        let index = self
+            .sweep_rules
+            .iter()
+            .position(|rule| rule.id == rule_id)
+            .ok_or(AccountError::State(StateError::SweepRuleNotFound))?;
+        Ok(self.sweep_rules.remove(index))
+    }
+
+    /// Runs every active rule in the order they were added, returning how
+    /// many moved money and the total moved. Held funds are never swept.
+    pub(crate) fn run_sweeps(&mut self) -> (usize, CurrencyTotals) {
+        let mut swept = (0, CurrencyTotals::new());
+        for index in 0..self.sweep_rules.len() {
+            let rule = &self.sweep_rules[index];
+            if rule.paused {
+                continue;
+            }
+            let (rule_id, threshold) = (rule.id, rule.threshold);
+            let (from_id, to_id) = match rule.kind {
+                SweepKind::Excess => (rule.account_id, rule.counterparty_id),
+                SweepKind::TopUp => (rule.counterparty_id, rule.account_id),
+            };
+            let (Some(from), Some(to)) = (self.accounts.get(&from_id), self.accounts.get(&to_id))
+            else {
+                continue;
+            };
+            let amount = match rule.kind {
+                SweepKind::Excess => from.available_balance() - threshold,
+                SweepKind::TopUp => (threshold - to.balance).min(from.available_balance()),
+            };
+            if amount <= 0 || to.balance.checked_add(amount).is_none() {
+                continue;
+            }
+            self.accounts.get_mut(&from_id).unwrap().balance -= amount;
+            self.accounts.get_mut(&to_id).unwrap().balance += amount;
+
+            let memo = format!("Sweep rule {}", rule_id);
+            self.record(
+                from_id,
+                TransactionKind::TransferOut,
+                -amount,
+                Some(to_id),
+                &memo,
+            );
+            self.record(
+                to_id,
+                TransactionKind::TransferIn,
+                amount,
+                Some(from_id),
+                &memo,
+            );
+            swept.0 += 1;
+            swept.1.add(self.accounts[&to_id].currency, amount);
+        }
+        swept
+    }
+}
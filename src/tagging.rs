@@ -0,0 +1,286 @@
+//! Tags and categories on entries already booked. Amounts never change,
+//! only how entries are labelled, so reports by category or tag can be
+//! refined after the fact; every change is kept in an audit trail with who
+//! made it.
+
+use std::fmt;
+
+use crate::account::AccountRef;
+use crate::bank::Bank;
+use crate::chain::reseal;
+use crate::error::{AccountError, InputError, StateError};
+use crate::time::Timestamp;
+use crate::transaction::{Transaction, TransactionKind};
+
+/// A change to an entry's labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagChange {
+    AddTag(String),
+    RemoveTag(String),
+    /// Replaces the category; `None` clears it.
+    SetCategory(Option<String>),
+}
+
+impl TagChange {
+    fn validate(&self) -> Result<(), AccountError> {
+        let text = match self {
+            TagChange::AddTag(tag) | TagChange::RemoveTag(tag) => tag,
+            TagChange::SetCategory(Some(category)) => category,
+            TagChange::SetCategory(None) => return Ok(()),
+        };
+        match text.trim().is_empty() {
+            true => Err(AccountError::Validation(InputError::EmptyTag)),
+            false => Ok(()),
+        }
+    }
+
+    /// Applies the change, returning the category it replaced, if it
+    /// replaced one, or `None` when there was nothing to change.
+    fn apply(&self, txn: &mut Transaction) -> Option<Option<String>> {
+        match self {
+            TagChange::AddTag(tag) => txn.tags.insert(tag.trim().to_string()).then_some(None),
+            TagChange::RemoveTag(tag) => txn.tags.remove(tag.trim()).then_some(None),
+            TagChange::SetCategory(category) => {
+                let category = category.as_deref().map(str::trim);
+                if txn.category.as_deref() == category {
+                    return None;
+                }
+                Some(std::mem::replace(
+                    &mut txn.category,
+                    category.map(str::to_string),
+                ))
+            }
+        }
+    }
+}
+
+impl fmt::Display for TagChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagChange::AddTag(tag) => write!(f, "tagged '{}'", tag),
+            TagChange::RemoveTag(tag) => write!(f, "untagged '{}'", tag),
+            TagChange::SetCategory(Some(category)) => write!(f, "categorised as '{}'", category),
+            TagChange::SetCategory(None) => write!(f, "category cleared"),
+        }
+    }
+}
+
+/// One change in the tagging audit trail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagEdit {
+    pub timestamp: Timestamp,
+    pub account_id: u32,
+    pub transaction_id: u64,
+    pub change: TagChange,
+    /// For category changes, the category that was replaced.
+    pub replaced_category: Option<String>,
+    pub actor: String,
+}
+
+impl fmt::Display for TagEdit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} account {} transaction #{} {} by {}",
+            self.timestamp.date(),
+            self.account_id,
+            self.transaction_id,
+            self.change,
+            self.actor
+        )?;
+        if let Some(replaced) = &self.replaced_category {
+            write!(f, " (was '{}')", replaced)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which entries a bulk edit or report covers. Every condition set must
+/// hold; the default filter matches every entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionFilter {
+    account: Option<u32>,
+    kind: Option<TransactionKind>,
+    period: Option<(Timestamp, Timestamp)>,
+    category: Option<Option<String>>,
+    tag: Option<String>,
+    memo: Option<String>,
+    counterparty: Option<u32>,
+}
+
+impl TransactionFilter {
+    pub fn new() -> Self {
+        TransactionFilter::default()
+    }
+
+    pub fn account(mut self, account_id: u32) -> Self {
+        self.account = Some(account_id);
+        self
+    }
+
+    pub fn kind(mut self, kind: TransactionKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only entries booked between `from` and `to`, inclusive.
+    pub fn between(mut self, from: Timestamp, to: Timestamp) -> Self {
+        self.period = Some((from, to));
+        self
+    }
+
+    pub fn category(mut self, category: &str) -> Self {
+        self.category = Some(Some(category.to_string()));
+        self
+    }
+
+    pub fn uncategorized(mut self) -> Self {
+        self.category = Some(None);
+        self
+    }
+
+    pub fn tagged(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    /// Only entries whose memo contains `text`, ignoring case.
+    pub fn memo_contains(mut self, text: &str) -> Self {
+        self.memo = Some(text.to_lowercase());
+        self
+    }
+
+    pub fn counterparty(mut self, account_id: u32) -> Self {
+        self.counterparty = Some(account_id);
+        self
+    }
+
+    pub fn matches(&self, txn: &Transaction) -> bool {
+        self.account.is_none_or(|id| txn.account_id == id)
+            && self.kind.is_none_or(|kind| txn.kind == kind)
+            && self
+                .period
+                .is_none_or(|(from, to)| (from..=to).contains(&txn.timestamp))
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| txn.category == *category)
+            && self.tag.as_ref().is_none_or(|tag| txn.tags.contains(tag))
+            && self
+                .memo
+                .as_ref()
+                .is_none_or(|text| txn.memo.to_lowercase().contains(text))
+            && self
+                .counterparty
+                .is_none_or(|id| txn.counterparty == Some(id))
+    }
+}
+
+impl Bank {
+    /// Changes the labels of entry `txn_id`. Returns whether anything
+    /// changed; adding a tag already there, say, is not recorded.
+    pub fn edit_transaction(
+        &mut self,
+        account: impl Into<AccountRef>,
+        txn_id: u64,
+        change: TagChange,
+        actor: &str,
+    ) -> Result<bool, AccountError> {
+        let id = self.resolve(account)?;
+        if !self.accounts[&id]
+            .history
+            .iter()
+            .any(|txn| txn.id == txn_id)
+        {
+            return Err(AccountError::State(StateError::TransactionNotFound));
+        }
+        let filter = TransactionFilter::new().account(id);
+        let edited = self.edit_where(&filter, Some(txn_id), change, actor)?;
+        Ok(edited > 0)
+    }
+
+    /// Applies `change` to every entry `filter` matches, returning how many
+    /// changed.
+    pub fn edit_transactions(
+        &mut self,
+        filter: &TransactionFilter,
+        change: TagChange,
+        actor: &str,
+    ) -> Result<usize, AccountError> {
+        self.edit_where(filter, None, change, actor)
+    }
+
+    /// Every entry `filter` matches, by account and then in booking order.
+    pub fn transactions_matching(&self, filter: &TransactionFilter) -> Vec<&Transaction> {
+        let mut matching = Vec::new();
+        for id in self.sorted_account_ids() {
+            if filter.account.is_some_and(|account| account != id) {
+                continue;
+            }
+            let account = &self.accounts[&id];
+            let candidates = match filter.period {
+                Some((from, to)) => account.history_between(from, to),
+                None => account.history.iter().collect(),
+            };
+            matching.extend(candidates.into_iter().filter(|txn| filter.matches(txn)));
+        }
+        matching
+    }
+
+    /// Every tag and category change, oldest first.
+    pub fn tag_edits(&self) -> &[TagEdit] {
+        &self.tag_edits
+    }
+
+    /// The changes made to entry `txn_id`, oldest first.
+    pub fn tag_edits_for(&self, txn_id: u64) -> Vec<&TagEdit> {
+        self.tag_edits
+            .iter()
+            .filter(|edit| edit.transaction_id == txn_id)
+            .collect()
+    }
+
+    fn edit_where(
+        &mut self,
+        filter: &TransactionFilter,
+        only: Option<u64>,
+        change: TagChange,
+        actor: &str,
+    ) -> Result<usize, AccountError> {
+        if actor.trim().is_empty() {
+            return Err(AccountError::Validation(InputError::MissingAuditDetails));
+        }
+        change.validate()?;
+        let now = self.now;
+        let mut edited = 0;
+        for id in self.sorted_account_ids() {
+            if filter.account.is_some_and(|account| account != id) {
+                continue;
+            }
+            let account = self.accounts.get_mut(&id).unwrap();
+            let mut first_changed = None;
+            for (index, txn) in account.history.iter_mut().enumerate() {
+                if only.is_some_and(|txn_id| txn.id != txn_id) || !filter.matches(txn) {
+                    continue;
+                }
+                let Some(replaced_category) = change.apply(txn) else {
+                    continue;
+                };
+                first_changed.get_or_insert(index);
+                self.tag_edits.push(TagEdit {
+                    timestamp: now,
+                    account_id: id,
+                    transaction_id: txn.id,
+                    change: change.clone(),
+                    replaced_category,
+                    actor: actor.to_string(),
+                });
+                edited += 1;
+            }
+            if let Some(index) = first_changed {
+                reseal(&mut account.history, index);
+            }
+        }
+        Ok(edited)
+    }
+}
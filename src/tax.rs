@@ -0,0 +1,241 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::account::{Account, AccountRef};
+use crate::bank::Bank;
+use crate::error::AccountError;
+use crate::time::Timestamp;
+use crate::transaction::TransactionKind;
+
+/// Decides how much tax is due on interest and transfers. Amounts are in
+/// cents; returning zero charges nothing.
+pub trait TaxPolicy: fmt::Debug + Send + Sync {
+    /// Withheld from `interest` just credited to `account`. Capped at the
+    /// interest itself.
+    fn interest_withholding(&self, _account: &Account, _interest: i64) -> i64 {
+        0
+    }
+
+    /// Charged to the sender on top of a transfer of `amount`.
+    fn transfer_tax(&self, _from: &Account, _to: &Account, _amount: i64) -> i64 {
+        0
+    }
+}
+
+/// The same rates for every account, rounded down to the cent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlatTax {
+    pub interest_bps: u32,
+    pub transfer_bps: u32,
+}
+
+impl TaxPolicy for FlatTax {
+    fn interest_withholding(&self, _account: &Account, interest: i64) -> i64 {
+        share(interest, self.interest_bps)
+    }
+
+    fn transfer_tax(&self, _from: &Account, _to: &Account, amount: i64) -> i64 {
+        share(amount, self.transfer_bps)
+    }
+}
+
+fn share(amount: i64, bps: u32) -> i64 {
+    (i128::from(amount) * i128::from(bps) / 10_000) as i64
+}
+
+/// What the tax account collected over a period, to hand over to the tax
+/// authority.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaxRemittance {
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+    pub interest_withheld: i64,
+    pub transfer_tax: i64,
+    /// Tax collected per paying account, by account id.
+    pub by_account: Vec<(u32, i64)>,
+}
+
+impl TaxRemittance {
+    pub fn total(&self) -> i64 {
+        self.interest_withheld + self.transfer_tax
+    }
+}
+
+impl fmt::Display for TaxRemittance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Tax collected {} to {}: ${:.2} (interest ${:.2}, transfers ${:.2})",
+            self.period_start.date(),
+            self.period_end.date(),
+            self.total() as f64 / 100.0,
+            self.interest_withheld as f64 / 100.0,
+            self.transfer_tax as f64 / 100.0
+        )?;
+        for (account, amount) in &self.by_account {
+            writeln!(f, "  Account {}: ${:.2}", account, *amount as f64 / 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// The tax due on a transfer under the bank's `policy`, or zero when no tax
+/// is collected or the tax account itself is involved. Takes the bank's
+/// fields rather than the bank so it can run while the pair is borrowed.
+pub(crate) fn transfer_tax(
+    policy: &Option<Arc<dyn TaxPolicy>>,
+    tax_account: Option<u32>,
+    from: &Account,
+    to: &Account,
+    amount: i64,
+) -> i64 {
+    match (policy, tax_account) {
+        (Some(policy), Some(tax_account)) if from.id != tax_account && to.id != tax_account => {
+            policy.transfer_tax(from, to, amount).max(0)
+        }
+        _ => 0,
+    }
+}
+
+impl Bank {
+    /// Charges tax under `policy` from now on. Nothing is collected until a
+    /// tax account is set. Like price sources, the policy is not saved in
+    /// snapshots; the tax account is.
+    pub fn set_tax_policy(&mut self, policy: impl TaxPolicy + 'static) {
+        self.tax_policy = Some(Arc::new(policy));
+    }
+
+    pub fn clear_tax_policy(&mut self) {
+        self.tax_policy = None;
+    }
+
+    /// Collects tax into `account`.
+    pub fn set_tax_account(&mut self, account: impl Into<AccountRef>) -> Result<(), AccountError> {
+        self.tax_account = Some(self.resolve(account)?);
+        Ok(())
+    }
+
+    pub fn clear_tax_account(&mut self) {
+        self.tax_account = None;
+    }
+
+    pub fn tax_account(&self) -> Option<u32> {
+        self.tax_account
+    }
+
+    /// Withholds tax on interest just booked as `interest_transaction`.
+    pub(crate) fn withhold_interest_tax(
+        &mut self,
+        account_id: u32,
+        interest: i64,
+        interest_transaction: u64,
+    ) {
+        let (Some(policy), Some(tax_account)) = (&self.tax_policy, self.tax_account) else {
+            return;
+        };
+        if account_id == tax_account {
+            return;
+        }
+        let tax = policy
+            .interest_withholding(&self.accounts[&account_id], interest)
+            .clamp(0, interest);
+        self.collect_tax(account_id, tax, "interest", interest_transaction);
+    }
+
+    /// Moves `tax` from `account_id` to the tax account, tagging both
+    /// entries with the kind of tax and the transaction it was due on.
+    pub(crate) fn collect_tax(&mut self, account_id: u32, tax: i64, kind: &str, taxed: u64) {
+        let Some(tax_account) = self.tax_account else {
+            return;
+        };
+        if tax <= 0 || !self.accounts.contains_key(&tax_account) {
+            return;
+        }
+        let Some(new_balance) = self.accounts[&tax_account].balance.checked_add(tax) else {
+            return;
+        };
+        self.accounts.get_mut(&tax_account).unwrap().balance = new_balance;
+        self.accounts.get_mut(&account_id).unwrap().balance -= tax;
+        let memo = match kind {
+            "interest" => "Interest withholding tax",
+            _ => "Transfer tax",
+        };
+        let out_id = self.record(
+            account_id,
+            TransactionKind::TransferOut,
+            -tax,
+            Some(tax_account),
+            memo,
+        );
+        let in_id = self.record(
+            tax_account,
+            TransactionKind::TransferIn,
+            tax,
+            Some(account_id),
+            memo,
+        );
+        for (account, txn_id) in [(account_id, out_id), (tax_account, in_id)] {
+            self.amend_transaction(account, txn_id, |txn| {
+                txn.metadata.insert("tax".to_string(), kind.to_string());
+                txn.metadata
+                    .insert("taxed_transaction".to_string(), taxed.to_string());
+            });
+        }
+    }
+
+    /// The tax the tax account collected between `period_start` and
+    /// `period_end`, inclusive. Empty without a tax account.
+    pub fn tax_remittance(&self, period_start: Timestamp, period_end: Timestamp) -> TaxRemittance {
+        let mut remittance = TaxRemittance {
+            period_start,
+            period_end,
+            interest_withheld: 0,
+            transfer_tax: 0,
+            by_account: Vec::new(),
+        };
+        let Some(account) = self.tax_account.and_then(|id| self.accounts.get(&id)) else {
+            return remittance;
+        };
+        for txn in account.history_between(period_start, period_end) {
+            match txn.metadata.get("tax").map(String::as_str) {
+                Some("interest") => remittance.interest_withheld += txn.amount,
+                Some(_) => remittance.transfer_tax += txn.amount,
+                None => continue,
+            }
+            let payer = txn.counterparty.unwrap_or_default();
+            match remittance
+                .by_account
+                .iter_mut()
+                .find(|(account, _)| *account == payer)
+            {
+                Some((_, total)) => *total += txn.amount,
+                None => remittance.by_account.push((payer, txn.amount)),
+            }
+        }
+        remittance.by_account.sort_unstable();
+        remittance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_tax_is_debited_on_top_and_credited_to_the_tax_account() {
+        let mut bank = Bank::new();
+        for id in 1..=3 {
+            bank.add_account(Account::new(id, format!("Holder {}", id)));
+        }
+        bank.deposit(1, 10_000).unwrap();
+        bank.set_tax_account(3).unwrap();
+        bank.set_tax_policy(FlatTax {
+            interest_bps: 0,
+            transfer_bps: 100,
+        });
+
+        bank.transfer(1, 2, 5_000).unwrap();
+        let balance = |id| bank.get_account(id).unwrap().balance();
+        assert_eq!((balance(1), balance(2), balance(3)), (4_950, 5_000, 50));
+    }
+}
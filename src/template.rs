@@ -0,0 +1,287 @@
+//! A small Handlebars-style template language for statements and alerts.
+//!
+//! `{{name}}` inserts a value, `{{#each rows}}...{{/each}}` repeats a block
+//! for every row of a list (row fields shadow outer ones) and
+//! `{{#if name}}...{{/if}}` keeps a block only when the value is non-empty.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::account::Account;
+use crate::locale::{Locale, Localize};
+use crate::outcome::Warning;
+use crate::statement::Statement;
+use crate::time::Timestamp;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    Unclosed(String),
+    UnexpectedClose(String),
+    UnknownVariable(String),
+    NotAList(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Unclosed(tag) => write!(f, "Unclosed tag '{}'", tag),
+            TemplateError::UnexpectedClose(tag) => write!(f, "Unexpected closing tag '{}'", tag),
+            TemplateError::UnknownVariable(name) => write!(f, "Unknown variable '{}'", name),
+            TemplateError::NotAList(name) => write!(f, "'{}' is not a list", name),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// How values are escaped when they are inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Html,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateValue {
+    Text(String),
+    List(Vec<TemplateContext>),
+}
+
+/// The variables a template is rendered with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateContext {
+    values: BTreeMap<String, TemplateValue>,
+}
+
+impl TemplateContext {
+    pub fn new() -> Self {
+        TemplateContext::default()
+    }
+
+    pub fn set(&mut self, name: &str, value: impl Into<String>) -> &mut Self {
+        self.values
+            .insert(name.to_string(), TemplateValue::Text(value.into()));
+        self
+    }
+
+    pub fn set_list(&mut self, name: &str, rows: Vec<TemplateContext>) -> &mut Self {
+        self.values
+            .insert(name.to_string(), TemplateValue::List(rows));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TemplateValue> {
+        self.values.get(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Variable(String),
+    Each(String, Vec<Node>),
+    If(String, Vec<Node>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Template, TemplateError> {
+        // Each open block keeps its kind, name and the nodes collected so far.
+        let mut stack: Vec<(&str, String, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                nodes.push(Node::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| TemplateError::Unclosed(after.to_string()))?;
+            let tag = after[..end].trim();
+            rest = &after[end + 2..];
+
+            if let Some(name) = tag.strip_prefix("#each ") {
+                stack.push(("each", name.trim().to_string(), std::mem::take(&mut nodes)));
+            } else if let Some(name) = tag.strip_prefix("#if ") {
+                stack.push(("if", name.trim().to_string(), std::mem::take(&mut nodes)));
+            } else if let Some(kind) = tag.strip_prefix('/') {
+                let kind = kind.trim();
+                match stack.pop() {
+                    Some((open, name, outer)) if open == kind => {
+                        let body = std::mem::replace(&mut nodes, outer);
+                        nodes.push(if open == "each" {
+                            Node::Each(name, body)
+                        } else {
+                            Node::If(name, body)
+                        });
+                    }
+                    _ => return Err(TemplateError::UnexpectedClose(kind.to_string())),
+                }
+            } else {
+                nodes.push(Node::Variable(tag.to_string()));
+            }
+        }
+        if !rest.is_empty() {
+            nodes.push(Node::Text(rest.to_string()));
+        }
+        if let Some((open, name, _)) = stack.pop() {
+            return Err(TemplateError::Unclosed(format!("#{} {}", open, name)));
+        }
+        Ok(Template { nodes })
+    }
+
+    pub fn render(
+        &self,
+        context: &TemplateContext,
+        format: OutputFormat,
+    ) -> Result<String, TemplateError> {
+        let mut output = String::new();
+        render_nodes(&self.nodes, &mut vec![context], format, &mut output)?;
+        Ok(output)
+    }
+}
+
+fn lookup<'a>(scopes: &[&'a TemplateContext], name: &str) -> Option<&'a TemplateValue> {
+    scopes.iter().rev().find_map(|scope| scope.get(name))
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    scopes: &mut Vec<&'a TemplateContext>,
+    format: OutputFormat,
+    output: &mut String,
+) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable(name) => match lookup(scopes, name) {
+                Some(TemplateValue::Text(value)) => push_escaped(output, value, format),
+                Some(TemplateValue::List(_)) => {
+                    return Err(TemplateError::UnknownVariable(name.clone()))
+                }
+                None => return Err(TemplateError::UnknownVariable(name.clone())),
+            },
+            Node::Each(name, body) => match lookup(scopes, name) {
+                Some(TemplateValue::List(rows)) => {
+                    for row in rows {
+                        scopes.push(row);
+                        render_nodes(body, scopes, format, output)?;
+                        scopes.pop();
+                    }
+                }
+                Some(TemplateValue::Text(_)) => return Err(TemplateError::NotAList(name.clone())),
+                None => return Err(TemplateError::UnknownVariable(name.clone())),
+            },
+            Node::If(name, body) => {
+                let present = match lookup(scopes, name) {
+                    Some(TemplateValue::Text(value)) => !value.is_empty(),
+                    Some(TemplateValue::List(rows)) => !rows.is_empty(),
+                    None => false,
+                };
+                if present {
+                    render_nodes(body, scopes, format, output)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn push_escaped(output: &mut String, value: &str, format: OutputFormat) {
+    if format == OutputFormat::Text {
+        output.push_str(value);
+        return;
+    }
+    for ch in value.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(ch),
+        }
+    }
+}
+
+impl Statement {
+    /// Variables: `account_id`, `holder`, `period_start`, `period_end`,
+    /// `opening_balance`, `closing_balance`, `net_change` and `rows`, each
+    /// row having `id`, `date`, `kind`, `amount`, `balance` and `memo`.
+    pub fn template_context(&self, locale: Locale) -> TemplateContext {
+        let money = |amount| locale.money_in(amount, self.currency);
+        let rows = self
+            .transactions
+            .iter()
+            .map(|txn| {
+                let mut row = TemplateContext::new();
+                row.set("id", txn.id.to_string())
+                    .set("date", txn.timestamp.date().to_string())
+                    .set("kind", txn.kind.localize(locale))
+                    .set("amount", money(txn.amount))
+                    .set("balance", money(txn.balance_after))
+                    .set("memo", txn.memo.clone());
+                row
+            })
+            .collect();
+        let mut context = TemplateContext::new();
+        context
+            .set("account_id", self.account_id.to_string())
+            .set("holder", self.holder.clone())
+            .set("period_start", self.period_start.date().to_string())
+            .set("period_end", self.period_end.date().to_string())
+            .set("opening_balance", money(self.opening_balance))
+            .set("closing_balance", money(self.closing_balance))
+            .set("net_change", money(self.net_change()))
+            .set_list("rows", rows);
+        context
+    }
+
+    pub fn render(
+        &self,
+        template: &Template,
+        format: OutputFormat,
+        locale: Locale,
+    ) -> Result<String, TemplateError> {
+        template.render(&self.template_context(locale), format)
+    }
+}
+
+impl Warning {
+    /// Variables: `account_id`, `holder`, `balance`, `time` and `message`.
+    pub fn template_context(
+        &self,
+        account: &Account,
+        time: Timestamp,
+        locale: Locale,
+    ) -> TemplateContext {
+        let mut context = TemplateContext::new();
+        context
+            .set("account_id", account.id.to_string())
+            .set("holder", account.holder.clone())
+            .set(
+                "balance",
+                locale.money_in(account.balance, account.currency),
+            )
+            .set("time", time.to_string())
+            .set("message", self.localize(locale));
+        context
+    }
+
+    pub fn render(
+        &self,
+        account: &Account,
+        time: Timestamp,
+        template: &Template,
+        format: OutputFormat,
+        locale: Locale,
+    ) -> Result<String, TemplateError> {
+        template.render(&self.template_context(account, time, locale), format)
+    }
+}
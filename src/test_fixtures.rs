@@ -0,0 +1,135 @@
+//! Helpers for building populated banks in tests.
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::rng::SeededRng;
+use crate::time::{Date, Timestamp};
+
+const HOLDERS: [&str; 8] = [
+    "Giorgi", "Nino", "Luka", "Mariam", "Davit", "Ana", "Levan", "Tamar",
+];
+
+#[derive(Debug, Clone)]
+struct AccountSpec {
+    id: u32,
+    holder: String,
+    balance: i64,
+}
+
+/// Fluent construction of a [`Bank`] with preset accounts and history.
+///
+/// Everything random is derived from the seed, so the same builder always
+/// produces the same bank.
+#[derive(Debug, Clone)]
+pub struct BankBuilder {
+    start: Timestamp,
+    seed: u64,
+    accounts: Vec<AccountSpec>,
+    random_accounts: Vec<(usize, i64, i64)>,
+    history_days: u32,
+    transactions_per_day: u32,
+}
+
+impl BankBuilder {
+    pub fn new() -> Self {
+        BankBuilder {
+            start: Timestamp::from_date(Date::new(2024, 1, 1)),
+            seed: 0,
+            accounts: Vec::new(),
+            random_accounts: Vec::new(),
+            history_days: 0,
+            transactions_per_day: 0,
+        }
+    }
+
+    pub fn starting_at(mut self, start: Timestamp) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn account(mut self, id: u32, holder: &str, balance: i64) -> Self {
+        self.accounts.push(AccountSpec {
+            id,
+            holder: holder.to_string(),
+            balance,
+        });
+        self
+    }
+
+    /// Adds `count` accounts that all open with `balance`.
+    pub fn accounts(self, count: usize, balance: i64) -> Self {
+        self.random_accounts(count, balance, balance)
+    }
+
+    /// Adds `count` accounts with balances drawn from `[min_balance, max_balance]`.
+    pub fn random_accounts(mut self, count: usize, min_balance: i64, max_balance: i64) -> Self {
+        self.random_accounts.push((count, min_balance, max_balance));
+        self
+    }
+
+    /// Generates `days` days of random deposits and withdrawals after opening.
+    pub fn history(mut self, days: u32, transactions_per_day: u32) -> Self {
+        self.history_days = days;
+        self.transactions_per_day = transactions_per_day;
+        self
+    }
+
+    pub fn build(self) -> Bank {
+        let mut rng = SeededRng::new(self.seed);
+        let mut bank = Bank::starting_at(self.start);
+        let mut specs = self.accounts;
+        let mut next_id = specs.iter().map(|spec| spec.id).max().unwrap_or(0) + 1;
+        for (count, min_balance, max_balance) in self.random_accounts {
+            for _ in 0..count {
+                let holder = rng.pick(&HOLDERS).copied().unwrap_or("Holder");
+                specs.push(AccountSpec {
+                    id: next_id,
+                    holder: holder.to_string(),
+                    balance: rng.range(min_balance, max_balance),
+                });
+                next_id += 1;
+            }
+        }
+
+        let mut ids = Vec::with_capacity(specs.len());
+        for spec in specs {
+            bank.add_account(Account::new(spec.id, spec.holder));
+            if spec.balance > 0 {
+                let _ = bank.deposit(spec.id, spec.balance);
+            }
+            ids.push(spec.id);
+        }
+
+        for day in 0..self.history_days {
+            let day_start = self.start.plus_days(day + 1);
+            for slot in 0..self.transactions_per_day {
+                let Some(&id) = rng.pick(&ids) else {
+                    break;
+                };
+                let offset = u64::from(slot) * 60 + rng.range(0, 59) as u64;
+                bank.set_time(Timestamp(day_start.seconds() + 9 * 3600 + offset));
+                let amount = rng.range(100, 20_000);
+                if rng.chance(0.5) {
+                    let _ = bank.deposit(id, amount);
+                } else {
+                    let _ = bank.withdraw(id, amount);
+                }
+            }
+        }
+        if self.history_days > 0 {
+            bank.set_time(self.start.plus_days(self.history_days + 1));
+        }
+        bank
+    }
+}
+
+impl Default for BankBuilder {
+    fn default() -> Self {
+        BankBuilder::new()
+    }
+}
@@ -0,0 +1,152 @@
+//! Assertions for downstream test suites, behind the `testing` feature.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::bank::Bank;
+
+fn dollars(cents: impl Into<i128>) -> String {
+    let cents: i128 = cents.into();
+    format!(
+        "{}${:.2}",
+        if cents < 0 { "-" } else { "" },
+        cents.unsigned_abs() as f64 / 100.0
+    )
+}
+
+impl Bank {
+    /// Panics unless every listed account exists and holds the expected
+    /// balance, in cents. The message lists every mismatch at once, with
+    /// expected and actual balances and the difference.
+    #[track_caller]
+    pub fn assert_balances(&self, expected: &[(u32, i64)]) {
+        let mut diff = String::new();
+        let mut mismatches = 0;
+        for &(id, expected) in expected {
+            match self.accounts.get(&id) {
+                Some(account) if account.balance == expected => {}
+                Some(account) => {
+                    mismatches += 1;
+                    let _ = writeln!(
+                        diff,
+                        "  account {:<8} expected {:>14}  actual {:>14}  diff {:>14}",
+                        id,
+                        dollars(expected),
+                        dollars(account.balance),
+                        dollars(account.balance - expected)
+                    );
+                }
+                None => {
+                    mismatches += 1;
+                    let _ = writeln!(
+                        diff,
+                        "  account {:<8} expected {:>14}  actual {:>14}",
+                        id,
+                        dollars(expected),
+                        "missing"
+                    );
+                }
+            }
+        }
+        if mismatches > 0 {
+            panic!(
+                "{} of {} balances differ:\n{}",
+                mismatches,
+                expected.len(),
+                diff
+            );
+        }
+    }
+
+    /// What is wrong with the bank's books, one line per problem, or
+    /// nothing if they are consistent. Checked for every account:
+    ///
+    /// - each entry's balance follows from the one before it and its amount;
+    /// - the last entry's balance is the account's balance;
+    /// - held amounts are not negative;
+    /// - the account's reference leads back to it;
+    /// - the hash chain is intact.
+    ///
+    /// Across the bank, transaction ids must be unique, and every rounding
+    /// remainder collected must be either booked or still carried.
+    pub fn invariant_violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        let mut seen = HashSet::new();
+        for id in self.sorted_account_ids() {
+            let account = &self.accounts[&id];
+            let mut previous: Option<i128> = None;
+            for txn in &account.history {
+                if !seen.insert(txn.id) {
+                    violations.push(format!(
+                        "account {}: transaction #{} id is used more than once",
+                        id, txn.id
+                    ));
+                }
+                let after = txn.exact_balance_after();
+                if let Some(before) = previous {
+                    if before + i128::from(txn.amount) != after {
+                        violations.push(format!(
+                            "account {}: transaction #{} moves {} from {} but leaves {}",
+                            id,
+                            txn.id,
+                            dollars(txn.amount),
+                            dollars(before),
+                            dollars(after)
+                        ));
+                    }
+                }
+                previous = Some(after);
+            }
+            if let Some(last) = previous {
+                if last != account.exact_balance() {
+                    violations.push(format!(
+                        "account {}: balance is {} but the last entry leaves {}",
+                        id,
+                        dollars(account.exact_balance()),
+                        dollars(last)
+                    ));
+                }
+            }
+            for hold in account.holds.iter().filter(|hold| hold.amount < 0) {
+                violations.push(format!(
+                    "account {}: hold {} is negative ({})",
+                    id,
+                    hold.id,
+                    dollars(hold.amount)
+                ));
+            }
+            if self.references.get(&account.reference) != Some(&id) {
+                violations.push(format!(
+                    "account {}: reference {} does not resolve to it",
+                    id, account.reference
+                ));
+            }
+        }
+        if let Err(broken) = self.verify_chain() {
+            violations.push(format!(
+                "account {}: hash chain breaks at transaction #{}",
+                broken.account_id, broken.transaction_id
+            ));
+        }
+        for line in self.remainders.values().filter(|line| !line.is_balanced()) {
+            violations.push(format!(
+                "{} {} remainders: {}/{} collected but {} units booked and {} carried",
+                line.currency, line.source, line.total, line.denominator, line.posted, line.carried
+            ));
+        }
+        violations
+    }
+
+    /// Panics with every problem [`Bank::invariant_violations`] finds.
+    #[track_caller]
+    pub fn expect_invariants(&self) {
+        let violations = self.invariant_violations();
+        if !violations.is_empty() {
+            panic!(
+                "{} invariant violations:\n  {}",
+                violations.len(),
+                violations.join("\n  ")
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A point in simulated time, in whole seconds since the Unix epoch (UTC).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(pub u64);
+
+impl Timestamp {
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        Timestamp(seconds)
+    }
+
+    pub fn from_date(date: Date) -> Self {
+        Timestamp(date.days_since_epoch() as u64 * SECONDS_PER_DAY)
+    }
+
+    pub fn seconds(self) -> u64 {
+        self.0
+    }
+
+    pub fn day_number(self) -> u64 {
+        self.0 / SECONDS_PER_DAY
+    }
+
+    pub fn start_of_day(self) -> Self {
+        Timestamp(self.day_number() * SECONDS_PER_DAY)
+    }
+
+    pub fn end_of_day(self) -> Self {
+        Timestamp(self.start_of_day().0 + SECONDS_PER_DAY - 1)
+    }
+
+    pub fn date(self) -> Date {
+        Date::from_days_since_epoch(self.day_number() as i64)
+    }
+
+    pub fn plus(self, duration: Duration) -> Self {
+        Timestamp(self.0.saturating_add(duration.as_secs()))
+    }
+
+    pub fn plus_days(self, days: u32) -> Self {
+        Timestamp(self.0.saturating_add(u64::from(days) * SECONDS_PER_DAY))
+    }
+
+    pub fn minus_days(self, days: u32) -> Self {
+        Timestamp(self.0.saturating_sub(u64::from(days) * SECONDS_PER_DAY))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds_of_day = self.0 % SECONDS_PER_DAY;
+        write!(
+            f,
+            "{} {:02}:{:02}:{:02}",
+            self.date(),
+            seconds_of_day / 3600,
+            seconds_of_day % 3600 / 60,
+            seconds_of_day % 60
+        )
+    }
+}
+
+/// A proleptic Gregorian calendar date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Self {
+        Date { year, month, day }
+    }
+
+    /// Parses an ISO 8601 `YYYY-MM-DD` date.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        let date = Date::new(year, month, day);
+        ((1..=12).contains(&month) && (1..=date.days_in_month()).contains(&day)).then_some(date)
+    }
+
+    // Civil date conversions after Howard Hinnant's `days_from_civil`.
+    pub fn days_since_epoch(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Date { year, month, day }
+    }
+
+    pub fn is_leap_year(self) -> bool {
+        (self.year % 4 == 0 && self.year % 100 != 0) || self.year % 400 == 0
+    }
+
+    pub fn days_in_month(self) -> u32 {
+        match self.month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            _ if self.is_leap_year() => 29,
+            _ => 28,
+        }
+    }
+
+    pub fn is_last_day_of_month(self) -> bool {
+        self.day == self.days_in_month()
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use crate::bank::Bank;
+use crate::time::{Date, Timestamp, SECONDS_PER_DAY};
+
+/// A fixed offset from UTC. Zones with daylight saving time have to be
+/// given the offset that applies to the period being rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TimeZone {
+    offset_seconds: i32,
+}
+
+impl TimeZone {
+    pub const UTC: TimeZone = TimeZone { offset_seconds: 0 };
+
+    /// `hours` and `minutes` east of UTC, e.g. `east(4, 0)` for Tbilisi or
+    /// `east(-5, 0)` for New York in winter. `None` beyond ±18 hours.
+    pub fn east(hours: i32, minutes: i32) -> Option<TimeZone> {
+        TimeZone::from_offset_seconds(
+            hours
+                .checked_mul(3600)?
+                .checked_add(minutes.checked_mul(60)?)?,
+        )
+    }
+
+    pub fn from_offset_seconds(offset_seconds: i32) -> Option<TimeZone> {
+        if offset_seconds.unsigned_abs() > 18 * 3600 {
+            return None;
+        }
+        Some(TimeZone { offset_seconds })
+    }
+
+    pub fn offset_seconds(self) -> i32 {
+        self.offset_seconds
+    }
+}
+
+impl fmt::Display for TimeZone {
+    /// `+04:00`, `-05:30` or `Z` for UTC.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.offset_seconds == 0 {
+            return f.write_str("Z");
+        }
+        let sign = if self.offset_seconds < 0 { '-' } else { '+' };
+        let minutes = self.offset_seconds.unsigned_abs() / 60;
+        write!(f, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
+/// How timestamps and dates are written in statements and transaction
+/// lines. Patterns understand `%Y`, `%m`, `%d`, `%H`, `%M`, `%S`, `%z` (the
+/// zone's offset) and `%%`; anything else is copied as is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimeFormat {
+    pub(crate) zone: TimeZone,
+    pub(crate) timestamp_pattern: String,
+    pub(crate) date_pattern: String,
+}
+
+impl Default for TimeFormat {
+    /// UTC, `2024-01-31 14:05:00`, the same as [`Timestamp`]'s `Display`.
+    fn default() -> Self {
+        TimeFormat {
+            zone: TimeZone::UTC,
+            timestamp_pattern: "%Y-%m-%d %H:%M:%S".to_string(),
+            date_pattern: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+impl TimeFormat {
+    pub fn new() -> Self {
+        TimeFormat::default()
+    }
+
+    pub fn zone(mut self, zone: TimeZone) -> Self {
+        self.zone = zone;
+        self
+    }
+
+    pub fn timestamps(mut self, pattern: &str) -> Self {
+        self.timestamp_pattern = pattern.to_string();
+        self
+    }
+
+    pub fn dates(mut self, pattern: &str) -> Self {
+        self.date_pattern = pattern.to_string();
+        self
+    }
+
+    pub fn time_zone(&self) -> TimeZone {
+        self.zone
+    }
+
+    pub fn timestamp_pattern(&self) -> &str {
+        &self.timestamp_pattern
+    }
+
+    pub fn date_pattern(&self) -> &str {
+        &self.date_pattern
+    }
+
+    /// `timestamp` in this format's zone.
+    pub fn timestamp(&self, timestamp: Timestamp) -> String {
+        let local = i64::try_from(timestamp.seconds())
+            .unwrap_or(i64::MAX)
+            .saturating_add(i64::from(self.zone.offset_seconds));
+        let seconds_per_day = SECONDS_PER_DAY as i64;
+        let date = Date::from_days_since_epoch(local.div_euclid(seconds_per_day));
+        let seconds_of_day = local.rem_euclid(seconds_per_day);
+        self.render(&self.timestamp_pattern, date, seconds_of_day)
+    }
+
+    /// A calendar date, such as a statement period. Dates are business days
+    /// and are not shifted into the zone.
+    pub fn date(&self, date: Date) -> String {
+        self.render(&self.date_pattern, date, 0)
+    }
+
+    fn render(&self, pattern: &str, date: Date, seconds_of_day: i64) -> String {
+        let mut rendered = String::with_capacity(pattern.len() + 8);
+        let mut chars = pattern.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                rendered.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => rendered.push_str(&format!("{:04}", date.year)),
+                Some('m') => rendered.push_str(&format!("{:02}", date.month)),
+                Some('d') => rendered.push_str(&format!("{:02}", date.day)),
+                Some('H') => rendered.push_str(&format!("{:02}", seconds_of_day / 3600)),
+                Some('M') => rendered.push_str(&format!("{:02}", seconds_of_day % 3600 / 60)),
+                Some('S') => rendered.push_str(&format!("{:02}", seconds_of_day % 60)),
+                Some('z') => rendered.push_str(&self.zone.to_string()),
+                Some('%') => rendered.push('%'),
+                Some(other) => {
+                    rendered.push('%');
+                    rendered.push(other);
+                }
+                None => rendered.push('%'),
+            }
+        }
+        rendered
+    }
+}
+
+impl Bank {
+    pub fn time_format(&self) -> &TimeFormat {
+        &self.time_format
+    }
+
+    /// Sets how [`Bank::localized`] writes timestamps and dates.
+    pub fn set_time_format(&mut self, format: TimeFormat) {
+        self.time_format = format;
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::chain::ChainHash;
+use crate::locale::{Locale, Localize};
+use crate::rounding::RoundingPolicy;
+use crate::time::Timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    OpeningBalance,
+    Deposit,
+    Withdrawal,
+    TransferIn,
+    TransferOut,
+    ChequeDeposit,
+    Interest,
+    Fee,
+    Reward,
+    Redenomination,
+    /// A manual correction booked through [`crate::Bank::adjust`].
+    Adjustment,
+    /// Money moved to or from another tenant's bank.
+    Settlement,
+    /// A gain or loss on a unit-priced account after its unit price moved.
+    Revaluation,
+    /// The reversal of a disputed deposit.
+    Chargeback,
+    /// A zero-amount record of the account changing holder.
+    OwnershipTransfer,
+    /// Whole units of rounding remainders booked to a rounding account.
+    RoundingRemainder,
+}
+
+impl fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
+
+/// A single booked entry in an account's history. `amount` is signed:
+/// credits are positive and debits negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub id: u64,
+    pub account_id: u32,
+    pub kind: TransactionKind,
+    pub amount: i64,
+    pub balance_after: i64,
+    pub counterparty: Option<u32>,
+    pub timestamp: Timestamp,
+    pub memo: String,
+    pub category: Option<String>,
+    /// Free-form labels for reports; see [`crate::Bank::edit_transaction`].
+    pub tags: BTreeSet<String>,
+    /// The account's rounding policy when this entry was booked.
+    pub rounding: RoundingPolicy,
+    /// Extra detail about how the entry came about, such as an interest
+    /// breakdown.
+    pub metadata: BTreeMap<String, String>,
+    /// Hash of this entry chained to the one before it; see
+    /// [`crate::Bank::verify_chain`].
+    pub chain_hash: ChainHash,
+}
+
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::English))
+    }
+}
@@ -0,0 +1,211 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::currency::Currency;
+
+/// Holder names longer than this many characters are refused unless the
+/// bank sets its own limit.
+const DEFAULT_MAX_HOLDER_LENGTH: usize = 100;
+
+/// How the bank treats inputs that are unusual but not wrong.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Accept them: zero amounts follow the
+    /// [`ZeroAmountPolicy`](crate::ZeroAmountPolicy), transfers to the same
+    /// account move nothing and book a zero-amount entry, with a
+    /// [`Warning::SelfTransfer`](crate::Warning::SelfTransfer), and any
+    /// three-letter currency code is taken as given.
+    #[default]
+    Lenient,
+    /// Refuse them: zero amounts whatever the zero-amount policy, transfers
+    /// to the same account, and accounts in a currency ISO 4217 doesn't
+    /// assign.
+    Strict,
+}
+
+/// One rule an input broke.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationRule {
+    /// Nothing is left once surrounding whitespace is trimmed.
+    EmptyHolder,
+    /// Longer than the limit, counted in characters after trimming.
+    HolderTooLong { length: usize, max: usize },
+    /// Control characters such as newlines or tabs, by character position.
+    ControlCharacters { positions: Vec<usize> },
+    /// A currency ISO 4217 doesn't assign, refused in a strict
+    /// [`ValidationMode`].
+    UnknownCurrency { currency: Currency },
+}
+
+impl fmt::Display for ValidationRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationRule::EmptyHolder => write!(f, "Holder name is empty"),
+            ValidationRule::HolderTooLong { length, max } => write!(
+                f,
+                "Holder name is {} characters long; at most {} are allowed",
+                length, max
+            ),
+            ValidationRule::ControlCharacters { positions } => {
+                let positions: Vec<String> = positions.iter().map(usize::to_string).collect();
+                write!(
+                    f,
+                    "Holder name contains control characters at {}",
+                    positions.join(", ")
+                )
+            }
+            ValidationRule::UnknownCurrency { currency } => {
+                write!(f, "{} is not an ISO 4217 currency", currency)
+            }
+        }
+    }
+}
+
+/// Every rule an input broke, in the order they are checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub violations: Vec<ValidationRule>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let violations: Vec<String> = self.violations.iter().map(ToString::to_string).collect();
+        f.write_str(&violations.join("; "))
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Rules for holder names given to [`Bank::add_validated_account`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HolderValidation {
+    pub max_length: usize,
+    /// Turns every kind of Unicode whitespace into a plain space, collapses
+    /// runs of them and drops zero-width characters before the other rules
+    /// run. Canonical (NFC) normalization is not applied.
+    pub normalize_whitespace: bool,
+}
+
+impl Default for HolderValidation {
+    fn default() -> Self {
+        HolderValidation {
+            max_length: DEFAULT_MAX_HOLDER_LENGTH,
+            normalize_whitespace: false,
+        }
+    }
+}
+
+impl HolderValidation {
+    pub fn new() -> Self {
+        HolderValidation::default()
+    }
+
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn normalize_whitespace(mut self, normalize: bool) -> Self {
+        self.normalize_whitespace = normalize;
+        self
+    }
+
+    /// Checks `holder` against every rule, returning the trimmed (and, if
+    /// enabled, normalized) name.
+    pub fn validate(&self, holder: &str) -> Result<String, ValidationError> {
+        let holder = if self.normalize_whitespace {
+            normalize_whitespace(holder)
+        } else {
+            holder.trim().to_string()
+        };
+        let mut violations = Vec::new();
+        if holder.is_empty() {
+            violations.push(ValidationRule::EmptyHolder);
+        }
+        let length = holder.chars().count();
+        if length > self.max_length {
+            violations.push(ValidationRule::HolderTooLong {
+                length,
+                max: self.max_length,
+            });
+        }
+        let positions: Vec<usize> = holder
+            .chars()
+            .enumerate()
+            .filter(|(_, ch)| ch.is_control())
+            .map(|(position, _)| position)
+            .collect();
+        if !positions.is_empty() {
+            violations.push(ValidationRule::ControlCharacters { positions });
+        }
+        if violations.is_empty() {
+            Ok(holder)
+        } else {
+            Err(ValidationError { violations })
+        }
+    }
+}
+
+/// Whitespace other than newlines and tabs becomes one space; those two are
+/// control characters and are left for the control-character rule.
+fn normalize_whitespace(holder: &str) -> String {
+    let mut normalized = String::with_capacity(holder.len());
+    let mut pending_space = false;
+    for ch in holder.chars() {
+        if matches!(ch, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}') {
+            continue;
+        }
+        if ch.is_whitespace() && !ch.is_control() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space && !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        pending_space = false;
+        normalized.push(ch);
+    }
+    normalized
+}
+
+impl Bank {
+    pub fn holder_validation(&self) -> HolderValidation {
+        self.holder_validation
+    }
+
+    pub fn set_holder_validation(&mut self, validation: HolderValidation) {
+        self.holder_validation = validation;
+    }
+
+    pub fn validation_mode(&self) -> ValidationMode {
+        self.validation_mode
+    }
+
+    pub fn set_validation_mode(&mut self, mode: ValidationMode) {
+        self.validation_mode = mode;
+    }
+
+    /// Adds `account` after checking its holder name against the bank's
+    /// rules, storing the cleaned-up name, and in a strict mode its
+    /// currency. [`Bank::add_account`] adds accounts unchecked.
+    pub fn add_validated_account(&mut self, mut account: Account) -> Result<(), ValidationError> {
+        let holder = self.holder_validation.validate(&account.holder);
+        let mut violations = match &holder {
+            Ok(_) => Vec::new(),
+            Err(error) => error.violations.clone(),
+        };
+        if self.validation_mode == ValidationMode::Strict && !account.currency.is_iso4217() {
+            violations.push(ValidationRule::UnknownCurrency {
+                currency: account.currency,
+            });
+        }
+        match holder {
+            Ok(holder) if violations.is_empty() => account.holder = holder,
+            _ => return Err(ValidationError { violations }),
+        }
+        self.add_account(account);
+        Ok(())
+    }
+}
@@ -0,0 +1,385 @@
+//! A write-ahead log: every mutation made through a
+//! [`BankHandle`](crate::BankHandle) is appended and synced to disk before
+//! it is applied, so replaying the log over the last snapshot brings
+//! back everything done since, even after a crash.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::error::AccountError;
+use crate::snapshot::{escape, Fields, PersistError};
+use crate::time::Timestamp;
+use crate::uuid::Uuid;
+
+/// A mutation as it is written to the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalEntry {
+    OpenAccount {
+        id: u32,
+        holder: String,
+        /// The external reference the account was given, so it keeps it
+        /// when the entry is replayed.
+        reference: Uuid,
+    },
+    Deposit {
+        account: u32,
+        amount: i64,
+    },
+    Withdraw {
+        account: u32,
+        amount: i64,
+    },
+    Transfer {
+        from: u32,
+        to: u32,
+        amount: i64,
+    },
+    /// Moves the clock on by `seconds`, closing each business day passed.
+    AdvanceTime {
+        seconds: u64,
+    },
+    EndOfDay,
+    /// The state of every balance when it was written, for
+    /// [`Bank::rebuild_from_audit_log`] to check against. Replay skips it.
+    Checkpoint {
+        accounts: usize,
+        total: i64,
+        checksum: String,
+    },
+}
+
+/// An entry read back from the log. Sequence numbers start at 1 and grow
+/// by one per entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedEntry {
+    pub sequence: u64,
+    /// The bank's clock when the entry was logged; replay books the entry
+    /// at the same time.
+    pub timestamp: Timestamp,
+    pub entry: WalEntry,
+}
+
+#[derive(Debug)]
+pub struct WriteAheadLog {
+    path: PathBuf,
+    file: File,
+    last_sequence: u64,
+    audit: bool,
+}
+
+impl WriteAheadLog {
+    /// Opens the log at `path` for appending, creating it if needed. A last
+    /// line cut short by a crash is cut off, so the next entry starts on a
+    /// line of its own.
+    pub fn open(path: impl Into<PathBuf>) -> Result<WriteAheadLog, PersistError> {
+        let path = path.into();
+        let last_sequence = read_entries(&path)?
+            .last()
+            .map_or(0, |logged| logged.sequence);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let contents = fs::read(&path)?;
+        let complete = contents
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |newline| newline + 1);
+        if complete < contents.len() {
+            file.set_len(complete as u64)?;
+            file.sync_data()?;
+        }
+        Ok(WriteAheadLog {
+            path,
+            file,
+            last_sequence,
+            audit: false,
+        })
+    }
+
+    /// Keeps the whole history so the bank can be rebuilt from the log
+    /// alone: the daemon and actor write a checkpoint where they would
+    /// otherwise compact. The log grows without bound.
+    pub fn as_audit_log(mut self) -> Self {
+        self.audit = true;
+        self
+    }
+
+    pub fn is_audit_log(&self) -> bool {
+        self.audit
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    /// Numbers the next entry after `sequence` if the log has not got that
+    /// far itself. A log compacted after a snapshot no longer holds the
+    /// entries the snapshot does, so reopened it would start again from 1
+    /// and replay would skip the new entries as already applied.
+    pub fn continue_after(&mut self, sequence: u64) {
+        self.last_sequence = self.last_sequence.max(sequence);
+    }
+
+    /// Appends `entry` and waits for it to reach the disk, returning its
+    /// sequence number.
+    pub fn append(&mut self, timestamp: Timestamp, entry: &WalEntry) -> io::Result<u64> {
+        let sequence = self.last_sequence + 1;
+        let fields = match entry {
+            WalEntry::OpenAccount {
+                id,
+                holder,
+                reference,
+            } => format!("open\t{}\t{}\t{}", id, escape(holder), reference),
+            WalEntry::Deposit { account, amount } => format!("deposit\t{}\t{}", account, amount),
+            WalEntry::Withdraw { account, amount } => format!("withdraw\t{}\t{}", account, amount),
+            WalEntry::Transfer { from, to, amount } => {
+                format!("transfer\t{}\t{}\t{}", from, to, amount)
+            }
+            WalEntry::AdvanceTime { seconds } => format!("advance\t{}", seconds),
+            WalEntry::EndOfDay => "end_of_day".to_string(),
+            WalEntry::Checkpoint {
+                accounts,
+                total,
+                checksum,
+            } => format!("checkpoint\t{}\t{}\t{}", accounts, total, checksum),
+        };
+        writeln!(
+            self.file,
+            "{}\t{}\t{}",
+            sequence,
+            timestamp.seconds(),
+            fields
+        )?;
+        self.file.sync_data()?;
+        self.last_sequence = sequence;
+        Ok(sequence)
+    }
+
+    /// Appends a [`WalEntry::Checkpoint`] of `bank`'s balances as they are
+    /// now.
+    pub fn checkpoint(&mut self, bank: &Bank) -> io::Result<u64> {
+        let entry = WalEntry::Checkpoint {
+            accounts: bank.len(),
+            total: bank.total_balance(),
+            checksum: bank.balance_checksum(),
+        };
+        self.append(bank.now(), &entry)
+    }
+
+    pub fn entries(&self) -> Result<Vec<LoggedEntry>, PersistError> {
+        read_entries(&self.path)
+    }
+
+    /// Drops the entries up to and including `through`, once a snapshot
+    /// holding them is safely on disk.
+    pub fn compact(&mut self, through: u64) -> Result<(), PersistError> {
+        let kept: Vec<String> = fs::read_to_string(&self.path)?
+            .lines()
+            .filter(|line| {
+                line.split('\t')
+                    .next()
+                    .and_then(|sequence| sequence.parse::<u64>().ok())
+                    .is_some_and(|sequence| sequence > through)
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let temporary = self.path.with_extension("tmp");
+        let mut file = File::create(&temporary)?;
+        file.write_all(kept.concat().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temporary, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Reads every complete entry. A last line without its newline was cut
+/// short by a crash before its sync finished, so it was never applied and
+/// is skipped.
+pub(crate) fn read_entries(path: &Path) -> Result<Vec<LoggedEntry>, PersistError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    let mut reader = BufReader::new(file);
+    let mut entries = Vec::new();
+    let mut line = String::new();
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+            break;
+        }
+        number += 1;
+        let mut fields = Fields::new(number, line.trim_end_matches('\n'));
+        let sequence = fields.parse()?;
+        let timestamp = Timestamp(fields.parse()?);
+        let entry = match fields.raw()? {
+            "open" => WalEntry::OpenAccount {
+                id: fields.parse()?,
+                holder: fields.text()?,
+                reference: fields.parse()?,
+            },
+            "deposit" => WalEntry::Deposit {
+                account: fields.parse()?,
+                amount: fields.parse()?,
+            },
+            "withdraw" => WalEntry::Withdraw {
+                account: fields.parse()?,
+                amount: fields.parse()?,
+            },
+            "transfer" => WalEntry::Transfer {
+                from: fields.parse()?,
+                to: fields.parse()?,
+                amount: fields.parse()?,
+            },
+            "advance" => WalEntry::AdvanceTime {
+                seconds: fields.parse()?,
+            },
+            "end_of_day" => WalEntry::EndOfDay,
+            "checkpoint" => WalEntry::Checkpoint {
+                accounts: fields.parse()?,
+                total: fields.parse()?,
+                checksum: fields.raw()?.to_string(),
+            },
+            other => return Err(fields.corrupt(format!("unknown log entry '{}'", other))),
+        };
+        entries.push(LoggedEntry {
+            sequence,
+            timestamp,
+            entry,
+        });
+    }
+    Ok(entries)
+}
+
+impl Bank {
+    /// The sequence number of the last log entry applied to this bank, kept
+    /// in snapshots so replay knows where to pick up.
+    pub fn wal_sequence(&self) -> u64 {
+        self.wal_sequence
+    }
+
+    /// Applies one entry as of its logged time. An operation refused when
+    /// it was first applied is refused again, so the error is only returned
+    /// for the caller to report.
+    pub fn apply_logged(&mut self, logged: &LoggedEntry) -> Result<(), AccountError> {
+        self.wal_sequence = logged.sequence;
+        self.set_time(logged.timestamp);
+        self.apply_entry(&logged.entry)
+    }
+
+    pub(crate) fn apply_entry(&mut self, entry: &WalEntry) -> Result<(), AccountError> {
+        match entry {
+            WalEntry::OpenAccount {
+                id,
+                holder,
+                reference,
+            } => {
+                let mut account = Account::new(*id, holder.clone());
+                account.reference = *reference;
+                self.add_account(account);
+                Ok(())
+            }
+            WalEntry::Deposit { account, amount } => self.deposit(*account, *amount).map(|_| ()),
+            WalEntry::Withdraw { account, amount } => self.withdraw(*account, *amount).map(|_| ()),
+            WalEntry::Transfer { from, to, amount } => {
+                self.transfer(*from, *to, *amount).map(|_| ())
+            }
+            WalEntry::AdvanceTime { seconds } => {
+                self.advance_time(Duration::from_secs(*seconds));
+                Ok(())
+            }
+            WalEntry::EndOfDay => {
+                self.end_of_day();
+                Ok(())
+            }
+            WalEntry::Checkpoint { .. } => Ok(()),
+        }
+    }
+
+    /// Applies the entries of `wal` this bank has not seen yet, returning
+    /// how many were replayed. The clock is left at the later of its own
+    /// time and the last entry's.
+    pub fn replay(&mut self, wal: &WriteAheadLog) -> Result<usize, PersistError> {
+        let now = self.now;
+        let mut replayed = 0;
+        for logged in wal.entries()? {
+            if logged.sequence <= self.wal_sequence {
+                continue;
+            }
+            let _ = self.apply_logged(&logged);
+            replayed += 1;
+        }
+        if self.now < now {
+            self.set_time(now);
+        }
+        Ok(replayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::BankActor;
+
+    fn scratch(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("bank-wal-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn restore(snapshot: &[u8], path: &Path) -> Bank {
+        let mut bank = Bank::load_snapshot(snapshot).unwrap();
+        bank.replay(&WriteAheadLog::open(path).unwrap()).unwrap();
+        bank
+    }
+
+    #[test]
+    fn entries_logged_after_compaction_survive_a_crash() {
+        let path = scratch("compacted");
+        let actor = BankActor::spawn_logged(Bank::new(), 8, WriteAheadLog::open(&path).unwrap());
+        let handle = actor.handle();
+        handle.open_account(1, "Ann").unwrap();
+        handle.deposit(1, 300).unwrap();
+        let bank = actor.shutdown();
+        let reference = bank.get_account(1).unwrap().reference();
+        let mut snapshot = Vec::new();
+        bank.save_snapshot(&mut snapshot).unwrap();
+        let mut wal = WriteAheadLog::open(&path).unwrap();
+        wal.compact(bank.wal_sequence()).unwrap();
+
+        // Restart on the empty log, deposit, then crash without a snapshot.
+        let restarted = restore(&snapshot, &path);
+        let actor = BankActor::spawn_logged(restarted, 8, WriteAheadLog::open(&path).unwrap());
+        actor.handle().deposit(1, 5_000).unwrap();
+        actor.shutdown();
+
+        let recovered = restore(&snapshot, &path);
+        let account = recovered.get_account(1).unwrap();
+        assert_eq!(account.balance(), 5_300);
+        assert_eq!(account.reference(), reference);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn replayed_accounts_keep_their_reference() {
+        let path = scratch("reference");
+        let actor = BankActor::spawn_logged(Bank::new(), 8, WriteAheadLog::open(&path).unwrap());
+        actor.handle().open_account(1, "Ann").unwrap();
+        let reference = actor.shutdown().get_account(1).unwrap().reference();
+
+        let mut bank = Bank::new();
+        bank.replay(&WriteAheadLog::open(&path).unwrap()).unwrap();
+        assert_eq!(bank.get_account(1).unwrap().reference(), reference);
+        assert_eq!(bank.id_of(reference).unwrap(), 1);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,226 @@
+//! The interactive account-opening wizard behind `bank new-account`.
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::account::Account;
+use crate::bank::Bank;
+use crate::currency::Currency;
+use crate::locale::Locale;
+use crate::money::Money;
+
+/// What the account is for, which decides the questions asked about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountKind {
+    /// An everyday account that may have an overdraft facility.
+    Checking,
+    /// An account that earns interest and is never overdrawn.
+    Savings,
+}
+
+impl fmt::Display for AccountKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AccountKind::Checking => "checking",
+            AccountKind::Savings => "savings",
+        })
+    }
+}
+
+/// Everything the wizard collected, ready to become an [`Account`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewAccount {
+    pub id: u32,
+    pub holder: String,
+    pub kind: AccountKind,
+    pub currency: Currency,
+    /// In the currency's minor unit; always zero for savings.
+    pub overdraft_limit: i64,
+    pub overdraft_opt_in: bool,
+    pub interest_rate_bps: u32,
+    pub minimum_balance: Option<i64>,
+}
+
+impl NewAccount {
+    pub fn into_account(self) -> Account {
+        let mut account = Account::new(self.id, self.holder);
+        account.set_currency(self.currency);
+        account.set_overdraft_limit(self.overdraft_limit);
+        account.set_overdraft_opt_in(self.overdraft_opt_in);
+        account.set_interest_rate_bps(self.interest_rate_bps);
+        account.set_minimum_balance(self.minimum_balance);
+        account.set_meta("kind", &self.kind.to_string());
+        account
+    }
+}
+
+impl fmt::Display for NewAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let money = |amount| Locale::English.money_in(amount, self.currency);
+        writeln!(f, "Account id:      {}", self.id)?;
+        writeln!(f, "Holder:          {}", self.holder)?;
+        writeln!(f, "Kind:            {}", self.kind)?;
+        writeln!(f, "Currency:        {}", self.currency)?;
+        if self.kind == AccountKind::Checking {
+            write!(f, "Overdraft:       ")?;
+            match (self.overdraft_limit, self.overdraft_opt_in) {
+                (0, _) => writeln!(f, "none")?,
+                (limit, true) => writeln!(f, "{}", money(limit))?,
+                (limit, false) => writeln!(f, "{} (not opted in)", money(limit))?,
+            }
+        }
+        if self.kind == AccountKind::Savings {
+            writeln!(
+                f,
+                "Interest rate:   {}.{:02}%",
+                self.interest_rate_bps / 100,
+                self.interest_rate_bps % 100
+            )?;
+        }
+        write!(
+            f,
+            "Minimum balance: {}",
+            self.minimum_balance.map_or("none".to_string(), money)
+        )
+    }
+}
+
+/// Asks for an account's details one step at a time, checking each answer
+/// against `bank` before moving on and asking again until it is valid.
+#[derive(Debug)]
+pub struct AccountWizard<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> AccountWizard<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        AccountWizard { input, output }
+    }
+
+    /// Runs every step and shows a summary. Returns `None` if the summary
+    /// is not confirmed; running out of input is an
+    /// [`io::ErrorKind::UnexpectedEof`] error.
+    pub fn run(&mut self, bank: &Bank) -> io::Result<Option<NewAccount>> {
+        let holder = self.ask("Holder name", None, |answer| {
+            bank.holder_validation()
+                .validate(answer)
+                .map_err(|error| error.to_string())
+        })?;
+        let next_id = bank.sorted_account_ids().last().map_or(1, |id| id + 1);
+        let id = self.ask("Account id", Some(&next_id.to_string()), |answer| {
+            let id: u32 = answer
+                .parse()
+                .map_err(|_| "Enter a whole number".to_string())?;
+            match bank.get_account(id) {
+                Some(_) => Err(format!("Account {} already exists", id)),
+                None => Ok(id),
+            }
+        })?;
+        let kind = self.ask(
+            "Account kind (checking/savings)",
+            Some("checking"),
+            |answer| match answer.to_ascii_lowercase().as_str() {
+                "c" | "checking" => Ok(AccountKind::Checking),
+                "s" | "savings" => Ok(AccountKind::Savings),
+                _ => Err("Enter checking or savings".to_string()),
+            },
+        )?;
+        let currency = self.ask("Currency", Some("USD"), |answer| {
+            Currency::new(answer).ok_or_else(|| "Enter a three-letter ISO 4217 code".to_string())
+        })?;
+        let amount = |answer: &str| {
+            let amount = Money::parse_in(answer, Locale::English, currency)
+                .map_err(|error| error.to_string())?
+                .cents();
+            match amount < 0 {
+                true => Err("The amount can't be negative".to_string()),
+                false => Ok(amount),
+            }
+        };
+
+        let (mut overdraft_limit, mut overdraft_opt_in, mut interest_rate_bps) = (0, false, 0);
+        match kind {
+            AccountKind::Checking => {
+                overdraft_limit = self.ask("Overdraft limit", Some("0"), amount)?;
+                if overdraft_limit > 0 {
+                    overdraft_opt_in =
+                        self.ask("Opt in to overdraft coverage? (y/n)", Some("y"), yes_or_no)?;
+                }
+            }
+            AccountKind::Savings => {
+                interest_rate_bps =
+                    self.ask("Interest rate in basis points", Some("0"), |answer| {
+                        answer
+                            .parse()
+                            .map_err(|_| "Enter a whole number, e.g. 150 for 1.50%".to_string())
+                    })?;
+            }
+        }
+        let minimum_balance =
+            self.ask(
+                "Minimum balance (blank for none)",
+                Some(""),
+                |answer| match answer {
+                    "" => Ok(None),
+                    answer => amount(answer).map(Some),
+                },
+            )?;
+
+        let new_account = NewAccount {
+            id,
+            holder,
+            kind,
+            currency,
+            overdraft_limit,
+            overdraft_opt_in,
+            interest_rate_bps,
+            minimum_balance,
+        };
+        writeln!(self.output, "\n{}\n", new_account)?;
+        let confirmed = self.ask("Create this account? (y/n)", None, yes_or_no)?;
+        Ok(confirmed.then_some(new_account))
+    }
+
+    /// Prompts until `parse` accepts the answer. An empty answer takes
+    /// `default`, when there is one.
+    fn ask<T>(
+        &mut self,
+        prompt: &str,
+        default: Option<&str>,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> io::Result<T> {
+        loop {
+            match default {
+                Some(default) if !default.is_empty() => {
+                    write!(self.output, "{} [{}]: ", prompt, default)?
+                }
+                _ => write!(self.output, "{}: ", prompt)?,
+            }
+            self.output.flush()?;
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "input ended before the wizard finished",
+                ));
+            }
+            let answer = match (line.trim(), default) {
+                ("", Some(default)) => default,
+                (answer, _) => answer,
+            };
+            match parse(answer) {
+                Ok(value) => return Ok(value),
+                Err(message) => writeln!(self.output, "  {}", message)?,
+            }
+        }
+    }
+}
+
+pub(crate) fn yes_or_no(answer: &str) -> Result<bool, String> {
+    match answer.to_ascii_lowercase().as_str() {
+        "y" | "yes" => Ok(true),
+        "n" | "no" => Ok(false),
+        _ => Err("Enter y or n".to_string()),
+    }
+}
@@ -0,0 +1,43 @@
+use crate::bank::Bank;
+use crate::error::{AccountError, InputError};
+use crate::outcome::Warning;
+use crate::validation::ValidationMode;
+
+/// What deposits, withdrawals and transfers of zero do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZeroAmountPolicy {
+    /// Succeed without booking anything, with a [`Warning::ZeroAmount`].
+    #[default]
+    Ignore,
+    /// Book a zero-amount entry like any other operation.
+    Record,
+    /// Refuse with [`InputError::ZeroAmount`](crate::InputError::ZeroAmount).
+    Reject,
+}
+
+impl Bank {
+    pub fn zero_amount_policy(&self) -> ZeroAmountPolicy {
+        self.zero_amount_policy
+    }
+
+    pub fn set_zero_amount_policy(&mut self, policy: ZeroAmountPolicy) {
+        self.zero_amount_policy = policy;
+    }
+
+    /// Applies the zero-amount policy to `amount`, or refuses it in a strict
+    /// [`ValidationMode`]. `Ok(Some(warning))` means the operation should
+    /// succeed without booking anything.
+    pub(crate) fn check_zero_amount(&self, amount: i64) -> Result<Option<Warning>, AccountError> {
+        if amount != 0 {
+            return Ok(None);
+        }
+        if self.validation_mode == ValidationMode::Strict {
+            return Err(AccountError::Validation(InputError::ZeroAmount));
+        }
+        match self.zero_amount_policy {
+            ZeroAmountPolicy::Ignore => Ok(Some(Warning::ZeroAmount)),
+            ZeroAmountPolicy::Record => Ok(None),
+            ZeroAmountPolicy::Reject => Err(AccountError::Validation(InputError::ZeroAmount)),
+        }
+    }
+}